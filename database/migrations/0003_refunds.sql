@@ -0,0 +1,24 @@
+ALTER TABLE order_items
+    ADD COLUMN refunded_quantity INT NOT NULL DEFAULT 0,
+    ADD COLUMN refunded_amount DECIMAL(10, 2) NOT NULL DEFAULT 0.00;
+
+CREATE TABLE refunds (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    order_id BIGINT NOT NULL,
+    operator_id BIGINT NOT NULL, -- Staff account that issued the refund
+    reason VARCHAR(255) NOT NULL,
+    refund_time DATETIME NOT NULL,
+    total_amount DECIMAL(10, 2) NOT NULL,
+    FOREIGN KEY (order_id) REFERENCES orders (id),
+    FOREIGN KEY (operator_id) REFERENCES account (id)
+);
+
+CREATE TABLE refund_items (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    refund_id BIGINT NOT NULL,
+    order_item_id BIGINT NOT NULL,
+    quantity INT NOT NULL,
+    amount DECIMAL(10, 2) NOT NULL,
+    FOREIGN KEY (refund_id) REFERENCES refunds (id),
+    FOREIGN KEY (order_item_id) REFERENCES order_items (id)
+);
@@ -0,0 +1,27 @@
+CREATE TABLE modifier_group (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    goods_id INT NOT NULL,
+    group_name VARCHAR(20) NOT NULL, -- e.g. size, milk, add-ons
+    is_required TINYINT(1) NOT NULL DEFAULT 0, -- At least one option must be chosen
+    allow_multiple TINYINT(1) NOT NULL DEFAULT 0, -- More than one option may be chosen
+    FOREIGN KEY (goods_id) REFERENCES goods (id) ON DELETE CASCADE
+);
+
+CREATE TABLE modifier_option (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    group_id BIGINT NOT NULL,
+    option_name VARCHAR(20) NOT NULL,
+    price_delta DECIMAL(10, 2) NOT NULL DEFAULT 0.00, -- Added to the goods price, may be negative
+    FOREIGN KEY (group_id) REFERENCES modifier_group (id) ON DELETE CASCADE
+);
+
+CREATE TABLE order_item_modifier (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    order_item_id BIGINT NOT NULL,
+    option_id BIGINT, -- NULL once the option has been deleted
+    group_name VARCHAR(20) NOT NULL, -- Names and price copied at the time of sale
+    option_name VARCHAR(20) NOT NULL,
+    price_delta DECIMAL(10, 2) NOT NULL,
+    FOREIGN KEY (order_item_id) REFERENCES order_items (id),
+    FOREIGN KEY (option_id) REFERENCES modifier_option (id) ON DELETE SET NULL
+);
@@ -0,0 +1,2 @@
+-- Stock level at or below which the goods need reordering; NULL turns alerts off
+ALTER TABLE goods ADD COLUMN reorder_threshold INT NULL;
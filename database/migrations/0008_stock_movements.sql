@@ -0,0 +1,19 @@
+CREATE TABLE stock_movement (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    goods_id INT NOT NULL,
+    reason VARCHAR(20) NOT NULL, -- sale, restock, waste, correction, refund
+    delta INT NOT NULL, -- Positive when stock comes in, negative when it goes out
+    stock_after INT NOT NULL,
+    reference VARCHAR(100),
+    operator_id BIGINT, -- Account that made the change, NULL for system entries
+    created_at DATETIME NOT NULL,
+    FOREIGN KEY (goods_id) REFERENCES goods (id) ON DELETE CASCADE,
+    FOREIGN KEY (operator_id) REFERENCES account (id),
+    INDEX idx_stock_movement_goods_time (goods_id, created_at)
+);
+
+-- Opening entries so that every existing stock level is explained by the movements
+INSERT INTO stock_movement (goods_id, reason, delta, stock_after, reference, operator_id, created_at)
+SELECT id, 'correction', stock, stock, 'opening stock', NULL, NOW()
+FROM goods
+WHERE stock IS NOT NULL AND stock <> 0;
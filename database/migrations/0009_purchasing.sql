@@ -0,0 +1,29 @@
+CREATE TABLE supplier (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    supplier_name VARCHAR(50) NOT NULL UNIQUE,
+    contact VARCHAR(100) -- Phone number, email or contact person
+);
+
+CREATE TABLE purchase_order (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    supplier_id BIGINT NOT NULL,
+    status VARCHAR(20) NOT NULL, -- open, received, cancelled
+    total_cost DECIMAL(10, 2) NOT NULL,
+    created_by BIGINT NOT NULL,
+    created_at DATETIME NOT NULL,
+    closed_by BIGINT, -- Account that received or cancelled the order
+    closed_at DATETIME,
+    FOREIGN KEY (supplier_id) REFERENCES supplier (id),
+    FOREIGN KEY (created_by) REFERENCES account (id),
+    FOREIGN KEY (closed_by) REFERENCES account (id)
+);
+
+CREATE TABLE purchase_order_item (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    purchase_order_id BIGINT NOT NULL,
+    goods_id INT NOT NULL,
+    quantity INT NOT NULL,
+    unit_cost DECIMAL(10, 2) NOT NULL, -- Cost price agreed with the supplier
+    FOREIGN KEY (purchase_order_id) REFERENCES purchase_order (id) ON DELETE CASCADE,
+    FOREIGN KEY (goods_id) REFERENCES goods (id)
+);
@@ -0,0 +1,17 @@
+-- What the goods currently cost the cafe; NULL until it is known
+ALTER TABLE goods ADD COLUMN cost_price DECIMAL(10, 2) NULL;
+
+-- Cost per item at the time of sale; NULL when the goods had no cost price yet
+ALTER TABLE order_items ADD COLUMN unit_cost DECIMAL(10, 2) NULL;
+
+CREATE TABLE goods_cost_history (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    goods_id INT NOT NULL,
+    cost_price DECIMAL(10, 2) NOT NULL,
+    reference VARCHAR(100), -- e.g. the purchase order that set the cost
+    operator_id BIGINT,
+    changed_at DATETIME NOT NULL,
+    FOREIGN KEY (goods_id) REFERENCES goods (id) ON DELETE CASCADE,
+    FOREIGN KEY (operator_id) REFERENCES account (id),
+    INDEX idx_goods_cost_history_goods_time (goods_id, changed_at)
+);
@@ -0,0 +1,12 @@
+ALTER TABLE goods ADD COLUMN description TEXT NULL;
+-- 0 while the goods are temporarily sold out, whatever the stock says
+ALTER TABLE goods ADD COLUMN available TINYINT NOT NULL DEFAULT 1 CHECK (available IN (0, 1));
+
+-- Goods names must be unique from now on; later duplicates get their ID appended
+UPDATE goods g
+JOIN (SELECT goods_name, MIN(id) AS first_id FROM goods GROUP BY goods_name) AS firsts
+    ON g.goods_name = firsts.goods_name
+SET g.goods_name = CONCAT(LEFT(g.goods_name, 12), ' #', g.id)
+WHERE g.id <> firsts.first_id;
+
+ALTER TABLE goods ADD CONSTRAINT uq_goods_name UNIQUE (goods_name);
@@ -0,0 +1,22 @@
+CREATE TABLE category (
+    id INT PRIMARY KEY AUTO_INCREMENT,
+    category_name VARCHAR(20) NOT NULL UNIQUE, -- Short unique key, the old free-text goods_type
+    display_name VARCHAR(50) NOT NULL, -- Shown to customers
+    sort_order INT NOT NULL DEFAULT 0 -- Lower comes first
+);
+
+-- Every goods_type in use becomes a category, in the order it was first used
+INSERT INTO category (category_name, display_name, sort_order)
+SELECT TRIM(goods_type), TRIM(goods_type), MIN(id)
+FROM goods
+WHERE TRIM(goods_type) <> ''
+GROUP BY TRIM(goods_type);
+
+ALTER TABLE goods ADD COLUMN category_id INT NULL;
+ALTER TABLE goods ADD CONSTRAINT fk_goods_category FOREIGN KEY (category_id) REFERENCES category (id);
+
+UPDATE goods g
+JOIN category c ON TRIM(g.goods_type) = c.category_name
+SET g.category_id = c.id;
+
+ALTER TABLE goods DROP COLUMN goods_type;
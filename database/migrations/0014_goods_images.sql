@@ -0,0 +1,8 @@
+-- One picture per goods, re-encoded as JPEG when uploaded
+CREATE TABLE goods_image (
+    goods_id INT PRIMARY KEY,
+    image_key CHAR(32) NOT NULL, -- Changes with every upload so webviews never show a stale copy
+    image MEDIUMBLOB NOT NULL,
+    thumbnail MEDIUMBLOB NOT NULL,
+    FOREIGN KEY (goods_id) REFERENCES goods (id) ON DELETE CASCADE
+);
@@ -0,0 +1,23 @@
+CREATE TABLE promotion (
+    id INT PRIMARY KEY AUTO_INCREMENT,
+    promotion_name VARCHAR(50) NOT NULL,
+    discount_type VARCHAR(10) NOT NULL, -- 'percent' off the unit price, or a 'fixed' amount off each unit
+    discount_value DECIMAL(10, 2) NOT NULL,
+    goods_id INT NULL, -- Exactly one of goods_id and category_id is set
+    category_id INT NULL,
+    weekdays TINYINT NOT NULL DEFAULT 127, -- Bit 0 is Monday, bit 6 is Sunday
+    start_time TIME NULL, -- Daily window from start_time up to end_time; NULL for all day
+    end_time TIME NULL,
+    start_date DATE NULL, -- Inclusive; NULL leaves that end open
+    end_date DATE NULL,
+    paused TINYINT(1) NOT NULL DEFAULT 0,
+    FOREIGN KEY (goods_id) REFERENCES goods (id) ON DELETE CASCADE,
+    FOREIGN KEY (category_id) REFERENCES category (id) ON DELETE CASCADE
+);
+
+-- Promotion applied to the line, its name copied at the time of sale, and the total taken off
+ALTER TABLE order_items
+    ADD COLUMN promotion_id INT NULL,
+    ADD COLUMN promotion_name VARCHAR(50) NULL,
+    ADD COLUMN discount DECIMAL(10, 2) NOT NULL DEFAULT 0.00,
+    ADD CONSTRAINT fk_order_items_promotion FOREIGN KEY (promotion_id) REFERENCES promotion (id) ON DELETE SET NULL;
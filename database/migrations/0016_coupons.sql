@@ -0,0 +1,28 @@
+CREATE TABLE coupon (
+    id INT PRIMARY KEY AUTO_INCREMENT,
+    code VARCHAR(32) NOT NULL UNIQUE, -- Stored in upper case and matched regardless of case
+    discount_type VARCHAR(10) NOT NULL, -- 'percent' off the order, or a 'fixed' amount off it
+    discount_value DECIMAL(10, 2) NOT NULL,
+    min_spend DECIMAL(10, 2) NULL, -- Order total needed after promotions; NULL for none
+    expires_at DATETIME NULL,
+    max_redemptions INT NULL, -- Across all customers; NULL for no limit
+    max_per_customer INT NULL,
+    disabled TINYINT(1) NOT NULL DEFAULT 0,
+    created_at DATETIME NOT NULL
+);
+
+CREATE TABLE coupon_redemption (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    coupon_id INT NOT NULL,
+    order_id BIGINT NOT NULL UNIQUE, -- At most one coupon per order
+    user_id BIGINT NOT NULL,
+    discount DECIMAL(10, 2) NOT NULL,
+    redeemed_at DATETIME NOT NULL,
+    FOREIGN KEY (coupon_id) REFERENCES coupon (id),
+    FOREIGN KEY (order_id) REFERENCES orders (id),
+    FOREIGN KEY (user_id) REFERENCES account (id),
+    INDEX idx_coupon_redemption_coupon_user (coupon_id, user_id)
+);
+
+-- The line's share of the order's coupon discount, already taken off line_total
+ALTER TABLE order_items ADD COLUMN coupon_discount DECIMAL(10, 2) NOT NULL DEFAULT 0.00;
@@ -0,0 +1,26 @@
+ALTER TABLE account ADD COLUMN points INT NOT NULL DEFAULT 0;
+
+-- Points that buy one unit for free; NULL when the goods cannot be had for points
+ALTER TABLE goods ADD COLUMN points_price INT NULL;
+
+CREATE TABLE points_ledger (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    user_id BIGINT NOT NULL,
+    entry_type VARCHAR(20) NOT NULL, -- earned, redeemed, expired, reversed
+    points INT NOT NULL, -- Positive for credits, negative for debits
+    points_after INT NOT NULL,
+    remaining INT NULL, -- Earned points not yet spent or expired; NULL for debits
+    expires_at DATETIME NULL, -- Set on earned points only
+    reference VARCHAR(100),
+    created_at DATETIME NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES account (id),
+    INDEX idx_points_ledger_user_time (user_id, created_at),
+    INDEX idx_points_ledger_user_expiry (user_id, expires_at)
+);
+
+ALTER TABLE orders
+    ADD COLUMN points_redeemed INT NOT NULL DEFAULT 0,
+    ADD COLUMN points_earned INT NOT NULL DEFAULT 0;
+
+-- What the line's share of redeemed points was worth, already taken off line_total
+ALTER TABLE order_items ADD COLUMN points_discount DECIMAL(10, 2) NOT NULL DEFAULT 0.00;
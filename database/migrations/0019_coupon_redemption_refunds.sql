@@ -0,0 +1,10 @@
+-- Set when the order is fully refunded. Refunded redemptions no longer count towards
+-- the coupon's limits but stay on the order.
+ALTER TABLE coupon_redemption ADD COLUMN refunded_at DATETIME NULL;
+
+UPDATE coupon_redemption
+SET refunded_at = (SELECT MAX(refund_time) FROM refunds WHERE refunds.order_id = coupon_redemption.order_id)
+WHERE NOT EXISTS (
+    SELECT 1 FROM order_items
+    WHERE order_items.order_id = coupon_redemption.order_id AND order_items.refunded_quantity < order_items.quantity
+);
@@ -0,0 +1,47 @@
+CREATE TABLE account (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username VARCHAR(20) UNIQUE NOT NULL,
+    password VARCHAR(255) NOT NULL,
+    phone VARCHAR(50),
+    gender TINYINT CHECK (gender IN (0, 1)), -- 0: Male, 1: Female
+    join_time DATE,
+    balance DECIMAL(10, 2) DEFAULT 0.00,
+    user_type TINYINT CHECK (user_type IN (0, 1)) -- 0: Staff, 1: Customer
+);
+
+CREATE TABLE lost_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_name VARCHAR(20) NOT NULL,
+    pick_place VARCHAR(20),
+    pick_user_id BIGINT REFERENCES account (id),
+    claim_user_id BIGINT REFERENCES account (id),
+    pick_time DATE,
+    claim_time DATE,
+    status TINYINT CHECK (status IN (0, 1)) -- 0: Unclaimed, 1: Claimed
+);
+
+CREATE TABLE goods (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    goods_name VARCHAR(20) NOT NULL,
+    goods_type VARCHAR(20),
+    stock INT DEFAULT 0,
+    price DECIMAL(10, 2) NOT NULL
+);
+
+CREATE TABLE consumption (
+    user_id BIGINT NOT NULL REFERENCES account (id),
+    month VARCHAR(7) NOT NULL, -- Format 'YYYY-MM'
+    goods_id INT NOT NULL REFERENCES goods (id),
+    amount DECIMAL(10, 2) DEFAULT 0.00,
+    PRIMARY KEY (user_id, month, goods_id)
+);
+
+CREATE TABLE message (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    sender_id BIGINT NOT NULL REFERENCES account (id),
+    receiver_id BIGINT NOT NULL REFERENCES account (id),
+    title VARCHAR(255),
+    message_content TEXT,
+    send_date DATE,
+    read_status TINYINT CHECK (read_status IN (0, 1)) -- 0: Unread, 1: Read
+);
@@ -0,0 +1,17 @@
+CREATE TABLE orders (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id BIGINT NOT NULL REFERENCES account (id),
+    order_time DATETIME NOT NULL,
+    total_amount DECIMAL(10, 2) NOT NULL
+);
+
+CREATE INDEX idx_orders_user_time ON orders (user_id, order_time);
+
+CREATE TABLE order_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    order_id BIGINT NOT NULL REFERENCES orders (id),
+    goods_id INT NOT NULL REFERENCES goods (id),
+    quantity INT NOT NULL,
+    unit_price DECIMAL(10, 2) NOT NULL, -- Price per item at the time of sale
+    line_total DECIMAL(10, 2) NOT NULL
+);
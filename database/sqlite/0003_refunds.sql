@@ -0,0 +1,19 @@
+ALTER TABLE order_items ADD COLUMN refunded_quantity INT NOT NULL DEFAULT 0;
+ALTER TABLE order_items ADD COLUMN refunded_amount DECIMAL(10, 2) NOT NULL DEFAULT 0.00;
+
+CREATE TABLE refunds (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    order_id BIGINT NOT NULL REFERENCES orders (id),
+    operator_id BIGINT NOT NULL REFERENCES account (id), -- Staff account that issued the refund
+    reason VARCHAR(255) NOT NULL,
+    refund_time DATETIME NOT NULL,
+    total_amount DECIMAL(10, 2) NOT NULL
+);
+
+CREATE TABLE refund_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    refund_id BIGINT NOT NULL REFERENCES refunds (id),
+    order_item_id BIGINT NOT NULL REFERENCES order_items (id),
+    quantity INT NOT NULL,
+    amount DECIMAL(10, 2) NOT NULL
+);
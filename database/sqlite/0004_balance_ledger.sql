@@ -0,0 +1,18 @@
+CREATE TABLE balance_ledger (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id BIGINT NOT NULL REFERENCES account (id),
+    entry_type VARCHAR(20) NOT NULL, -- recharge, purchase, refund, adjustment
+    amount DECIMAL(10, 2) NOT NULL, -- Positive for credits, negative for debits
+    balance_after DECIMAL(10, 2) NOT NULL,
+    reference VARCHAR(100),
+    operator_id BIGINT REFERENCES account (id), -- Account that made the change, NULL for system entries
+    created_at DATETIME NOT NULL
+);
+
+CREATE INDEX idx_balance_ledger_user_time ON balance_ledger (user_id, created_at);
+
+-- Opening entries so that every existing balance is explained by the ledger
+INSERT INTO balance_ledger (user_id, entry_type, amount, balance_after, reference, operator_id, created_at)
+SELECT id, 'adjustment', balance, balance, 'opening balance', NULL, datetime('now', 'localtime')
+FROM account
+WHERE user_type = 1 AND balance IS NOT NULL AND balance <> 0;
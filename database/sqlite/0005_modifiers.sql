@@ -0,0 +1,23 @@
+CREATE TABLE modifier_group (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    goods_id INT NOT NULL REFERENCES goods (id) ON DELETE CASCADE,
+    group_name VARCHAR(20) NOT NULL, -- e.g. size, milk, add-ons
+    is_required TINYINT NOT NULL DEFAULT 0, -- At least one option must be chosen
+    allow_multiple TINYINT NOT NULL DEFAULT 0 -- More than one option may be chosen
+);
+
+CREATE TABLE modifier_option (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    group_id BIGINT NOT NULL REFERENCES modifier_group (id) ON DELETE CASCADE,
+    option_name VARCHAR(20) NOT NULL,
+    price_delta DECIMAL(10, 2) NOT NULL DEFAULT 0.00 -- Added to the goods price, may be negative
+);
+
+CREATE TABLE order_item_modifier (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    order_item_id BIGINT NOT NULL REFERENCES order_items (id),
+    option_id BIGINT REFERENCES modifier_option (id) ON DELETE SET NULL, -- NULL once the option has been deleted
+    group_name VARCHAR(20) NOT NULL, -- Names and price copied at the time of sale
+    option_name VARCHAR(20) NOT NULL,
+    price_delta DECIMAL(10, 2) NOT NULL
+);
@@ -0,0 +1,16 @@
+CREATE TABLE ingredient (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    ingredient_name VARCHAR(20) NOT NULL UNIQUE,
+    unit VARCHAR(10) NOT NULL, -- e.g. g, ml, pcs
+    stock INT NOT NULL DEFAULT 0 -- Counted in `unit`
+);
+
+CREATE TABLE recipe_item (
+    goods_id INT NOT NULL REFERENCES goods (id) ON DELETE CASCADE,
+    ingredient_id BIGINT NOT NULL REFERENCES ingredient (id),
+    quantity INT NOT NULL, -- Used per unit of goods sold, in the ingredient's unit
+    PRIMARY KEY (goods_id, ingredient_id)
+);
+
+-- Set when the sale drew on the goods' recipe instead of `goods.stock`
+ALTER TABLE order_items ADD COLUMN made_from_recipe TINYINT NOT NULL DEFAULT 0;
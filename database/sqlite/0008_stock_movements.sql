@@ -0,0 +1,18 @@
+CREATE TABLE stock_movement (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    goods_id INT NOT NULL REFERENCES goods (id) ON DELETE CASCADE,
+    reason VARCHAR(20) NOT NULL, -- sale, restock, waste, correction, refund
+    delta INT NOT NULL, -- Positive when stock comes in, negative when it goes out
+    stock_after INT NOT NULL,
+    reference VARCHAR(100),
+    operator_id BIGINT REFERENCES account (id), -- Account that made the change, NULL for system entries
+    created_at DATETIME NOT NULL
+);
+
+CREATE INDEX idx_stock_movement_goods_time ON stock_movement (goods_id, created_at);
+
+-- Opening entries so that every existing stock level is explained by the movements
+INSERT INTO stock_movement (goods_id, reason, delta, stock_after, reference, operator_id, created_at)
+SELECT id, 'correction', stock, stock, 'opening stock', NULL, datetime('now', 'localtime')
+FROM goods
+WHERE stock IS NOT NULL AND stock <> 0;
@@ -0,0 +1,24 @@
+CREATE TABLE supplier (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    supplier_name VARCHAR(50) NOT NULL UNIQUE,
+    contact VARCHAR(100) -- Phone number, email or contact person
+);
+
+CREATE TABLE purchase_order (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    supplier_id BIGINT NOT NULL REFERENCES supplier (id),
+    status VARCHAR(20) NOT NULL, -- open, received, cancelled
+    total_cost DECIMAL(10, 2) NOT NULL,
+    created_by BIGINT NOT NULL REFERENCES account (id),
+    created_at DATETIME NOT NULL,
+    closed_by BIGINT REFERENCES account (id), -- Account that received or cancelled the order
+    closed_at DATETIME
+);
+
+CREATE TABLE purchase_order_item (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    purchase_order_id BIGINT NOT NULL REFERENCES purchase_order (id) ON DELETE CASCADE,
+    goods_id INT NOT NULL REFERENCES goods (id),
+    quantity INT NOT NULL,
+    unit_cost DECIMAL(10, 2) NOT NULL -- Cost price agreed with the supplier
+);
@@ -0,0 +1,2 @@
+-- Set when the goods are taken off sale; archived goods stay in the reports
+ALTER TABLE goods ADD COLUMN archived_at DATETIME NULL;
@@ -0,0 +1,10 @@
+ALTER TABLE goods ADD COLUMN description TEXT NULL;
+-- 0 while the goods are temporarily sold out, whatever the stock says
+ALTER TABLE goods ADD COLUMN available TINYINT NOT NULL DEFAULT 1 CHECK (available IN (0, 1));
+
+-- Goods names must be unique from now on; later duplicates get their ID appended
+UPDATE goods
+SET goods_name = substr(goods_name, 1, 12) || ' #' || id
+WHERE id NOT IN (SELECT MIN(id) FROM goods GROUP BY goods_name);
+
+CREATE UNIQUE INDEX uq_goods_name ON goods (goods_name);
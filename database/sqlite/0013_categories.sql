@@ -0,0 +1,20 @@
+CREATE TABLE category (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    category_name VARCHAR(20) NOT NULL UNIQUE, -- Short unique key, the old free-text goods_type
+    display_name VARCHAR(50) NOT NULL, -- Shown to customers
+    sort_order INT NOT NULL DEFAULT 0 -- Lower comes first
+);
+
+-- Every goods_type in use becomes a category, in the order it was first used
+INSERT INTO category (category_name, display_name, sort_order)
+SELECT TRIM(goods_type), TRIM(goods_type), MIN(id)
+FROM goods
+WHERE TRIM(goods_type) <> ''
+GROUP BY TRIM(goods_type);
+
+ALTER TABLE goods ADD COLUMN category_id INTEGER REFERENCES category (id);
+
+UPDATE goods
+SET category_id = (SELECT id FROM category WHERE category_name = TRIM(goods.goods_type));
+
+ALTER TABLE goods DROP COLUMN goods_type;
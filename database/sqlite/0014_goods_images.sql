@@ -0,0 +1,7 @@
+-- One picture per goods, re-encoded as JPEG when uploaded
+CREATE TABLE goods_image (
+    goods_id INTEGER PRIMARY KEY REFERENCES goods (id) ON DELETE CASCADE,
+    image_key CHAR(32) NOT NULL, -- Changes with every upload so webviews never show a stale copy
+    image BLOB NOT NULL,
+    thumbnail BLOB NOT NULL
+);
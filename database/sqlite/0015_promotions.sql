@@ -0,0 +1,19 @@
+CREATE TABLE promotion (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    promotion_name VARCHAR(50) NOT NULL,
+    discount_type VARCHAR(10) NOT NULL, -- 'percent' off the unit price, or a 'fixed' amount off each unit
+    discount_value DECIMAL(10, 2) NOT NULL,
+    goods_id INT REFERENCES goods (id) ON DELETE CASCADE, -- Exactly one of goods_id and category_id is set
+    category_id INT REFERENCES category (id) ON DELETE CASCADE,
+    weekdays TINYINT NOT NULL DEFAULT 127, -- Bit 0 is Monday, bit 6 is Sunday
+    start_time TIME, -- Daily window from start_time up to end_time; NULL for all day
+    end_time TIME,
+    start_date DATE, -- Inclusive; NULL leaves that end open
+    end_date DATE,
+    paused TINYINT NOT NULL DEFAULT 0
+);
+
+-- Promotion applied to the line, its name copied at the time of sale, and the total taken off
+ALTER TABLE order_items ADD COLUMN promotion_id INT REFERENCES promotion (id) ON DELETE SET NULL;
+ALTER TABLE order_items ADD COLUMN promotion_name VARCHAR(50);
+ALTER TABLE order_items ADD COLUMN discount DECIMAL(10, 2) NOT NULL DEFAULT 0.00;
@@ -0,0 +1,26 @@
+CREATE TABLE coupon (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    code VARCHAR(32) NOT NULL UNIQUE, -- Stored in upper case and matched regardless of case
+    discount_type VARCHAR(10) NOT NULL, -- 'percent' off the order, or a 'fixed' amount off it
+    discount_value DECIMAL(10, 2) NOT NULL,
+    min_spend DECIMAL(10, 2), -- Order total needed after promotions; NULL for none
+    expires_at DATETIME,
+    max_redemptions INT, -- Across all customers; NULL for no limit
+    max_per_customer INT,
+    disabled TINYINT NOT NULL DEFAULT 0,
+    created_at DATETIME NOT NULL
+);
+
+CREATE TABLE coupon_redemption (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    coupon_id INT NOT NULL REFERENCES coupon (id),
+    order_id BIGINT NOT NULL UNIQUE REFERENCES orders (id), -- At most one coupon per order
+    user_id BIGINT NOT NULL REFERENCES account (id),
+    discount DECIMAL(10, 2) NOT NULL,
+    redeemed_at DATETIME NOT NULL
+);
+
+CREATE INDEX idx_coupon_redemption_coupon_user ON coupon_redemption (coupon_id, user_id);
+
+-- The line's share of the order's coupon discount, already taken off line_total
+ALTER TABLE order_items ADD COLUMN coupon_discount DECIMAL(10, 2) NOT NULL DEFAULT 0.00;
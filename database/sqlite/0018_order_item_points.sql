@@ -0,0 +1,4 @@
+-- Points spent on the line: the points price of goods redeemed with points, plus the
+-- line's share of points taken off the order. Orders placed earlier record none.
+ALTER TABLE order_items ADD COLUMN points_redeemed INT NOT NULL DEFAULT 0;
+ALTER TABLE order_items ADD COLUMN points_restored INT NOT NULL DEFAULT 0; -- Given back by refunds so far
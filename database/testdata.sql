@@ -11,32 +11,39 @@ VALUES
 
 -- password: 123456 ($2b$12$d9n2xF3hslElNuQAqrln/OeEeYRLnMZIOwvnlpqns5uwr92Wq65yK)
 
-INSERT INTO goods (goods_name, goods_type, stock, price) VALUES
-('意式浓缩', '咖啡类', 50, 18.00),
-('拿铁咖啡', '咖啡类', 50, 25.00),
-('卡布奇诺', '咖啡类', 50, 25.00),
-('焦糖玛奇朵', '咖啡类', 50, 28.00),
-('冰美式咖啡', '咖啡类', 50, 22.00),
-('冷萃咖啡', '咖啡类', 30, 30.00),
-('英式早餐茶', '非咖啡饮品', 40, 20.00),
-('抹茶拿铁', '非咖啡饮品', 40, 25.00),
-('热巧克力', '非咖啡饮品', 40, 22.00),
-('草莓奶昔', '非咖啡饮品', 30, 28.00),
-('柠檬蜂蜜水', '非咖啡饮品', 50, 18.00),
-('芝士蛋糕', '烘焙食品', 20, 32.00),
-('提拉米苏', '烘焙食品', 20, 35.00),
-('牛角包', '烘焙食品', 30, 15.00),
-('肉桂卷', '烘焙食品', 25, 18.00),
-('巧克力曲奇', '烘焙食品', 40, 12.00),
-('火腿芝士三明治', '轻食简餐', 25, 28.00),
-('鸡肉牛油果三明治', '轻食简餐', 25, 32.00),
-('凯撒沙拉', '轻食简餐', 20, 30.00),
-('全日早餐', '轻食简餐', 15, 38.00),
-('南瓜汤', '轻食简餐', 30, 22.00),
-('埃塞俄比亚单品豆', '咖啡豆与周边', 15, 120.00),
-('哥伦比亚单品豆', '咖啡豆与周边', 15, 110.00),
-('法压壶', '咖啡豆与周边', 10, 180.00),
-('品牌咖啡杯', '咖啡豆与周边', 20, 65.00);
+INSERT INTO category (id, category_name, display_name, sort_order) VALUES
+(1, '咖啡类', '咖啡类', 1),
+(2, '非咖啡饮品', '非咖啡饮品', 2),
+(3, '烘焙食品', '烘焙食品', 3),
+(4, '轻食简餐', '轻食简餐', 4),
+(5, '咖啡豆与周边', '咖啡豆与周边', 5);
+
+INSERT INTO goods (goods_name, category_id, stock, price) VALUES
+('意式浓缩', 1, 50, 18.00),
+('拿铁咖啡', 1, 50, 25.00),
+('卡布奇诺', 1, 50, 25.00),
+('焦糖玛奇朵', 1, 50, 28.00),
+('冰美式咖啡', 1, 50, 22.00),
+('冷萃咖啡', 1, 30, 30.00),
+('英式早餐茶', 2, 40, 20.00),
+('抹茶拿铁', 2, 40, 25.00),
+('热巧克力', 2, 40, 22.00),
+('草莓奶昔', 2, 30, 28.00),
+('柠檬蜂蜜水', 2, 50, 18.00),
+('芝士蛋糕', 3, 20, 32.00),
+('提拉米苏', 3, 20, 35.00),
+('牛角包', 3, 30, 15.00),
+('肉桂卷', 3, 25, 18.00),
+('巧克力曲奇', 3, 40, 12.00),
+('火腿芝士三明治', 4, 25, 28.00),
+('鸡肉牛油果三明治', 4, 25, 32.00),
+('凯撒沙拉', 4, 20, 30.00),
+('全日早餐', 4, 15, 38.00),
+('南瓜汤', 4, 30, 22.00),
+('埃塞俄比亚单品豆', 5, 15, 120.00),
+('哥伦比亚单品豆', 5, 15, 110.00),
+('法压壶', 5, 10, 180.00),
+('品牌咖啡杯', 5, 20, 65.00);
 
 INSERT INTO lost_items (item_name, pick_place, pick_user_id, claim_user_id, pick_time, claim_time, status)
 VALUES
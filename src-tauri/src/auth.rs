@@ -0,0 +1,62 @@
+use std::fmt;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Role {
+    Staff,    // user_type 0
+    Customer, // user_type 1
+}
+
+impl Role {
+    pub fn from_user_type(user_type: i8) -> Option<Role> {
+        match user_type {
+            0 => Some(Role::Staff),
+            1 => Some(Role::Customer),
+            _ => None,
+        }
+    }
+}
+
+// Required access level, declared at the top of every command that needs a session.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Access {
+    AnyUser,
+    StaffOnly,
+    CustomerOnly,
+}
+
+impl Access {
+    pub fn allows(&self, role: Role) -> bool {
+        match self {
+            Access::AnyUser => true,
+            Access::StaffOnly => role == Role::Staff,
+            Access::CustomerOnly => role == Role::Customer,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AuthError {
+    NotLoggedIn,
+    SessionExpired,
+    Forbidden { required: Access, actual: Role },
+    UnknownRole(i8),
+    StoreUnavailable,
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::NotLoggedIn => write!(f, "Not logged in or session is invalid"),
+            AuthError::SessionExpired => write!(f, "Session has expired, please log in again"),
+            AuthError::Forbidden { required, actual } => write!(
+                f,
+                "Permission denied: this action requires {:?} but the current user is {:?}",
+                required, actual
+            ),
+            AuthError::UnknownRole(user_type) => {
+                write!(f, "Account has an unknown user type {}", user_type)
+            }
+            AuthError::StoreUnavailable => write!(f, "Session store is unavailable"),
+        }
+    }
+}
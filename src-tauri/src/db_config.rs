@@ -1,23 +1,400 @@
-pub struct MySQLConfig {
+use mysql::{Opts, OptsBuilder, PoolConstraints, PoolOpts, SslOpts};
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+pub const CONFIG_FILE_NAME: &str = "database.toml";
+pub const DEFAULT_SQLITE_FILE_NAME: &str = "cafehub.db";
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Mysql,
+    Sqlite, // Embedded database file, no server required
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "mysql" => Ok(Backend::Mysql),
+            "sqlite" => Ok(Backend::Sqlite),
+            _ => Err(format!("Unknown database backend '{}'", s)),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    backend: Backend,
+    sqlite_path: Option<PathBuf>,
     user: String,
     password: String,
     host: String,
+    port: u16,
     database: String,
+    pool_min: usize,
+    pool_max: usize,
+    tls: bool,
+    tls_ca_cert: Option<PathBuf>,
 }
 
-impl MySQLConfig {
-    pub fn new(user: String, password: String, host: String, database: String) -> Self {
-        MySQLConfig {
-            user,
-            password,
-            host,
-            database,
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            backend: Backend::Mysql,
+            sqlite_path: None,
+            user: "root".to_string(),
+            password: "123456".to_string(),
+            host: "localhost".to_string(),
+            port: 3306,
+            database: "cafehub".to_string(),
+            pool_min: 10,
+            pool_max: 100,
+            tls: false,
+            tls_ca_cert: None,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    // Reads `database.toml` from the app config dir (if present), then applies
+    // CAFEHUB_DB_* environment variable overrides and validates the result.
+    pub fn load(config_dir: &Path) -> Result<Self, String> {
+        Self::load_with_env(config_dir, env_var)
+    }
+
+    // `load` with the environment looked up through `env`, so it can be faked.
+    fn load_with_env(
+        config_dir: &Path,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, String> {
+        let config_path = config_dir.join(CONFIG_FILE_NAME);
+        let mut config = if config_path.exists() {
+            let contents = fs::read_to_string(&config_path).map_err(|e| {
+                format!(
+                    "Failed to read database config {}: {}",
+                    config_path.display(),
+                    e
+                )
+            })?;
+            toml::from_str(&contents)
+                .map_err(|e| format!("Invalid database config {}: {}", config_path.display(), e))?
+        } else {
+            println!(
+                "Database config {} not found, using defaults and environment variables.",
+                config_path.display()
+            );
+            DatabaseConfig::default()
+        };
+
+        config.apply_env_overrides(env)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn apply_env_overrides(&mut self, env: impl Fn(&str) -> Option<String>) -> Result<(), String> {
+        if let Some(backend) = env("CAFEHUB_DB_BACKEND") {
+            self.backend = backend.parse()?;
+        }
+        if let Some(sqlite_path) = env("CAFEHUB_DB_SQLITE_PATH") {
+            self.sqlite_path = Some(PathBuf::from(sqlite_path));
+        }
+        if let Some(user) = env("CAFEHUB_DB_USER") {
+            self.user = user;
+        }
+        if let Some(password) = env("CAFEHUB_DB_PASSWORD") {
+            self.password = password;
+        }
+        if let Some(host) = env("CAFEHUB_DB_HOST") {
+            self.host = host;
+        }
+        if let Some(port) = env("CAFEHUB_DB_PORT") {
+            self.port = parse_env("CAFEHUB_DB_PORT", &port)?;
+        }
+        if let Some(database) = env("CAFEHUB_DB_NAME") {
+            self.database = database;
+        }
+        if let Some(pool_min) = env("CAFEHUB_DB_POOL_MIN") {
+            self.pool_min = parse_env("CAFEHUB_DB_POOL_MIN", &pool_min)?;
+        }
+        if let Some(pool_max) = env("CAFEHUB_DB_POOL_MAX") {
+            self.pool_max = parse_env("CAFEHUB_DB_POOL_MAX", &pool_max)?;
+        }
+        if let Some(tls) = env("CAFEHUB_DB_TLS") {
+            self.tls = parse_env("CAFEHUB_DB_TLS", &tls)?;
+        }
+        if let Some(ca_cert) = env("CAFEHUB_DB_TLS_CA_CERT") {
+            self.tls_ca_cert = Some(PathBuf::from(ca_cert));
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if let Some(ref sqlite_path) = self.sqlite_path {
+            if sqlite_path.as_os_str().is_empty() {
+                return Err("sqlite_path cannot be empty".to_string());
+            }
+        }
+        if self.host.trim().is_empty() {
+            return Err("Database host cannot be empty".to_string());
+        }
+        if self.port == 0 {
+            return Err("Database port must be between 1 and 65535".to_string());
+        }
+        if self.user.trim().is_empty() {
+            return Err("Database user cannot be empty".to_string());
+        }
+        if self.database.is_empty()
+            || !self
+                .database
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        {
+            return Err(format!(
+                "Invalid database name '{}': only letters, digits, '_' and '$' are allowed",
+                self.database
+            ));
+        }
+        if self.pool_max == 0 {
+            return Err("pool_max must be greater than 0".to_string());
+        }
+        if self.pool_min > self.pool_max {
+            return Err(format!(
+                "pool_min ({}) cannot be greater than pool_max ({})",
+                self.pool_min, self.pool_max
+            ));
+        }
+        if let Some(ref ca_cert) = self.tls_ca_cert {
+            if !self.tls {
+                return Err("tls_ca_cert is set but tls is disabled".to_string());
+            }
+            if !ca_cert.is_file() {
+                return Err(format!(
+                    "TLS CA certificate {} does not exist",
+                    ca_cert.display()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    pub fn database(&self) -> &str {
+        &self.database
+    }
+
+    // The SQLite database file; relative paths and the default live in the app data dir.
+    pub fn sqlite_file(&self, data_dir: &Path) -> PathBuf {
+        match self.sqlite_path {
+            Some(ref path) => data_dir.join(path),
+            None => data_dir.join(DEFAULT_SQLITE_FILE_NAME),
         }
     }
-    pub fn format_url(&self) -> String {
+
+    // Connection options for the configured database.
+    pub fn to_opts(&self) -> Result<Opts, String> {
+        Ok(Opts::from(
+            self.server_opts_builder()?
+                .db_name(Some(self.database.clone())),
+        ))
+    }
+
+    // Connection options without a default database, used to create it on first run.
+    pub fn to_server_opts(&self) -> Result<Opts, String> {
+        Ok(Opts::from(self.server_opts_builder()?))
+    }
+
+    fn server_opts_builder(&self) -> Result<OptsBuilder, String> {
+        let constraints = PoolConstraints::new(self.pool_min, self.pool_max).ok_or_else(|| {
+            format!(
+                "Invalid pool size: min {} / max {}",
+                self.pool_min, self.pool_max
+            )
+        })?;
+
+        let ssl_opts = if self.tls {
+            let ssl_opts = SslOpts::default();
+            Some(match self.tls_ca_cert {
+                Some(ref ca_cert) => ssl_opts.with_root_cert_path(Some(ca_cert.clone())),
+                None => ssl_opts,
+            })
+        } else {
+            None
+        };
+
+        Ok(OptsBuilder::new()
+            .ip_or_hostname(Some(self.host.clone()))
+            .tcp_port(self.port)
+            .user(Some(self.user.clone()))
+            .pass(Some(self.password.clone()))
+            .ssl_opts(ssl_opts)
+            .pool_opts(PoolOpts::default().with_constraints(constraints)))
+    }
+}
+
+fn env_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+fn parse_env<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| {
         format!(
-            "mysql://{}:{}@{}/{}",
-            self.user, self.password, self.host, self.database
+            "Invalid value '{}' for environment variable {}",
+            value, name
         )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    // A config dir of its own for each test, removed when the test ends.
+    struct ConfigDir(PathBuf);
+
+    impl ConfigDir {
+        fn new(name: &str, contents: Option<&str>) -> Self {
+            let dir =
+                env::temp_dir().join(format!("cafehub-db-config-{}-{}", std::process::id(), name));
+            fs::create_dir_all(&dir).unwrap();
+            if let Some(contents) = contents {
+                fs::write(dir.join(CONFIG_FILE_NAME), contents).unwrap();
+            }
+            ConfigDir(dir)
+        }
+
+        fn load(&self, vars: &[(&str, &str)]) -> Result<DatabaseConfig, String> {
+            let vars: HashMap<String, String> = vars
+                .iter()
+                .map(|&(name, value)| (name.to_string(), value.to_string()))
+                .collect();
+            DatabaseConfig::load_with_env(&self.0, |name| vars.get(name).cloned())
+        }
+    }
+
+    impl Drop for ConfigDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn reads_the_config_file_over_the_defaults() {
+        let dir = ConfigDir::new(
+            "file",
+            Some(
+                r#"
+                backend = "sqlite"
+                sqlite_path = "data/shop.db"
+                host = "db.internal"
+                port = 3307
+                pool_min = 2
+                pool_max = 5
+                "#,
+            ),
+        );
+        let config = dir.load(&[]).unwrap();
+        assert_eq!(config.backend(), Backend::Sqlite);
+        assert_eq!(config.sqlite_path, Some(PathBuf::from("data/shop.db")));
+        assert_eq!(config.host, "db.internal");
+        assert_eq!(config.port, 3307);
+        assert_eq!((config.pool_min, config.pool_max), (2, 5));
+        // Settings left out of the file keep their defaults.
+        assert_eq!(config.user, "root");
+        assert_eq!(config.database(), "cafehub");
+
+        let missing = ConfigDir::new("missing", None);
+        assert_eq!(missing.load(&[]).unwrap().backend(), Backend::Mysql);
+    }
+
+    #[test]
+    fn rejects_invalid_config_files() {
+        for (name, contents) in [
+            ("unknown-field", "hostname = \"db\""),
+            ("bad-backend", "backend = \"postgres\""),
+            ("bad-port", "port = \"many\""),
+        ] {
+            let dir = ConfigDir::new(name, Some(contents));
+            let error = dir.load(&[]).unwrap_err();
+            assert!(error.starts_with("Invalid database config"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn environment_variables_override_the_file() {
+        let dir = ConfigDir::new(
+            "env",
+            Some("host = \"file-host\"\nport = 3307\nuser = \"file\""),
+        );
+        let config = dir
+            .load(&[
+                ("CAFEHUB_DB_BACKEND", "SQLite"),
+                ("CAFEHUB_DB_HOST", "env-host"),
+                ("CAFEHUB_DB_PORT", "3310"),
+                ("CAFEHUB_DB_NAME", "cafehub_env"),
+                ("CAFEHUB_DB_POOL_MAX", "20"),
+            ])
+            .unwrap();
+        assert_eq!(config.backend(), Backend::Sqlite);
+        assert_eq!(config.host, "env-host");
+        assert_eq!(config.port, 3310);
+        assert_eq!(config.database(), "cafehub_env");
+        assert_eq!(config.pool_max, 20);
+        assert_eq!(config.user, "file");
+    }
+
+    #[test]
+    fn rejects_bad_numeric_environment_values() {
+        let dir = ConfigDir::new("bad-env", None);
+        for (name, value) in [
+            ("CAFEHUB_DB_PORT", "abc"),
+            ("CAFEHUB_DB_PORT", "70000"),
+            ("CAFEHUB_DB_POOL_MIN", "-1"),
+            ("CAFEHUB_DB_POOL_MAX", "ten"),
+            ("CAFEHUB_DB_TLS", "yes"),
+        ] {
+            assert_eq!(
+                dir.load(&[(name, value)]).unwrap_err(),
+                format!(
+                    "Invalid value '{}' for environment variable {}",
+                    value, name
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn pool_min_cannot_exceed_pool_max() {
+        let dir = ConfigDir::new("pool", Some("pool_min = 8\npool_max = 4"));
+        assert_eq!(
+            dir.load(&[]).unwrap_err(),
+            "pool_min (8) cannot be greater than pool_max (4)"
+        );
+        let config = dir.load(&[("CAFEHUB_DB_POOL_MAX", "8")]).unwrap();
+        assert_eq!((config.pool_min, config.pool_max), (8, 8));
+    }
+
+    #[test]
+    fn sqlite_file_is_resolved_against_the_data_dir() {
+        let data_dir = Path::new("/var/lib/cafehub");
+        let mut config = DatabaseConfig::default();
+        assert_eq!(
+            config.sqlite_file(data_dir),
+            data_dir.join(DEFAULT_SQLITE_FILE_NAME)
+        );
+        config.sqlite_path = Some(PathBuf::from("data/shop.db"));
+        assert_eq!(config.sqlite_file(data_dir), data_dir.join("data/shop.db"));
+        let absolute = env::temp_dir().join("shop.db");
+        config.sqlite_path = Some(absolute.clone());
+        assert_eq!(config.sqlite_file(data_dir), absolute);
     }
 }
@@ -0,0 +1,385 @@
+use crate::auth::{Access, AuthError, Role};
+use rust_decimal::Decimal;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json::{json, Value};
+use std::fmt;
+
+// Why a coupon cannot be used on an order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CouponRejection {
+    Unknown,
+    Disabled,
+    Expired,
+    BelowMinimumSpend,
+    UsedUp,
+    CustomerLimitReached,
+}
+
+impl CouponRejection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CouponRejection::Unknown => "unknown",
+            CouponRejection::Disabled => "disabled",
+            CouponRejection::Expired => "expired",
+            CouponRejection::BelowMinimumSpend => "below_minimum_spend",
+            CouponRejection::UsedUp => "used_up",
+            CouponRejection::CustomerLimitReached => "customer_limit_reached",
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            CouponRejection::Unknown => "no such coupon",
+            CouponRejection::Disabled => "the coupon has been withdrawn",
+            CouponRejection::Expired => "the coupon has expired",
+            CouponRejection::BelowMinimumSpend => "the order does not reach the minimum spend",
+            CouponRejection::UsedUp => "the coupon has been used up",
+            CouponRejection::CustomerLimitReached => "you have already used this coupon",
+        }
+    }
+}
+
+// Error returned by every command. Serialized to the webview as
+// `{ "code": "...", "message": "...", "details": { ... } }`; the codes are stable
+// and the frontend should branch on them rather than on the message text.
+#[derive(Debug)]
+pub enum CafeHubError {
+    NotLoggedIn,
+    SessionExpired,
+    Forbidden {
+        required: Access,
+        actual: Role,
+    },
+    InvalidCredentials,
+    IncorrectPassword,
+    Validation {
+        message: String,
+    },
+    InvalidPhone {
+        phone: String,
+    },
+    InvalidGender {
+        gender: i8,
+    },
+    NotFound {
+        entity: &'static str,
+        id: i64,
+    },
+    DuplicateUsername {
+        username: String,
+    },
+    DuplicateGoodsName {
+        goods_name: String,
+    },
+    DuplicateIngredientName {
+        ingredient_name: String,
+    },
+    DuplicateSupplierName {
+        supplier_name: String,
+    },
+    DuplicateCategoryName {
+        category_name: String,
+    },
+    DuplicateCouponCode {
+        code: String,
+    },
+    CouponRejected {
+        code: String,
+        reason: CouponRejection,
+    },
+    GoodsUnavailable {
+        goods_id: i32,
+    },
+    InsufficientStock {
+        goods_id: i32,
+        requested: i32,
+        available: i32,
+    },
+    InsufficientIngredient {
+        ingredient_id: i64,
+        ingredient_name: String,
+        required: i64,
+        available: i64,
+    },
+    InsufficientBalance {
+        required: Decimal,
+        available: Decimal,
+        shortfall: Decimal,
+    },
+    InsufficientPoints {
+        required: i64,
+        available: i64,
+    },
+    AlreadyClaimed {
+        item_id: i64,
+    },
+    NotMessageReceiver {
+        message_id: i64,
+    },
+    NoAdministrator,
+    Conflict {
+        message: String,
+    },
+    Database {
+        message: String,
+    },
+    Internal {
+        message: String,
+    },
+}
+
+impl CafeHubError {
+    pub fn validation(message: impl Into<String>) -> Self {
+        CafeHubError::Validation {
+            message: message.into(),
+        }
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        CafeHubError::Conflict {
+            message: message.into(),
+        }
+    }
+
+    pub fn database(context: impl fmt::Display, e: impl fmt::Display) -> Self {
+        CafeHubError::Database {
+            message: format!("{}: {}", context, e),
+        }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        CafeHubError::Internal {
+            message: message.into(),
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            CafeHubError::NotLoggedIn => "NOT_LOGGED_IN",
+            CafeHubError::SessionExpired => "SESSION_EXPIRED",
+            CafeHubError::Forbidden { .. } => "FORBIDDEN",
+            CafeHubError::InvalidCredentials => "INVALID_CREDENTIALS",
+            CafeHubError::IncorrectPassword => "INCORRECT_PASSWORD",
+            CafeHubError::Validation { .. } => "VALIDATION_FAILED",
+            CafeHubError::InvalidPhone { .. } => "INVALID_PHONE",
+            CafeHubError::InvalidGender { .. } => "INVALID_GENDER",
+            CafeHubError::NotFound { .. } => "NOT_FOUND",
+            CafeHubError::DuplicateUsername { .. } => "DUPLICATE_USERNAME",
+            CafeHubError::DuplicateGoodsName { .. } => "DUPLICATE_GOODS_NAME",
+            CafeHubError::DuplicateIngredientName { .. } => "DUPLICATE_INGREDIENT_NAME",
+            CafeHubError::DuplicateSupplierName { .. } => "DUPLICATE_SUPPLIER_NAME",
+            CafeHubError::DuplicateCategoryName { .. } => "DUPLICATE_CATEGORY_NAME",
+            CafeHubError::DuplicateCouponCode { .. } => "DUPLICATE_COUPON_CODE",
+            CafeHubError::CouponRejected { .. } => "COUPON_REJECTED",
+            CafeHubError::GoodsUnavailable { .. } => "GOODS_UNAVAILABLE",
+            CafeHubError::InsufficientStock { .. } => "INSUFFICIENT_STOCK",
+            CafeHubError::InsufficientIngredient { .. } => "INSUFFICIENT_INGREDIENT",
+            CafeHubError::InsufficientBalance { .. } => "INSUFFICIENT_BALANCE",
+            CafeHubError::InsufficientPoints { .. } => "INSUFFICIENT_POINTS",
+            CafeHubError::AlreadyClaimed { .. } => "ALREADY_CLAIMED",
+            CafeHubError::NotMessageReceiver { .. } => "NOT_MESSAGE_RECEIVER",
+            CafeHubError::NoAdministrator => "NO_ADMINISTRATOR",
+            CafeHubError::Conflict { .. } => "CONFLICT",
+            CafeHubError::Database { .. } => "DATABASE_ERROR",
+            CafeHubError::Internal { .. } => "INTERNAL_ERROR",
+        }
+    }
+
+    fn details(&self) -> Value {
+        match self {
+            CafeHubError::Forbidden { required, actual } => json!({
+                "required": format!("{:?}", required),
+                "actual": format!("{:?}", actual),
+            }),
+            CafeHubError::InvalidPhone { phone } => json!({ "phone": phone }),
+            CafeHubError::InvalidGender { gender } => json!({ "gender": gender }),
+            CafeHubError::NotFound { entity, id } => json!({ "entity": entity, "id": id }),
+            CafeHubError::DuplicateUsername { username } => json!({ "username": username }),
+            CafeHubError::DuplicateGoodsName { goods_name } => {
+                json!({ "goods_name": goods_name })
+            }
+            CafeHubError::DuplicateIngredientName { ingredient_name } => {
+                json!({ "ingredient_name": ingredient_name })
+            }
+            CafeHubError::DuplicateSupplierName { supplier_name } => {
+                json!({ "supplier_name": supplier_name })
+            }
+            CafeHubError::DuplicateCategoryName { category_name } => {
+                json!({ "category_name": category_name })
+            }
+            CafeHubError::DuplicateCouponCode { code } => json!({ "code": code }),
+            CafeHubError::CouponRejected { code, reason } => {
+                json!({ "code": code, "reason": reason.as_str() })
+            }
+            CafeHubError::GoodsUnavailable { goods_id } => json!({ "goods_id": goods_id }),
+            CafeHubError::InsufficientStock {
+                goods_id,
+                requested,
+                available,
+            } => json!({
+                "goods_id": goods_id,
+                "requested": requested,
+                "available": available,
+            }),
+            CafeHubError::InsufficientIngredient {
+                ingredient_id,
+                ingredient_name,
+                required,
+                available,
+            } => json!({
+                "ingredient_id": ingredient_id,
+                "ingredient_name": ingredient_name,
+                "required": required,
+                "available": available,
+            }),
+            CafeHubError::InsufficientBalance {
+                required,
+                available,
+                shortfall,
+            } => json!({
+                "required": required,
+                "available": available,
+                "shortfall": shortfall,
+            }),
+            CafeHubError::InsufficientPoints {
+                required,
+                available,
+            } => json!({ "required": required, "available": available }),
+            CafeHubError::AlreadyClaimed { item_id } => json!({ "item_id": item_id }),
+            CafeHubError::NotMessageReceiver { message_id } => {
+                json!({ "message_id": message_id })
+            }
+            _ => Value::Null,
+        }
+    }
+}
+
+impl fmt::Display for CafeHubError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CafeHubError::NotLoggedIn => write!(f, "Not logged in or session is invalid"),
+            CafeHubError::SessionExpired => write!(f, "Session has expired, please log in again"),
+            CafeHubError::Forbidden { required, actual } => write!(
+                f,
+                "Permission denied: this action requires {:?} but the current user is {:?}",
+                required, actual
+            ),
+            CafeHubError::InvalidCredentials => write!(f, "Invalid username or password"),
+            CafeHubError::IncorrectPassword => write!(f, "Incorrect current password"),
+            CafeHubError::Validation { message } => write!(f, "{}", message),
+            CafeHubError::InvalidPhone { phone } => {
+                write!(f, "Invalid phone number '{}'. Must be 11 digits.", phone)
+            }
+            CafeHubError::InvalidGender { gender } => write!(
+                f,
+                "Invalid gender value {}. Must be 0 (Male) or 1 (Female).",
+                gender
+            ),
+            CafeHubError::NotFound { entity, id } => {
+                write!(f, "{} with ID {} not found.", entity, id)
+            }
+            CafeHubError::DuplicateUsername { username } => {
+                write!(f, "Username '{}' is already taken.", username)
+            }
+            CafeHubError::DuplicateGoodsName { goods_name } => {
+                write!(f, "Goods with name '{}' already exists.", goods_name)
+            }
+            CafeHubError::DuplicateIngredientName { ingredient_name } => {
+                write!(f, "Ingredient '{}' already exists.", ingredient_name)
+            }
+            CafeHubError::DuplicateSupplierName { supplier_name } => {
+                write!(f, "Supplier '{}' already exists.", supplier_name)
+            }
+            CafeHubError::DuplicateCategoryName { category_name } => {
+                write!(f, "Category '{}' already exists.", category_name)
+            }
+            CafeHubError::DuplicateCouponCode { code } => {
+                write!(f, "Coupon code '{}' already exists.", code)
+            }
+            CafeHubError::CouponRejected { code, reason } => write!(
+                f,
+                "Coupon '{}' cannot be used: {}.",
+                code,
+                reason.describe()
+            ),
+            CafeHubError::GoodsUnavailable { goods_id } => {
+                write!(f, "Goods ID {} is currently unavailable.", goods_id)
+            }
+            CafeHubError::InsufficientStock {
+                goods_id,
+                requested,
+                available,
+            } => write!(
+                f,
+                "Insufficient stock for goods ID {}: requested {}, available {}.",
+                goods_id, requested, available
+            ),
+            CafeHubError::InsufficientIngredient {
+                ingredient_name,
+                required,
+                available,
+                ..
+            } => write!(
+                f,
+                "Not enough {}: required {}, available {}.",
+                ingredient_name, required, available
+            ),
+            CafeHubError::InsufficientBalance {
+                required,
+                available,
+                shortfall,
+            } => write!(
+                f,
+                "Insufficient balance: required {}, available {}, short by {}.",
+                required, available, shortfall
+            ),
+            CafeHubError::InsufficientPoints {
+                required,
+                available,
+            } => write!(
+                f,
+                "Not enough loyalty points: required {}, available {}.",
+                required, available
+            ),
+            CafeHubError::AlreadyClaimed { item_id } => {
+                write!(f, "Item ID {} has already been claimed.", item_id)
+            }
+            CafeHubError::NotMessageReceiver { message_id } => write!(
+                f,
+                "Only the receiver can mark message ID {} as read.",
+                message_id
+            ),
+            CafeHubError::NoAdministrator => write!(f, "No administrator account exists."),
+            CafeHubError::Conflict { message } => write!(f, "{}", message),
+            CafeHubError::Database { message } => write!(f, "{}", message),
+            CafeHubError::Internal { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for CafeHubError {}
+
+impl Serialize for CafeHubError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("CafeHubError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("details", &self.details())?;
+        state.end()
+    }
+}
+
+impl From<AuthError> for CafeHubError {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::NotLoggedIn => CafeHubError::NotLoggedIn,
+            AuthError::SessionExpired => CafeHubError::SessionExpired,
+            AuthError::Forbidden { required, actual } => {
+                CafeHubError::Forbidden { required, actual }
+            }
+            AuthError::UnknownRole(_) | AuthError::StoreUnavailable => {
+                CafeHubError::internal(e.to_string())
+            }
+        }
+    }
+}
@@ -0,0 +1,1812 @@
+use crate::auth::{Access, Role};
+use crate::error::CafeHubError;
+use crate::images;
+use crate::models::*;
+use crate::session::SessionStore;
+use crate::storage::{
+    CustomerChanges, GoodsChanges, MarginGrouping, NewAccount, NewMessage, Storage,
+};
+use bcrypt::{hash, verify, DEFAULT_COST};
+use chrono::{Datelike, Local, NaiveDate};
+use rust_decimal::Decimal;
+
+pub fn login(
+    username: String,
+    password: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<LoginResponse, CafeHubError> {
+    let (account, stored_hashed_password) = match storage.find_account_by_username(&username) {
+        Ok(Some(found)) => found,
+        Ok(None) => {
+            println!("Login failed for user {}: User not found", username);
+            return Err(CafeHubError::InvalidCredentials);
+        }
+        Err(e) => {
+            eprintln!("Database query failed for user {}: {}", username, e);
+            return Err(e);
+        }
+    };
+
+    let valid_password = verify(&password, &stored_hashed_password).map_err(|e| {
+        eprintln!("Password verification error for user {}: {}", username, e);
+        CafeHubError::internal("Password verification process failed")
+    })?;
+
+    if valid_password {
+        let token = session_store.create(account.id, account.user_type)?;
+        Ok(LoginResponse { token, account })
+    } else {
+        println!("Login failed for user {}: Invalid password", username);
+        Err(CafeHubError::InvalidCredentials)
+    }
+}
+
+pub fn logout(token: String, session_store: &SessionStore) -> Result<String, CafeHubError> {
+    if session_store.revoke(&token)? {
+        Ok("Logged out successfully.".to_string())
+    } else {
+        Err(CafeHubError::NotLoggedIn)
+    }
+}
+
+pub fn register_user(
+    data: RegistrationData,
+    storage: &dyn Storage,
+) -> Result<String, CafeHubError> {
+    if data.username.is_empty() || data.password.is_empty() {
+        return Err(CafeHubError::validation(
+            "Username and password cannot be empty",
+        ));
+    }
+
+    if let Some(gender_val) = data.gender {
+        if gender_val != 0 && gender_val != 1 {
+            return Err(CafeHubError::InvalidGender { gender: gender_val });
+        }
+    }
+
+    if let Some(ref phone_str) = data.phone {
+        if !phone_str.chars().all(|c| c.is_ascii_digit()) || phone_str.len() != 11 {
+            return Err(CafeHubError::InvalidPhone {
+                phone: phone_str.clone(),
+            });
+        }
+    }
+
+    let hashed_password = hash(&data.password, DEFAULT_COST).map_err(|e| {
+        eprintln!("Failed to hash password for user {}: {}", data.username, e);
+        CafeHubError::internal("Password hashing failed")
+    })?;
+
+    let result = storage.create_customer(&NewAccount {
+        username: data.username.clone(),
+        password_hash: hashed_password,
+        phone: data.phone,
+        gender: data.gender,
+        join_time: Local::now().date_naive(),
+    });
+
+    match result {
+        Ok(_) => Ok(format!("User '{}' registered successfully.", data.username)),
+        Err(e) => {
+            eprintln!("Database insert failed for user {}: {}", data.username, e);
+            Err(e)
+        }
+    }
+}
+
+pub fn update_user_password(
+    token: String,
+    data: UpdatePasswordData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    let user_id = session_store
+        .authorize(&token, Access::CustomerOnly)?
+        .user_id;
+
+    if data.new_password.is_empty() {
+        return Err(CafeHubError::validation("New password cannot be empty"));
+    }
+    if data.current_password.is_empty() {
+        return Err(CafeHubError::validation("Current password cannot be empty"));
+    }
+
+    let stored_hashed_password =
+        storage
+            .customer_password_hash(user_id)?
+            .ok_or(CafeHubError::NotFound {
+                entity: "Customer",
+                id: user_id,
+            })?;
+
+    let valid_current_password =
+        verify(&data.current_password, &stored_hashed_password).map_err(|e| {
+            eprintln!(
+                "Current password verification error for user ID {}: {}",
+                user_id, e
+            );
+            CafeHubError::internal("Password verification process failed")
+        })?;
+
+    if !valid_current_password {
+        return Err(CafeHubError::IncorrectPassword);
+    }
+
+    let new_hashed_password = hash(&data.new_password, DEFAULT_COST).map_err(|e| {
+        eprintln!("Failed to hash new password for user ID {}: {}", user_id, e);
+        CafeHubError::internal("Password hashing failed")
+    })?;
+
+    if storage.update_customer_password(user_id, &new_hashed_password)? {
+        Ok("Password updated successfully.".to_string())
+    } else {
+        Err(CafeHubError::conflict(
+            "Failed to update password, user not found or no change made.",
+        ))
+    }
+}
+
+pub fn get_total_users(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<i64, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    storage.count_customers(None).map_err(|e| {
+        eprintln!("Database query failed for total users: {}", e);
+        e
+    })
+}
+
+pub fn get_new_users_this_month(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<i64, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    let now = Local::now();
+    let first_day_current_month = NaiveDate::from_ymd_opt(now.year(), now.month(), 1)
+        .ok_or_else(|| CafeHubError::internal("Failed to construct first day of current month"))?;
+
+    storage
+        .count_customers(Some(first_day_current_month))
+        .map_err(|e| {
+            eprintln!("Database query failed for new users this month: {}", e);
+            e
+        })
+}
+
+pub fn get_monthly_consumption_summary(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<MonthlyConsumptionSummary>, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    storage.monthly_consumption(None)
+}
+
+pub fn get_goods_consumption_share_current_month(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<GoodsConsumptionShare>, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    let current_month_str = Local::now().format("%Y-%m").to_string();
+    storage
+        .goods_consumption_share(&current_month_str)
+        .map_err(|e| {
+            eprintln!("[RUST ERROR] {}", e);
+            e
+        })
+}
+
+// Accepts a "YYYY-MM" month, as used by the consumption statistics.
+fn validate_month(month: &str) -> Result<(), CafeHubError> {
+    NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+        .map(|_| ())
+        .map_err(|_| CafeHubError::validation("Month must be in the format YYYY-MM"))
+}
+
+pub fn get_goods_margins(
+    token: String,
+    month: Option<String>,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<MarginSummary>, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    if let Some(ref month) = month {
+        validate_month(month)?;
+    }
+    storage.margin_summary(MarginGrouping::Goods, month.as_deref())
+}
+
+pub fn get_category_margins(
+    token: String,
+    month: Option<String>,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<MarginSummary>, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    if let Some(ref month) = month {
+        validate_month(month)?;
+    }
+    storage.margin_summary(MarginGrouping::Category, month.as_deref())
+}
+
+pub fn get_monthly_margins(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<MarginSummary>, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    storage.margin_summary(MarginGrouping::Month, None)
+}
+
+pub fn get_user_details(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Account, CafeHubError> {
+    let user_id = session_store
+        .authorize(&token, Access::CustomerOnly)?
+        .user_id;
+
+    let mut account = storage
+        .find_customer(user_id)?
+        .ok_or(CafeHubError::NotFound {
+            entity: "Customer",
+            id: user_id,
+        })?;
+    // So that the points shown are the ones the customer can still spend.
+    account.points = storage.spendable_points(user_id, Local::now().naive_local())?;
+    Ok(account)
+}
+
+pub fn get_user_monthly_consumption(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<MonthlyConsumptionSummary>, CafeHubError> {
+    let user_id = session_store
+        .authorize(&token, Access::CustomerOnly)?
+        .user_id;
+
+    storage.monthly_consumption(Some(user_id))
+}
+
+pub fn update_user_details(
+    token: String,
+    data: UpdateUserData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    let user_id = session_store
+        .authorize(&token, Access::CustomerOnly)?
+        .user_id;
+
+    let mut changes = CustomerChanges {
+        username: None,
+        phone: None,
+        gender: None,
+    };
+
+    if let Some(uname_val) = data.username {
+        if uname_val.is_empty() {
+            return Err(CafeHubError::validation("Username cannot be empty."));
+        }
+
+        let is_changing_username = match storage.find_customer(user_id)? {
+            Some(ref current) => current.username != uname_val,
+            None => true,
+        };
+        if is_changing_username {
+            changes.username = Some(uname_val);
+        }
+    }
+
+    if let Some(phone_val) = data.phone {
+        if phone_val.is_empty() {
+            changes.phone = Some(None);
+        } else {
+            if !phone_val.chars().all(|c| c.is_ascii_digit()) || phone_val.len() != 11 {
+                return Err(CafeHubError::InvalidPhone { phone: phone_val });
+            }
+            changes.phone = Some(Some(phone_val));
+        }
+    }
+
+    if let Some(gender_val) = data.gender {
+        if gender_val == 0 || gender_val == 1 {
+            changes.gender = Some(gender_val);
+        } else {
+            return Err(CafeHubError::InvalidGender { gender: gender_val });
+        }
+    }
+
+    if changes.username.is_none() && changes.phone.is_none() && changes.gender.is_none() {
+        return Ok("No details provided to update or values are the same.".to_string());
+    }
+
+    match storage.update_customer_details(user_id, &changes) {
+        Ok(true) => Ok("User details updated successfully.".to_string()),
+        Ok(false) => Ok("No changes made to user details (user not found, not a customer, or new values match old values).".to_string()),
+        Err(e) => {
+            eprintln!("Database update failed for user ID {}: {}", user_id, e);
+            Err(e)
+        }
+    }
+}
+
+pub fn get_all_goods(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<Goods>, CafeHubError> {
+    let session = session_store.authorize(&token, Access::AnyUser)?;
+
+    let mut goods = storage.list_goods()?;
+    // Customers only see what is on sale, and not what the cafe pays for it.
+    if session.role == Role::Customer {
+        goods.retain(|item| item.archived_at.is_none());
+        hide_cost_prices(&mut goods);
+    }
+    Ok(goods)
+}
+
+const DEFAULT_GOODS_PAGE_SIZE: u32 = 20;
+const MAX_GOODS_PAGE_SIZE: u32 = 100;
+
+pub fn search_goods(
+    token: String,
+    mut query: GoodsQuery,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<GoodsPage, CafeHubError> {
+    let session = session_store.authorize(&token, Access::AnyUser)?;
+
+    let page = query.page.unwrap_or(1);
+    if page == 0 {
+        return Err(CafeHubError::validation("Page numbers start at 1"));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_GOODS_PAGE_SIZE);
+    if limit == 0 || limit > MAX_GOODS_PAGE_SIZE {
+        return Err(CafeHubError::validation(format!(
+            "Page size must be between 1 and {}",
+            MAX_GOODS_PAGE_SIZE
+        )));
+    }
+    let offset = (page - 1)
+        .checked_mul(limit)
+        .ok_or_else(|| CafeHubError::validation("Page number is too large"))?;
+    if query.min_price.is_some_and(|price| price < Decimal::ZERO)
+        || query.max_price.is_some_and(|price| price < Decimal::ZERO)
+    {
+        return Err(CafeHubError::validation("Prices cannot be negative"));
+    }
+    if let (Some(min_price), Some(max_price)) = (query.min_price, query.max_price) {
+        if min_price > max_price {
+            return Err(CafeHubError::validation(
+                "Minimum price cannot be above the maximum price",
+            ));
+        }
+    }
+    query.search = query
+        .search
+        .map(|search| search.trim().to_string())
+        .filter(|search| !search.is_empty());
+
+    let customer = session.role == Role::Customer;
+    let include_archived = query.include_archived && !customer;
+    let (mut items, total) = storage.search_goods(&query, include_archived, offset, limit)?;
+    if customer {
+        hide_cost_prices(&mut items);
+    }
+    Ok(GoodsPage {
+        items,
+        total,
+        page,
+        limit,
+    })
+}
+
+fn hide_cost_prices(goods: &mut [Goods]) {
+    for item in goods {
+        item.cost_price = None;
+    }
+}
+
+// The menu: goods grouped by category in menu order, with uncategorized goods last.
+// Categories without any goods are left out.
+pub fn get_goods_by_category(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<CategoryGoods>, CafeHubError> {
+    let goods = get_all_goods(token, storage, session_store)?;
+
+    let mut sections: Vec<CategoryGoods> = storage
+        .list_categories()?
+        .into_iter()
+        .map(|category| CategoryGoods {
+            category: Some(category),
+            goods: Vec::new(),
+        })
+        .collect();
+    let mut uncategorized = Vec::new();
+    for item in goods {
+        let section = sections.iter_mut().find(|section| {
+            section.category.as_ref().map(|category| category.id) == item.category_id
+        });
+        match section {
+            Some(section) => section.goods.push(item),
+            None => uncategorized.push(item),
+        }
+    }
+    sections.retain(|section| !section.goods.is_empty());
+    if !uncategorized.is_empty() {
+        sections.push(CategoryGoods {
+            category: None,
+            goods: uncategorized,
+        });
+    }
+    Ok(sections)
+}
+
+// Trims a required name and checks it fits `max_chars`, the width of the column it is
+// stored in. `label` names the field in the error message.
+fn validate_name(label: &str, name: &str, max_chars: usize) -> Result<String, CafeHubError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(CafeHubError::validation(format!(
+            "{} cannot be empty",
+            label
+        )));
+    }
+    if name.chars().count() > max_chars {
+        return Err(CafeHubError::validation(format!(
+            "{} cannot be longer than {} characters",
+            label, max_chars
+        )));
+    }
+    Ok(name.to_string())
+}
+
+pub fn add_goods(
+    token: String,
+    mut data: AddGoodsData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    let operator_id = session_store.authorize(&token, Access::StaffOnly)?.user_id;
+
+    data.goods_name = validate_name("Goods name", &data.goods_name, 20)?;
+    if data.price <= Decimal::ZERO {
+        return Err(CafeHubError::validation("Price must be positive"));
+    }
+    if data
+        .reorder_threshold
+        .is_some_and(|threshold| threshold < 0)
+    {
+        return Err(CafeHubError::validation(
+            "Reorder threshold cannot be negative",
+        ));
+    }
+
+    if data.stock.is_some_and(|stock| stock < 0) {
+        return Err(CafeHubError::validation("Stock cannot be negative"));
+    }
+    if data.cost_price.is_some_and(|cost| cost < Decimal::ZERO) {
+        return Err(CafeHubError::validation("Cost price cannot be negative"));
+    }
+    if data.points_price.is_some_and(|points| points <= 0) {
+        return Err(CafeHubError::validation("Points price must be positive"));
+    }
+
+    match storage.create_goods(&data, operator_id) {
+        Ok(_) => Ok(format!("Goods '{}' added successfully.", data.goods_name)),
+        Err(e) => {
+            eprintln!(
+                "Database insert failed for goods {}: {}",
+                data.goods_name, e
+            );
+            Err(e)
+        }
+    }
+}
+
+pub fn update_goods_info(
+    token: String,
+    goods_id: i32,
+    data: UpdateGoodsData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    let operator_id = session_store.authorize(&token, Access::StaffOnly)?.user_id;
+
+    let mut changes = GoodsChanges {
+        goods_name: None,
+        category_id: None,
+        description: None,
+        price: None,
+        available: data.available,
+        reorder_threshold: None,
+        cost_price: None,
+        points_price: None,
+    };
+
+    if let Some(ref name_val) = data.goods_name {
+        changes.goods_name = Some(validate_name("Goods name", name_val, 20)?);
+    }
+    if data.clear_category {
+        if data.category_id.is_some() {
+            return Err(CafeHubError::validation(
+                "Choose a category or clear it, not both",
+            ));
+        }
+        changes.category_id = Some(None);
+    } else {
+        changes.category_id = data.category_id.map(Some);
+    }
+    // An empty string clears the description.
+    changes.description = data
+        .description
+        .map(|desc_val| Some(desc_val.trim().to_string()).filter(|d| !d.is_empty()));
+
+    if let Some(price_val) = data.price {
+        if price_val <= Decimal::ZERO {
+            return Err(CafeHubError::validation("Price must be positive"));
+        }
+        changes.price = Some(price_val);
+    }
+    if let Some(threshold) = data.reorder_threshold {
+        if threshold < 0 {
+            return Err(CafeHubError::validation(
+                "Reorder threshold cannot be negative",
+            ));
+        }
+        changes.reorder_threshold = Some(threshold);
+    }
+    if let Some(cost) = data.cost_price {
+        if cost < Decimal::ZERO {
+            return Err(CafeHubError::validation("Cost price cannot be negative"));
+        }
+        changes.cost_price = Some(cost);
+    }
+    // A points price of 0 stops the goods being redeemed with points.
+    if let Some(points) = data.points_price {
+        if points < 0 {
+            return Err(CafeHubError::validation("Points price cannot be negative"));
+        }
+        changes.points_price = Some(Some(points).filter(|&points| points > 0));
+    }
+    if changes.is_empty() {
+        return Ok("No details provided to update.".to_string());
+    }
+
+    match storage.update_goods(goods_id, &changes, operator_id) {
+        Ok(true) => Ok(format!(
+            "Info for goods ID {} updated successfully.",
+            goods_id
+        )),
+        Ok(false) => Err(CafeHubError::NotFound {
+            entity: "Goods",
+            id: goods_id.into(),
+        }),
+        Err(e) => {
+            eprintln!(
+                "Database update failed for goods info (ID {}): {}",
+                goods_id, e
+            );
+            Err(e)
+        }
+    }
+}
+
+pub fn archive_goods(
+    token: String,
+    goods_id: i32,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    if storage.set_goods_archived(goods_id, Some(Local::now().naive_local()))? {
+        Ok(format!("Goods ID {} archived.", goods_id))
+    } else {
+        Err(CafeHubError::NotFound {
+            entity: "Goods",
+            id: goods_id.into(),
+        })
+    }
+}
+
+pub fn restore_goods(
+    token: String,
+    goods_id: i32,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    if storage.set_goods_archived(goods_id, None)? {
+        Ok(format!("Goods ID {} restored.", goods_id))
+    } else {
+        Err(CafeHubError::NotFound {
+            entity: "Goods",
+            id: goods_id.into(),
+        })
+    }
+}
+
+pub fn delete_goods(
+    token: String,
+    goods_id: i32,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    if storage.delete_goods(goods_id)? {
+        Ok(format!("Goods ID {} deleted.", goods_id))
+    } else {
+        Err(CafeHubError::NotFound {
+            entity: "Goods",
+            id: goods_id.into(),
+        })
+    }
+}
+
+// Accepts JPEG, PNG or WebP; the picture is stored resized, together with a thumbnail.
+pub fn upload_goods_image(
+    token: String,
+    goods_id: i32,
+    image: Vec<u8>,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    let image = images::encode_goods_image(&image)?;
+    if storage.set_goods_image(goods_id, &image)? {
+        Ok(format!(
+            "Image for goods ID {} uploaded successfully.",
+            goods_id
+        ))
+    } else {
+        Err(CafeHubError::NotFound {
+            entity: "Goods",
+            id: goods_id.into(),
+        })
+    }
+}
+
+pub fn remove_goods_image(
+    token: String,
+    goods_id: i32,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    if storage.remove_goods_image(goods_id)? {
+        Ok(format!("Image for goods ID {} removed.", goods_id))
+    } else {
+        Err(CafeHubError::NotFound {
+            entity: "Goods image",
+            id: goods_id.into(),
+        })
+    }
+}
+
+pub fn get_categories(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<Category>, CafeHubError> {
+    session_store.authorize(&token, Access::AnyUser)?;
+
+    storage.list_categories()
+}
+
+pub fn add_category(
+    token: String,
+    mut data: AddCategoryData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    data.category_name = validate_name("Category name", &data.category_name, 20)?;
+    data.display_name = data
+        .display_name
+        .map(|display_name| validate_name("Display name", &display_name, 50))
+        .transpose()?;
+
+    storage.create_category(&data)?;
+    Ok(format!(
+        "Category '{}' added successfully.",
+        data.category_name
+    ))
+}
+
+pub fn update_category(
+    token: String,
+    category_id: i32,
+    mut data: UpdateCategoryData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    data.category_name = data
+        .category_name
+        .map(|category_name| validate_name("Category name", &category_name, 20))
+        .transpose()?;
+    data.display_name = data
+        .display_name
+        .map(|display_name| validate_name("Display name", &display_name, 50))
+        .transpose()?;
+    if data.category_name.is_none() && data.display_name.is_none() && data.sort_order.is_none() {
+        return Ok("No details provided to update.".to_string());
+    }
+
+    if storage.update_category(category_id, &data)? {
+        Ok(format!("Category ID {} updated successfully.", category_id))
+    } else {
+        Err(CafeHubError::NotFound {
+            entity: "Category",
+            id: category_id.into(),
+        })
+    }
+}
+
+pub fn delete_category(
+    token: String,
+    category_id: i32,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    if storage.delete_category(category_id)? {
+        Ok(format!("Category ID {} deleted.", category_id))
+    } else {
+        Err(CafeHubError::NotFound {
+            entity: "Category",
+            id: category_id.into(),
+        })
+    }
+}
+
+pub fn get_promotions(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<Promotion>, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    storage.list_promotions()
+}
+
+pub fn add_promotion(
+    token: String,
+    mut data: PromotionData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    validate_promotion(&mut data)?;
+    let promotion_id = storage.create_promotion(&data)?;
+    Ok(format!(
+        "Promotion '{}' added with ID {}.",
+        data.promotion_name, promotion_id
+    ))
+}
+
+pub fn update_promotion(
+    token: String,
+    promotion_id: i32,
+    mut data: PromotionData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    validate_promotion(&mut data)?;
+    if storage.update_promotion(promotion_id, &data)? {
+        Ok(format!(
+            "Promotion ID {} updated successfully.",
+            promotion_id
+        ))
+    } else {
+        Err(CafeHubError::NotFound {
+            entity: "Promotion",
+            id: promotion_id.into(),
+        })
+    }
+}
+
+pub fn delete_promotion(
+    token: String,
+    promotion_id: i32,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    if storage.delete_promotion(promotion_id)? {
+        Ok(format!("Promotion ID {} deleted.", promotion_id))
+    } else {
+        Err(CafeHubError::NotFound {
+            entity: "Promotion",
+            id: promotion_id.into(),
+        })
+    }
+}
+
+fn validate_promotion(data: &mut PromotionData) -> Result<(), CafeHubError> {
+    data.promotion_name = validate_name("Promotion name", &data.promotion_name, 50)?;
+    if data.goods_id.is_some() == data.category_id.is_some() {
+        return Err(CafeHubError::validation(
+            "A promotion must target either one goods or one category",
+        ));
+    }
+    if data.discount_value <= Decimal::ZERO {
+        return Err(CafeHubError::validation("Discount must be positive"));
+    }
+    if data.discount_type == DiscountType::Percent && data.discount_value > Decimal::ONE_HUNDRED {
+        return Err(CafeHubError::validation(
+            "A percentage discount cannot exceed 100",
+        ));
+    }
+    if let Some(day) = data.weekdays.iter().find(|day| !(1..=7).contains(*day)) {
+        return Err(CafeHubError::validation(format!(
+            "Weekday {} is invalid; use 1 for Monday through 7 for Sunday",
+            day
+        )));
+    }
+    match (data.start_time, data.end_time) {
+        (None, None) => {}
+        (Some(start_time), Some(end_time)) if start_time < end_time => {}
+        (Some(_), Some(_)) => {
+            return Err(CafeHubError::validation(
+                "The time window must end after it starts",
+            ));
+        }
+        _ => {
+            return Err(CafeHubError::validation(
+                "Start and end time must be given together",
+            ));
+        }
+    }
+    if let (Some(start_date), Some(end_date)) = (data.start_date, data.end_date) {
+        if start_date > end_date {
+            return Err(CafeHubError::validation(
+                "The end date cannot be before the start date",
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub fn get_coupons(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<Coupon>, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    storage.list_coupons()
+}
+
+pub fn add_coupon(
+    token: String,
+    mut data: AddCouponData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    data.code = validate_name("Coupon code", &data.code, 32)?.to_uppercase();
+    if !data
+        .code
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(CafeHubError::validation(
+            "Coupon codes may only contain letters, digits, '-' and '_'",
+        ));
+    }
+    if data.discount_value <= Decimal::ZERO {
+        return Err(CafeHubError::validation("Discount must be positive"));
+    }
+    if data.discount_type == DiscountType::Percent && data.discount_value > Decimal::ONE_HUNDRED {
+        return Err(CafeHubError::validation(
+            "A percentage discount cannot exceed 100",
+        ));
+    }
+    if data
+        .min_spend
+        .is_some_and(|min_spend| min_spend < Decimal::ZERO)
+    {
+        return Err(CafeHubError::validation("Minimum spend cannot be negative"));
+    }
+    if data.max_redemptions.is_some_and(|max| max <= 0)
+        || data.max_per_customer.is_some_and(|max| max <= 0)
+    {
+        return Err(CafeHubError::validation("Usage limits must be positive"));
+    }
+    let now = Local::now().naive_local();
+    if data.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Err(CafeHubError::validation("Expiry must be in the future"));
+    }
+
+    storage.create_coupon(&data, now)?;
+    Ok(format!("Coupon '{}' added successfully.", data.code))
+}
+
+pub fn disable_coupon(
+    token: String,
+    coupon_id: i32,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    if storage.disable_coupon(coupon_id)? {
+        Ok(format!("Coupon ID {} disabled.", coupon_id))
+    } else {
+        Err(CafeHubError::NotFound {
+            entity: "Coupon",
+            id: coupon_id.into(),
+        })
+    }
+}
+
+pub fn adjust_goods_stock(
+    token: String,
+    goods_id: i32,
+    data: AdjustStockData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    let operator_id = session_store.authorize(&token, Access::StaffOnly)?.user_id;
+
+    if data.delta == 0 {
+        return Err(CafeHubError::validation("Stock adjustment cannot be zero"));
+    }
+    match data.reason {
+        StockMovementReason::Restock if data.delta < 0 => {
+            return Err(CafeHubError::validation("A restock must add stock"));
+        }
+        StockMovementReason::Waste if data.delta > 0 => {
+            return Err(CafeHubError::validation("Waste must take stock out"));
+        }
+        // Sales and refunds are recorded by the orders that cause them.
+        StockMovementReason::Sale | StockMovementReason::Refund => {
+            return Err(CafeHubError::validation(format!(
+                "Stock cannot be adjusted manually with reason '{}'",
+                data.reason.as_str()
+            )));
+        }
+        _ => {}
+    }
+    let note = data
+        .note
+        .as_deref()
+        .map(str::trim)
+        .filter(|note| !note.is_empty());
+
+    let stock_after = storage.adjust_stock(goods_id, data.delta, data.reason, note, operator_id)?;
+
+    Ok(format!(
+        "Stock of goods ID {} adjusted by {}, new stock {}.",
+        goods_id, data.delta, stock_after
+    ))
+}
+
+pub fn get_stock_movements(
+    token: String,
+    goods_id: i32,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<StockMovement>, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    storage.stock_movements(goods_id)
+}
+
+pub fn get_goods_cost_history(
+    token: String,
+    goods_id: i32,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<GoodsCostChange>, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    storage.cost_history(goods_id)
+}
+
+pub fn get_low_stock_goods(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<Goods>, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    storage.list_low_stock_goods()
+}
+
+pub fn get_goods_modifiers(
+    token: String,
+    goods_id: i32,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<ModifierGroup>, CafeHubError> {
+    session_store.authorize(&token, Access::AnyUser)?;
+
+    storage.list_modifier_groups(goods_id)
+}
+
+pub fn add_modifier_group(
+    token: String,
+    mut data: AddModifierGroupData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    data.group_name = validate_name("Modifier group name", &data.group_name, 20)?;
+    if data.options.is_empty() {
+        return Err(CafeHubError::validation(
+            "A modifier group needs at least one option",
+        ));
+    }
+    for i in 0..data.options.len() {
+        let option_name = validate_name("Modifier option name", &data.options[i].option_name, 20)?;
+        if data.options[..i]
+            .iter()
+            .any(|other| other.option_name == option_name)
+        {
+            return Err(CafeHubError::validation(format!(
+                "Modifier option '{}' is listed more than once",
+                option_name
+            )));
+        }
+        data.options[i].option_name = option_name;
+    }
+
+    storage.create_modifier_group(&data)?;
+
+    Ok(format!(
+        "Modifier group '{}' added to goods ID {}.",
+        data.group_name, data.goods_id
+    ))
+}
+
+pub fn delete_modifier_group(
+    token: String,
+    group_id: i64,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    if storage.delete_modifier_group(group_id)? {
+        Ok(format!("Modifier group ID {} deleted.", group_id))
+    } else {
+        Err(CafeHubError::NotFound {
+            entity: "Modifier group",
+            id: group_id,
+        })
+    }
+}
+
+pub fn get_all_ingredients(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<Ingredient>, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    storage.list_ingredients()
+}
+
+pub fn add_ingredient(
+    token: String,
+    mut data: AddIngredientData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    data.ingredient_name = validate_name("Ingredient name", &data.ingredient_name, 20)?;
+    data.unit = validate_name("Ingredient unit", &data.unit, 10)?;
+    if data.stock.is_some_and(|stock| stock < 0) {
+        return Err(CafeHubError::validation("Stock cannot be negative"));
+    }
+
+    storage.create_ingredient(&data)?;
+
+    Ok(format!(
+        "Ingredient '{}' added successfully.",
+        data.ingredient_name
+    ))
+}
+
+pub fn update_ingredient(
+    token: String,
+    ingredient_id: i64,
+    mut data: UpdateIngredientData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    data.unit = data
+        .unit
+        .map(|unit| validate_name("Ingredient unit", &unit, 10))
+        .transpose()?;
+    if data.stock.is_some_and(|stock| stock < 0) {
+        return Err(CafeHubError::validation("Stock cannot be negative"));
+    }
+    if data.unit.is_none() && data.stock.is_none() {
+        return Ok("No details provided to update.".to_string());
+    }
+
+    if storage.update_ingredient(ingredient_id, &data)? {
+        Ok(format!(
+            "Ingredient ID {} updated successfully.",
+            ingredient_id
+        ))
+    } else {
+        Err(CafeHubError::NotFound {
+            entity: "Ingredient",
+            id: ingredient_id,
+        })
+    }
+}
+
+pub fn get_goods_recipe(
+    token: String,
+    goods_id: i32,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<RecipeItem>, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    storage.find_recipe(goods_id)
+}
+
+pub fn set_goods_recipe(
+    token: String,
+    goods_id: i32,
+    data: SetRecipeData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    for (i, item) in data.items.iter().enumerate() {
+        if item.quantity <= 0 {
+            return Err(CafeHubError::validation(
+                "Recipe quantities must be positive",
+            ));
+        }
+        if data.items[..i]
+            .iter()
+            .any(|other| other.ingredient_id == item.ingredient_id)
+        {
+            return Err(CafeHubError::validation(format!(
+                "Ingredient ID {} is listed more than once",
+                item.ingredient_id
+            )));
+        }
+    }
+
+    storage.set_recipe(goods_id, &data.items)?;
+
+    if data.items.is_empty() {
+        Ok(format!("Recipe removed from goods ID {}.", goods_id))
+    } else {
+        Ok(format!("Recipe for goods ID {} saved.", goods_id))
+    }
+}
+
+pub fn get_suppliers(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<Supplier>, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    storage.list_suppliers()
+}
+
+pub fn add_supplier(
+    token: String,
+    mut data: AddSupplierData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    data.supplier_name = validate_name("Supplier name", &data.supplier_name, 50)?;
+    // A blank contact is stored as none.
+    data.contact = data
+        .contact
+        .map(|contact| contact.trim().to_string())
+        .filter(|contact| !contact.is_empty());
+    if data
+        .contact
+        .as_ref()
+        .is_some_and(|contact| contact.chars().count() > 100)
+    {
+        return Err(CafeHubError::validation(
+            "Supplier contact cannot be longer than 100 characters",
+        ));
+    }
+
+    storage.create_supplier(&data)?;
+
+    Ok(format!(
+        "Supplier '{}' added successfully.",
+        data.supplier_name
+    ))
+}
+
+pub fn create_purchase_order(
+    token: String,
+    data: CreatePurchaseOrderData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<i64, CafeHubError> {
+    let operator_id = session_store.authorize(&token, Access::StaffOnly)?.user_id;
+
+    if data.items.is_empty() {
+        return Err(CafeHubError::validation(
+            "A purchase order needs at least one item",
+        ));
+    }
+    for (i, item) in data.items.iter().enumerate() {
+        if item.quantity <= 0 {
+            return Err(CafeHubError::validation(
+                "Purchase quantities must be positive",
+            ));
+        }
+        if item.unit_cost < Decimal::ZERO {
+            return Err(CafeHubError::validation("Unit cost cannot be negative"));
+        }
+        if data.items[..i]
+            .iter()
+            .any(|other| other.goods_id == item.goods_id)
+        {
+            return Err(CafeHubError::validation(format!(
+                "Goods ID {} is listed more than once",
+                item.goods_id
+            )));
+        }
+    }
+
+    storage.create_purchase_order(&data, operator_id, Local::now().naive_local())
+}
+
+pub fn get_purchase_orders(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<PurchaseOrderSummary>, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    storage.list_purchase_orders()
+}
+
+pub fn get_purchase_order_details(
+    token: String,
+    purchase_order_id: i64,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<PurchaseOrderDetail, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    storage
+        .find_purchase_order(purchase_order_id)?
+        .ok_or(CafeHubError::NotFound {
+            entity: "Purchase order",
+            id: purchase_order_id,
+        })
+}
+
+pub fn receive_purchase_order(
+    token: String,
+    purchase_order_id: i64,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    let operator_id = session_store.authorize(&token, Access::StaffOnly)?.user_id;
+
+    storage.receive_purchase_order(purchase_order_id, operator_id, Local::now().naive_local())?;
+
+    Ok(format!(
+        "Purchase order ID {} received and stock updated.",
+        purchase_order_id
+    ))
+}
+
+pub fn cancel_purchase_order(
+    token: String,
+    purchase_order_id: i64,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    let operator_id = session_store.authorize(&token, Access::StaffOnly)?.user_id;
+
+    storage.cancel_purchase_order(purchase_order_id, operator_id, Local::now().naive_local())?;
+
+    Ok(format!(
+        "Purchase order ID {} cancelled.",
+        purchase_order_id
+    ))
+}
+
+pub fn recharge_balance(
+    token: String,
+    data: RechargeBalanceData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    let operator_id = session_store.authorize(&token, Access::StaffOnly)?.user_id;
+
+    if data.amount <= Decimal::ZERO {
+        return Err(CafeHubError::validation("Recharge amount must be positive"));
+    }
+
+    storage
+        .recharge_balance(data.user_id, data.amount, operator_id)
+        .map_err(|e| {
+            eprintln!(
+                "Database update failed for balance recharge (user ID {}): {}",
+                data.user_id, e
+            );
+            e
+        })?;
+
+    Ok(format!(
+        "Successfully recharged {} for user ID {}.",
+        data.amount, data.user_id
+    ))
+}
+
+pub fn adjust_balance(
+    token: String,
+    data: AdjustBalanceData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    let operator_id = session_store.authorize(&token, Access::StaffOnly)?.user_id;
+
+    if data.amount == Decimal::ZERO {
+        return Err(CafeHubError::validation("Adjustment amount cannot be zero"));
+    }
+    if data.note.trim().is_empty() {
+        return Err(CafeHubError::validation(
+            "A note is required for balance adjustments",
+        ));
+    }
+
+    let balance_after =
+        storage.adjust_balance(data.user_id, data.amount, data.note.trim(), operator_id)?;
+
+    Ok(format!(
+        "Balance of user ID {} adjusted by {}, new balance {}.",
+        data.user_id, data.amount, balance_after
+    ))
+}
+
+pub fn purchase_goods(
+    token: String,
+    data: PurchaseGoodsData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<i64, CafeHubError> {
+    let user_id = session_store
+        .authorize(&token, Access::CustomerOnly)?
+        .user_id;
+
+    if data.items.is_empty() {
+        return Err(CafeHubError::validation("No items provided for purchase."));
+    }
+
+    for item in &data.items {
+        if item.quantity <= 0 {
+            return Err(CafeHubError::validation(format!(
+                "Quantity for goods ID {} must be positive.",
+                item.goods_id
+            )));
+        }
+    }
+    if data.points_to_redeem.is_some_and(|points| points < 0) {
+        return Err(CafeHubError::validation(
+            "Points to redeem cannot be negative.",
+        ));
+    }
+
+    // Codes are stored in upper case and typed in any case.
+    let coupon_code = data
+        .coupon_code
+        .map(|code| code.trim().to_uppercase())
+        .filter(|code| !code.is_empty());
+
+    storage.place_order(
+        user_id,
+        &data.items,
+        coupon_code.as_deref(),
+        data.points_to_redeem.unwrap_or(0),
+        Local::now().naive_local(),
+    )
+}
+
+pub fn get_order_history(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<OrderSummary>, CafeHubError> {
+    let user_id = session_store
+        .authorize(&token, Access::CustomerOnly)?
+        .user_id;
+
+    storage.list_orders(user_id)
+}
+
+pub fn get_order_details(
+    token: String,
+    order_id: i64,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<OrderDetail, CafeHubError> {
+    let session = session_store.authorize(&token, Access::AnyUser)?;
+
+    let order = storage
+        .find_order(order_id)?
+        .ok_or(CafeHubError::NotFound {
+            entity: "Order",
+            id: order_id,
+        })?;
+
+    // Customers may only see their own orders; staff may see any.
+    if session.role == Role::Customer && order.user_id != session.user_id {
+        return Err(CafeHubError::NotFound {
+            entity: "Order",
+            id: order_id,
+        });
+    }
+
+    Ok(order)
+}
+
+pub fn refund_order(
+    token: String,
+    data: RefundOrderData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    let operator_id = session_store.authorize(&token, Access::StaffOnly)?.user_id;
+
+    if data.reason.trim().is_empty() {
+        return Err(CafeHubError::validation(
+            "A reason is required for refunds.",
+        ));
+    }
+    for item in &data.items {
+        if item.quantity <= 0 {
+            return Err(CafeHubError::validation(format!(
+                "Refund quantity for order item ID {} must be positive.",
+                item.order_item_id
+            )));
+        }
+    }
+
+    let total_refund = storage.refund_order(&data, operator_id, Local::now().naive_local())?;
+
+    Ok(format!(
+        "Refunded {} for order ID {}.",
+        total_refund, data.order_id
+    ))
+}
+
+pub fn get_balance_statement(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<BalanceLedgerEntry>, CafeHubError> {
+    let user_id = session_store
+        .authorize(&token, Access::CustomerOnly)?
+        .user_id;
+
+    storage.balance_statement(user_id)
+}
+
+pub fn get_points_history(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<PointsHistory, CafeHubError> {
+    let user_id = session_store
+        .authorize(&token, Access::CustomerOnly)?
+        .user_id;
+
+    let points = storage.spendable_points(user_id, Local::now().naive_local())?;
+    Ok(PointsHistory {
+        points,
+        entries: storage.points_history(user_id)?,
+    })
+}
+
+pub fn check_balance_consistency(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<BalanceMismatch>, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    storage.balance_mismatches()
+}
+
+pub fn get_all_lost_items(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<LostItem>, CafeHubError> {
+    session_store.authorize(&token, Access::AnyUser)?;
+
+    storage.list_lost_items()
+}
+
+pub fn report_lost_item(
+    token: String,
+    data: ReportLostItemData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    let pick_user_id = session_store
+        .authorize(&token, Access::CustomerOnly)?
+        .user_id;
+
+    if data.item_name.is_empty() {
+        return Err(CafeHubError::validation("Item name cannot be empty"));
+    }
+
+    match storage.create_lost_item(&data, pick_user_id, Local::now().date_naive()) {
+        Ok(_) => Ok(format!(
+            "Lost item '{}' reported successfully.",
+            data.item_name
+        )),
+        Err(e) => {
+            eprintln!(
+                "Database insert failed for lost item {}: {}",
+                data.item_name, e
+            );
+            Err(e)
+        }
+    }
+}
+
+pub fn claim_lost_item(
+    token: String,
+    data: ClaimLostItemData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    let claim_user_id = session_store
+        .authorize(&token, Access::CustomerOnly)?
+        .user_id;
+
+    storage.claim_lost_item(data.item_id, claim_user_id, Local::now().date_naive())?;
+
+    Ok(format!("Item ID {} claimed successfully.", data.item_id))
+}
+
+pub fn admin_send_message(
+    token: String,
+    data: SendMessageData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    let sender_id = session_store.authorize(&token, Access::StaffOnly)?.user_id;
+
+    if data.message_content.is_empty() {
+        return Err(CafeHubError::validation("Message content cannot be empty"));
+    }
+    if sender_id == data.receiver_id {
+        return Err(CafeHubError::validation(
+            "Sender and receiver cannot be the same user",
+        ));
+    }
+
+    if storage.find_user_type(sender_id)?.is_none() {
+        println!(
+            "Send message failed: Sender with ID {} not found.",
+            sender_id
+        );
+        return Err(CafeHubError::NotFound {
+            entity: "Sender",
+            id: sender_id,
+        });
+    }
+
+    if storage.find_user_type(data.receiver_id)?.is_none() {
+        println!(
+            "Send message failed: Receiver with ID {} not found.",
+            data.receiver_id
+        );
+        return Err(CafeHubError::NotFound {
+            entity: "Receiver",
+            id: data.receiver_id,
+        });
+    }
+
+    storage
+        .create_message(&NewMessage {
+            sender_id,
+            receiver_id: data.receiver_id,
+            title: data.title,
+            message_content: data.message_content,
+            send_date: Local::now().date_naive(),
+        })
+        .map_err(|e| {
+            eprintln!("Database insert failed for message: {}", e);
+            e
+        })?;
+
+    Ok("Message sent successfully.".to_string())
+}
+
+pub fn customer_send_message(
+    token: String,
+    data: CusSendMessageData,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    let sender_id = session_store
+        .authorize(&token, Access::CustomerOnly)?
+        .user_id;
+
+    if data.message_content.is_empty() {
+        return Err(CafeHubError::validation("Message content cannot be empty"));
+    }
+
+    let admin_id = match storage.find_first_staff_id()? {
+        Some(id) => id,
+        None => {
+            println!("Send message failed: Administrator account (user_type = 0) not found.");
+            return Err(CafeHubError::NoAdministrator);
+        }
+    };
+
+    match storage.find_user_type(sender_id)? {
+        Some(1) => {}
+        Some(user_type) => {
+            println!(
+                "Send message failed: Sender ID {} is not a customer (user_type: {}).",
+                sender_id, user_type
+            );
+            return Err(CafeHubError::validation(format!(
+                "Sender ID {} is not a customer. Only customers can send messages to the administrator.",
+                sender_id
+            )));
+        }
+        None => {
+            println!(
+                "Send message failed: Sender (customer) with ID {} not found.",
+                sender_id
+            );
+            return Err(CafeHubError::NotFound {
+                entity: "Sender",
+                id: sender_id,
+            });
+        }
+    }
+
+    storage
+        .create_message(&NewMessage {
+            sender_id,
+            receiver_id: admin_id,
+            title: data.title,
+            message_content: data.message_content,
+            send_date: Local::now().date_naive(),
+        })
+        .map_err(|e| {
+            eprintln!("Database insert failed for message: {}", e);
+            e
+        })?;
+
+    Ok("Message sent successfully.".to_string())
+}
+
+pub fn get_sent_messages(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<MessageInfo>, CafeHubError> {
+    let user_id = session_store.authorize(&token, Access::AnyUser)?.user_id;
+
+    storage.list_sent_messages(user_id)
+}
+
+pub fn get_recived_messages(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<MessageInfo>, CafeHubError> {
+    let user_id = session_store.authorize(&token, Access::AnyUser)?.user_id;
+
+    storage.list_received_messages(user_id)
+}
+
+pub fn mark_message_as_read(
+    data: MarkReadData,
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<String, CafeHubError> {
+    let current_user_id = session_store.authorize(&token, Access::AnyUser)?.user_id;
+
+    let (receiver_id_db, read_status_db) =
+        storage
+            .find_message_receipt(data.message_id)?
+            .ok_or(CafeHubError::NotFound {
+                entity: "Message",
+                id: data.message_id,
+            })?;
+
+    if receiver_id_db != current_user_id {
+        println!(
+            "User ID {} attempted to mark message ID {} as read, but is not the receiver (receiver ID {}).",
+            current_user_id, data.message_id, receiver_id_db
+        );
+        return Err(CafeHubError::NotMessageReceiver {
+            message_id: data.message_id,
+        });
+    }
+
+    if read_status_db == 1 {
+        println!(
+            "Message ID {} was already read by user ID {}.",
+            data.message_id, current_user_id
+        );
+        return Ok(format!("Message ID {} was already read.", data.message_id));
+    }
+
+    if storage.mark_message_read(data.message_id, current_user_id)? {
+        Ok(format!("Message ID {} marked as read.", data.message_id))
+    } else {
+        eprintln!(
+            "Failed to mark message ID {} as read for user ID {}: 0 rows affected despite prior checks.",
+            data.message_id, current_user_id
+        );
+        Err(CafeHubError::conflict(format!(
+            "Failed to mark message ID {} as read. The message state might have changed concurrently or an unexpected issue occurred.",
+            data.message_id
+        )))
+    }
+}
+
+pub fn get_all_users(
+    token: String,
+    storage: &dyn Storage,
+    session_store: &SessionStore,
+) -> Result<Vec<UserBasicInfo>, CafeHubError> {
+    session_store.authorize(&token, Access::StaffOnly)?;
+
+    storage.list_users()
+}
@@ -0,0 +1,47 @@
+use crate::storage::{ImageSize, Storage};
+use tauri::http::{header, Response, StatusCode};
+
+// Goods images are served to the webview as `goods-image://localhost/<goods ID>/image.jpg`
+// and `.../thumbnail.jpg`. No session is needed: the pictures are part of the menu.
+pub const SCHEME: &str = "goods-image";
+
+// Origin the webview reaches the protocol under, as `convertFileSrc` would build it:
+// Windows and Android serve custom protocols from `http://<scheme>.localhost`.
+#[cfg(any(windows, target_os = "android"))]
+pub const BASE_URL: &str = "http://goods-image.localhost/";
+#[cfg(not(any(windows, target_os = "android")))]
+pub const BASE_URL: &str = "goods-image://localhost/";
+
+// `storage` is None until the first-run setup has created the database.
+pub fn respond(storage: Option<&dyn Storage>, path: &str) -> Response<Vec<u8>> {
+    let Some(storage) = storage else {
+        return empty_response(StatusCode::SERVICE_UNAVAILABLE);
+    };
+
+    let mut segments = path.trim_start_matches('/').split('/');
+    let goods_id = segments.next().and_then(|id| id.parse::<i32>().ok());
+    let size = segments.next().and_then(ImageSize::from_file_name);
+    let (Some(goods_id), Some(size), None) = (goods_id, size, segments.next()) else {
+        return empty_response(StatusCode::NOT_FOUND);
+    };
+
+    match storage.find_goods_image(goods_id, size) {
+        Ok(Some(bytes)) => Response::builder()
+            .header(header::CONTENT_TYPE, "image/jpeg")
+            // Every upload gets a new URL, so a cached copy is never stale.
+            .header(header::CACHE_CONTROL, "max-age=31536000, immutable")
+            .body(bytes)
+            .unwrap_or_else(|_| empty_response(StatusCode::INTERNAL_SERVER_ERROR)),
+        Ok(None) => empty_response(StatusCode::NOT_FOUND),
+        Err(e) => {
+            eprintln!("Failed to serve image of goods ID {}: {}", goods_id, e);
+            empty_response(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+fn empty_response(status: StatusCode) -> Response<Vec<u8>> {
+    let mut response = Response::new(Vec::new());
+    *response.status_mut() = status;
+    response
+}
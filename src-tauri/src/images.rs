@@ -0,0 +1,83 @@
+use crate::error::CafeHubError;
+use crate::storage::GoodsImage;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageError, ImageReader, Limits, Rgb, RgbImage};
+use std::io::Cursor;
+use uuid::Uuid;
+
+// Uploads are refused above this size, before any decoding.
+pub const MAX_UPLOAD_BYTES: usize = 10 * 1024 * 1024;
+// Longest side in pixels of the stored image and of its thumbnail.
+const IMAGE_SIZE: u32 = 1024;
+const THUMBNAIL_SIZE: u32 = 256;
+const JPEG_QUALITY: u8 = 85;
+// A small, highly compressed file can still describe a huge bitmap, so decoding is
+// refused past these dimensions and memory use.
+const MAX_DECODED_SIDE: u32 = 8192;
+const MAX_DECODE_ALLOC: u64 = 256 * 1024 * 1024;
+
+// Decodes an uploaded JPEG, PNG or WebP picture and stores it as JPEG in both sizes.
+// Pictures are scaled down to fit but never scaled up.
+pub fn encode_goods_image(bytes: &[u8]) -> Result<GoodsImage, CafeHubError> {
+    if bytes.len() > MAX_UPLOAD_BYTES {
+        return Err(CafeHubError::validation(format!(
+            "Images cannot be larger than {} MB",
+            MAX_UPLOAD_BYTES / (1024 * 1024)
+        )));
+    }
+    let picture = decode(bytes)?;
+
+    // The thumbnail is scaled from the already reduced image, not from the upload.
+    let image = fit(&picture, IMAGE_SIZE);
+    let thumbnail = fit(&image, THUMBNAIL_SIZE);
+    Ok(GoodsImage {
+        image_key: Uuid::new_v4().simple().to_string(),
+        image: encode_jpeg(&image)?,
+        thumbnail: encode_jpeg(&thumbnail)?,
+    })
+}
+
+fn decode(bytes: &[u8]) -> Result<DynamicImage, CafeHubError> {
+    let not_an_image = || CafeHubError::validation("The file is not a JPEG, PNG or WebP image");
+    let mut reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|_| not_an_image())?;
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DECODED_SIDE);
+    limits.max_image_height = Some(MAX_DECODED_SIDE);
+    limits.max_alloc = Some(MAX_DECODE_ALLOC);
+    reader.limits(limits);
+
+    reader.decode().map_err(|e| match e {
+        ImageError::Limits(_) => CafeHubError::validation(format!(
+            "Images cannot be wider or taller than {} pixels",
+            MAX_DECODED_SIDE
+        )),
+        _ => not_an_image(),
+    })
+}
+
+fn fit(picture: &DynamicImage, max_side: u32) -> DynamicImage {
+    if picture.width() <= max_side && picture.height() <= max_side {
+        picture.clone()
+    } else {
+        picture.resize(max_side, max_side, FilterType::Lanczos3)
+    }
+}
+
+fn encode_jpeg(picture: &DynamicImage) -> Result<Vec<u8>, CafeHubError> {
+    // JPEG has no transparency, so transparent areas are laid over white.
+    let rgba = picture.to_rgba8();
+    let flattened = RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let over_white = |c: u8| ((c as u16 * a as u16 + 255 * (255 - a as u16)) / 255) as u8;
+        Rgb([over_white(r), over_white(g), over_white(b)])
+    });
+
+    let mut bytes = Vec::new();
+    JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY)
+        .encode_image(&flattened)
+        .map_err(|e| CafeHubError::internal(format!("Failed to encode image: {}", e)))?;
+    Ok(bytes)
+}
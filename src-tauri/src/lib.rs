@@ -1,42 +1,129 @@
+pub mod auth;
 mod commands;
 mod db_config;
-mod models;
+pub mod error;
+pub mod handlers;
+pub mod image_protocol;
+mod images;
+mod migrations;
+pub mod models;
+pub mod session;
+mod setup;
+pub mod storage;
 
-use db_config::MySQLConfig;
-use mysql::{Opts, OptsBuilder, Pool};
+use db_config::DatabaseConfig;
+use session::SessionStore;
+use storage::Storage;
+use tauri::Manager;
+
+fn load_database_config(app: &tauri::App) -> Result<DatabaseConfig, String> {
+    let config_dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to resolve app config directory: {}", e))?;
+    DatabaseConfig::load(&config_dir)
+}
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let mysql_config: MySQLConfig = MySQLConfig::new(
-        "root".to_string(),
-        "123456".to_string(),
-        "localhost".to_string(),
-        "cafehub".to_string(),
-    );
-
-    let mysql_url = mysql_config.format_url();
-    let pool_options =
-        OptsBuilder::from_opts(Opts::from_url(&mysql_url).expect("Invalid database URL"));
-    let pool = Pool::new(pool_options).expect("Failed to create DB pool.");
-
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .manage(pool)
+        .setup(|app| {
+            let db_config = load_database_config(app).map_err(|e| {
+                eprintln!("Database setup failed: {}", e);
+                e
+            })?;
+            let data_dir = app
+                .path()
+                .app_data_dir()
+                .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+            match storage::open(&db_config, &data_dir).map_err(|e| {
+                eprintln!("Database setup failed: {}", e);
+                e
+            })? {
+                Some(storage) => {
+                    app.manage(storage);
+                }
+                // Leave storage unmanaged so the first-run setup commands can create the database.
+                None => {
+                    println!(
+                        "Database '{}' does not exist yet, waiting for first-run setup.",
+                        db_config.database()
+                    );
+                }
+            }
+            app.manage(db_config);
+            Ok(())
+        })
+        .manage(SessionStore::default())
+        .register_uri_scheme_protocol(image_protocol::SCHEME, |ctx, request| {
+            let storage = ctx.app_handle().try_state::<Box<dyn Storage>>();
+            image_protocol::respond(
+                storage.as_ref().map(|storage| storage.inner().as_ref()),
+                request.uri().path(),
+            )
+        })
         .invoke_handler(tauri::generate_handler![
+            setup::get_setup_status,
+            setup::initialize_database,
             commands::login,
+            commands::logout,
             commands::register_user,
             commands::get_total_users,
             commands::get_new_users_this_month,
             commands::get_monthly_consumption_summary,
             commands::get_goods_consumption_share_current_month,
+            commands::get_goods_margins,
+            commands::get_category_margins,
+            commands::get_monthly_margins,
             commands::get_user_details,
             commands::get_user_monthly_consumption,
             commands::update_user_details,
             commands::update_user_password,
             commands::get_all_goods,
+            commands::search_goods,
+            commands::get_goods_by_category,
             commands::add_goods,
             commands::update_goods_info,
+            commands::archive_goods,
+            commands::restore_goods,
+            commands::delete_goods,
+            commands::upload_goods_image,
+            commands::remove_goods_image,
+            commands::get_categories,
+            commands::add_category,
+            commands::update_category,
+            commands::delete_category,
+            commands::get_promotions,
+            commands::add_promotion,
+            commands::update_promotion,
+            commands::delete_promotion,
+            commands::get_coupons,
+            commands::add_coupon,
+            commands::disable_coupon,
+            commands::adjust_goods_stock,
+            commands::get_stock_movements,
+            commands::get_goods_cost_history,
+            commands::get_low_stock_goods,
+            commands::get_goods_modifiers,
+            commands::add_modifier_group,
+            commands::delete_modifier_group,
+            commands::get_all_ingredients,
+            commands::add_ingredient,
+            commands::update_ingredient,
+            commands::get_goods_recipe,
+            commands::set_goods_recipe,
+            commands::get_suppliers,
+            commands::add_supplier,
+            commands::create_purchase_order,
+            commands::get_purchase_orders,
+            commands::get_purchase_order_details,
+            commands::receive_purchase_order,
+            commands::cancel_purchase_order,
             commands::purchase_goods,
+            commands::get_order_history,
+            commands::get_order_details,
+            commands::refund_order,
             commands::get_all_lost_items,
             commands::report_lost_item,
             commands::claim_lost_item,
@@ -46,7 +133,11 @@ pub fn run() {
             commands::get_recived_messages,
             commands::mark_message_as_read,
             commands::get_all_users,
-            commands::recharge_balance
+            commands::recharge_balance,
+            commands::adjust_balance,
+            commands::get_balance_statement,
+            commands::get_points_history,
+            commands::check_balance_consistency
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
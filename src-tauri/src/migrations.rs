@@ -0,0 +1,613 @@
+use mysql::{params, prelude::Queryable, Pool, PooledConn};
+use rusqlite::Connection;
+use std::iter::Peekable;
+use std::str::Chars;
+
+// Each migration has a MySQL and an equivalent SQLite script sharing the same version.
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub mysql: &'static str,
+    pub sqlite: &'static str,
+}
+
+// Ordered by version. Never edit a migration that has shipped; add a new one instead.
+// Version 1 is the original hand-imported schema, so existing installs can be baselined.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        mysql: include_str!("../../database/cafehub.sql"),
+        sqlite: include_str!("../../database/sqlite/0001_initial_schema.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "orders",
+        mysql: include_str!("../../database/migrations/0002_orders.sql"),
+        sqlite: include_str!("../../database/sqlite/0002_orders.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "refunds",
+        mysql: include_str!("../../database/migrations/0003_refunds.sql"),
+        sqlite: include_str!("../../database/sqlite/0003_refunds.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "balance_ledger",
+        mysql: include_str!("../../database/migrations/0004_balance_ledger.sql"),
+        sqlite: include_str!("../../database/sqlite/0004_balance_ledger.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "modifiers",
+        mysql: include_str!("../../database/migrations/0005_modifiers.sql"),
+        sqlite: include_str!("../../database/sqlite/0005_modifiers.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "ingredients",
+        mysql: include_str!("../../database/migrations/0006_ingredients.sql"),
+        sqlite: include_str!("../../database/sqlite/0006_ingredients.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "low_stock",
+        mysql: include_str!("../../database/migrations/0007_low_stock.sql"),
+        sqlite: include_str!("../../database/sqlite/0007_low_stock.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "stock_movements",
+        mysql: include_str!("../../database/migrations/0008_stock_movements.sql"),
+        sqlite: include_str!("../../database/sqlite/0008_stock_movements.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "purchasing",
+        mysql: include_str!("../../database/migrations/0009_purchasing.sql"),
+        sqlite: include_str!("../../database/sqlite/0009_purchasing.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "cost_price",
+        mysql: include_str!("../../database/migrations/0010_cost_price.sql"),
+        sqlite: include_str!("../../database/sqlite/0010_cost_price.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "goods_archive",
+        mysql: include_str!("../../database/migrations/0011_goods_archive.sql"),
+        sqlite: include_str!("../../database/sqlite/0011_goods_archive.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "goods_details",
+        mysql: include_str!("../../database/migrations/0012_goods_details.sql"),
+        sqlite: include_str!("../../database/sqlite/0012_goods_details.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "categories",
+        mysql: include_str!("../../database/migrations/0013_categories.sql"),
+        sqlite: include_str!("../../database/sqlite/0013_categories.sql"),
+    },
+    Migration {
+        version: 14,
+        name: "goods_images",
+        mysql: include_str!("../../database/migrations/0014_goods_images.sql"),
+        sqlite: include_str!("../../database/sqlite/0014_goods_images.sql"),
+    },
+    Migration {
+        version: 15,
+        name: "promotions",
+        mysql: include_str!("../../database/migrations/0015_promotions.sql"),
+        sqlite: include_str!("../../database/sqlite/0015_promotions.sql"),
+    },
+    Migration {
+        version: 16,
+        name: "coupons",
+        mysql: include_str!("../../database/migrations/0016_coupons.sql"),
+        sqlite: include_str!("../../database/sqlite/0016_coupons.sql"),
+    },
+    Migration {
+        version: 17,
+        name: "loyalty_points",
+        mysql: include_str!("../../database/migrations/0017_loyalty_points.sql"),
+        sqlite: include_str!("../../database/sqlite/0017_loyalty_points.sql"),
+    },
+    Migration {
+        version: 18,
+        name: "order_item_points",
+        mysql: include_str!("../../database/migrations/0018_order_item_points.sql"),
+        sqlite: include_str!("../../database/sqlite/0018_order_item_points.sql"),
+    },
+    Migration {
+        version: 19,
+        name: "coupon_redemption_refunds",
+        mysql: include_str!("../../database/migrations/0019_coupon_redemption_refunds.sql"),
+        sqlite: include_str!("../../database/sqlite/0019_coupon_redemption_refunds.sql"),
+    },
+];
+
+const MIGRATION_LOCK_NAME: &str = "cafehub_schema_migrations";
+const MIGRATION_LOCK_TIMEOUT_SECS: u32 = 30;
+
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+// Splits a migration into individual statements on `;`, dropping `--` and `#` comments.
+// Quoted strings, quoted identifiers and `/* */` comments are kept intact, so a `;` or `--`
+// inside them does not end the statement.
+fn split_statements(sql: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut chars = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                current.push(c);
+                while let Some(inner) = chars.next() {
+                    current.push(inner);
+                    if inner == '\\' && c != '`' {
+                        if let Some(escaped) = chars.next() {
+                            current.push(escaped);
+                        }
+                    } else if inner == c {
+                        // A doubled quote reopens the literal on the next pass.
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                current.push_str("/*");
+                let mut previous = '\0';
+                for inner in chars.by_ref() {
+                    current.push(inner);
+                    if previous == '*' && inner == '/' {
+                        break;
+                    }
+                    previous = inner;
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                chars.next();
+                match chars.peek() {
+                    // MySQL only treats `--` as a comment when whitespace follows it.
+                    None | Some(' ' | '\t' | '\r' | '\n') => skip_line(&mut chars, &mut current),
+                    Some(_) => current.push_str("--"),
+                }
+            }
+            '#' => skip_line(&mut chars, &mut current),
+            ';' => push_statement(&mut statements, &mut current),
+            _ => current.push(c),
+        }
+    }
+    push_statement(&mut statements, &mut current);
+    statements
+}
+
+// Drops the rest of a comment line, keeping the line break so the words around it stay apart.
+fn skip_line(chars: &mut Peekable<Chars>, current: &mut String) {
+    for c in chars.by_ref() {
+        if c == '\n' {
+            break;
+        }
+    }
+    current.push('\n');
+}
+
+fn push_statement(statements: &mut Vec<String>, current: &mut String) {
+    let statement = current.trim();
+    if !statement.is_empty() {
+        statements.push(statement.to_string());
+    }
+    current.clear();
+}
+
+fn table_exists(conn: &mut PooledConn, table_name: &str) -> Result<bool, String> {
+    let found: Option<String> = conn
+        .exec_first(
+            "SELECT TABLE_NAME FROM information_schema.TABLES WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = :table_name",
+            params! { "table_name" => table_name },
+        )
+        .map_err(|e| format!("Failed to inspect table {}: {}", table_name, e))?;
+    Ok(found.is_some())
+}
+
+fn record_version(conn: &mut PooledConn, migration: &Migration) -> Result<(), String> {
+    conn.exec_drop(
+        "INSERT INTO schema_migrations (version, name, applied_at) VALUES (:version, :name, NOW())",
+        params! {
+            "version" => migration.version,
+            "name" => migration.name,
+        },
+    )
+    .map_err(|e| {
+        format!(
+            "Failed to record migration {} ({}): {}",
+            migration.version, migration.name, e
+        )
+    })
+}
+
+// MySQL commits DDL as it goes, so a script that fails part way cannot be rolled back.
+// The number of statements run so far is kept per version, and a later start resumes from there.
+fn create_progress_table(conn: &mut PooledConn) -> Result<(), String> {
+    conn.query_drop(
+        "CREATE TABLE IF NOT EXISTS schema_migration_progress (
+            version INT PRIMARY KEY,
+            statements_applied INT NOT NULL
+        )",
+    )
+    .map_err(|e| format!("Failed to create schema_migration_progress table: {}", e))
+}
+
+fn statements_applied(conn: &mut PooledConn, migration: &Migration) -> Result<usize, String> {
+    let applied: Option<u32> = conn
+        .exec_first(
+            "SELECT statements_applied FROM schema_migration_progress WHERE version = :version",
+            params! { "version" => migration.version },
+        )
+        .map_err(|e| {
+            format!(
+                "Failed to read progress of migration {} ({}): {}",
+                migration.version, migration.name, e
+            )
+        })?;
+    Ok(applied.unwrap_or(0) as usize)
+}
+
+fn record_progress(
+    conn: &mut PooledConn,
+    migration: &Migration,
+    applied: usize,
+) -> Result<(), String> {
+    conn.exec_drop(
+        "INSERT INTO schema_migration_progress (version, statements_applied) VALUES (:version, :applied)
+         ON DUPLICATE KEY UPDATE statements_applied = VALUES(statements_applied)",
+        params! {
+            "version" => migration.version,
+            "applied" => applied,
+        },
+    )
+    .map_err(|e| {
+        format!(
+            "Failed to record progress of migration {} ({}): {}",
+            migration.version, migration.name, e
+        )
+    })
+}
+
+fn clear_progress(conn: &mut PooledConn, migration: &Migration) -> Result<(), String> {
+    conn.exec_drop(
+        "DELETE FROM schema_migration_progress WHERE version = :version",
+        params! { "version" => migration.version },
+    )
+    .map_err(|e| {
+        format!(
+            "Failed to clear progress of migration {} ({}): {}",
+            migration.version, migration.name, e
+        )
+    })
+}
+
+// Runs the statements after the first `already_applied`, passing each one to `apply` along
+// with the count applied once it succeeds. Stops at the first statement that fails.
+fn apply_remaining(
+    statements: &[String],
+    already_applied: usize,
+    mut apply: impl FnMut(&str, usize) -> Result<(), String>,
+) -> Result<(), String> {
+    for (index, statement) in statements.iter().enumerate().skip(already_applied) {
+        apply(statement, index + 1)?;
+    }
+    Ok(())
+}
+
+fn migrate(conn: &mut PooledConn) -> Result<u32, String> {
+    if !table_exists(conn, "schema_migrations")? {
+        let has_legacy_schema = table_exists(conn, "account")?;
+        conn.query_drop(
+            "CREATE TABLE schema_migrations (
+                version INT PRIMARY KEY,
+                name VARCHAR(100) NOT NULL,
+                applied_at DATETIME NOT NULL
+            )",
+        )
+        .map_err(|e| format!("Failed to create schema_migrations table: {}", e))?;
+
+        // Databases set up from cafehub.sql before migrations existed already have version 1.
+        if has_legacy_schema {
+            println!("Existing schema found without migration history, baselining at version 1.");
+            record_version(conn, &MIGRATIONS[0])?;
+        }
+    }
+
+    create_progress_table(conn)?;
+
+    let current_version: u32 = conn
+        .query_first("SELECT COALESCE(MAX(version), 0) FROM schema_migrations")
+        .map_err(|e| format!("Failed to read schema version: {}", e))?
+        .unwrap_or(0);
+
+    if current_version > latest_version() {
+        return Err(format!(
+            "Database schema version {} is newer than this application supports ({}). Please upgrade CafeHub.",
+            current_version,
+            latest_version()
+        ));
+    }
+
+    let mut applied = 0;
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current_version) {
+        let statements = split_statements(migration.mysql);
+        let already_applied = statements_applied(conn, migration)?;
+        if already_applied > 0 {
+            println!(
+                "Resuming migration {} ({}) after statement {} of {}...",
+                migration.version,
+                migration.name,
+                already_applied,
+                statements.len()
+            );
+        } else {
+            println!(
+                "Applying migration {} ({})...",
+                migration.version, migration.name
+            );
+        }
+        apply_remaining(&statements, already_applied, |statement, applied| {
+            conn.query_drop(statement).map_err(|e| {
+                format!(
+                    "Migration {} ({}) failed at statement {} of {}: {}",
+                    migration.version,
+                    migration.name,
+                    applied,
+                    statements.len(),
+                    e
+                )
+            })?;
+            record_progress(conn, migration, applied)
+        })?;
+        record_version(conn, migration)?;
+        clear_progress(conn, migration)?;
+        applied += 1;
+    }
+    Ok(applied)
+}
+
+// Brings a MySQL database up to `latest_version()`. Must run before the pool is handed to Tauri.
+pub fn run_pending_mysql(pool: &Pool) -> Result<u32, String> {
+    let mut conn = pool
+        .get_conn()
+        .map_err(|e| format!("Failed to get DB connection: {}", e))?;
+
+    // Serializes concurrent app instances starting against the same database.
+    let locked: Option<Option<i32>> = conn
+        .exec_first(
+            "SELECT GET_LOCK(:lock_name, :timeout)",
+            params! {
+                "lock_name" => MIGRATION_LOCK_NAME,
+                "timeout" => MIGRATION_LOCK_TIMEOUT_SECS,
+            },
+        )
+        .map_err(|e| format!("Failed to acquire migration lock: {}", e))?;
+    if locked.flatten() != Some(1) {
+        return Err(
+            "Timed out waiting for another instance to finish migrating the database".to_string(),
+        );
+    }
+
+    let result = migrate(&mut conn);
+
+    if let Err(e) = conn.exec_drop(
+        "SELECT RELEASE_LOCK(:lock_name)",
+        params! { "lock_name" => MIGRATION_LOCK_NAME },
+    ) {
+        eprintln!("Failed to release migration lock: {}", e);
+    }
+
+    result
+}
+
+// SQLite equivalent of `run_pending_mysql`. DDL is transactional in SQLite, so each
+// migration is applied and recorded atomically.
+pub fn run_pending_sqlite(conn: &mut Connection) -> Result<u32, String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name VARCHAR(100) NOT NULL,
+            applied_at DATETIME NOT NULL
+        )",
+    )
+    .map_err(|e| format!("Failed to create schema_migrations table: {}", e))?;
+
+    let current_version: u32 = conn
+        .query_row(
+            "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to read schema version: {}", e))?;
+
+    if current_version > latest_version() {
+        return Err(format!(
+            "Database schema version {} is newer than this application supports ({}). Please upgrade CafeHub.",
+            current_version,
+            latest_version()
+        ));
+    }
+
+    let mut applied = 0;
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current_version) {
+        println!(
+            "Applying migration {} ({})...",
+            migration.version, migration.name
+        );
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start migration transaction: {}", e))?;
+        tx.execute_batch(migration.sqlite).map_err(|e| {
+            format!(
+                "Migration {} ({}) failed: {}",
+                migration.version, migration.name, e
+            )
+        })?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name, applied_at) VALUES (?1, ?2, datetime('now', 'localtime'))",
+            (migration.version, migration.name),
+        )
+        .map_err(|e| {
+            format!(
+                "Failed to record migration {} ({}): {}",
+                migration.version, migration.name, e
+            )
+        })?;
+        tx.commit().map_err(|e| {
+            format!(
+                "Failed to commit migration {} ({}): {}",
+                migration.version, migration.name, e
+            )
+        })?;
+        applied += 1;
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_on_semicolons_outside_quotes() {
+        assert_eq!(
+            split_statements(
+                "INSERT INTO t VALUES ('a;b', \"c;d\");\nSELECT `odd;name` FROM t;\n\nSELECT 1"
+            ),
+            vec![
+                "INSERT INTO t VALUES ('a;b', \"c;d\")",
+                "SELECT `odd;name` FROM t",
+                "SELECT 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_doubled_quotes_and_escapes_inside_literals() {
+        assert_eq!(
+            split_statements("SELECT 'it''s; fine', \"say \"\"hi;\"\"\"; SELECT 2"),
+            vec!["SELECT 'it''s; fine', \"say \"\"hi;\"\"\"", "SELECT 2"]
+        );
+        assert_eq!(
+            split_statements(r"SELECT 'a\';b', 'c\\'; SELECT 2"),
+            vec![r"SELECT 'a\';b', 'c\\'", "SELECT 2"]
+        );
+        // Backslashes do not escape inside backtick identifiers.
+        assert_eq!(
+            split_statements(r"SELECT `a\`; SELECT 2"),
+            vec![r"SELECT `a\`", "SELECT 2"]
+        );
+    }
+
+    #[test]
+    fn drops_double_dash_comments_followed_by_whitespace() {
+        assert_eq!(
+            split_statements(
+                "-- header; not a statement\nSELECT 1; -- trailing; note\nSELECT 2;\n--"
+            ),
+            vec!["SELECT 1", "SELECT 2"]
+        );
+        assert_eq!(
+            split_statements("SELECT 1\t--\tcomment\nFROM dual"),
+            vec!["SELECT 1\t\nFROM dual"]
+        );
+        // Without whitespace after it, `--` is two minus signs.
+        assert_eq!(
+            split_statements("SELECT 5--1; SELECT 2"),
+            vec!["SELECT 5--1", "SELECT 2"]
+        );
+    }
+
+    #[test]
+    fn drops_hash_comments() {
+        assert_eq!(
+            split_statements("# header; comment\nSELECT 1; # note; here\nSELECT '#1'"),
+            vec!["SELECT 1", "SELECT '#1'"]
+        );
+    }
+
+    #[test]
+    fn keeps_block_comments_intact() {
+        assert_eq!(
+            split_statements(
+                "/*!40101 SET NAMES utf8mb4; */;\nSELECT /* a; -- b */ 1; SELECT 2 /* unterminated;"
+            ),
+            vec![
+                "/*!40101 SET NAMES utf8mb4; */",
+                "SELECT /* a; -- b */ 1",
+                "SELECT 2 /* unterminated;",
+            ]
+        );
+    }
+
+    #[test]
+    fn every_migration_has_statements() {
+        for migration in MIGRATIONS {
+            let statements = split_statements(migration.mysql);
+            assert!(!statements.is_empty(), "{}", migration.name);
+            assert!(
+                statements
+                    .iter()
+                    .all(|s| !s.starts_with("--") && !s.starts_with('#')),
+                "{}",
+                migration.name
+            );
+        }
+    }
+
+    fn statements(count: usize) -> Vec<String> {
+        (1..=count).map(|n| format!("SELECT {}", n)).collect()
+    }
+
+    #[test]
+    fn resumes_after_the_recorded_statements() {
+        let mut applied = Vec::new();
+        apply_remaining(&statements(4), 2, |statement, count| {
+            applied.push((statement.to_string(), count));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(
+            applied,
+            vec![("SELECT 3".to_string(), 3), ("SELECT 4".to_string(), 4)]
+        );
+
+        let mut called = false;
+        apply_remaining(&statements(4), 4, |_, _| {
+            called = true;
+            Ok(())
+        })
+        .unwrap();
+        assert!(!called);
+    }
+
+    #[test]
+    fn stops_at_the_first_failing_statement() {
+        let mut recorded = 0;
+        let error = apply_remaining(&statements(4), 1, |statement, count| {
+            if statement == "SELECT 3" {
+                return Err(format!("statement {} failed", count));
+            }
+            recorded = count;
+            Ok(())
+        })
+        .unwrap_err();
+        assert_eq!(error, "statement 3 failed");
+        // A later start resumes at the failed statement.
+        assert_eq!(recorded, 2);
+    }
+}
@@ -1,6 +1,7 @@
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Account {
@@ -10,9 +11,16 @@ pub struct Account {
     pub gender: Option<i8>,
     pub join_time: Option<NaiveDate>,
     pub balance: Option<Decimal>,
+    pub points: i64, // Loyalty points
     pub user_type: i8,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LoginResponse {
+    pub token: String,
+    pub account: Account,
+}
+
 #[derive(Deserialize)]
 pub struct RegistrationData {
     pub username: String,
@@ -33,6 +41,18 @@ pub struct GoodsConsumptionShare {
     pub amount: Decimal,
 }
 
+// Sales net of refunds. Units sold before the goods had a cost price add to
+// revenue but not to cost, so they are counted separately.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MarginSummary {
+    pub label: String, // Goods name, category or "YYYY-MM"
+    pub quantity_sold: i64,
+    pub revenue: Decimal,
+    pub cost: Decimal,
+    pub gross_margin: Decimal,
+    pub uncosted_quantity: i64,
+}
+
 #[derive(Deserialize)]
 pub struct UpdateUserData {
     pub username: Option<String>,
@@ -50,23 +70,404 @@ pub struct UpdatePasswordData {
 pub struct Goods {
     pub id: i32,
     pub goods_name: String,
-    pub goods_type: Option<String>,
+    pub category_id: Option<i32>,
+    pub category: Option<String>, // Display name of the category
+    pub description: Option<String>,
     pub price: Decimal,
-    pub stock: Option<i32>,
+    pub available: bool, // false while temporarily sold out, whatever the stock
+    pub stock: Option<i32>, // For goods with a recipe, how many the ingredients allow
+    pub reorder_threshold: Option<i32>, // None: no low-stock alerts
+    pub cost_price: Option<Decimal>, // Only shown to staff
+    pub points_price: Option<i32>, // None: cannot be redeemed with points
+    pub archived_at: Option<NaiveDateTime>, // Archived goods are off sale and hidden from customers
+    // URLs under the `goods-image` protocol, usable directly as an `<img>` source
+    pub image: Option<String>,
+    pub thumbnail: Option<String>,
 }
 
 #[derive(Deserialize)]
 pub struct AddGoodsData {
     pub goods_name: String,
-    pub goods_type: Option<String>,
+    pub category_id: Option<i32>,
+    pub description: Option<String>,
     pub price: Decimal,
     pub stock: Option<i32>,
+    pub reorder_threshold: Option<i32>,
+    pub cost_price: Option<Decimal>,
+    pub points_price: Option<i32>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GoodsSort {
+    #[default]
+    Name,
+    PriceAsc,
+    PriceDesc,
+    Newest,
+    Category, // Menu order of the categories
+}
+
+// Every condition is optional; the search text matches part of the name.
+#[derive(Deserialize, Default)]
+pub struct GoodsQuery {
+    pub search: Option<String>,
+    pub category_id: Option<i32>,
+    pub min_price: Option<Decimal>,
+    pub max_price: Option<Decimal>,
+    #[serde(default)]
+    pub in_stock_only: bool, // Also leaves out goods marked unavailable
+    #[serde(default)]
+    pub include_archived: bool, // Ignored for customers
+    #[serde(default)]
+    pub sort: GoodsSort,
+    pub page: Option<u32>,  // Starts at 1
+    pub limit: Option<u32>, // Goods per page
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GoodsPage {
+    pub items: Vec<Goods>,
+    pub total: i64, // Matching goods across all pages
+    pub page: u32,
+    pub limit: u32,
+}
+
+// Stock is changed through `AdjustStockData` so that every change is recorded, and a
+// payload that still sends `stock` here is rejected rather than silently ignored.
+// An empty `description` clears it, and so does a `points_price` of 0.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
 pub struct UpdateGoodsData {
-    pub stock: Option<i32>,
+    pub goods_name: Option<String>,
+    pub category_id: Option<i32>,
+    #[serde(default)]
+    pub clear_category: bool, // Leaves the goods uncategorized
+    pub description: Option<String>,
     pub price: Option<Decimal>,
+    pub available: Option<bool>,
+    pub reorder_threshold: Option<i32>,
+    pub cost_price: Option<Decimal>,
+    pub points_price: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Category {
+    pub id: i32,
+    pub category_name: String,
+    pub display_name: String,
+    pub sort_order: i32, // Lower comes first
+}
+
+#[derive(Deserialize)]
+pub struct AddCategoryData {
+    pub category_name: String,
+    pub display_name: Option<String>, // Defaults to the category name
+    pub sort_order: Option<i32>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct UpdateCategoryData {
+    pub category_name: Option<String>,
+    pub display_name: Option<String>,
+    pub sort_order: Option<i32>,
+}
+
+// One section of the menu. Uncategorized goods come last, with no category.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CategoryGoods {
+    pub category: Option<Category>,
+    pub goods: Vec<Goods>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscountType {
+    Percent, // Percentage off the unit price
+    Fixed,   // Amount off each unit, never below zero
+}
+
+impl DiscountType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiscountType::Percent => "percent",
+            DiscountType::Fixed => "fixed",
+        }
+    }
+}
+
+impl FromStr for DiscountType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "percent" => Ok(DiscountType::Percent),
+            "fixed" => Ok(DiscountType::Fixed),
+            _ => Err(format!("Unknown discount type '{}'", s)),
+        }
+    }
+}
+
+// A price rule applied at checkout. It targets either one goods or a whole category,
+// and is limited to the listed weekdays, a daily time window and a date range.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Promotion {
+    pub id: i32,
+    pub promotion_name: String,
+    pub discount_type: DiscountType,
+    pub discount_value: Decimal,
+    pub goods_id: Option<i32>,
+    pub category_id: Option<i32>,
+    pub weekdays: Vec<u32>, // 1 is Monday, 7 is Sunday
+    pub start_time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>, // Exclusive
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>, // Inclusive
+    pub paused: bool,
+}
+
+// Used both to create a promotion and to replace all of its settings.
+#[derive(Deserialize, Clone)]
+pub struct PromotionData {
+    pub promotion_name: String,
+    pub discount_type: DiscountType,
+    pub discount_value: Decimal,
+    pub goods_id: Option<i32>,
+    pub category_id: Option<i32>,
+    #[serde(default)]
+    pub weekdays: Vec<u32>, // Empty for every day
+    pub start_time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub paused: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Coupon {
+    pub id: i32,
+    pub code: String,
+    pub discount_type: DiscountType,
+    pub discount_value: Decimal,
+    pub min_spend: Option<Decimal>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub max_redemptions: Option<i32>,
+    pub max_per_customer: Option<i32>,
+    pub times_redeemed: i64,
+    pub disabled: bool,
+    pub created_at: NaiveDateTime,
+}
+
+// A coupon takes its discount off the order total, after any promotions.
+#[derive(Deserialize, Clone)]
+pub struct AddCouponData {
+    pub code: String,
+    pub discount_type: DiscountType,
+    pub discount_value: Decimal,
+    pub min_spend: Option<Decimal>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub max_redemptions: Option<i32>,  // None for no limit
+    pub max_per_customer: Option<i32>, // None for no limit
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum StockMovementReason {
+    Sale,
+    Restock,
+    Waste,
+    Correction,
+    Refund,
+}
+
+impl StockMovementReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StockMovementReason::Sale => "sale",
+            StockMovementReason::Restock => "restock",
+            StockMovementReason::Waste => "waste",
+            StockMovementReason::Correction => "correction",
+            StockMovementReason::Refund => "refund",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AdjustStockData {
+    pub delta: i32, // Negative to take stock out
+    pub reason: StockMovementReason,
+    pub note: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct StockMovement {
+    pub id: i64,
+    pub reason: String,
+    pub delta: i32,
+    pub stock_after: i32,
+    pub reference: Option<String>,
+    pub operator_name: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GoodsCostChange {
+    pub id: i64,
+    pub cost_price: Decimal,
+    pub reference: Option<String>,
+    pub operator_name: Option<String>,
+    pub changed_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ModifierOption {
+    pub id: i64,
+    pub option_name: String,
+    pub price_delta: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ModifierGroup {
+    pub id: i64,
+    pub goods_id: i32,
+    pub group_name: String,
+    pub is_required: bool,
+    pub allow_multiple: bool,
+    pub options: Vec<ModifierOption>,
+}
+
+#[derive(Deserialize)]
+pub struct AddModifierOptionData {
+    pub option_name: String,
+    pub price_delta: Decimal,
+}
+
+#[derive(Deserialize)]
+pub struct AddModifierGroupData {
+    pub goods_id: i32,
+    pub group_name: String,
+    pub is_required: bool,
+    pub allow_multiple: bool,
+    pub options: Vec<AddModifierOptionData>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Ingredient {
+    pub id: i64,
+    pub ingredient_name: String,
+    pub unit: String,
+    pub stock: i32,
+}
+
+#[derive(Deserialize)]
+pub struct AddIngredientData {
+    pub ingredient_name: String,
+    pub unit: String,
+    pub stock: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateIngredientData {
+    pub unit: Option<String>,
+    pub stock: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RecipeItem {
+    pub ingredient_id: i64,
+    pub ingredient_name: String,
+    pub unit: String,
+    pub quantity: i32, // Per unit of goods sold
+}
+
+#[derive(Deserialize, Clone)]
+pub struct RecipeItemData {
+    pub ingredient_id: i64,
+    pub quantity: i32,
+}
+
+#[derive(Deserialize)]
+pub struct SetRecipeData {
+    pub items: Vec<RecipeItemData>, // Empty removes the recipe
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Supplier {
+    pub id: i64,
+    pub supplier_name: String,
+    pub contact: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AddSupplierData {
+    pub supplier_name: String,
+    pub contact: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PurchaseOrderStatus {
+    Open,
+    Received,
+    Cancelled,
+}
+
+impl PurchaseOrderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PurchaseOrderStatus::Open => "open",
+            PurchaseOrderStatus::Received => "received",
+            PurchaseOrderStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct PurchaseOrderItemData {
+    pub goods_id: i32,
+    pub quantity: i32,
+    pub unit_cost: Decimal,
+}
+
+#[derive(Deserialize)]
+pub struct CreatePurchaseOrderData {
+    pub supplier_id: i64,
+    pub items: Vec<PurchaseOrderItemData>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PurchaseOrderSummary {
+    pub id: i64,
+    pub supplier_id: i64,
+    pub supplier_name: String,
+    pub status: String, // open, received, cancelled
+    pub total_cost: Decimal,
+    pub created_at: NaiveDateTime,
+    pub closed_at: Option<NaiveDateTime>, // When it was received or cancelled
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PurchaseOrderLine {
+    pub id: i64,
+    pub goods_id: i32,
+    pub goods_name: String,
+    pub quantity: i32,
+    pub unit_cost: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PurchaseOrderDetail {
+    pub id: i64,
+    pub supplier_id: i64,
+    pub supplier_name: String,
+    pub status: String,
+    pub total_cost: Decimal,
+    pub created_by: String,
+    pub created_at: NaiveDateTime,
+    pub closed_by: Option<String>,
+    pub closed_at: Option<NaiveDateTime>,
+    pub items: Vec<PurchaseOrderLine>,
 }
 
 #[derive(Deserialize)]
@@ -75,16 +476,164 @@ pub struct RechargeBalanceData {
     pub amount: Decimal,
 }
 
+#[derive(Deserialize)]
+pub struct AdjustBalanceData {
+    pub user_id: i64,
+    pub amount: Decimal, // Negative to debit
+    pub note: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerEntryType {
+    Recharge,
+    Purchase,
+    Refund,
+    Adjustment,
+}
+
+impl LedgerEntryType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LedgerEntryType::Recharge => "recharge",
+            LedgerEntryType::Purchase => "purchase",
+            LedgerEntryType::Refund => "refund",
+            LedgerEntryType::Adjustment => "adjustment",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BalanceLedgerEntry {
+    pub id: i64,
+    pub entry_type: String,
+    pub amount: Decimal,
+    pub balance_after: Decimal,
+    pub reference: Option<String>,
+    pub operator_name: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PointsEntryType {
+    Earned,
+    Redeemed,
+    Expired,
+    Reversed, // Taken back when the order they were earned on is refunded
+    Restored, // Given back, as a fresh batch, when goods paid for with points are refunded
+}
+
+impl PointsEntryType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PointsEntryType::Earned => "earned",
+            PointsEntryType::Redeemed => "redeemed",
+            PointsEntryType::Expired => "expired",
+            PointsEntryType::Reversed => "reversed",
+            PointsEntryType::Restored => "restored",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PointsLedgerEntry {
+    pub id: i64,
+    pub entry_type: String,
+    pub points: i64,
+    pub points_after: i64,
+    pub remaining: Option<i64>, // Earned points not yet spent or expired
+    pub expires_at: Option<NaiveDateTime>,
+    pub reference: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PointsHistory {
+    pub points: i64,
+    pub entries: Vec<PointsLedgerEntry>, // Newest first
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BalanceMismatch {
+    pub user_id: i64,
+    pub username: String,
+    pub balance: Decimal,
+    pub ledger_total: Decimal,
+}
+
 #[derive(Deserialize, Clone)]
 pub struct PurchaseItem {
     pub goods_id: i32,
     pub quantity: i32,
+    #[serde(default)]
+    pub modifier_ids: Vec<i64>, // Chosen modifier option IDs
+    #[serde(default)]
+    pub redeem_with_points: bool, // The whole line is paid for with the goods' points price
 }
 
 #[derive(Deserialize)]
 pub struct PurchaseGoodsData {
-    pub user_id: i64,
     pub items: Vec<PurchaseItem>,
+    pub coupon_code: Option<String>,
+    pub points_to_redeem: Option<i64>, // Spent as payment, after the coupon
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OrderSummary {
+    pub id: i64,
+    pub order_time: NaiveDateTime,
+    pub total_amount: Decimal,
+    pub item_count: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OrderItemInfo {
+    pub id: i64,
+    pub goods_id: i32,
+    pub goods_name: String,
+    pub quantity: i32,
+    pub unit_price: Decimal, // Includes the modifier price deltas
+    pub line_total: Decimal, // After the promotion discount
+    pub promotion_name: Option<String>,
+    pub discount: Decimal,        // Taken off the whole line
+    pub points_discount: Decimal, // Paid with points, already taken off line_total
+    pub refunded_quantity: i32,
+    pub modifiers: Vec<OrderItemModifier>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OrderItemModifier {
+    pub option_id: Option<i64>, // None once the option has been deleted
+    pub group_name: String,
+    pub option_name: String,
+    pub price_delta: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OrderDetail {
+    pub id: i64,
+    pub user_id: i64,
+    pub order_time: NaiveDateTime,
+    pub total_amount: Decimal,
+    pub coupon_code: Option<String>,
+    pub coupon_discount: Decimal, // Already taken off the totals
+    pub points_redeemed: i64,
+    pub points_earned: i64,
+    pub items: Vec<OrderItemInfo>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct RefundItem {
+    pub order_item_id: i64,
+    pub quantity: i32,
+}
+
+#[derive(Deserialize)]
+pub struct RefundOrderData {
+    pub order_id: i64,
+    pub reason: String,
+    pub items: Vec<RefundItem>, // Empty refunds everything still refundable
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -105,18 +654,15 @@ pub struct LostItem {
 pub struct ReportLostItemData {
     pub item_name: String,
     pub pick_place: Option<String>,
-    pub pick_user_id: Option<i64>,
 }
 
 #[derive(Deserialize)]
 pub struct ClaimLostItemData {
     pub item_id: i64,
-    pub claim_user_id: i64,
 }
 
 #[derive(Deserialize)]
 pub struct SendMessageData {
-    pub sender_id: i64,
     pub receiver_id: i64,
     pub title: Option<String>,
     pub message_content: String,
@@ -124,7 +670,6 @@ pub struct SendMessageData {
 
 #[derive(Deserialize)]
 pub struct CusSendMessageData {
-    pub sender_id: i64,
     pub title: Option<String>,
     pub message_content: String,
 }
@@ -152,3 +697,17 @@ pub struct UserBasicInfo {
     pub id: i64,
     pub username: String,
 }
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SetupStatus {
+    pub database_exists: bool,
+    pub schema_installed: bool,
+    pub admin_exists: bool,
+    pub needs_setup: bool,
+}
+
+#[derive(Deserialize)]
+pub struct InitialAdminData {
+    pub username: String,
+    pub password: String,
+}
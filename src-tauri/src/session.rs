@@ -0,0 +1,175 @@
+use crate::auth::{Access, AuthError, Role};
+use chrono::{DateTime, Duration, Local};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+// Sessions are refreshed on every authenticated call, so this is an idle timeout.
+pub const SESSION_IDLE_TIMEOUT_MINUTES: i64 = 120;
+
+#[derive(Clone)]
+pub struct Session {
+    pub user_id: i64,
+    pub role: Role,
+    pub expires_at: DateTime<Local>,
+}
+
+pub struct SessionStore {
+    sessions: Mutex<HashMap<String, Session>>,
+    idle_timeout: Duration,
+}
+
+impl SessionStore {
+    pub fn new(idle_timeout: Duration) -> Self {
+        SessionStore {
+            sessions: Mutex::new(HashMap::new()),
+            idle_timeout,
+        }
+    }
+
+    pub fn create(&self, user_id: i64, user_type: i8) -> Result<String, AuthError> {
+        self.create_at(user_id, user_type, Local::now())
+    }
+
+    fn create_at(
+        &self,
+        user_id: i64,
+        user_type: i8,
+        now: DateTime<Local>,
+    ) -> Result<String, AuthError> {
+        let role = Role::from_user_type(user_type).ok_or(AuthError::UnknownRole(user_type))?;
+        let mut sessions = self
+            .sessions
+            .lock()
+            .map_err(|_| AuthError::StoreUnavailable)?;
+
+        sessions.retain(|_, session| session.expires_at > now);
+
+        let token = Uuid::new_v4().simple().to_string();
+        sessions.insert(
+            token.clone(),
+            Session {
+                user_id,
+                role,
+                expires_at: now + self.idle_timeout,
+            },
+        );
+        Ok(token)
+    }
+
+    pub fn authorize(&self, token: &str, access: Access) -> Result<Session, AuthError> {
+        self.authorize_at(token, access, Local::now())
+    }
+
+    fn authorize_at(
+        &self,
+        token: &str,
+        access: Access,
+        now: DateTime<Local>,
+    ) -> Result<Session, AuthError> {
+        let mut sessions = self
+            .sessions
+            .lock()
+            .map_err(|_| AuthError::StoreUnavailable)?;
+
+        let session = match sessions.get_mut(token) {
+            Some(session) if session.expires_at > now => {
+                session.expires_at = now + self.idle_timeout;
+                session.clone()
+            }
+            Some(_) => {
+                sessions.remove(token);
+                return Err(AuthError::SessionExpired);
+            }
+            None => return Err(AuthError::NotLoggedIn),
+        };
+
+        if !access.allows(session.role) {
+            return Err(AuthError::Forbidden {
+                required: access,
+                actual: session.role,
+            });
+        }
+        Ok(session)
+    }
+
+    pub fn revoke(&self, token: &str) -> Result<bool, AuthError> {
+        let mut sessions = self
+            .sessions
+            .lock()
+            .map_err(|_| AuthError::StoreUnavailable)?;
+        Ok(sessions.remove(token).is_some())
+    }
+}
+
+impl Default for SessionStore {
+    fn default() -> Self {
+        SessionStore::new(Duration::minutes(SESSION_IDLE_TIMEOUT_MINUTES))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sessions_expire_after_120_idle_minutes() {
+        let store = SessionStore::default();
+        let start = Local::now();
+        let token = store.create_at(7, 1, start).unwrap();
+
+        let session = store
+            .authorize_at(&token, Access::AnyUser, start + Duration::minutes(119))
+            .unwrap();
+        assert_eq!(session.user_id, 7);
+        assert_eq!(session.role, Role::Customer);
+
+        // Each call restarts the idle timer.
+        let last_use = start + Duration::minutes(119);
+        assert!(store
+            .authorize_at(&token, Access::AnyUser, last_use + Duration::minutes(119))
+            .is_ok());
+        let last_use = last_use + Duration::minutes(119);
+        assert!(matches!(
+            store.authorize_at(&token, Access::AnyUser, last_use + Duration::minutes(120)),
+            Err(AuthError::SessionExpired)
+        ));
+        // An expired session is dropped rather than revived.
+        assert!(matches!(
+            store.authorize_at(&token, Access::AnyUser, last_use),
+            Err(AuthError::NotLoggedIn)
+        ));
+    }
+
+    #[test]
+    fn revoked_sessions_are_rejected() {
+        let store = SessionStore::default();
+        let token = store.create(1, 0).unwrap();
+        assert!(store.authorize(&token, Access::StaffOnly).is_ok());
+
+        assert!(store.revoke(&token).unwrap());
+        assert!(!store.revoke(&token).unwrap());
+        assert!(matches!(
+            store.authorize(&token, Access::StaffOnly),
+            Err(AuthError::NotLoggedIn)
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_tokens_and_roles() {
+        let store = SessionStore::default();
+        let token = store.create(1, 1).unwrap();
+        assert!(matches!(
+            store.authorize("not-a-session", Access::AnyUser),
+            Err(AuthError::NotLoggedIn)
+        ));
+        assert!(matches!(
+            store.authorize(&token, Access::StaffOnly),
+            Err(AuthError::Forbidden {
+                required: Access::StaffOnly,
+                actual: Role::Customer,
+            })
+        ));
+        assert!(matches!(store.create(2, 5), Err(AuthError::UnknownRole(5))));
+    }
+}
@@ -0,0 +1,103 @@
+use crate::db_config::{Backend, DatabaseConfig};
+use crate::error::CafeHubError;
+use crate::models::{InitialAdminData, SetupStatus};
+use crate::storage::{self, MySqlStorage, NewAccount, Storage};
+use bcrypt::{hash, DEFAULT_COST};
+use chrono::Local;
+use tauri::{AppHandle, Manager, State};
+
+fn app_data_dir(app: &AppHandle) -> Result<std::path::PathBuf, CafeHubError> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| CafeHubError::internal(format!("Failed to resolve app data directory: {}", e)))
+}
+
+// Opening and creating the database report plain messages, as they also run before the app starts.
+fn database_error(message: String) -> CafeHubError {
+    CafeHubError::Database { message }
+}
+
+#[tauri::command]
+pub fn get_setup_status(
+    app: AppHandle,
+    db_config: State<DatabaseConfig>,
+) -> Result<SetupStatus, CafeHubError> {
+    // Storage is only missing when the configured MySQL database does not exist yet.
+    let storage = match app.try_state::<Box<dyn Storage>>() {
+        Some(storage) => storage,
+        None => {
+            let database_exists = match db_config.backend() {
+                Backend::Mysql => {
+                    MySqlStorage::database_exists(&db_config).map_err(database_error)?
+                }
+                Backend::Sqlite => false,
+            };
+            return Ok(SetupStatus {
+                database_exists,
+                schema_installed: false,
+                admin_exists: false,
+                needs_setup: true,
+            });
+        }
+    };
+
+    // Pending migrations were applied at startup, so the schema is current here.
+    let admin_exists = storage.count_staff()? > 0;
+
+    Ok(SetupStatus {
+        database_exists: true,
+        schema_installed: true,
+        admin_exists,
+        needs_setup: !admin_exists,
+    })
+}
+
+#[tauri::command]
+pub fn initialize_database(
+    data: InitialAdminData,
+    app: AppHandle,
+    db_config: State<DatabaseConfig>,
+) -> Result<String, CafeHubError> {
+    if data.username.is_empty() || data.password.is_empty() {
+        return Err(CafeHubError::validation(
+            "Username and password cannot be empty",
+        ));
+    }
+    if data.username.chars().count() > 20 {
+        return Err(CafeHubError::validation(
+            "Username cannot be longer than 20 characters",
+        ));
+    }
+
+    if app.try_state::<Box<dyn Storage>>().is_none() {
+        if db_config.backend() == Backend::Mysql {
+            MySqlStorage::create_database(&db_config).map_err(database_error)?;
+        }
+        let storage = storage::open(&db_config, &app_data_dir(&app)?)
+            .map_err(database_error)?
+            .ok_or_else(|| {
+                CafeHubError::internal("Database is still unavailable after creating it")
+            })?;
+        app.manage(storage);
+    }
+
+    let storage = app.state::<Box<dyn Storage>>();
+
+    let hashed_password = hash(&data.password, DEFAULT_COST).map_err(|e| {
+        eprintln!("Failed to hash password for admin {}: {}", data.username, e);
+        CafeHubError::internal("Password hashing failed")
+    })?;
+
+    storage.create_first_staff(&NewAccount {
+        username: data.username.clone(),
+        password_hash: hashed_password,
+        phone: None,
+        gender: None,
+        join_time: Local::now().date_naive(),
+    })?;
+
+    Ok(format!(
+        "Database initialized and administrator '{}' created.",
+        data.username
+    ))
+}
@@ -0,0 +1,1462 @@
+mod mysql_storage;
+mod sqlite_storage;
+
+pub use mysql_storage::MySqlStorage;
+pub use sqlite_storage::SqliteStorage;
+
+use crate::db_config::{Backend, DatabaseConfig};
+use crate::error::{CafeHubError, CouponRejection};
+use crate::image_protocol;
+use crate::models::*;
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+// Data access is split into one repository per area. Operations that must be atomic
+// (placing an order, refunds, balance changes) are single methods so that each
+// backend can run them inside its own transaction.
+
+pub struct NewAccount {
+    pub username: String,
+    pub password_hash: String,
+    pub phone: Option<String>,
+    pub gender: Option<i8>,
+    pub join_time: NaiveDate,
+}
+
+pub struct CustomerChanges {
+    pub username: Option<String>,
+    pub phone: Option<Option<String>>, // Some(None) clears the phone number
+    pub gender: Option<i8>,
+}
+
+// How `margin_summary` groups the sales.
+#[derive(Clone, Copy)]
+pub enum MarginGrouping {
+    Goods,
+    Category,
+    Month,
+}
+
+// The two sizes every goods image is stored in.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ImageSize {
+    Full,
+    Thumbnail,
+}
+
+impl ImageSize {
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ImageSize::Full => "image.jpg",
+            ImageSize::Thumbnail => "thumbnail.jpg",
+        }
+    }
+
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        [ImageSize::Full, ImageSize::Thumbnail]
+            .into_iter()
+            .find(|size| size.file_name() == file_name)
+    }
+}
+
+// An uploaded image, already resized and encoded as JPEG.
+pub struct GoodsImage {
+    pub image_key: String,
+    pub image: Vec<u8>,
+    pub thumbnail: Vec<u8>,
+}
+
+pub struct GoodsChanges {
+    pub goods_name: Option<String>,
+    pub category_id: Option<Option<i32>>, // Some(None) clears the category
+    pub description: Option<Option<String>>, // Some(None) clears the description
+    pub price: Option<Decimal>,
+    pub available: Option<bool>,
+    pub reorder_threshold: Option<i32>,
+    pub cost_price: Option<Decimal>,
+    pub points_price: Option<Option<i32>>, // Some(None) stops redemption with points
+}
+
+impl GoodsChanges {
+    pub fn is_empty(&self) -> bool {
+        self.goods_name.is_none()
+            && self.category_id.is_none()
+            && self.description.is_none()
+            && self.price.is_none()
+            && self.available.is_none()
+            && self.reorder_threshold.is_none()
+            && self.cost_price.is_none()
+            && self.points_price.is_none()
+    }
+}
+
+pub struct NewMessage {
+    pub sender_id: i64,
+    pub receiver_id: i64,
+    pub title: Option<String>,
+    pub message_content: String,
+    pub send_date: NaiveDate,
+}
+
+pub trait AccountRepository {
+    // Returns the account together with its password hash.
+    fn find_account_by_username(
+        &self,
+        username: &str,
+    ) -> Result<Option<(Account, String)>, CafeHubError>;
+    fn find_customer(&self, user_id: i64) -> Result<Option<Account>, CafeHubError>;
+    fn find_user_type(&self, user_id: i64) -> Result<Option<i8>, CafeHubError>;
+    fn find_first_staff_id(&self) -> Result<Option<i64>, CafeHubError>;
+    fn customer_password_hash(&self, user_id: i64) -> Result<Option<String>, CafeHubError>;
+    fn create_customer(&self, account: &NewAccount) -> Result<i64, CafeHubError>;
+    // Fails with a conflict if any staff account already exists.
+    fn create_first_staff(&self, account: &NewAccount) -> Result<i64, CafeHubError>;
+    fn update_customer_password(
+        &self,
+        user_id: i64,
+        password_hash: &str,
+    ) -> Result<bool, CafeHubError>;
+    fn update_customer_details(
+        &self,
+        user_id: i64,
+        changes: &CustomerChanges,
+    ) -> Result<bool, CafeHubError>;
+    fn count_customers(&self, joined_since: Option<NaiveDate>) -> Result<i64, CafeHubError>;
+    fn count_staff(&self) -> Result<i64, CafeHubError>;
+    fn list_users(&self) -> Result<Vec<UserBasicInfo>, CafeHubError>;
+}
+
+pub trait WalletRepository {
+    fn recharge_balance(
+        &self,
+        user_id: i64,
+        amount: Decimal,
+        operator_id: i64,
+    ) -> Result<Decimal, CafeHubError>;
+    // Refuses to take a balance below zero.
+    fn adjust_balance(
+        &self,
+        user_id: i64,
+        amount: Decimal,
+        note: &str,
+        operator_id: i64,
+    ) -> Result<Decimal, CafeHubError>;
+    fn balance_statement(&self, user_id: i64) -> Result<Vec<BalanceLedgerEntry>, CafeHubError>;
+    fn balance_mismatches(&self) -> Result<Vec<BalanceMismatch>, CafeHubError>;
+}
+
+pub trait LoyaltyRepository {
+    // The points the user can spend at `now`. Batches that ran out by then are left out
+    // without writing anything; the next order or refund records their expiry.
+    fn spendable_points(&self, user_id: i64, now: NaiveDateTime) -> Result<i64, CafeHubError>;
+    // Newest first.
+    fn points_history(&self, user_id: i64) -> Result<Vec<PointsLedgerEntry>, CafeHubError>;
+}
+
+pub trait GoodsRepository {
+    fn list_goods(&self) -> Result<Vec<Goods>, CafeHubError>;
+    // One page of the goods matching the query, and how many match in all.
+    fn search_goods(
+        &self,
+        query: &GoodsQuery,
+        include_archived: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<(Vec<Goods>, i64), CafeHubError>;
+    fn create_goods(&self, data: &AddGoodsData, operator_id: i64) -> Result<i64, CafeHubError>;
+    // A cost price change is recorded in the cost history.
+    fn update_goods(
+        &self,
+        goods_id: i32,
+        changes: &GoodsChanges,
+        operator_id: i64,
+    ) -> Result<bool, CafeHubError>;
+    // Refuses to take stock below zero. Returns the new stock.
+    fn adjust_stock(
+        &self,
+        goods_id: i32,
+        delta: i32,
+        reason: StockMovementReason,
+        note: Option<&str>,
+        operator_id: i64,
+    ) -> Result<i32, CafeHubError>;
+    fn stock_movements(&self, goods_id: i32) -> Result<Vec<StockMovement>, CafeHubError>;
+    fn cost_history(&self, goods_id: i32) -> Result<Vec<GoodsCostChange>, CafeHubError>;
+    // Goods whose stock is at or below their reorder threshold. Archived goods are left out.
+    fn list_low_stock_goods(&self) -> Result<Vec<Goods>, CafeHubError>;
+    // None restores archived goods. Archiving goods again keeps the time they were first archived.
+    fn set_goods_archived(
+        &self,
+        goods_id: i32,
+        archived_at: Option<NaiveDateTime>,
+    ) -> Result<bool, CafeHubError>;
+    // Only goods that were never sold or ordered from a supplier can be deleted;
+    // their recipe, modifiers, image, stock movements and cost history go with them.
+    fn delete_goods(&self, goods_id: i32) -> Result<bool, CafeHubError>;
+    // Replaces any earlier image of the goods.
+    fn set_goods_image(&self, goods_id: i32, image: &GoodsImage) -> Result<bool, CafeHubError>;
+    fn remove_goods_image(&self, goods_id: i32) -> Result<bool, CafeHubError>;
+    // Returns the JPEG bytes of the current image.
+    fn find_goods_image(
+        &self,
+        goods_id: i32,
+        size: ImageSize,
+    ) -> Result<Option<Vec<u8>>, CafeHubError>;
+}
+
+pub trait CategoryRepository {
+    // In menu order: by sort order, then ID.
+    fn list_categories(&self) -> Result<Vec<Category>, CafeHubError>;
+    fn create_category(&self, data: &AddCategoryData) -> Result<i64, CafeHubError>;
+    fn update_category(
+        &self,
+        category_id: i32,
+        data: &UpdateCategoryData,
+    ) -> Result<bool, CafeHubError>;
+    // Only empty categories can be deleted.
+    fn delete_category(&self, category_id: i32) -> Result<bool, CafeHubError>;
+}
+
+pub trait PromotionRepository {
+    fn list_promotions(&self) -> Result<Vec<Promotion>, CafeHubError>;
+    fn create_promotion(&self, data: &PromotionData) -> Result<i64, CafeHubError>;
+    // Replaces every setting of the promotion.
+    fn update_promotion(
+        &self,
+        promotion_id: i32,
+        data: &PromotionData,
+    ) -> Result<bool, CafeHubError>;
+    // Past sales keep the promotion's name.
+    fn delete_promotion(&self, promotion_id: i32) -> Result<bool, CafeHubError>;
+}
+
+pub trait CouponRepository {
+    // Newest first, with how often each has been redeemed.
+    fn list_coupons(&self) -> Result<Vec<Coupon>, CafeHubError>;
+    fn create_coupon(
+        &self,
+        data: &AddCouponData,
+        created_at: NaiveDateTime,
+    ) -> Result<i64, CafeHubError>;
+    // Disabled coupons are kept for the orders that redeemed them.
+    fn disable_coupon(&self, coupon_id: i32) -> Result<bool, CafeHubError>;
+}
+
+pub trait ModifierRepository {
+    fn list_modifier_groups(&self, goods_id: i32) -> Result<Vec<ModifierGroup>, CafeHubError>;
+    // Creates the group together with its options. Returns the group ID.
+    fn create_modifier_group(&self, data: &AddModifierGroupData) -> Result<i64, CafeHubError>;
+    fn delete_modifier_group(&self, group_id: i64) -> Result<bool, CafeHubError>;
+}
+
+pub trait IngredientRepository {
+    fn list_ingredients(&self) -> Result<Vec<Ingredient>, CafeHubError>;
+    fn create_ingredient(&self, data: &AddIngredientData) -> Result<i64, CafeHubError>;
+    fn update_ingredient(
+        &self,
+        ingredient_id: i64,
+        data: &UpdateIngredientData,
+    ) -> Result<bool, CafeHubError>;
+    fn find_recipe(&self, goods_id: i32) -> Result<Vec<RecipeItem>, CafeHubError>;
+    // Replaces the whole recipe of a goods.
+    fn set_recipe(&self, goods_id: i32, items: &[RecipeItemData]) -> Result<(), CafeHubError>;
+}
+
+pub trait PurchasingRepository {
+    fn list_suppliers(&self) -> Result<Vec<Supplier>, CafeHubError>;
+    fn create_supplier(&self, data: &AddSupplierData) -> Result<i64, CafeHubError>;
+    fn create_purchase_order(
+        &self,
+        data: &CreatePurchaseOrderData,
+        operator_id: i64,
+        created_at: NaiveDateTime,
+    ) -> Result<i64, CafeHubError>;
+    fn list_purchase_orders(&self) -> Result<Vec<PurchaseOrderSummary>, CafeHubError>;
+    fn find_purchase_order(
+        &self,
+        purchase_order_id: i64,
+    ) -> Result<Option<PurchaseOrderDetail>, CafeHubError>;
+    // Adds every line to stock as a restock and takes its unit cost as the new
+    // cost price of the goods. Only open orders can be received.
+    fn receive_purchase_order(
+        &self,
+        purchase_order_id: i64,
+        operator_id: i64,
+        received_at: NaiveDateTime,
+    ) -> Result<(), CafeHubError>;
+    fn cancel_purchase_order(
+        &self,
+        purchase_order_id: i64,
+        operator_id: i64,
+        cancelled_at: NaiveDateTime,
+    ) -> Result<(), CafeHubError>;
+}
+
+pub trait ConsumptionRepository {
+    // Totals per month, for a single customer or for everyone.
+    fn monthly_consumption(
+        &self,
+        user_id: Option<i64>,
+    ) -> Result<Vec<MonthlyConsumptionSummary>, CafeHubError>;
+    fn goods_consumption_share(
+        &self,
+        month: &str,
+    ) -> Result<Vec<GoodsConsumptionShare>, CafeHubError>;
+    // Revenue against cost of the sold goods, for one "YYYY-MM" month or all time.
+    fn margin_summary(
+        &self,
+        grouping: MarginGrouping,
+        month: Option<&str>,
+    ) -> Result<Vec<MarginSummary>, CafeHubError>;
+}
+
+pub trait OrderRepository {
+    // Checks stock and balance, then debits both and records the order. A coupon code and
+    // loyalty points are redeemed, and new points earned, in the same transaction.
+    // Returns the order ID.
+    fn place_order(
+        &self,
+        user_id: i64,
+        items: &[PurchaseItem],
+        coupon_code: Option<&str>,
+        points_to_redeem: i64,
+        order_time: NaiveDateTime,
+    ) -> Result<i64, CafeHubError>;
+    fn list_orders(&self, user_id: i64) -> Result<Vec<OrderSummary>, CafeHubError>;
+    fn find_order(&self, order_id: i64) -> Result<Option<OrderDetail>, CafeHubError>;
+    // Returns the refunded amount.
+    fn refund_order(
+        &self,
+        refund: &RefundOrderData,
+        operator_id: i64,
+        refund_time: NaiveDateTime,
+    ) -> Result<Decimal, CafeHubError>;
+}
+
+pub trait LostItemRepository {
+    fn list_lost_items(&self) -> Result<Vec<LostItem>, CafeHubError>;
+    fn create_lost_item(
+        &self,
+        data: &ReportLostItemData,
+        pick_user_id: i64,
+        pick_time: NaiveDate,
+    ) -> Result<i64, CafeHubError>;
+    fn claim_lost_item(
+        &self,
+        item_id: i64,
+        claim_user_id: i64,
+        claim_time: NaiveDate,
+    ) -> Result<(), CafeHubError>;
+}
+
+pub trait MessageRepository {
+    fn create_message(&self, message: &NewMessage) -> Result<i64, CafeHubError>;
+    fn list_sent_messages(&self, user_id: i64) -> Result<Vec<MessageInfo>, CafeHubError>;
+    fn list_received_messages(&self, user_id: i64) -> Result<Vec<MessageInfo>, CafeHubError>;
+    // Returns the receiver ID and read status.
+    fn find_message_receipt(&self, message_id: i64) -> Result<Option<(i64, i8)>, CafeHubError>;
+    fn mark_message_read(&self, message_id: i64, receiver_id: i64) -> Result<bool, CafeHubError>;
+}
+
+pub trait Storage:
+    AccountRepository
+    + WalletRepository
+    + LoyaltyRepository
+    + GoodsRepository
+    + CategoryRepository
+    + PromotionRepository
+    + CouponRepository
+    + ModifierRepository
+    + IngredientRepository
+    + PurchasingRepository
+    + ConsumptionRepository
+    + OrderRepository
+    + LostItemRepository
+    + MessageRepository
+    + Send
+    + Sync
+{
+}
+
+impl<T> Storage for T where
+    T: AccountRepository
+        + WalletRepository
+        + LoyaltyRepository
+        + GoodsRepository
+        + CategoryRepository
+        + PromotionRepository
+        + CouponRepository
+        + ModifierRepository
+        + IngredientRepository
+        + PurchasingRepository
+        + ConsumptionRepository
+        + OrderRepository
+        + LostItemRepository
+        + MessageRepository
+        + Send
+        + Sync
+{
+}
+
+// Conditions of `search_goods`, shared by both backends. `!` escapes the wildcards of
+// the search text, see `like_pattern`.
+pub(crate) const GOODS_SEARCH_FILTER: &str = "
+    WHERE (:include_archived OR archived_at IS NULL)
+        AND (:search IS NULL OR goods_name LIKE :search ESCAPE '!')
+        AND (:category_id IS NULL OR category_id = :category_id)
+        AND (:min_price IS NULL OR price >= :min_price)
+        AND (:max_price IS NULL OR price <= :max_price)
+        AND (NOT :in_stock_only OR (available = 1 AND stock > 0))";
+
+pub(crate) fn goods_sort_order(sort: GoodsSort) -> &'static str {
+    match sort {
+        GoodsSort::Name => "goods_name ASC, id ASC",
+        GoodsSort::PriceAsc => "price ASC, id ASC",
+        GoodsSort::PriceDesc => "price DESC, id ASC",
+        GoodsSort::Newest => "id DESC",
+        // Menu order, with uncategorized goods last.
+        GoodsSort::Category => {
+            "category_order IS NULL, category_order ASC, category_id ASC, goods_name ASC, id ASC"
+        }
+    }
+}
+
+// Matches names containing `search`, taking `%` and `_` in it literally.
+pub(crate) fn like_pattern(search: &str) -> String {
+    let mut pattern = String::from("%");
+    for c in search.chars() {
+        if matches!(c, '%' | '_' | '!') {
+            pattern.push('!');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+// URL of a goods image under the `goods-image` protocol. The key makes every upload a
+// new URL, so the webview may cache images for good.
+pub(crate) fn goods_image_url(goods_id: i32, image_key: &str, size: ImageSize) -> String {
+    format!(
+        "{}{}/{}?v={}",
+        image_protocol::BASE_URL,
+        goods_id,
+        size.file_name(),
+        image_key
+    )
+}
+
+// Share of a line's total refunded for `quantity` more units. The last units take
+// whatever is left so rounding never over- or under-refunds a line.
+pub(crate) fn refund_line_amount(
+    line_quantity: i32,
+    line_total: Decimal,
+    refunded_quantity: i32,
+    refunded_amount: Decimal,
+    quantity: i32,
+) -> Decimal {
+    if refunded_quantity + quantity == line_quantity {
+        line_total - refunded_amount
+    } else {
+        (line_total * Decimal::from(quantity) / Decimal::from(line_quantity)).round_dp(2)
+    }
+}
+
+// Points given back for `quantity` more units of a line paid for with `line_points`.
+// As with amounts, the last units take whatever is left.
+pub(crate) fn refund_line_points(
+    line_quantity: i32,
+    line_points: i64,
+    refunded_quantity: i32,
+    restored_points: i64,
+    quantity: i32,
+) -> i64 {
+    if refunded_quantity + quantity == line_quantity {
+        line_points - restored_points
+    } else {
+        line_points * i64::from(quantity) / i64::from(line_quantity)
+    }
+}
+
+// One row of `modifier_group LEFT JOIN modifier_option`, ordered by group then option.
+pub(crate) type ModifierRow = (
+    i64,
+    String,
+    bool,
+    bool,
+    Option<i64>,
+    Option<String>,
+    Option<Decimal>,
+);
+
+pub(crate) fn modifier_groups_from_rows(
+    goods_id: i32,
+    rows: Vec<ModifierRow>,
+) -> Vec<ModifierGroup> {
+    let mut groups: Vec<ModifierGroup> = Vec::new();
+    for (group_id, group_name, is_required, allow_multiple, option_id, option_name, price_delta) in
+        rows
+    {
+        if groups.last().map(|group| group.id) != Some(group_id) {
+            groups.push(ModifierGroup {
+                id: group_id,
+                goods_id,
+                group_name,
+                is_required,
+                allow_multiple,
+                options: Vec::new(),
+            });
+        }
+        if let (Some(id), Some(option_name), Some(price_delta), Some(group)) =
+            (option_id, option_name, price_delta, groups.last_mut())
+        {
+            group.options.push(ModifierOption {
+                id,
+                option_name,
+                price_delta,
+            });
+        }
+    }
+    groups
+}
+
+// Prices one unit of a goods with the chosen modifier options. Every option must belong
+// to one of the goods' groups, required groups need a choice and single-choice groups
+// take at most one option. Returns the unit price and the modifiers to record on the sale.
+pub(crate) fn price_with_modifiers(
+    goods_id: i32,
+    base_price: Decimal,
+    groups: &[ModifierGroup],
+    modifier_ids: &[i64],
+) -> Result<(Decimal, Vec<OrderItemModifier>), CafeHubError> {
+    for (i, option_id) in modifier_ids.iter().enumerate() {
+        if modifier_ids[..i].contains(option_id) {
+            return Err(CafeHubError::validation(format!(
+                "Modifier option ID {} was chosen more than once for goods ID {}.",
+                option_id, goods_id
+            )));
+        }
+        if !groups
+            .iter()
+            .any(|group| group.options.iter().any(|option| option.id == *option_id))
+        {
+            return Err(CafeHubError::validation(format!(
+                "Modifier option ID {} is not available for goods ID {}.",
+                option_id, goods_id
+            )));
+        }
+    }
+
+    let mut unit_price = base_price;
+    let mut modifiers = Vec::new();
+    for group in groups {
+        let chosen: Vec<&ModifierOption> = group
+            .options
+            .iter()
+            .filter(|option| modifier_ids.contains(&option.id))
+            .collect();
+
+        if chosen.is_empty() && group.is_required {
+            return Err(CafeHubError::validation(format!(
+                "A choice for '{}' is required for goods ID {}.",
+                group.group_name, goods_id
+            )));
+        }
+        if chosen.len() > 1 && !group.allow_multiple {
+            return Err(CafeHubError::validation(format!(
+                "Only one choice for '{}' is allowed for goods ID {}.",
+                group.group_name, goods_id
+            )));
+        }
+
+        for option in chosen {
+            unit_price += option.price_delta;
+            modifiers.push(OrderItemModifier {
+                option_id: Some(option.id),
+                group_name: group.group_name.clone(),
+                option_name: option.option_name.clone(),
+                price_delta: option.price_delta,
+            });
+        }
+    }
+
+    if unit_price < Decimal::ZERO {
+        return Err(CafeHubError::validation(format!(
+            "The chosen modifiers make the price of goods ID {} negative.",
+            goods_id
+        )));
+    }
+    Ok((unit_price, modifiers))
+}
+
+// Promotion weekdays are stored as a bit mask, bit 0 for Monday. No days means every day.
+pub(crate) fn weekday_mask(weekdays: &[u32]) -> i32 {
+    if weekdays.is_empty() {
+        return 0x7f;
+    }
+    weekdays.iter().fold(0, |mask, day| mask | 1 << (day - 1))
+}
+
+pub(crate) fn weekdays_from_mask(mask: i32) -> Vec<u32> {
+    (1..=7).filter(|day| mask & 1 << (day - 1) != 0).collect()
+}
+
+// The promotion taking the most off one unit of a goods sold at `at`, and how much it
+// takes off. Ties go to the promotion listed first.
+pub(crate) fn best_promotion(
+    promotions: &[Promotion],
+    goods_id: i32,
+    category_id: Option<i32>,
+    unit_price: Decimal,
+    at: NaiveDateTime,
+) -> Option<(&Promotion, Decimal)> {
+    let mut best: Option<(&Promotion, Decimal)> = None;
+    for promotion in promotions {
+        let targeted = promotion.goods_id == Some(goods_id)
+            || (promotion.category_id.is_some() && promotion.category_id == category_id);
+        if promotion.paused || !targeted || !promotion_runs_at(promotion, at) {
+            continue;
+        }
+        let discount = if promotion.discount_type == DiscountType::Percent {
+            (unit_price * promotion.discount_value / Decimal::ONE_HUNDRED).round_dp(2)
+        } else {
+            promotion.discount_value
+        }
+        .min(unit_price);
+        if best.is_none_or(|(_, best_discount)| discount > best_discount) {
+            best = Some((promotion, discount));
+        }
+    }
+    best
+}
+
+fn promotion_runs_at(promotion: &Promotion, at: NaiveDateTime) -> bool {
+    let date = at.date();
+    let time = at.time();
+    promotion
+        .weekdays
+        .contains(&date.weekday().number_from_monday())
+        && promotion.start_date.is_none_or(|start| start <= date)
+        && promotion.end_date.is_none_or(|end| date <= end)
+        && promotion.start_time.is_none_or(|start| start <= time)
+        && promotion.end_time.is_none_or(|end| time < end)
+}
+
+// Checks that `coupon` can be used by a customer who has redeemed it
+// `customer_redemptions` times, on an order totalling `subtotal` placed at `at`.
+// Returns the discount, which never exceeds the subtotal.
+pub(crate) fn coupon_discount(
+    coupon: &Coupon,
+    customer_redemptions: i64,
+    subtotal: Decimal,
+    at: NaiveDateTime,
+) -> Result<Decimal, CafeHubError> {
+    let rejection = if coupon.disabled {
+        Some(CouponRejection::Disabled)
+    } else if coupon.expires_at.is_some_and(|expires_at| expires_at <= at) {
+        Some(CouponRejection::Expired)
+    } else if coupon
+        .min_spend
+        .is_some_and(|min_spend| subtotal < min_spend)
+    {
+        Some(CouponRejection::BelowMinimumSpend)
+    } else if coupon
+        .max_redemptions
+        .is_some_and(|max| coupon.times_redeemed >= i64::from(max))
+    {
+        Some(CouponRejection::UsedUp)
+    } else if coupon
+        .max_per_customer
+        .is_some_and(|max| customer_redemptions >= i64::from(max))
+    {
+        Some(CouponRejection::CustomerLimitReached)
+    } else {
+        None
+    };
+    if let Some(reason) = rejection {
+        return Err(CafeHubError::CouponRejected {
+            code: coupon.code.clone(),
+            reason,
+        });
+    }
+
+    let discount = if coupon.discount_type == DiscountType::Percent {
+        (subtotal * coupon.discount_value / Decimal::ONE_HUNDRED).round_dp(2)
+    } else {
+        coupon.discount_value
+    };
+    Ok(discount.min(subtotal))
+}
+
+// Spreads an order discount over its lines in proportion to their totals, so refunds
+// of a line give back what was actually paid for it. Shares are rounded down to the
+// cent and the cents left over go to the lines that lost the most to rounding, so no
+// share is ever negative or larger than its line's total.
+pub(crate) fn split_discount(line_totals: &[Decimal], discount: Decimal) -> Vec<Decimal> {
+    let subtotal: Decimal = line_totals.iter().sum();
+    if subtotal.is_zero() {
+        return vec![Decimal::ZERO; line_totals.len()];
+    }
+    let exact: Vec<Decimal> = line_totals
+        .iter()
+        .map(|line_total| discount * line_total / subtotal)
+        .collect();
+    let mut shares: Vec<Decimal> = exact
+        .iter()
+        .map(|share| share.round_dp_with_strategy(2, RoundingStrategy::ToZero))
+        .collect();
+
+    let cent = Decimal::new(1, 2);
+    let mut left_over = discount - shares.iter().sum::<Decimal>();
+    let mut by_rounding_loss: Vec<usize> = (0..line_totals.len()).collect();
+    by_rounding_loss.sort_by_key(|&i| std::cmp::Reverse(exact[i] - shares[i]));
+    for i in by_rounding_loss {
+        if left_over <= Decimal::ZERO {
+            break;
+        }
+        let extra = cent.min(left_over).min(line_totals[i] - shares[i]);
+        shares[i] += extra;
+        left_over -= extra;
+    }
+    shares
+}
+
+// A point is earned for every whole unit of currency paid from the wallet and is worth
+// a cent when redeemed. Earned points expire a year later.
+pub const POINTS_PER_CURRENCY_UNIT: i64 = 1;
+pub const POINT_VALUE: Decimal = Decimal::from_parts(1, 0, 0, false, 2);
+pub const POINTS_VALID_DAYS: i64 = 365;
+
+pub(crate) fn points_earned(amount_paid: Decimal) -> i64 {
+    i64::try_from(amount_paid.floor()).unwrap_or(0) * POINTS_PER_CURRENCY_UNIT
+}
+
+pub(crate) fn points_value(points: i64) -> Decimal {
+    Decimal::from(points) * POINT_VALUE
+}
+
+// Points worth `value`, which is a share of what redeemed points were worth.
+pub(crate) fn points_for_value(value: Decimal) -> i64 {
+    i64::try_from((value / POINT_VALUE).floor()).unwrap_or(0)
+}
+
+pub(crate) fn points_expiry(earned_at: NaiveDateTime) -> NaiveDateTime {
+    earned_at + chrono::Duration::days(POINTS_VALID_DAYS)
+}
+
+// One ingredient of a recipe together with the ingredient's current stock.
+pub(crate) struct RecipeUsage {
+    pub ingredient_id: i64,
+    pub ingredient_name: String,
+    pub quantity: i64,
+    pub stock: i64,
+}
+
+struct IngredientNeed {
+    ingredient_name: String,
+    required: i64,
+    available: i64,
+}
+
+// Adds up the ingredients an order draws on, so that several lines using the same
+// ingredient are checked against its stock together.
+#[derive(Default)]
+pub(crate) struct IngredientDemand {
+    needs: BTreeMap<i64, IngredientNeed>,
+}
+
+impl IngredientDemand {
+    pub fn add(&mut self, recipe: &[RecipeUsage], quantity: i32) {
+        for usage in recipe {
+            let need = self
+                .needs
+                .entry(usage.ingredient_id)
+                .or_insert_with(|| IngredientNeed {
+                    ingredient_name: usage.ingredient_name.clone(),
+                    required: 0,
+                    available: usage.stock,
+                });
+            need.required += usage.quantity * i64::from(quantity);
+        }
+    }
+
+    pub fn check(&self) -> Result<(), CafeHubError> {
+        for (&ingredient_id, need) in &self.needs {
+            if need.available < need.required {
+                return Err(CafeHubError::InsufficientIngredient {
+                    ingredient_id,
+                    ingredient_name: need.ingredient_name.clone(),
+                    required: need.required,
+                    available: need.available,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // (ingredient ID, quantity to deduct) pairs.
+    pub fn required(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.needs
+            .iter()
+            .map(|(&ingredient_id, need)| (ingredient_id, need.required))
+    }
+}
+
+// Receiving and cancelling both close a purchase order, which only works once.
+pub(crate) fn ensure_purchase_order_open(
+    purchase_order_id: i64,
+    status: Option<String>,
+) -> Result<(), CafeHubError> {
+    match status {
+        None => Err(CafeHubError::NotFound {
+            entity: "Purchase order",
+            id: purchase_order_id,
+        }),
+        Some(status) if status == PurchaseOrderStatus::Open.as_str() => Ok(()),
+        Some(status) => Err(CafeHubError::conflict(format!(
+            "Purchase order ID {} is already {}.",
+            purchase_order_id, status
+        ))),
+    }
+}
+
+pub(crate) const LOW_STOCK_ALERT_TITLE: &str = "Low stock alert";
+
+// Stock of a goods item with a reorder threshold, as it was before the sale.
+pub(crate) struct StockLevel {
+    pub goods_name: String,
+    pub stock: i32,
+    pub reorder_threshold: i32,
+}
+
+// Builds the admin inbox message for goods that a sale takes to or below their reorder
+// threshold. Goods that were already low are left out, so each drop alerts only once.
+pub(crate) fn low_stock_alert(
+    levels: &BTreeMap<i32, StockLevel>,
+    sold_by_goods: &HashMap<i32, i32>,
+) -> Option<String> {
+    let lines: Vec<String> = levels
+        .iter()
+        .filter_map(|(goods_id, level)| {
+            let remaining = level.stock - sold_by_goods.get(goods_id).copied().unwrap_or(0);
+            (level.stock > level.reorder_threshold && remaining <= level.reorder_threshold).then(
+                || {
+                    format!(
+                        "{} (ID {}) is down to {}, reorder threshold {}.",
+                        level.goods_name, goods_id, remaining, level.reorder_threshold
+                    )
+                },
+            )
+        })
+        .collect();
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
+// Price, stock, name, reorder threshold, cost price, availability, category and points
+// price of goods being sold.
+pub(crate) type SaleGoodsRow = (
+    Decimal,
+    i32,
+    String,
+    Option<i32>,
+    Option<Decimal>,
+    bool,
+    Option<i32>,
+    Option<i32>,
+);
+
+// Goods, quantity, refunded quantity, line total, refunded amount, whether it was made
+// from a recipe, and the points redeemed and already restored of a line being refunded.
+pub(crate) type RefundLineRow = (i32, i32, i32, Decimal, Decimal, bool, i64, i64);
+
+// A priced line of an order about to be recorded.
+pub(crate) struct OrderLine {
+    pub goods_id: i32,
+    pub quantity: i32,
+    pub unit_price: Decimal,
+    pub line_total: Decimal,
+    pub unit_cost: Option<Decimal>,
+    pub modifiers: Vec<OrderItemModifier>,
+    pub made_from_recipe: bool,
+    pub promotion: Option<(i32, String)>,
+    pub discount: Decimal,
+    pub coupon_discount: Decimal,
+    pub points_discount: Decimal,
+    pub points_redeemed: i64,
+}
+
+// The reads and writes of placing and refunding an order, run by each backend inside
+// its own transaction. Reads of rows the order goes on to change lock them until the
+// transaction ends. `checkout` and `issue_refund` hold the logic shared by both backends.
+pub(crate) trait OrderTransaction {
+    fn active_promotions(&mut self) -> Result<Vec<Promotion>, CafeHubError>;
+    // `None` when the goods do not exist or are archived.
+    fn lock_sale_goods(&mut self, goods_id: i32) -> Result<Option<SaleGoodsRow>, CafeHubError>;
+    fn lock_recipe(&mut self, goods_id: i32) -> Result<Vec<RecipeUsage>, CafeHubError>;
+    fn modifier_groups(&mut self, goods_id: i32) -> Result<Vec<ModifierGroup>, CafeHubError>;
+    // The coupon with its redemptions that still count, and how many of those are the
+    // customer's.
+    fn lock_coupon(
+        &mut self,
+        code: &str,
+        user_id: i64,
+    ) -> Result<Option<(Coupon, i64)>, CafeHubError>;
+    // `None` when the user is not a customer.
+    fn lock_balance(&mut self, user_id: i64) -> Result<Option<Decimal>, CafeHubError>;
+    // Expires the points that ran out by `now` and returns the points left.
+    fn expire_points(&mut self, user_id: i64, now: NaiveDateTime) -> Result<i64, CafeHubError>;
+    fn use_ingredient(&mut self, ingredient_id: i64, quantity: i64) -> Result<(), CafeHubError>;
+    // Returns the order ID.
+    fn insert_order(
+        &mut self,
+        user_id: i64,
+        order_time: NaiveDateTime,
+        total_amount: Decimal,
+        points_redeemed: i64,
+        points_earned: i64,
+    ) -> Result<i64, CafeHubError>;
+    fn insert_coupon_redemption(
+        &mut self,
+        coupon_id: i32,
+        order_id: i64,
+        user_id: i64,
+        discount: Decimal,
+        redeemed_at: NaiveDateTime,
+    ) -> Result<(), CafeHubError>;
+    // Records the line together with its modifiers.
+    fn insert_order_line(&mut self, order_id: i64, line: &OrderLine) -> Result<(), CafeHubError>;
+    fn add_consumption(
+        &mut self,
+        user_id: i64,
+        month: &str,
+        goods_id: i32,
+        amount: Decimal,
+    ) -> Result<(), CafeHubError>;
+    fn change_balance(
+        &mut self,
+        user_id: i64,
+        amount: Decimal,
+        entry_type: LedgerEntryType,
+        reference: String,
+        operator_id: i64,
+    ) -> Result<(), CafeHubError>;
+    fn change_points(
+        &mut self,
+        user_id: i64,
+        points: i64,
+        entry_type: PointsEntryType,
+        reference: String,
+        expires_at: Option<NaiveDateTime>,
+        at: NaiveDateTime,
+    ) -> Result<(), CafeHubError>;
+    fn change_stock(
+        &mut self,
+        goods_id: i32,
+        delta: i32,
+        reason: StockMovementReason,
+        reference: String,
+        operator_id: i64,
+    ) -> Result<(), CafeHubError>;
+    fn notify_administrator(
+        &mut self,
+        title: &str,
+        content: &str,
+        send_date: NaiveDate,
+    ) -> Result<(), CafeHubError>;
+
+    // The customer, time and points earned of an order.
+    fn lock_order(
+        &mut self,
+        order_id: i64,
+    ) -> Result<Option<(i64, NaiveDateTime, i64)>, CafeHubError>;
+    // Every line of the order with units left to refund, and how many.
+    fn refundable_items(&mut self, order_id: i64) -> Result<Vec<RefundItem>, CafeHubError>;
+    // `None` when the line is not part of the order.
+    fn lock_refund_line(
+        &mut self,
+        order_id: i64,
+        order_item_id: i64,
+    ) -> Result<Option<RefundLineRow>, CafeHubError>;
+    // Adds to the refunded quantity, amount and restored points of a line.
+    fn add_line_refund(
+        &mut self,
+        order_item_id: i64,
+        quantity: i32,
+        amount: Decimal,
+        points: i64,
+    ) -> Result<(), CafeHubError>;
+    // Never takes the consumption below zero.
+    fn reduce_consumption(
+        &mut self,
+        user_id: i64,
+        month: &str,
+        goods_id: i32,
+        amount: Decimal,
+    ) -> Result<(), CafeHubError>;
+    // Returns the refund ID.
+    fn insert_refund(
+        &mut self,
+        order_id: i64,
+        operator_id: i64,
+        reason: &str,
+        refund_time: NaiveDateTime,
+        total_amount: Decimal,
+    ) -> Result<i64, CafeHubError>;
+    fn insert_refund_line(
+        &mut self,
+        refund_id: i64,
+        order_item_id: i64,
+        quantity: i32,
+        amount: Decimal,
+    ) -> Result<(), CafeHubError>;
+    // Stops the order's coupon use counting towards the coupon's limits once every line
+    // of the order is fully refunded.
+    fn release_coupon_redemption(
+        &mut self,
+        order_id: i64,
+        refunded_at: NaiveDateTime,
+    ) -> Result<(), CafeHubError>;
+}
+
+// Places an order inside `tx`, see `OrderRepository::place_order`. Returns the order ID.
+pub(crate) fn checkout(
+    tx: &mut impl OrderTransaction,
+    user_id: i64,
+    items: &[PurchaseItem],
+    coupon_code: Option<&str>,
+    points_to_redeem: i64,
+    order_time: NaiveDateTime,
+) -> Result<i64, CafeHubError> {
+    let mut total_purchase_price = Decimal::ZERO;
+    let mut lines: Vec<OrderLine> = Vec::new();
+    // The same goods may be on several lines with different modifiers.
+    let mut requested_by_goods: HashMap<i32, i32> = HashMap::new();
+    let mut ingredient_demand = IngredientDemand::default();
+    let mut stock_levels: BTreeMap<i32, StockLevel> = BTreeMap::new();
+    let promotions = tx.active_promotions()?;
+    let mut points_to_spend = points_to_redeem;
+
+    for item in items {
+        let (
+            price_per_item,
+            current_stock,
+            goods_name,
+            reorder_threshold,
+            unit_cost,
+            available,
+            category_id,
+            points_price,
+        ) = tx
+            .lock_sale_goods(item.goods_id)?
+            .ok_or(CafeHubError::NotFound {
+                entity: "Goods",
+                id: item.goods_id.into(),
+            })?;
+
+        if !available {
+            return Err(CafeHubError::GoodsUnavailable {
+                goods_id: item.goods_id,
+            });
+        }
+
+        // Goods with a recipe are made to order and limited by their ingredients.
+        let recipe = tx.lock_recipe(item.goods_id)?;
+        let made_from_recipe = !recipe.is_empty();
+        if made_from_recipe {
+            ingredient_demand.add(&recipe, item.quantity);
+        } else {
+            if let Some(reorder_threshold) = reorder_threshold {
+                stock_levels
+                    .entry(item.goods_id)
+                    .or_insert_with(|| StockLevel {
+                        goods_name,
+                        stock: current_stock,
+                        reorder_threshold,
+                    });
+            }
+            let requested = requested_by_goods.entry(item.goods_id).or_insert(0);
+            *requested += item.quantity;
+            if current_stock < *requested {
+                return Err(CafeHubError::InsufficientStock {
+                    goods_id: item.goods_id,
+                    requested: *requested,
+                    available: current_stock,
+                });
+            }
+        }
+
+        let groups = tx.modifier_groups(item.goods_id)?;
+        let (unit_price, modifiers) =
+            price_with_modifiers(item.goods_id, price_per_item, &groups, &item.modifier_ids)?;
+
+        // Lines redeemed with points are free, so no promotion applies to them.
+        let mut points_discount = Decimal::ZERO;
+        let mut points_redeemed = 0;
+        let (promotion, unit_discount) = if item.redeem_with_points {
+            let points_price = points_price.ok_or_else(|| {
+                CafeHubError::validation(format!(
+                    "Goods ID {} cannot be redeemed with points.",
+                    item.goods_id
+                ))
+            })?;
+            points_redeemed = i64::from(points_price) * i64::from(item.quantity);
+            points_to_spend += points_redeemed;
+            points_discount = unit_price * Decimal::from(item.quantity);
+            (None, Decimal::ZERO)
+        } else {
+            match best_promotion(
+                &promotions,
+                item.goods_id,
+                category_id,
+                unit_price,
+                order_time,
+            ) {
+                Some((promotion, discount)) => (
+                    Some((promotion.id, promotion.promotion_name.clone())),
+                    discount,
+                ),
+                None => (None, Decimal::ZERO),
+            }
+        };
+        let discount = unit_discount * Decimal::from(item.quantity);
+        let line_total = unit_price * Decimal::from(item.quantity) - discount - points_discount;
+        total_purchase_price += line_total;
+        lines.push(OrderLine {
+            goods_id: item.goods_id,
+            quantity: item.quantity,
+            unit_price,
+            line_total,
+            unit_cost,
+            modifiers,
+            made_from_recipe,
+            promotion,
+            discount,
+            coupon_discount: Decimal::ZERO,
+            points_discount,
+            points_redeemed,
+        });
+    }
+    ingredient_demand.check()?;
+
+    let mut redemption: Option<(i32, Decimal)> = None;
+    if let Some(code) = coupon_code {
+        let (coupon, customer_redemptions) =
+            tx.lock_coupon(code, user_id)?
+                .ok_or_else(|| CafeHubError::CouponRejected {
+                    code: code.to_string(),
+                    reason: CouponRejection::Unknown,
+                })?;
+        let discount = coupon_discount(
+            &coupon,
+            customer_redemptions,
+            total_purchase_price,
+            order_time,
+        )?;
+
+        let line_totals: Vec<Decimal> = lines.iter().map(|line| line.line_total).collect();
+        for (line, share) in lines.iter_mut().zip(split_discount(&line_totals, discount)) {
+            line.coupon_discount = share;
+            line.line_total -= share;
+        }
+        total_purchase_price -= discount;
+        redemption = Some((coupon.id, discount));
+    }
+
+    // Points paid with cover what is left after the coupon.
+    if points_to_redeem > 0 {
+        let value = points_value(points_to_redeem);
+        if value > total_purchase_price {
+            return Err(CafeHubError::validation(format!(
+                "{} points are worth {}, more than the {} left to pay.",
+                points_to_redeem, value, total_purchase_price
+            )));
+        }
+        let line_totals: Vec<Decimal> = lines.iter().map(|line| line.line_total).collect();
+        for (line, share) in lines.iter_mut().zip(split_discount(&line_totals, value)) {
+            line.points_discount += share;
+            line.points_redeemed += points_for_value(share);
+            line.line_total -= share;
+        }
+        total_purchase_price -= value;
+    }
+
+    let current_balance = tx.lock_balance(user_id)?.ok_or(CafeHubError::NotFound {
+        entity: "Customer",
+        id: user_id,
+    })?;
+    if current_balance < total_purchase_price {
+        return Err(CafeHubError::InsufficientBalance {
+            required: total_purchase_price,
+            available: current_balance,
+            shortfall: total_purchase_price - current_balance,
+        });
+    }
+
+    let available_points = tx.expire_points(user_id, order_time)?;
+    if available_points < points_to_spend {
+        return Err(CafeHubError::InsufficientPoints {
+            required: points_to_spend,
+            available: available_points,
+        });
+    }
+    let points_to_earn = points_earned(total_purchase_price);
+
+    for (ingredient_id, quantity) in ingredient_demand.required() {
+        tx.use_ingredient(ingredient_id, quantity)?;
+    }
+
+    let order_id = tx.insert_order(
+        user_id,
+        order_time,
+        total_purchase_price,
+        points_to_spend,
+        points_to_earn,
+    )?;
+    let reference = format!("order:{}", order_id);
+
+    if let Some((coupon_id, discount)) = redemption {
+        tx.insert_coupon_redemption(coupon_id, order_id, user_id, discount, order_time)?;
+    }
+
+    tx.change_balance(
+        user_id,
+        -total_purchase_price,
+        LedgerEntryType::Purchase,
+        reference.clone(),
+        user_id,
+    )?;
+    if points_to_spend > 0 {
+        tx.change_points(
+            user_id,
+            -points_to_spend,
+            PointsEntryType::Redeemed,
+            reference.clone(),
+            None,
+            order_time,
+        )?;
+    }
+    if points_to_earn > 0 {
+        tx.change_points(
+            user_id,
+            points_to_earn,
+            PointsEntryType::Earned,
+            reference.clone(),
+            Some(points_expiry(order_time)),
+            order_time,
+        )?;
+    }
+
+    for line in lines.iter().filter(|line| !line.made_from_recipe) {
+        tx.change_stock(
+            line.goods_id,
+            -line.quantity,
+            StockMovementReason::Sale,
+            reference.clone(),
+            user_id,
+        )?;
+    }
+
+    for line in &lines {
+        tx.insert_order_line(order_id, line)?;
+    }
+
+    let month = order_time.format("%Y-%m").to_string();
+    for line in &lines {
+        tx.add_consumption(user_id, &month, line.goods_id, line.line_total)?;
+    }
+
+    if let Some(content) = low_stock_alert(&stock_levels, &requested_by_goods) {
+        tx.notify_administrator(LOW_STOCK_ALERT_TITLE, &content, order_time.date())?;
+    }
+
+    Ok(order_id)
+}
+
+// Refunds an order inside `tx`, see `OrderRepository::refund_order`. Returns the
+// refunded amount.
+pub(crate) fn issue_refund(
+    tx: &mut impl OrderTransaction,
+    refund: &RefundOrderData,
+    operator_id: i64,
+    refund_time: NaiveDateTime,
+) -> Result<Decimal, CafeHubError> {
+    let (customer_id, order_time, order_points_earned) =
+        tx.lock_order(refund.order_id)?
+            .ok_or(CafeHubError::NotFound {
+                entity: "Order",
+                id: refund.order_id,
+            })?;
+
+    // An empty item list cancels whatever is still refundable on the order.
+    let requested_items: Vec<RefundItem> = if refund.items.is_empty() {
+        tx.refundable_items(refund.order_id)?
+    } else {
+        refund.items.clone()
+    };
+
+    if requested_items.is_empty() {
+        return Err(CafeHubError::conflict(format!(
+            "Order ID {} has already been fully refunded.",
+            refund.order_id
+        )));
+    }
+
+    struct RefundedLine {
+        order_item_id: i64,
+        goods_id: i32,
+        quantity: i32,
+        amount: Decimal,
+        made_from_recipe: bool,
+    }
+    let mut refunded_lines: Vec<RefundedLine> = Vec::new();
+    let mut total_refund = Decimal::ZERO;
+    let mut total_points = 0;
+
+    for item in &requested_items {
+        let (
+            goods_id,
+            quantity,
+            refunded_quantity,
+            line_total,
+            refunded_amount,
+            made_from_recipe,
+            points_redeemed,
+            points_restored,
+        ) = tx
+            .lock_refund_line(refund.order_id, item.order_item_id)?
+            .ok_or_else(|| {
+                CafeHubError::validation(format!(
+                    "Order item ID {} does not belong to order ID {}.",
+                    item.order_item_id, refund.order_id
+                ))
+            })?;
+
+        if refunded_quantity + item.quantity > quantity {
+            return Err(CafeHubError::conflict(format!(
+                "Cannot refund {} of order item ID {}: only {} remaining.",
+                item.quantity,
+                item.order_item_id,
+                quantity - refunded_quantity
+            )));
+        }
+
+        let amount = refund_line_amount(
+            quantity,
+            line_total,
+            refunded_quantity,
+            refunded_amount,
+            item.quantity,
+        );
+        let points = refund_line_points(
+            quantity,
+            points_redeemed,
+            refunded_quantity,
+            points_restored,
+            item.quantity,
+        );
+        tx.add_line_refund(item.order_item_id, item.quantity, amount, points)?;
+
+        total_refund += amount;
+        total_points += points;
+        refunded_lines.push(RefundedLine {
+            order_item_id: item.order_item_id,
+            goods_id,
+            quantity: item.quantity,
+            amount,
+            made_from_recipe,
+        });
+    }
+
+    let order_month = order_time.format("%Y-%m").to_string();
+    for line in &refunded_lines {
+        tx.reduce_consumption(customer_id, &order_month, line.goods_id, line.amount)?;
+    }
+
+    let refund_id = tx.insert_refund(
+        refund.order_id,
+        operator_id,
+        refund.reason.trim(),
+        refund_time,
+        total_refund,
+    )?;
+    let reference = format!("refund:{}", refund_id);
+
+    tx.change_balance(
+        customer_id,
+        total_refund,
+        LedgerEntryType::Refund,
+        reference.clone(),
+        operator_id,
+    )?;
+
+    // Points earned on the refunded amount are taken back, as far as the customer
+    // still has them. Points paid for the refunded units are given back afterwards,
+    // as a new batch, so the reversal cannot eat into them. The ledger does not record
+    // which batches a redemption drew on, so the new batch is valid for the full
+    // `POINTS_VALID_DAYS` from the refund rather than until the spent batches' expiry.
+    let points_to_reverse = points_earned(total_refund)
+        .min(order_points_earned)
+        .min(tx.expire_points(customer_id, refund_time)?);
+    if points_to_reverse > 0 {
+        tx.change_points(
+            customer_id,
+            -points_to_reverse,
+            PointsEntryType::Reversed,
+            reference.clone(),
+            None,
+            refund_time,
+        )?;
+    }
+    if total_points > 0 {
+        tx.change_points(
+            customer_id,
+            total_points,
+            PointsEntryType::Restored,
+            reference.clone(),
+            Some(points_expiry(refund_time)),
+            refund_time,
+        )?;
+    }
+
+    tx.release_coupon_redemption(refund.order_id, refund_time)?;
+
+    for line in &refunded_lines {
+        tx.insert_refund_line(refund_id, line.order_item_id, line.quantity, line.amount)?;
+
+        // Ingredients of prepared goods are used up, so only packaged goods go back on the shelf.
+        if !line.made_from_recipe {
+            tx.change_stock(
+                line.goods_id,
+                line.quantity,
+                StockMovementReason::Refund,
+                reference.clone(),
+                operator_id,
+            )?;
+        }
+    }
+
+    Ok(total_refund)
+}
+
+// Opens the configured backend and applies pending migrations. Returns `None` when the
+// MySQL database does not exist yet, so the first-run setup can create it.
+pub(crate) fn open(
+    config: &DatabaseConfig,
+    data_dir: &Path,
+) -> Result<Option<Box<dyn Storage>>, String> {
+    match config.backend() {
+        Backend::Mysql => {
+            Ok(MySqlStorage::open(config)?.map(|storage| Box::new(storage) as Box<dyn Storage>))
+        }
+        Backend::Sqlite => {
+            let storage = SqliteStorage::open(&config.sqlite_file(data_dir))?;
+            Ok(Some(Box::new(storage)))
+        }
+    }
+}
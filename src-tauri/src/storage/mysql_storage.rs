@@ -0,0 +1,3521 @@
+use super::{
+    checkout, ensure_purchase_order_open, goods_image_url, goods_sort_order, issue_refund,
+    like_pattern, modifier_groups_from_rows, weekday_mask, weekdays_from_mask, AccountRepository,
+    CategoryRepository, ConsumptionRepository, CouponRepository, CustomerChanges, GoodsChanges,
+    GoodsImage, GoodsRepository, ImageSize, IngredientRepository, LostItemRepository,
+    LoyaltyRepository, MarginGrouping, MessageRepository, ModifierRepository, ModifierRow,
+    NewAccount, NewMessage, OrderLine, OrderRepository, OrderTransaction, PromotionRepository,
+    PurchasingRepository, RecipeUsage, RefundLineRow, SaleGoodsRow, WalletRepository,
+    GOODS_SEARCH_FILTER,
+};
+use crate::db_config::DatabaseConfig;
+use crate::error::CafeHubError;
+use crate::migrations;
+use crate::models::*;
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime};
+use mysql::prelude::{FromRow, FromValue, Queryable};
+use mysql::{params, Conn, Error as MySQLError, FromValueError, Pool, PooledConn, Value};
+use rust_decimal::Decimal;
+
+// MySQL error code for "Unknown database".
+const ER_BAD_DB_ERROR: u16 = 1049;
+// MySQL error code for a duplicate key on a UNIQUE index.
+const ER_DUP_ENTRY: u16 = 1062;
+
+fn is_missing_database(e: &MySQLError) -> bool {
+    matches!(e, MySQLError::MySqlError(ref mysql_err) if mysql_err.code == ER_BAD_DB_ERROR)
+}
+
+fn is_duplicate_entry(e: &MySQLError) -> bool {
+    matches!(e, MySQLError::MySqlError(ref mysql_err) if mysql_err.code == ER_DUP_ENTRY)
+}
+
+type AccountRow = (
+    i64,
+    String,
+    Option<String>,
+    Option<i8>,
+    Option<NaiveDate>,
+    Option<Decimal>,
+    i64,
+    i8,
+);
+
+// Like `AccountRow`, with the password hash after the username.
+type CredentialsRow = (
+    i64,
+    String,
+    String,
+    Option<String>,
+    Option<i8>,
+    Option<NaiveDate>,
+    Option<Decimal>,
+    i64,
+    i8,
+);
+
+// Lets `discount_type` columns be read straight into the enum.
+pub struct ParsedDiscountType(DiscountType);
+
+impl TryFrom<Value> for ParsedDiscountType {
+    type Error = FromValueError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let parsed = match &value {
+            Value::Bytes(bytes) => std::str::from_utf8(bytes).ok().and_then(|s| s.parse().ok()),
+            _ => None,
+        };
+        parsed.map(ParsedDiscountType).ok_or(FromValueError(value))
+    }
+}
+
+impl From<ParsedDiscountType> for DiscountType {
+    fn from(parsed: ParsedDiscountType) -> Self {
+        parsed.0
+    }
+}
+
+impl From<ParsedDiscountType> for Value {
+    fn from(parsed: ParsedDiscountType) -> Self {
+        Value::from(parsed.0.as_str())
+    }
+}
+
+impl FromValue for DiscountType {
+    type Intermediate = ParsedDiscountType;
+}
+
+// Goods rows are wider than the tuples a row converts into, so they are read by column
+// name instead.
+#[derive(FromRow)]
+struct GoodsRow {
+    id: i32,
+    goods_name: String,
+    category_id: Option<i32>,
+    category: Option<String>,
+    description: Option<String>,
+    price: Decimal,
+    available: bool,
+    stock: Option<i32>,
+    reorder_threshold: Option<i32>,
+    cost_price: Option<Decimal>,
+    archived_at: Option<NaiveDateTime>,
+    image_key: Option<String>,
+    points_price: Option<i32>,
+}
+
+// Order header with the code and discount of the coupon it redeemed, if any, and the
+// points redeemed and earned.
+type OrderHeaderRow = (
+    i64,
+    i64,
+    NaiveDateTime,
+    Decimal,
+    Option<String>,
+    Option<Decimal>,
+    i64,
+    i64,
+);
+
+type CouponRow = (
+    i32,
+    String,
+    DiscountType,
+    Decimal,
+    Option<Decimal>,
+    Option<NaiveDateTime>,
+    Option<i32>,
+    Option<i32>,
+    i64,
+    bool,
+    NaiveDateTime,
+);
+
+type PromotionRow = (
+    i32,
+    String,
+    DiscountType,
+    Decimal,
+    Option<i32>,
+    Option<i32>,
+    i32,
+    Option<NaiveTime>,
+    Option<NaiveTime>,
+    Option<NaiveDate>,
+    Option<NaiveDate>,
+    bool,
+);
+
+// Purchase order header: creator and closer usernames sit before their timestamps.
+type PurchaseOrderRow = (
+    i64,
+    i64,
+    String,
+    String,
+    Decimal,
+    String,
+    NaiveDateTime,
+    Option<String>,
+    Option<NaiveDateTime>,
+);
+
+// Every goods with its category, image and stock. Goods with a recipe report how many
+// can be made from the ingredients.
+const GOODS_SOURCE: &str = "
+    SELECT g.id, g.goods_name, g.category_id, c.display_name AS category,
+        c.sort_order AS category_order, g.description, g.price, g.available,
+        COALESCE((
+            SELECT MIN(i.stock DIV r.quantity)
+            FROM recipe_item r
+            JOIN ingredient i ON r.ingredient_id = i.id
+            WHERE r.goods_id = g.id
+        ), g.stock) AS stock,
+        g.reorder_threshold, g.cost_price, g.archived_at, gi.image_key, g.points_price
+    FROM goods g
+    LEFT JOIN category c ON g.category_id = c.id
+    LEFT JOIN goods_image gi ON gi.goods_id = g.id";
+
+fn account_from_row(
+    (id, username, phone, gender, join_time, balance, points, user_type): AccountRow,
+) -> Account {
+    Account {
+        id,
+        username,
+        phone,
+        gender,
+        join_time,
+        balance,
+        points,
+        user_type,
+    }
+}
+
+// Applies a balance change and records it in `balance_ledger`. Must run inside the
+// caller's transaction so the ledger entry commits or rolls back with the balance.
+fn apply_balance_change(
+    tx: &mut impl Queryable,
+    user_id: i64,
+    amount: Decimal,
+    entry_type: LedgerEntryType,
+    reference: Option<String>,
+    operator_id: Option<i64>,
+) -> Result<Decimal, CafeHubError> {
+    tx.exec_drop(
+        "UPDATE account SET balance = COALESCE(balance, 0) + :amount WHERE id = :user_id",
+        params! {
+            "amount" => amount,
+            "user_id" => user_id,
+        },
+    )
+    .map_err(|e| {
+        CafeHubError::database(
+            format!("Failed to update balance for user ID {}", user_id),
+            e,
+        )
+    })?;
+
+    let balance_after: Decimal = tx
+        .exec_first(
+            "SELECT balance FROM account WHERE id = :user_id",
+            params! { "user_id" => user_id },
+        )
+        .map_err(|e| {
+            CafeHubError::database(format!("Failed to read balance for user ID {}", user_id), e)
+        })?
+        .ok_or(CafeHubError::NotFound {
+            entity: "User",
+            id: user_id,
+        })?;
+
+    tx.exec_drop(
+        "INSERT INTO balance_ledger (user_id, entry_type, amount, balance_after, reference, operator_id, created_at) VALUES (:user_id, :entry_type, :amount, :balance_after, :reference, :operator_id, :created_at)",
+        params! {
+            "user_id" => user_id,
+            "entry_type" => entry_type.as_str(),
+            "amount" => amount,
+            "balance_after" => balance_after,
+            "reference" => reference,
+            "operator_id" => operator_id,
+            "created_at" => Local::now().naive_local(),
+        },
+    )
+    .map_err(|e| CafeHubError::database(format!("Failed to record ledger entry for user ID {}", user_id), e))?;
+
+    Ok(balance_after)
+}
+
+// Applies a points change and records it in `points_ledger`, inside the caller's
+// transaction. Earned points are kept as a batch expiring at `expires_at`; debits use
+// up the batches that expire first, so expiring points never takes from newer batches.
+// Returns the points left.
+fn apply_points_change(
+    tx: &mut impl Queryable,
+    user_id: i64,
+    points: i64,
+    entry_type: PointsEntryType,
+    reference: Option<String>,
+    expires_at: Option<NaiveDateTime>,
+    at: NaiveDateTime,
+) -> Result<i64, CafeHubError> {
+    tx.exec_drop(
+        "UPDATE account SET points = points + :points WHERE id = :user_id",
+        params! {
+            "points" => points,
+            "user_id" => user_id,
+        },
+    )
+    .map_err(|e| {
+        CafeHubError::database(
+            format!("Failed to update points for user ID {}", user_id),
+            e,
+        )
+    })?;
+
+    let points_after: i64 = tx
+        .exec_first(
+            "SELECT points FROM account WHERE id = :user_id",
+            params! { "user_id" => user_id },
+        )
+        .map_err(|e| {
+            CafeHubError::database(format!("Failed to read points for user ID {}", user_id), e)
+        })?
+        .ok_or(CafeHubError::NotFound {
+            entity: "User",
+            id: user_id,
+        })?;
+
+    let mut to_use = -points;
+    if to_use > 0 {
+        let batches: Vec<(i64, i64)> = tx
+            .exec(
+                "SELECT id, remaining FROM points_ledger WHERE user_id = :user_id AND remaining > 0 ORDER BY expires_at ASC, id ASC FOR UPDATE",
+                params! { "user_id" => user_id },
+            )
+            .map_err(|e| CafeHubError::database(format!("Failed to read earned points of user ID {}", user_id), e))?;
+        for (batch_id, remaining) in batches {
+            if to_use == 0 {
+                break;
+            }
+            let used = remaining.min(to_use);
+            tx.exec_drop(
+                "UPDATE points_ledger SET remaining = remaining - :used WHERE id = :batch_id",
+                params! {
+                    "used" => used,
+                    "batch_id" => batch_id,
+                },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Failed to use earned points of user ID {}", user_id),
+                    e,
+                )
+            })?;
+            to_use -= used;
+        }
+    }
+
+    tx.exec_drop(
+        "INSERT INTO points_ledger (user_id, entry_type, points, points_after, remaining, expires_at, reference, created_at) VALUES (:user_id, :entry_type, :points, :points_after, :remaining, :expires_at, :reference, :created_at)",
+        params! {
+            "user_id" => user_id,
+            "entry_type" => entry_type.as_str(),
+            "points" => points,
+            "points_after" => points_after,
+            "remaining" => (points > 0).then_some(points),
+            "expires_at" => expires_at,
+            "reference" => reference,
+            "created_at" => at,
+        },
+    )
+    .map_err(|e| CafeHubError::database(format!("Failed to record points for user ID {}", user_id), e))?;
+
+    Ok(points_after)
+}
+
+// Takes the earned points that ran out by `now` off the customer's points. Returns the
+// points left. Locks the account row, so concurrent redemptions wait for each other.
+fn expire_points(
+    tx: &mut impl Queryable,
+    user_id: i64,
+    now: NaiveDateTime,
+) -> Result<i64, CafeHubError> {
+    let points: i64 = tx
+        .exec_first(
+            "SELECT points FROM account WHERE id = :user_id FOR UPDATE",
+            params! { "user_id" => user_id },
+        )
+        .map_err(|e| {
+            CafeHubError::database(format!("Failed to read points for user ID {}", user_id), e)
+        })?
+        .ok_or(CafeHubError::NotFound {
+            entity: "User",
+            id: user_id,
+        })?;
+    let expired: i64 = tx
+        .exec_first(
+            "SELECT CAST(COALESCE(SUM(remaining), 0) AS SIGNED) FROM points_ledger WHERE user_id = :user_id AND remaining > 0 AND expires_at <= :now",
+            params! {
+                "user_id" => user_id,
+                "now" => now,
+            },
+        )
+        .map_err(|e| CafeHubError::database(format!("Failed to read expired points of user ID {}", user_id), e))?
+        .unwrap_or(0);
+    if expired == 0 {
+        return Ok(points);
+    }
+    apply_points_change(
+        tx,
+        user_id,
+        -expired,
+        PointsEntryType::Expired,
+        None,
+        None,
+        now,
+    )
+}
+
+// Moves the stock of a goods item and records the movement. Returns the new stock.
+fn apply_stock_change(
+    tx: &mut impl Queryable,
+    goods_id: i32,
+    delta: i32,
+    reason: StockMovementReason,
+    reference: Option<String>,
+    operator_id: Option<i64>,
+) -> Result<i32, CafeHubError> {
+    tx.exec_drop(
+        "UPDATE goods SET stock = COALESCE(stock, 0) + :delta WHERE id = :goods_id",
+        params! {
+            "delta" => delta,
+            "goods_id" => goods_id,
+        },
+    )
+    .map_err(|e| {
+        CafeHubError::database(
+            format!("Failed to update stock for goods ID {}", goods_id),
+            e,
+        )
+    })?;
+
+    let stock_after: i32 = tx
+        .exec_first(
+            "SELECT stock FROM goods WHERE id = :goods_id",
+            params! { "goods_id" => goods_id },
+        )
+        .map_err(|e| {
+            CafeHubError::database(format!("Failed to read stock for goods ID {}", goods_id), e)
+        })?
+        .ok_or(CafeHubError::NotFound {
+            entity: "Goods",
+            id: goods_id.into(),
+        })?;
+
+    tx.exec_drop(
+        "INSERT INTO stock_movement (goods_id, reason, delta, stock_after, reference, operator_id, created_at) VALUES (:goods_id, :reason, :delta, :stock_after, :reference, :operator_id, :created_at)",
+        params! {
+            "goods_id" => goods_id,
+            "reason" => reason.as_str(),
+            "delta" => delta,
+            "stock_after" => stock_after,
+            "reference" => reference,
+            "operator_id" => operator_id,
+            "created_at" => Local::now().naive_local(),
+        },
+    )
+    .map_err(|e| CafeHubError::database(format!("Failed to record stock movement for goods ID {}", goods_id), e))?;
+
+    Ok(stock_after)
+}
+
+// Sets the cost price of a goods item and records it in the cost history,
+// unless the cost is unchanged.
+fn apply_cost_change(
+    tx: &mut impl Queryable,
+    goods_id: i32,
+    cost_price: Decimal,
+    reference: Option<String>,
+    operator_id: Option<i64>,
+) -> Result<(), CafeHubError> {
+    let current_cost: Option<Option<Decimal>> = tx
+        .exec_first(
+            "SELECT cost_price FROM goods WHERE id = :goods_id",
+            params! { "goods_id" => goods_id },
+        )
+        .map_err(|e| {
+            CafeHubError::database(
+                format!("Failed to read cost price for goods ID {}", goods_id),
+                e,
+            )
+        })?;
+    match current_cost {
+        None => {
+            return Err(CafeHubError::NotFound {
+                entity: "Goods",
+                id: goods_id.into(),
+            })
+        }
+        Some(Some(current_cost)) if current_cost == cost_price => return Ok(()),
+        Some(_) => {}
+    }
+
+    tx.exec_drop(
+        "UPDATE goods SET cost_price = :cost_price WHERE id = :goods_id",
+        params! {
+            "cost_price" => cost_price,
+            "goods_id" => goods_id,
+        },
+    )
+    .map_err(|e| {
+        CafeHubError::database(
+            format!("Failed to update cost price for goods ID {}", goods_id),
+            e,
+        )
+    })?;
+
+    tx.exec_drop(
+        "INSERT INTO goods_cost_history (goods_id, cost_price, reference, operator_id, changed_at) VALUES (:goods_id, :cost_price, :reference, :operator_id, :changed_at)",
+        params! {
+            "goods_id" => goods_id,
+            "cost_price" => cost_price,
+            "reference" => reference,
+            "operator_id" => operator_id,
+            "changed_at" => Local::now().naive_local(),
+        },
+    )
+    .map_err(|e| CafeHubError::database(format!("Failed to record cost price for goods ID {}", goods_id), e))?;
+
+    Ok(())
+}
+
+fn load_modifier_groups(
+    conn: &mut impl Queryable,
+    goods_id: i32,
+) -> Result<Vec<ModifierGroup>, CafeHubError> {
+    let rows: Vec<ModifierRow> = conn
+        .exec(
+            "SELECT g.id, g.group_name, g.is_required, g.allow_multiple, o.id, o.option_name, o.price_delta
+             FROM modifier_group g
+             LEFT JOIN modifier_option o ON o.group_id = g.id
+             WHERE g.goods_id = :goods_id
+             ORDER BY g.id ASC, o.id ASC",
+            params! { "goods_id" => goods_id },
+        )
+        .map_err(|e| {
+            CafeHubError::database(
+                format!("Failed to query modifiers for goods ID {}", goods_id),
+                e,
+            )
+        })?;
+    Ok(modifier_groups_from_rows(goods_id, rows))
+}
+
+// Locks the ingredients so that concurrent sales cannot both take the last of them.
+fn load_recipe_usage(
+    conn: &mut impl Queryable,
+    goods_id: i32,
+) -> Result<Vec<RecipeUsage>, CafeHubError> {
+    conn.exec_map(
+        "SELECT r.ingredient_id, i.ingredient_name, r.quantity, i.stock
+         FROM recipe_item r
+         JOIN ingredient i ON r.ingredient_id = i.id
+         WHERE r.goods_id = :goods_id
+         FOR UPDATE",
+        params! { "goods_id" => goods_id },
+        |(ingredient_id, ingredient_name, quantity, stock)| RecipeUsage {
+            ingredient_id,
+            ingredient_name,
+            quantity,
+            stock,
+        },
+    )
+    .map_err(|e| {
+        CafeHubError::database(
+            format!("Failed to query recipe for goods ID {}", goods_id),
+            e,
+        )
+    })
+}
+
+// Drops a message into the administrator's inbox, sent from their own account. Skipped
+// when there is no administrator to receive it.
+fn notify_administrator(
+    conn: &mut impl Queryable,
+    title: &str,
+    message_content: &str,
+    send_date: NaiveDate,
+) -> Result<(), CafeHubError> {
+    let admin_id: Option<i64> = conn
+        .query_first("SELECT id FROM account WHERE user_type = 0 ORDER BY id ASC LIMIT 1")
+        .map_err(|e| CafeHubError::database("Database error finding administrator", e))?;
+    let Some(admin_id) = admin_id else {
+        return Ok(());
+    };
+
+    conn.exec_drop(
+        "INSERT INTO message (sender_id, receiver_id, title, message_content, send_date, read_status) VALUES (:admin_id, :admin_id, :title, :message_content, :send_date, 0)",
+        params! {
+            "admin_id" => admin_id,
+            "title" => title,
+            "message_content" => message_content,
+            "send_date" => send_date,
+        },
+    )
+    .map_err(|e| CafeHubError::database("Failed to notify administrator", e))
+}
+
+fn close_purchase_order(
+    tx: &mut impl Queryable,
+    purchase_order_id: i64,
+    status: PurchaseOrderStatus,
+    operator_id: i64,
+    closed_at: NaiveDateTime,
+) -> Result<(), CafeHubError> {
+    tx.exec_drop(
+        "UPDATE purchase_order SET status = :status, closed_by = :closed_by, closed_at = :closed_at WHERE id = :purchase_order_id",
+        params! {
+            "status" => status.as_str(),
+            "closed_by" => operator_id,
+            "closed_at" => closed_at,
+            "purchase_order_id" => purchase_order_id,
+        },
+    )
+    .map_err(|e| {
+        CafeHubError::database(
+            format!("Failed to update purchase order ID {}", purchase_order_id),
+            e,
+        )
+    })
+}
+
+fn ensure_category_exists(tx: &mut impl Queryable, category_id: i32) -> Result<(), CafeHubError> {
+    let category_exists: Option<i32> = tx
+        .exec_first(
+            "SELECT id FROM category WHERE id = :category_id",
+            params! { "category_id" => category_id },
+        )
+        .map_err(|e| {
+            CafeHubError::database(format!("Failed to query category ID {}", category_id), e)
+        })?;
+    if category_exists.is_none() {
+        return Err(CafeHubError::NotFound {
+            entity: "Category",
+            id: category_id.into(),
+        });
+    }
+    Ok(())
+}
+
+fn ensure_goods_exists(tx: &mut impl Queryable, goods_id: i32) -> Result<(), CafeHubError> {
+    let goods_exists: Option<i32> = tx
+        .exec_first(
+            "SELECT id FROM goods WHERE id = :goods_id",
+            params! { "goods_id" => goods_id },
+        )
+        .map_err(|e| CafeHubError::database(format!("Failed to query goods ID {}", goods_id), e))?;
+    if goods_exists.is_none() {
+        return Err(CafeHubError::NotFound {
+            entity: "Goods",
+            id: goods_id.into(),
+        });
+    }
+    Ok(())
+}
+
+fn load_promotions(
+    conn: &mut impl Queryable,
+    filter: &str,
+) -> Result<Vec<Promotion>, CafeHubError> {
+    let query = format!(
+        "SELECT id, promotion_name, discount_type, discount_value, goods_id, category_id, weekdays, start_time, end_time, start_date, end_date, paused
+         FROM promotion {} ORDER BY id ASC",
+        filter
+    );
+    conn.query_map(
+        query,
+        |(
+            id,
+            promotion_name,
+            discount_type,
+            discount_value,
+            goods_id,
+            category_id,
+            weekdays,
+            start_time,
+            end_time,
+            start_date,
+            end_date,
+            paused,
+        ): PromotionRow| Promotion {
+            id,
+            promotion_name,
+            discount_type,
+            discount_value,
+            goods_id,
+            category_id,
+            weekdays: weekdays_from_mask(weekdays),
+            start_time,
+            end_time,
+            start_date,
+            end_date,
+            paused,
+        },
+    )
+    .map_err(|e| CafeHubError::database("Database query failed for promotions", e))
+}
+
+fn query_coupons<P: Into<mysql::Params>>(
+    conn: &mut impl Queryable,
+    filter: &str,
+    params: P,
+) -> Result<Vec<Coupon>, CafeHubError> {
+    let query = format!(
+        "SELECT c.id, c.code, c.discount_type, c.discount_value, c.min_spend, c.expires_at, c.max_redemptions, c.max_per_customer,
+                (SELECT COUNT(*) FROM coupon_redemption r WHERE r.coupon_id = c.id AND r.refunded_at IS NULL), c.disabled, c.created_at
+         FROM coupon c {}",
+        filter
+    );
+    conn.exec_map(
+        query,
+        params,
+        |(
+            id,
+            code,
+            discount_type,
+            discount_value,
+            min_spend,
+            expires_at,
+            max_redemptions,
+            max_per_customer,
+            times_redeemed,
+            disabled,
+            created_at,
+        ): CouponRow| Coupon {
+            id,
+            code,
+            discount_type,
+            discount_value,
+            min_spend,
+            expires_at,
+            max_redemptions,
+            max_per_customer,
+            times_redeemed,
+            disabled,
+            created_at,
+        },
+    )
+    .map_err(|e| CafeHubError::database("Database query failed for coupons", e))
+}
+
+// The promotion must target goods or a category that exists.
+fn ensure_promotion_target_exists(
+    tx: &mut impl Queryable,
+    data: &PromotionData,
+) -> Result<(), CafeHubError> {
+    if let Some(goods_id) = data.goods_id {
+        ensure_goods_exists(tx, goods_id)?;
+    }
+    if let Some(category_id) = data.category_id {
+        ensure_category_exists(tx, category_id)?;
+    }
+    Ok(())
+}
+
+fn promotion_params(data: &PromotionData) -> Vec<(String, mysql::Value)> {
+    vec![
+        (
+            "promotion_name".to_string(),
+            data.promotion_name.clone().into(),
+        ),
+        (
+            "discount_type".to_string(),
+            data.discount_type.as_str().into(),
+        ),
+        ("discount_value".to_string(), data.discount_value.into()),
+        ("goods_id".to_string(), data.goods_id.into()),
+        ("category_id".to_string(), data.category_id.into()),
+        ("weekdays".to_string(), weekday_mask(&data.weekdays).into()),
+        ("start_time".to_string(), data.start_time.into()),
+        ("end_time".to_string(), data.end_time.into()),
+        ("start_date".to_string(), data.start_date.into()),
+        ("end_date".to_string(), data.end_date.into()),
+        ("paused".to_string(), data.paused.into()),
+    ]
+}
+
+pub struct MySqlStorage {
+    pool: Pool,
+}
+
+impl MySqlStorage {
+    pub fn new(pool: Pool) -> Self {
+        MySqlStorage { pool }
+    }
+
+    // Connects and migrates. Returns `None` if the configured database does not exist yet.
+    pub fn open(config: &DatabaseConfig) -> Result<Option<Self>, String> {
+        let pool = match Pool::new(config.to_opts()?) {
+            Ok(pool) => pool,
+            Err(ref e) if is_missing_database(e) => return Ok(None),
+            Err(e) => return Err(format!("Failed to create DB pool: {}", e)),
+        };
+        MySqlStorage::from_pool(pool).map(Some)
+    }
+
+    // Migrates the database the pool points at. The tests use this with a scratch database.
+    pub fn from_pool(pool: Pool) -> Result<Self, String> {
+        let applied = migrations::run_pending_mysql(&pool)?;
+        println!(
+            "Database schema is at version {} ({} migration(s) applied).",
+            migrations::latest_version(),
+            applied
+        );
+        Ok(MySqlStorage::new(pool))
+    }
+
+    pub fn database_exists(config: &DatabaseConfig) -> Result<bool, String> {
+        let mut conn = Conn::new(config.to_server_opts()?)
+            .map_err(|e| format!("Failed to connect to database server: {}", e))?;
+        let schema: Option<String> = conn
+            .exec_first(
+                "SELECT SCHEMA_NAME FROM information_schema.SCHEMATA WHERE SCHEMA_NAME = :database",
+                params! { "database" => config.database() },
+            )
+            .map_err(|e| format!("Failed to look up database: {}", e))?;
+        Ok(schema.is_some())
+    }
+
+    pub fn create_database(config: &DatabaseConfig) -> Result<(), String> {
+        let mut server_conn = Conn::new(config.to_server_opts()?)
+            .map_err(|e| format!("Failed to connect to database server: {}", e))?;
+        server_conn
+            .query_drop(format!(
+                "CREATE DATABASE IF NOT EXISTS `{}` CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci",
+                config.database()
+            ))
+            .map_err(|e| format!("Failed to create database: {}", e))?;
+        println!("Created database '{}'.", config.database());
+        Ok(())
+    }
+
+    fn conn(&self) -> Result<PooledConn, CafeHubError> {
+        self.pool
+            .get_conn()
+            .map_err(|e| CafeHubError::database("Failed to get DB connection", e))
+    }
+
+    // `filter` is appended to the query and can refer to any column of `GOODS_SOURCE`.
+    fn query_goods<P: Into<mysql::Params>>(
+        &self,
+        filter: &str,
+        params: P,
+    ) -> Result<Vec<Goods>, CafeHubError> {
+        let query = format!(
+            "
+            SELECT id, goods_name, category_id, category, description, price, available, stock,
+                reorder_threshold, cost_price, archived_at, image_key, points_price
+            FROM ({}) AS available
+            {}",
+            GOODS_SOURCE, filter
+        );
+
+        self.conn()?
+            .exec_map(query, params, |row: GoodsRow| Goods {
+                id: row.id,
+                goods_name: row.goods_name,
+                category_id: row.category_id,
+                category: row.category,
+                description: row.description,
+                price: row.price,
+                available: row.available,
+                stock: row.stock,
+                reorder_threshold: row.reorder_threshold,
+                cost_price: row.cost_price,
+                points_price: row.points_price,
+                archived_at: row.archived_at,
+                image: row
+                    .image_key
+                    .as_deref()
+                    .map(|key| goods_image_url(row.id, key, ImageSize::Full)),
+                thumbnail: row
+                    .image_key
+                    .as_deref()
+                    .map(|key| goods_image_url(row.id, key, ImageSize::Thumbnail)),
+            })
+            .map_err(|e| CafeHubError::database("Database query failed for goods", e))
+    }
+
+    fn list_messages(&self, column: &str, user_id: i64) -> Result<Vec<MessageInfo>, CafeHubError> {
+        let query = format!(
+            "
+            SELECT
+                m.id, m.sender_id, m.receiver_id,
+                s_acc.username AS sender_username,
+                r_acc.username AS receiver_username,
+                m.title, m.message_content, m.send_date, m.read_status
+            FROM message m
+            JOIN account s_acc ON m.sender_id = s_acc.id
+            JOIN account r_acc ON m.receiver_id = r_acc.id
+            WHERE m.{} = :user_id",
+            column
+        );
+
+        self.conn()?
+            .exec_map(
+                query,
+                params! { "user_id" => user_id },
+                |(
+                    id,
+                    sender_id,
+                    receiver_id,
+                    sender_username,
+                    receiver_username,
+                    title,
+                    message_content,
+                    send_date,
+                    read_status,
+                )| {
+                    MessageInfo {
+                        id,
+                        sender_id,
+                        receiver_id,
+                        sender_username,
+                        receiver_username,
+                        title,
+                        message_content,
+                        send_date,
+                        read_status,
+                    }
+                },
+            )
+            .map_err(|e| CafeHubError::database("Database query failed for messages", e))
+    }
+}
+
+impl AccountRepository for MySqlStorage {
+    fn find_account_by_username(
+        &self,
+        username: &str,
+    ) -> Result<Option<(Account, String)>, CafeHubError> {
+        let row: Option<CredentialsRow> = self
+            .conn()?
+            .exec_first(
+                "SELECT id, username, password, phone, gender, join_time, balance, points, user_type FROM account WHERE username = :username",
+                params! { "username" => username },
+            )
+            .map_err(|e| CafeHubError::database("Database query failed", e))?;
+
+        Ok(row.map(
+            |(id, username, password, phone, gender, join_time, balance, points, user_type)| {
+                (
+                    account_from_row((
+                        id, username, phone, gender, join_time, balance, points, user_type,
+                    )),
+                    password,
+                )
+            },
+        ))
+    }
+
+    fn find_customer(&self, user_id: i64) -> Result<Option<Account>, CafeHubError> {
+        let row: Option<AccountRow> = self
+            .conn()?
+            .exec_first(
+                "SELECT id, username, phone, gender, join_time, balance, points, user_type FROM account WHERE id = :user_id AND user_type = 1",
+                params! { "user_id" => user_id },
+            )
+            .map_err(|e| CafeHubError::database("Database query failed", e))?;
+        Ok(row.map(account_from_row))
+    }
+
+    fn find_user_type(&self, user_id: i64) -> Result<Option<i8>, CafeHubError> {
+        self.conn()?
+            .exec_first(
+                "SELECT user_type FROM account WHERE id = :user_id",
+                params! { "user_id" => user_id },
+            )
+            .map_err(|e| CafeHubError::database("Failed to query user", e))
+    }
+
+    fn find_first_staff_id(&self) -> Result<Option<i64>, CafeHubError> {
+        self.conn()?
+            .query_first("SELECT id FROM account WHERE user_type = 0 ORDER BY id ASC LIMIT 1")
+            .map_err(|e| CafeHubError::database("Database error finding administrator", e))
+    }
+
+    fn customer_password_hash(&self, user_id: i64) -> Result<Option<String>, CafeHubError> {
+        self.conn()?
+            .exec_first(
+                "SELECT password FROM account WHERE id = :user_id AND user_type = 1",
+                params! { "user_id" => user_id },
+            )
+            .map_err(|e| CafeHubError::database("Database query failed", e))
+    }
+
+    fn create_customer(&self, account: &NewAccount) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        conn.exec_drop(
+            "INSERT INTO account (username, password, phone, gender, join_time, balance, user_type) VALUES (:username, :password, :phone, :gender, :join_time, :balance, :user_type)",
+            params! {
+                "username" => &account.username,
+                "password" => &account.password_hash,
+                "phone" => &account.phone,
+                "gender" => account.gender,
+                "join_time" => account.join_time,
+                "balance" => Decimal::new(0, 2),
+                "user_type" => 1i8,
+            },
+        )
+        .map_err(|e| {
+            if is_duplicate_entry(&e) {
+                CafeHubError::DuplicateUsername {
+                    username: account.username.clone(),
+                }
+            } else {
+                CafeHubError::database("Database error during registration", e)
+            }
+        })?;
+        Ok(conn.last_insert_id() as i64)
+    }
+
+    fn create_first_staff(&self, account: &NewAccount) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .start_transaction(mysql::TxOpts::default())
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        // Locks the account table so two setup calls cannot both create an admin.
+        let admin_ids: Vec<i64> = tx
+            .query("SELECT id FROM account WHERE user_type = 0 FOR UPDATE")
+            .map_err(|e| CafeHubError::database("Failed to check for existing administrator", e))?;
+        if !admin_ids.is_empty() {
+            return Err(CafeHubError::conflict(
+                "Setup has already been completed: an administrator account exists.",
+            ));
+        }
+
+        tx.exec_drop(
+            "INSERT INTO account (username, password, join_time, user_type) VALUES (:username, :password, :join_time, :user_type)",
+            params! {
+                "username" => &account.username,
+                "password" => &account.password_hash,
+                "join_time" => account.join_time,
+                "user_type" => 0i8,
+            },
+        )
+        .map_err(|e| CafeHubError::database("Failed to create administrator account", e))?;
+        let admin_id = tx
+            .last_insert_id()
+            .ok_or_else(|| CafeHubError::internal("Failed to get ID of the new administrator"))?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(admin_id as i64)
+    }
+
+    fn update_customer_password(
+        &self,
+        user_id: i64,
+        password_hash: &str,
+    ) -> Result<bool, CafeHubError> {
+        let mut conn = self.conn()?;
+        conn.exec_drop(
+            "UPDATE account SET password = :new_password WHERE id = :user_id AND user_type = 1",
+            params! {
+                "new_password" => password_hash,
+                "user_id" => user_id,
+            },
+        )
+        .map_err(|e| CafeHubError::database("Database password update failed", e))?;
+        Ok(conn.affected_rows() > 0)
+    }
+
+    fn update_customer_details(
+        &self,
+        user_id: i64,
+        changes: &CustomerChanges,
+    ) -> Result<bool, CafeHubError> {
+        let mut conn = self.conn()?;
+
+        let mut set_clauses: Vec<String> = Vec::new();
+        let mut query_params: Vec<(String, mysql::Value)> = Vec::new();
+
+        if let Some(ref username) = changes.username {
+            let existing_user: Option<i64> = conn.exec_first(
+                "SELECT id FROM account WHERE username = :username AND id != :user_id_to_exclude",
+                params! { "username" => username, "user_id_to_exclude" => user_id }
+            ).map_err(|e| CafeHubError::database("DB error checking username uniqueness", e))?;
+
+            if existing_user.is_some() {
+                return Err(CafeHubError::DuplicateUsername {
+                    username: username.clone(),
+                });
+            }
+            set_clauses.push("username = :username".to_string());
+            query_params.push(("username".to_string(), username.clone().into()));
+        }
+
+        if let Some(ref phone) = changes.phone {
+            set_clauses.push("phone = :phone".to_string());
+            query_params.push(("phone".to_string(), phone.clone().into()));
+        }
+
+        if let Some(gender) = changes.gender {
+            set_clauses.push("gender = :gender".to_string());
+            query_params.push(("gender".to_string(), gender.into()));
+        }
+
+        if set_clauses.is_empty() {
+            return Ok(false);
+        }
+
+        query_params.push(("user_id".to_string(), user_id.into()));
+        let query = format!(
+            "UPDATE account SET {} WHERE id = :user_id AND user_type = 1",
+            set_clauses.join(", ")
+        );
+
+        conn.exec_drop(&query, mysql::Params::from(query_params))
+            .map_err(|e| {
+                if is_duplicate_entry(&e) {
+                    CafeHubError::conflict("Update failed: Username or Phone number might already be in use by another account.")
+                } else {
+                    CafeHubError::database("Database update failed", e)
+                }
+            })?;
+        Ok(conn.affected_rows() > 0)
+    }
+
+    fn count_customers(&self, joined_since: Option<NaiveDate>) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        let count: Option<i64> = match joined_since {
+            Some(start_date) => conn.exec_first(
+                "SELECT COUNT(*) FROM account WHERE user_type = 1 AND join_time >= :start_date",
+                params! { "start_date" => start_date },
+            ),
+            None => conn.query_first("SELECT COUNT(*) FROM account WHERE user_type = 1"),
+        }
+        .map_err(|e| CafeHubError::database("Database query failed", e))?;
+        Ok(count.unwrap_or(0))
+    }
+
+    fn count_staff(&self) -> Result<i64, CafeHubError> {
+        let count: Option<i64> = self
+            .conn()?
+            .query_first("SELECT COUNT(*) FROM account WHERE user_type = 0")
+            .map_err(|e| CafeHubError::database("Failed to count administrator accounts", e))?;
+        Ok(count.unwrap_or(0))
+    }
+
+    fn list_users(&self) -> Result<Vec<UserBasicInfo>, CafeHubError> {
+        self.conn()?
+            .query_map(
+                "SELECT id, username FROM account ORDER BY id ASC",
+                |(id, username)| UserBasicInfo { id, username },
+            )
+            .map_err(|e| CafeHubError::database("Database query failed for fetching all users", e))
+    }
+}
+
+impl WalletRepository for MySqlStorage {
+    fn recharge_balance(
+        &self,
+        user_id: i64,
+        amount: Decimal,
+        operator_id: i64,
+    ) -> Result<Decimal, CafeHubError> {
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .start_transaction(mysql::TxOpts::default())
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let user_type: Option<i8> = tx
+            .exec_first(
+                "SELECT user_type FROM account WHERE id = :user_id FOR UPDATE",
+                params! { "user_id" => user_id },
+            )
+            .map_err(|e| CafeHubError::database("Failed to query user", e))?;
+
+        match user_type {
+            Some(1) => {}
+            Some(_) => {
+                return Err(CafeHubError::validation(format!(
+                    "User with ID {} is not a customer.",
+                    user_id
+                )))
+            }
+            None => {
+                return Err(CafeHubError::NotFound {
+                    entity: "User",
+                    id: user_id,
+                })
+            }
+        }
+
+        let balance_after = apply_balance_change(
+            &mut tx,
+            user_id,
+            amount,
+            LedgerEntryType::Recharge,
+            None,
+            Some(operator_id),
+        )?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(balance_after)
+    }
+
+    fn adjust_balance(
+        &self,
+        user_id: i64,
+        amount: Decimal,
+        note: &str,
+        operator_id: i64,
+    ) -> Result<Decimal, CafeHubError> {
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .start_transaction(mysql::TxOpts::default())
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let user_info: Option<(Option<Decimal>, i8)> = tx
+            .exec_first(
+                "SELECT balance, user_type FROM account WHERE id = :user_id FOR UPDATE",
+                params! { "user_id" => user_id },
+            )
+            .map_err(|e| CafeHubError::database("Failed to query user", e))?;
+
+        let current_balance = match user_info {
+            Some((balance, 1)) => balance.unwrap_or(Decimal::ZERO),
+            Some(_) => {
+                return Err(CafeHubError::validation(format!(
+                    "User with ID {} is not a customer.",
+                    user_id
+                )))
+            }
+            None => {
+                return Err(CafeHubError::NotFound {
+                    entity: "User",
+                    id: user_id,
+                })
+            }
+        };
+
+        if current_balance + amount < Decimal::ZERO {
+            return Err(CafeHubError::InsufficientBalance {
+                required: -amount,
+                available: current_balance,
+                shortfall: -(current_balance + amount),
+            });
+        }
+
+        let balance_after = apply_balance_change(
+            &mut tx,
+            user_id,
+            amount,
+            LedgerEntryType::Adjustment,
+            Some(note.to_string()),
+            Some(operator_id),
+        )?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(balance_after)
+    }
+
+    fn balance_statement(&self, user_id: i64) -> Result<Vec<BalanceLedgerEntry>, CafeHubError> {
+        let query = "
+            SELECT l.id, l.entry_type, l.amount, l.balance_after, l.reference, op.username, l.created_at
+            FROM balance_ledger l
+            LEFT JOIN account op ON l.operator_id = op.id
+            WHERE l.user_id = :user_id
+            ORDER BY l.created_at DESC, l.id DESC";
+
+        self.conn()?
+            .exec_map(
+                query,
+                params! { "user_id" => user_id },
+                |(id, entry_type, amount, balance_after, reference, operator_name, created_at)| {
+                    BalanceLedgerEntry {
+                        id,
+                        entry_type,
+                        amount,
+                        balance_after,
+                        reference,
+                        operator_name,
+                        created_at,
+                    }
+                },
+            )
+            .map_err(|e| CafeHubError::database("Database query failed for balance statement", e))
+    }
+
+    fn balance_mismatches(&self) -> Result<Vec<BalanceMismatch>, CafeHubError> {
+        let query = "
+            SELECT a.id, a.username, COALESCE(a.balance, 0) AS current_balance, COALESCE(SUM(l.amount), 0) AS ledger_total
+            FROM account a
+            LEFT JOIN balance_ledger l ON l.user_id = a.id
+            WHERE a.user_type = 1
+            GROUP BY a.id, a.username, a.balance
+            HAVING current_balance <> ledger_total
+            ORDER BY a.id ASC";
+
+        self.conn()?
+            .query_map(query, |(user_id, username, balance, ledger_total)| {
+                BalanceMismatch {
+                    user_id,
+                    username,
+                    balance,
+                    ledger_total,
+                }
+            })
+            .map_err(|e| {
+                CafeHubError::database("Database query failed for balance consistency check", e)
+            })
+    }
+}
+
+impl LoyaltyRepository for MySqlStorage {
+    fn spendable_points(&self, user_id: i64, now: NaiveDateTime) -> Result<i64, CafeHubError> {
+        self.conn()?
+            .exec_first(
+                "SELECT CAST(points - COALESCE((SELECT SUM(remaining) FROM points_ledger WHERE user_id = :user_id AND remaining > 0 AND expires_at <= :now), 0) AS SIGNED) FROM account WHERE id = :user_id",
+                params! {
+                    "user_id" => user_id,
+                    "now" => now,
+                },
+            )
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to read points for user ID {}", user_id), e)
+            })?
+            .ok_or(CafeHubError::NotFound {
+                entity: "User",
+                id: user_id,
+            })
+    }
+
+    fn points_history(&self, user_id: i64) -> Result<Vec<PointsLedgerEntry>, CafeHubError> {
+        let query = "
+            SELECT id, entry_type, points, points_after, remaining, expires_at, reference, created_at
+            FROM points_ledger
+            WHERE user_id = :user_id
+            ORDER BY created_at DESC, id DESC";
+
+        self.conn()?
+            .exec_map(
+                query,
+                params! { "user_id" => user_id },
+                |(
+                    id,
+                    entry_type,
+                    points,
+                    points_after,
+                    remaining,
+                    expires_at,
+                    reference,
+                    created_at,
+                )| PointsLedgerEntry {
+                    id,
+                    entry_type,
+                    points,
+                    points_after,
+                    remaining,
+                    expires_at,
+                    reference,
+                    created_at,
+                },
+            )
+            .map_err(|e| CafeHubError::database("Database query failed for points history", e))
+    }
+}
+
+impl GoodsRepository for MySqlStorage {
+    fn list_goods(&self) -> Result<Vec<Goods>, CafeHubError> {
+        self.query_goods("", ())
+    }
+
+    fn adjust_stock(
+        &self,
+        goods_id: i32,
+        delta: i32,
+        reason: StockMovementReason,
+        note: Option<&str>,
+        operator_id: i64,
+    ) -> Result<i32, CafeHubError> {
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .start_transaction(mysql::TxOpts::default())
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let current_stock: Option<Option<i32>> = tx
+            .exec_first(
+                "SELECT stock FROM goods WHERE id = :goods_id FOR UPDATE",
+                params! { "goods_id" => goods_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to query goods ID {}", goods_id), e)
+            })?;
+        let current_stock = match current_stock {
+            Some(stock) => stock.unwrap_or(0),
+            None => {
+                return Err(CafeHubError::NotFound {
+                    entity: "Goods",
+                    id: goods_id.into(),
+                })
+            }
+        };
+
+        if current_stock + delta < 0 {
+            return Err(CafeHubError::InsufficientStock {
+                goods_id,
+                requested: -delta,
+                available: current_stock,
+            });
+        }
+
+        let stock_after = apply_stock_change(
+            &mut tx,
+            goods_id,
+            delta,
+            reason,
+            note.map(str::to_string),
+            Some(operator_id),
+        )?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(stock_after)
+    }
+
+    fn stock_movements(&self, goods_id: i32) -> Result<Vec<StockMovement>, CafeHubError> {
+        let query = "
+            SELECT m.id, m.reason, m.delta, m.stock_after, m.reference, op.username, m.created_at
+            FROM stock_movement m
+            LEFT JOIN account op ON m.operator_id = op.id
+            WHERE m.goods_id = :goods_id
+            ORDER BY m.created_at DESC, m.id DESC";
+
+        self.conn()?
+            .exec_map(
+                query,
+                params! { "goods_id" => goods_id },
+                |(id, reason, delta, stock_after, reference, operator_name, created_at)| {
+                    StockMovement {
+                        id,
+                        reason,
+                        delta,
+                        stock_after,
+                        reference,
+                        operator_name,
+                        created_at,
+                    }
+                },
+            )
+            .map_err(|e| CafeHubError::database("Database query failed for stock movements", e))
+    }
+
+    fn cost_history(&self, goods_id: i32) -> Result<Vec<GoodsCostChange>, CafeHubError> {
+        let query = "
+            SELECT h.id, h.cost_price, h.reference, op.username, h.changed_at
+            FROM goods_cost_history h
+            LEFT JOIN account op ON h.operator_id = op.id
+            WHERE h.goods_id = :goods_id
+            ORDER BY h.changed_at DESC, h.id DESC";
+
+        self.conn()?
+            .exec_map(
+                query,
+                params! { "goods_id" => goods_id },
+                |(id, cost_price, reference, operator_name, changed_at)| GoodsCostChange {
+                    id,
+                    cost_price,
+                    reference,
+                    operator_name,
+                    changed_at,
+                },
+            )
+            .map_err(|e| CafeHubError::database("Database query failed for cost history", e))
+    }
+
+    fn set_goods_archived(
+        &self,
+        goods_id: i32,
+        archived_at: Option<NaiveDateTime>,
+    ) -> Result<bool, CafeHubError> {
+        let mut conn = self.conn()?;
+        let goods_exists: Option<i32> = conn
+            .exec_first(
+                "SELECT id FROM goods WHERE id = :goods_id",
+                params! { "goods_id" => goods_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to query goods ID {}", goods_id), e)
+            })?;
+        if goods_exists.is_none() {
+            return Ok(false);
+        }
+
+        conn.exec_drop(
+            "UPDATE goods SET archived_at = CASE WHEN :archived_at IS NULL THEN NULL ELSE COALESCE(archived_at, :archived_at) END
+             WHERE id = :goods_id",
+            params! {
+                "archived_at" => archived_at,
+                "goods_id" => goods_id,
+            },
+        )
+        .map_err(|e| {
+            CafeHubError::database(format!("Failed to archive goods ID {}", goods_id), e)
+        })?;
+        Ok(true)
+    }
+
+    fn delete_goods(&self, goods_id: i32) -> Result<bool, CafeHubError> {
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .start_transaction(mysql::TxOpts::default())
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let goods_exists: Option<i32> = tx
+            .exec_first(
+                "SELECT id FROM goods WHERE id = :goods_id FOR UPDATE",
+                params! { "goods_id" => goods_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to query goods ID {}", goods_id), e)
+            })?;
+        if goods_exists.is_none() {
+            return Ok(false);
+        }
+
+        let has_history: Option<bool> = tx
+            .exec_first(
+                "SELECT EXISTS (SELECT 1 FROM order_items WHERE goods_id = :goods_id)
+                    OR EXISTS (SELECT 1 FROM consumption WHERE goods_id = :goods_id)
+                    OR EXISTS (SELECT 1 FROM purchase_order_item WHERE goods_id = :goods_id)",
+                params! { "goods_id" => goods_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Failed to check history of goods ID {}", goods_id),
+                    e,
+                )
+            })?;
+        if has_history.unwrap_or(false) {
+            return Err(CafeHubError::conflict(format!(
+                "Goods ID {} has sales or purchase history and can only be archived.",
+                goods_id
+            )));
+        }
+
+        tx.exec_drop(
+            "DELETE FROM goods WHERE id = :goods_id",
+            params! { "goods_id" => goods_id },
+        )
+        .map_err(|e| {
+            CafeHubError::database(format!("Failed to delete goods ID {}", goods_id), e)
+        })?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(true)
+    }
+
+    fn set_goods_image(&self, goods_id: i32, image: &GoodsImage) -> Result<bool, CafeHubError> {
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .start_transaction(mysql::TxOpts::default())
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let goods_exists: Option<i32> = tx
+            .exec_first(
+                "SELECT id FROM goods WHERE id = :goods_id FOR UPDATE",
+                params! { "goods_id" => goods_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to query goods ID {}", goods_id), e)
+            })?;
+        if goods_exists.is_none() {
+            return Ok(false);
+        }
+
+        tx.exec_drop(
+            "INSERT INTO goods_image (goods_id, image_key, image, thumbnail) VALUES (:goods_id, :image_key, :image, :thumbnail)
+             ON DUPLICATE KEY UPDATE image_key = VALUES(image_key), image = VALUES(image), thumbnail = VALUES(thumbnail)",
+            params! {
+                "goods_id" => goods_id,
+                "image_key" => &image.image_key,
+                "image" => image.image.as_slice(),
+                "thumbnail" => image.thumbnail.as_slice(),
+            },
+        )
+        .map_err(|e| {
+            CafeHubError::database(format!("Failed to save image of goods ID {}", goods_id), e)
+        })?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(true)
+    }
+
+    fn remove_goods_image(&self, goods_id: i32) -> Result<bool, CafeHubError> {
+        let mut conn = self.conn()?;
+        conn.exec_drop(
+            "DELETE FROM goods_image WHERE goods_id = :goods_id",
+            params! { "goods_id" => goods_id },
+        )
+        .map_err(|e| {
+            CafeHubError::database(
+                format!("Failed to remove image of goods ID {}", goods_id),
+                e,
+            )
+        })?;
+        Ok(conn.affected_rows() > 0)
+    }
+
+    fn find_goods_image(
+        &self,
+        goods_id: i32,
+        size: ImageSize,
+    ) -> Result<Option<Vec<u8>>, CafeHubError> {
+        let column = match size {
+            ImageSize::Full => "image",
+            ImageSize::Thumbnail => "thumbnail",
+        };
+        self.conn()?
+            .exec_first(
+                format!(
+                    "SELECT {} FROM goods_image WHERE goods_id = :goods_id",
+                    column
+                ),
+                params! { "goods_id" => goods_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to load image of goods ID {}", goods_id), e)
+            })
+    }
+
+    fn search_goods(
+        &self,
+        query: &GoodsQuery,
+        include_archived: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<(Vec<Goods>, i64), CafeHubError> {
+        let search = query.search.as_deref().map(like_pattern);
+        let params = params! {
+            "include_archived" => include_archived,
+            "search" => search,
+            "category_id" => query.category_id,
+            "min_price" => query.min_price,
+            "max_price" => query.max_price,
+            "in_stock_only" => query.in_stock_only,
+        };
+
+        let total: Option<i64> = self
+            .conn()?
+            .exec_first(
+                format!(
+                    "SELECT COUNT(*) FROM ({}) AS available {}",
+                    GOODS_SOURCE, GOODS_SEARCH_FILTER
+                ),
+                params.clone(),
+            )
+            .map_err(|e| CafeHubError::database("Failed to count matching goods", e))?;
+        let goods = self.query_goods(
+            &format!(
+                "{} ORDER BY {} LIMIT {} OFFSET {}",
+                GOODS_SEARCH_FILTER,
+                goods_sort_order(query.sort),
+                limit,
+                offset
+            ),
+            params,
+        )?;
+        Ok((goods, total.unwrap_or(0)))
+    }
+
+    fn list_low_stock_goods(&self) -> Result<Vec<Goods>, CafeHubError> {
+        self.query_goods(
+            "WHERE archived_at IS NULL AND reorder_threshold IS NOT NULL AND stock <= reorder_threshold ORDER BY stock ASC, id ASC",
+            (),
+        )
+    }
+
+    fn create_goods(&self, data: &AddGoodsData, operator_id: i64) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .start_transaction(mysql::TxOpts::default())
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        if let Some(category_id) = data.category_id {
+            ensure_category_exists(&mut tx, category_id)?;
+        }
+        tx.exec_drop(
+            "INSERT INTO goods (goods_name, category_id, description, price, stock, reorder_threshold, points_price) VALUES (:goods_name, :category_id, :description, :price, 0, :reorder_threshold, :points_price)",
+            params! {
+                "goods_name" => &data.goods_name,
+                "category_id" => data.category_id,
+                "description" => &data.description,
+                "price" => data.price,
+                "reorder_threshold" => data.reorder_threshold,
+                "points_price" => data.points_price,
+            },
+        )
+        .map_err(|e| {
+            if is_duplicate_entry(&e) {
+                CafeHubError::DuplicateGoodsName {
+                    goods_name: data.goods_name.clone(),
+                }
+            } else {
+                CafeHubError::database("Database error while adding goods", e)
+            }
+        })?;
+        let goods_id = tx
+            .last_insert_id()
+            .ok_or_else(|| CafeHubError::internal("Failed to get ID of new goods"))?;
+
+        let initial_stock = data.stock.unwrap_or(0);
+        if initial_stock != 0 {
+            apply_stock_change(
+                &mut tx,
+                goods_id as i32,
+                initial_stock,
+                StockMovementReason::Restock,
+                Some("initial stock".to_string()),
+                Some(operator_id),
+            )?;
+        }
+        if let Some(cost_price) = data.cost_price {
+            apply_cost_change(
+                &mut tx,
+                goods_id as i32,
+                cost_price,
+                None,
+                Some(operator_id),
+            )?;
+        }
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(goods_id as i64)
+    }
+
+    fn update_goods(
+        &self,
+        goods_id: i32,
+        changes: &GoodsChanges,
+        operator_id: i64,
+    ) -> Result<bool, CafeHubError> {
+        let mut set_clauses: Vec<String> = Vec::new();
+        let mut query_params: Vec<(String, mysql::Value)> = Vec::new();
+
+        if let Some(ref goods_name) = changes.goods_name {
+            set_clauses.push("goods_name = :goods_name".to_string());
+            query_params.push(("goods_name".to_string(), goods_name.clone().into()));
+        }
+        if let Some(category_id) = changes.category_id {
+            set_clauses.push("category_id = :category_id".to_string());
+            query_params.push(("category_id".to_string(), category_id.into()));
+        }
+        if let Some(ref description) = changes.description {
+            set_clauses.push("description = :description".to_string());
+            query_params.push(("description".to_string(), description.clone().into()));
+        }
+        if let Some(price) = changes.price {
+            set_clauses.push("price = :price".to_string());
+            query_params.push(("price".to_string(), price.into()));
+        }
+        if let Some(available) = changes.available {
+            set_clauses.push("available = :available".to_string());
+            query_params.push(("available".to_string(), available.into()));
+        }
+        if let Some(reorder_threshold) = changes.reorder_threshold {
+            set_clauses.push("reorder_threshold = :reorder_threshold".to_string());
+            query_params.push(("reorder_threshold".to_string(), reorder_threshold.into()));
+        }
+        if let Some(points_price) = changes.points_price {
+            set_clauses.push("points_price = :points_price".to_string());
+            query_params.push(("points_price".to_string(), points_price.into()));
+        }
+        if changes.is_empty() {
+            return Ok(false);
+        }
+
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .start_transaction(mysql::TxOpts::default())
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let goods_exists: Option<i32> = tx
+            .exec_first(
+                "SELECT id FROM goods WHERE id = :goods_id FOR UPDATE",
+                params! { "goods_id" => goods_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to query goods ID {}", goods_id), e)
+            })?;
+        if goods_exists.is_none() {
+            return Ok(false);
+        }
+        if let Some(Some(category_id)) = changes.category_id {
+            ensure_category_exists(&mut tx, category_id)?;
+        }
+
+        if !set_clauses.is_empty() {
+            query_params.push(("goods_id".to_string(), goods_id.into()));
+            let query = format!(
+                "UPDATE goods SET {} WHERE id = :goods_id",
+                set_clauses.join(", ")
+            );
+            tx.exec_drop(&query, mysql::Params::from(query_params))
+                .map_err(|e| match changes.goods_name {
+                    Some(ref goods_name) if is_duplicate_entry(&e) => {
+                        CafeHubError::DuplicateGoodsName {
+                            goods_name: goods_name.clone(),
+                        }
+                    }
+                    _ => CafeHubError::database("Database error while updating goods info", e),
+                })?;
+        }
+        if let Some(cost_price) = changes.cost_price {
+            apply_cost_change(&mut tx, goods_id, cost_price, None, Some(operator_id))?;
+        }
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(true)
+    }
+}
+
+impl CategoryRepository for MySqlStorage {
+    fn list_categories(&self) -> Result<Vec<Category>, CafeHubError> {
+        self.conn()?
+            .query_map(
+                "SELECT id, category_name, display_name, sort_order FROM category ORDER BY sort_order ASC, id ASC",
+                |(id, category_name, display_name, sort_order)| Category {
+                    id,
+                    category_name,
+                    display_name,
+                    sort_order,
+                },
+            )
+            .map_err(|e| CafeHubError::database("Database query failed for categories", e))
+    }
+
+    fn create_category(&self, data: &AddCategoryData) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        conn.exec_drop(
+            "INSERT INTO category (category_name, display_name, sort_order) VALUES (:category_name, COALESCE(:display_name, :category_name), COALESCE(:sort_order, 0))",
+            params! {
+                "category_name" => &data.category_name,
+                "display_name" => &data.display_name,
+                "sort_order" => data.sort_order,
+            },
+        )
+        .map_err(|e| {
+            if is_duplicate_entry(&e) {
+                CafeHubError::DuplicateCategoryName {
+                    category_name: data.category_name.clone(),
+                }
+            } else {
+                CafeHubError::database("Database error while adding category", e)
+            }
+        })?;
+        Ok(conn.last_insert_id() as i64)
+    }
+
+    fn update_category(
+        &self,
+        category_id: i32,
+        data: &UpdateCategoryData,
+    ) -> Result<bool, CafeHubError> {
+        let mut set_clauses: Vec<String> = Vec::new();
+        let mut query_params: Vec<(String, mysql::Value)> = Vec::new();
+
+        if let Some(ref category_name) = data.category_name {
+            set_clauses.push("category_name = :category_name".to_string());
+            query_params.push(("category_name".to_string(), category_name.clone().into()));
+        }
+        if let Some(ref display_name) = data.display_name {
+            set_clauses.push("display_name = :display_name".to_string());
+            query_params.push(("display_name".to_string(), display_name.clone().into()));
+        }
+        if let Some(sort_order) = data.sort_order {
+            set_clauses.push("sort_order = :sort_order".to_string());
+            query_params.push(("sort_order".to_string(), sort_order.into()));
+        }
+        if set_clauses.is_empty() {
+            return Ok(false);
+        }
+
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .start_transaction(mysql::TxOpts::default())
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        // Affected rows leave out rows that already held the new values.
+        let category_exists: Option<i32> = tx
+            .exec_first(
+                "SELECT id FROM category WHERE id = :category_id FOR UPDATE",
+                params! { "category_id" => category_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to query category ID {}", category_id), e)
+            })?;
+        if category_exists.is_none() {
+            return Ok(false);
+        }
+
+        query_params.push(("category_id".to_string(), category_id.into()));
+        let query = format!(
+            "UPDATE category SET {} WHERE id = :category_id",
+            set_clauses.join(", ")
+        );
+        tx.exec_drop(&query, mysql::Params::from(query_params))
+            .map_err(|e| match data.category_name {
+                Some(ref category_name) if is_duplicate_entry(&e) => {
+                    CafeHubError::DuplicateCategoryName {
+                        category_name: category_name.clone(),
+                    }
+                }
+                _ => CafeHubError::database("Database error while updating category", e),
+            })?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(true)
+    }
+
+    fn delete_category(&self, category_id: i32) -> Result<bool, CafeHubError> {
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .start_transaction(mysql::TxOpts::default())
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let category_exists: Option<i32> = tx
+            .exec_first(
+                "SELECT id FROM category WHERE id = :category_id FOR UPDATE",
+                params! { "category_id" => category_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to query category ID {}", category_id), e)
+            })?;
+        if category_exists.is_none() {
+            return Ok(false);
+        }
+
+        let goods_count: Option<i64> = tx
+            .exec_first(
+                "SELECT COUNT(*) FROM goods WHERE category_id = :category_id",
+                params! { "category_id" => category_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Failed to count goods in category ID {}", category_id),
+                    e,
+                )
+            })?;
+        let goods_count = goods_count.unwrap_or(0);
+        if goods_count > 0 {
+            return Err(CafeHubError::conflict(format!(
+                "Category ID {} still has {} goods; move them to another category first.",
+                category_id, goods_count
+            )));
+        }
+
+        tx.exec_drop(
+            "DELETE FROM category WHERE id = :category_id",
+            params! { "category_id" => category_id },
+        )
+        .map_err(|e| {
+            CafeHubError::database(format!("Failed to delete category ID {}", category_id), e)
+        })?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(true)
+    }
+}
+
+impl PromotionRepository for MySqlStorage {
+    fn list_promotions(&self) -> Result<Vec<Promotion>, CafeHubError> {
+        load_promotions(&mut self.conn()?, "")
+    }
+
+    fn create_promotion(&self, data: &PromotionData) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .start_transaction(mysql::TxOpts::default())
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        ensure_promotion_target_exists(&mut tx, data)?;
+        tx.exec_drop(
+            "INSERT INTO promotion (promotion_name, discount_type, discount_value, goods_id, category_id, weekdays, start_time, end_time, start_date, end_date, paused)
+             VALUES (:promotion_name, :discount_type, :discount_value, :goods_id, :category_id, :weekdays, :start_time, :end_time, :start_date, :end_date, :paused)",
+            mysql::Params::from(promotion_params(data)),
+        )
+        .map_err(|e| CafeHubError::database("Database error while adding promotion", e))?;
+        let promotion_id = tx
+            .last_insert_id()
+            .ok_or_else(|| CafeHubError::internal("Failed to get ID of the new promotion"))?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(promotion_id as i64)
+    }
+
+    fn update_promotion(
+        &self,
+        promotion_id: i32,
+        data: &PromotionData,
+    ) -> Result<bool, CafeHubError> {
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .start_transaction(mysql::TxOpts::default())
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        // Affected rows leave out rows that already held the new values.
+        let promotion_exists: Option<i32> = tx
+            .exec_first(
+                "SELECT id FROM promotion WHERE id = :promotion_id FOR UPDATE",
+                params! { "promotion_id" => promotion_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to query promotion ID {}", promotion_id), e)
+            })?;
+        if promotion_exists.is_none() {
+            return Ok(false);
+        }
+
+        ensure_promotion_target_exists(&mut tx, data)?;
+        let mut query_params = promotion_params(data);
+        query_params.push(("promotion_id".to_string(), promotion_id.into()));
+        tx.exec_drop(
+            "UPDATE promotion SET
+                promotion_name = :promotion_name,
+                discount_type = :discount_type,
+                discount_value = :discount_value,
+                goods_id = :goods_id,
+                category_id = :category_id,
+                weekdays = :weekdays,
+                start_time = :start_time,
+                end_time = :end_time,
+                start_date = :start_date,
+                end_date = :end_date,
+                paused = :paused
+             WHERE id = :promotion_id",
+            mysql::Params::from(query_params),
+        )
+        .map_err(|e| {
+            CafeHubError::database(format!("Failed to update promotion ID {}", promotion_id), e)
+        })?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(true)
+    }
+
+    fn delete_promotion(&self, promotion_id: i32) -> Result<bool, CafeHubError> {
+        let mut conn = self.conn()?;
+        conn.exec_drop(
+            "DELETE FROM promotion WHERE id = :promotion_id",
+            params! { "promotion_id" => promotion_id },
+        )
+        .map_err(|e| {
+            CafeHubError::database(format!("Failed to delete promotion ID {}", promotion_id), e)
+        })?;
+        Ok(conn.affected_rows() > 0)
+    }
+}
+
+impl CouponRepository for MySqlStorage {
+    fn list_coupons(&self) -> Result<Vec<Coupon>, CafeHubError> {
+        query_coupons(&mut self.conn()?, "ORDER BY c.id DESC", ())
+    }
+
+    fn create_coupon(
+        &self,
+        data: &AddCouponData,
+        created_at: NaiveDateTime,
+    ) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        conn.exec_drop(
+            "INSERT INTO coupon (code, discount_type, discount_value, min_spend, expires_at, max_redemptions, max_per_customer, created_at)
+             VALUES (:code, :discount_type, :discount_value, :min_spend, :expires_at, :max_redemptions, :max_per_customer, :created_at)",
+            params! {
+                "code" => &data.code,
+                "discount_type" => data.discount_type.as_str(),
+                "discount_value" => data.discount_value,
+                "min_spend" => data.min_spend,
+                "expires_at" => data.expires_at,
+                "max_redemptions" => data.max_redemptions,
+                "max_per_customer" => data.max_per_customer,
+                "created_at" => created_at,
+            },
+        )
+        .map_err(|e| {
+            if is_duplicate_entry(&e) {
+                CafeHubError::DuplicateCouponCode {
+                    code: data.code.clone(),
+                }
+            } else {
+                CafeHubError::database("Database error while adding coupon", e)
+            }
+        })?;
+        Ok(conn.last_insert_id() as i64)
+    }
+
+    fn disable_coupon(&self, coupon_id: i32) -> Result<bool, CafeHubError> {
+        let mut conn = self.conn()?;
+        // Affected rows leave out coupons that were already disabled.
+        let coupon_exists: Option<i32> = conn
+            .exec_first(
+                "SELECT id FROM coupon WHERE id = :coupon_id",
+                params! { "coupon_id" => coupon_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to query coupon ID {}", coupon_id), e)
+            })?;
+        if coupon_exists.is_none() {
+            return Ok(false);
+        }
+
+        conn.exec_drop(
+            "UPDATE coupon SET disabled = 1 WHERE id = :coupon_id",
+            params! { "coupon_id" => coupon_id },
+        )
+        .map_err(|e| {
+            CafeHubError::database(format!("Failed to disable coupon ID {}", coupon_id), e)
+        })?;
+        Ok(true)
+    }
+}
+
+impl ModifierRepository for MySqlStorage {
+    fn list_modifier_groups(&self, goods_id: i32) -> Result<Vec<ModifierGroup>, CafeHubError> {
+        load_modifier_groups(&mut self.conn()?, goods_id)
+    }
+
+    fn create_modifier_group(&self, data: &AddModifierGroupData) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .start_transaction(mysql::TxOpts::default())
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let goods_exists: Option<i32> = tx
+            .exec_first(
+                "SELECT id FROM goods WHERE id = :goods_id",
+                params! { "goods_id" => data.goods_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to query goods ID {}", data.goods_id), e)
+            })?;
+        if goods_exists.is_none() {
+            return Err(CafeHubError::NotFound {
+                entity: "Goods",
+                id: data.goods_id.into(),
+            });
+        }
+
+        tx.exec_drop(
+            "INSERT INTO modifier_group (goods_id, group_name, is_required, allow_multiple) VALUES (:goods_id, :group_name, :is_required, :allow_multiple)",
+            params! {
+                "goods_id" => data.goods_id,
+                "group_name" => &data.group_name,
+                "is_required" => data.is_required,
+                "allow_multiple" => data.allow_multiple,
+            },
+        )
+        .map_err(|e| CafeHubError::database("Failed to create modifier group", e))?;
+        let group_id = tx
+            .last_insert_id()
+            .ok_or_else(|| CafeHubError::internal("Failed to get ID of the new modifier group"))?;
+
+        for option in &data.options {
+            tx.exec_drop(
+                "INSERT INTO modifier_option (group_id, option_name, price_delta) VALUES (:group_id, :option_name, :price_delta)",
+                params! {
+                    "group_id" => group_id,
+                    "option_name" => &option.option_name,
+                    "price_delta" => option.price_delta,
+                },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Failed to create modifier option {}", option.option_name),
+                    e,
+                )
+            })?;
+        }
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(group_id as i64)
+    }
+
+    fn delete_modifier_group(&self, group_id: i64) -> Result<bool, CafeHubError> {
+        let mut conn = self.conn()?;
+        conn.exec_drop(
+            "DELETE FROM modifier_group WHERE id = :group_id",
+            params! { "group_id" => group_id },
+        )
+        .map_err(|e| {
+            CafeHubError::database(
+                format!("Failed to delete modifier group ID {}", group_id),
+                e,
+            )
+        })?;
+        Ok(conn.affected_rows() > 0)
+    }
+}
+
+impl IngredientRepository for MySqlStorage {
+    fn list_ingredients(&self) -> Result<Vec<Ingredient>, CafeHubError> {
+        self.conn()?
+            .query_map(
+                "SELECT id, ingredient_name, unit, stock FROM ingredient ORDER BY ingredient_name ASC",
+                |(id, ingredient_name, unit, stock)| Ingredient {
+                    id,
+                    ingredient_name,
+                    unit,
+                    stock,
+                },
+            )
+            .map_err(|e| CafeHubError::database("Database query failed for ingredients", e))
+    }
+
+    fn create_ingredient(&self, data: &AddIngredientData) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        conn.exec_drop(
+            "INSERT INTO ingredient (ingredient_name, unit, stock) VALUES (:ingredient_name, :unit, :stock)",
+            params! {
+                "ingredient_name" => &data.ingredient_name,
+                "unit" => &data.unit,
+                "stock" => data.stock.unwrap_or(0),
+            },
+        )
+        .map_err(|e| {
+            if is_duplicate_entry(&e) {
+                CafeHubError::DuplicateIngredientName {
+                    ingredient_name: data.ingredient_name.clone(),
+                }
+            } else {
+                CafeHubError::database("Database error while adding ingredient", e)
+            }
+        })?;
+        Ok(conn.last_insert_id() as i64)
+    }
+
+    fn update_ingredient(
+        &self,
+        ingredient_id: i64,
+        data: &UpdateIngredientData,
+    ) -> Result<bool, CafeHubError> {
+        let mut set_clauses: Vec<String> = Vec::new();
+        let mut query_params: Vec<(String, mysql::Value)> = Vec::new();
+
+        if let Some(ref unit) = data.unit {
+            set_clauses.push("unit = :unit".to_string());
+            query_params.push(("unit".to_string(), unit.clone().into()));
+        }
+        if let Some(stock) = data.stock {
+            set_clauses.push("stock = :stock".to_string());
+            query_params.push(("stock".to_string(), stock.into()));
+        }
+        if set_clauses.is_empty() {
+            return Ok(false);
+        }
+
+        query_params.push(("ingredient_id".to_string(), ingredient_id.into()));
+        let query = format!(
+            "UPDATE ingredient SET {} WHERE id = :ingredient_id",
+            set_clauses.join(", ")
+        );
+
+        let mut conn = self.conn()?;
+        conn.exec_drop(&query, mysql::Params::from(query_params))
+            .map_err(|e| CafeHubError::database("Database error while updating ingredient", e))?;
+        Ok(conn.affected_rows() > 0)
+    }
+
+    fn find_recipe(&self, goods_id: i32) -> Result<Vec<RecipeItem>, CafeHubError> {
+        self.conn()?
+            .exec_map(
+                "SELECT r.ingredient_id, i.ingredient_name, i.unit, r.quantity
+                 FROM recipe_item r
+                 JOIN ingredient i ON r.ingredient_id = i.id
+                 WHERE r.goods_id = :goods_id
+                 ORDER BY i.ingredient_name ASC",
+                params! { "goods_id" => goods_id },
+                |(ingredient_id, ingredient_name, unit, quantity)| RecipeItem {
+                    ingredient_id,
+                    ingredient_name,
+                    unit,
+                    quantity,
+                },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Database query failed for recipe of goods ID {}", goods_id),
+                    e,
+                )
+            })
+    }
+
+    fn set_recipe(&self, goods_id: i32, items: &[RecipeItemData]) -> Result<(), CafeHubError> {
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .start_transaction(mysql::TxOpts::default())
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let goods_exists: Option<i32> = tx
+            .exec_first(
+                "SELECT id FROM goods WHERE id = :goods_id",
+                params! { "goods_id" => goods_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to query goods ID {}", goods_id), e)
+            })?;
+        if goods_exists.is_none() {
+            return Err(CafeHubError::NotFound {
+                entity: "Goods",
+                id: goods_id.into(),
+            });
+        }
+
+        tx.exec_drop(
+            "DELETE FROM recipe_item WHERE goods_id = :goods_id",
+            params! { "goods_id" => goods_id },
+        )
+        .map_err(|e| {
+            CafeHubError::database(
+                format!("Failed to clear recipe of goods ID {}", goods_id),
+                e,
+            )
+        })?;
+
+        for item in items {
+            let ingredient_exists: Option<i64> = tx
+                .exec_first(
+                    "SELECT id FROM ingredient WHERE id = :ingredient_id",
+                    params! { "ingredient_id" => item.ingredient_id },
+                )
+                .map_err(|e| {
+                    CafeHubError::database(
+                        format!("Failed to query ingredient ID {}", item.ingredient_id),
+                        e,
+                    )
+                })?;
+            if ingredient_exists.is_none() {
+                return Err(CafeHubError::NotFound {
+                    entity: "Ingredient",
+                    id: item.ingredient_id,
+                });
+            }
+
+            tx.exec_drop(
+                "INSERT INTO recipe_item (goods_id, ingredient_id, quantity) VALUES (:goods_id, :ingredient_id, :quantity)",
+                params! {
+                    "goods_id" => goods_id,
+                    "ingredient_id" => item.ingredient_id,
+                    "quantity" => item.quantity,
+                },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Failed to add ingredient ID {} to recipe", item.ingredient_id),
+                    e,
+                )
+            })?;
+        }
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))
+    }
+}
+
+impl PurchasingRepository for MySqlStorage {
+    fn list_suppliers(&self) -> Result<Vec<Supplier>, CafeHubError> {
+        self.conn()?
+            .query_map(
+                "SELECT id, supplier_name, contact FROM supplier ORDER BY supplier_name ASC",
+                |(id, supplier_name, contact)| Supplier {
+                    id,
+                    supplier_name,
+                    contact,
+                },
+            )
+            .map_err(|e| CafeHubError::database("Database query failed for suppliers", e))
+    }
+
+    fn create_supplier(&self, data: &AddSupplierData) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        conn.exec_drop(
+            "INSERT INTO supplier (supplier_name, contact) VALUES (:supplier_name, :contact)",
+            params! {
+                "supplier_name" => &data.supplier_name,
+                "contact" => &data.contact,
+            },
+        )
+        .map_err(|e| {
+            if is_duplicate_entry(&e) {
+                CafeHubError::DuplicateSupplierName {
+                    supplier_name: data.supplier_name.clone(),
+                }
+            } else {
+                CafeHubError::database("Database error while adding supplier", e)
+            }
+        })?;
+        Ok(conn.last_insert_id() as i64)
+    }
+
+    fn create_purchase_order(
+        &self,
+        data: &CreatePurchaseOrderData,
+        operator_id: i64,
+        created_at: NaiveDateTime,
+    ) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .start_transaction(mysql::TxOpts::default())
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let supplier_exists: Option<i64> = tx
+            .exec_first(
+                "SELECT id FROM supplier WHERE id = :supplier_id",
+                params! { "supplier_id" => data.supplier_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Failed to query supplier ID {}", data.supplier_id),
+                    e,
+                )
+            })?;
+        if supplier_exists.is_none() {
+            return Err(CafeHubError::NotFound {
+                entity: "Supplier",
+                id: data.supplier_id,
+            });
+        }
+
+        let mut total_cost = Decimal::ZERO;
+        for item in &data.items {
+            let goods_exists: Option<i32> = tx
+                .exec_first(
+                    "SELECT id FROM goods WHERE id = :goods_id",
+                    params! { "goods_id" => item.goods_id },
+                )
+                .map_err(|e| {
+                    CafeHubError::database(format!("Failed to query goods ID {}", item.goods_id), e)
+                })?;
+            if goods_exists.is_none() {
+                return Err(CafeHubError::NotFound {
+                    entity: "Goods",
+                    id: item.goods_id.into(),
+                });
+            }
+            total_cost += item.unit_cost * Decimal::from(item.quantity);
+        }
+
+        tx.exec_drop(
+            "INSERT INTO purchase_order (supplier_id, status, total_cost, created_by, created_at) VALUES (:supplier_id, :status, :total_cost, :created_by, :created_at)",
+            params! {
+                "supplier_id" => data.supplier_id,
+                "status" => PurchaseOrderStatus::Open.as_str(),
+                "total_cost" => total_cost,
+                "created_by" => operator_id,
+                "created_at" => created_at,
+            },
+        )
+        .map_err(|e| CafeHubError::database("Failed to create purchase order", e))?;
+        let purchase_order_id = tx
+            .last_insert_id()
+            .ok_or_else(|| CafeHubError::internal("Failed to get ID of new purchase order"))?;
+
+        for item in &data.items {
+            tx.exec_drop(
+                "INSERT INTO purchase_order_item (purchase_order_id, goods_id, quantity, unit_cost) VALUES (:purchase_order_id, :goods_id, :quantity, :unit_cost)",
+                params! {
+                    "purchase_order_id" => purchase_order_id,
+                    "goods_id" => item.goods_id,
+                    "quantity" => item.quantity,
+                    "unit_cost" => item.unit_cost,
+                },
+            )
+            .map_err(|e| CafeHubError::database(format!("Failed to add goods ID {} to purchase order", item.goods_id), e))?;
+        }
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(purchase_order_id as i64)
+    }
+
+    fn list_purchase_orders(&self) -> Result<Vec<PurchaseOrderSummary>, CafeHubError> {
+        let query = "
+            SELECT po.id, po.supplier_id, s.supplier_name, po.status, po.total_cost, po.created_at, po.closed_at
+            FROM purchase_order po
+            JOIN supplier s ON po.supplier_id = s.id
+            ORDER BY po.created_at DESC, po.id DESC";
+
+        self.conn()?
+            .query_map(
+                query,
+                |(id, supplier_id, supplier_name, status, total_cost, created_at, closed_at)| {
+                    PurchaseOrderSummary {
+                        id,
+                        supplier_id,
+                        supplier_name,
+                        status,
+                        total_cost,
+                        created_at,
+                        closed_at,
+                    }
+                },
+            )
+            .map_err(|e| CafeHubError::database("Database query failed for purchase orders", e))
+    }
+
+    fn find_purchase_order(
+        &self,
+        purchase_order_id: i64,
+    ) -> Result<Option<PurchaseOrderDetail>, CafeHubError> {
+        let mut conn = self.conn()?;
+
+        let header_query = "
+            SELECT po.id, po.supplier_id, s.supplier_name, po.status, po.total_cost,
+                creator.username, po.created_at, closer.username, po.closed_at
+            FROM purchase_order po
+            JOIN supplier s ON po.supplier_id = s.id
+            JOIN account creator ON po.created_by = creator.id
+            LEFT JOIN account closer ON po.closed_by = closer.id
+            WHERE po.id = :purchase_order_id";
+        let header: Option<PurchaseOrderRow> = conn
+            .exec_first(
+                header_query,
+                params! { "purchase_order_id" => purchase_order_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!(
+                        "Database query failed for purchase order ID {}",
+                        purchase_order_id
+                    ),
+                    e,
+                )
+            })?;
+        let Some((
+            id,
+            supplier_id,
+            supplier_name,
+            status,
+            total_cost,
+            created_by,
+            created_at,
+            closed_by,
+            closed_at,
+        )) = header
+        else {
+            return Ok(None);
+        };
+
+        let items = conn
+            .exec_map(
+                "SELECT i.id, i.goods_id, g.goods_name, i.quantity, i.unit_cost
+                 FROM purchase_order_item i
+                 JOIN goods g ON i.goods_id = g.id
+                 WHERE i.purchase_order_id = :purchase_order_id
+                 ORDER BY i.id ASC",
+                params! { "purchase_order_id" => purchase_order_id },
+                |(id, goods_id, goods_name, quantity, unit_cost)| PurchaseOrderLine {
+                    id,
+                    goods_id,
+                    goods_name,
+                    quantity,
+                    unit_cost,
+                },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!(
+                        "Database query failed for items of purchase order ID {}",
+                        purchase_order_id
+                    ),
+                    e,
+                )
+            })?;
+
+        Ok(Some(PurchaseOrderDetail {
+            id,
+            supplier_id,
+            supplier_name,
+            status,
+            total_cost,
+            created_by,
+            created_at,
+            closed_by,
+            closed_at,
+            items,
+        }))
+    }
+
+    fn receive_purchase_order(
+        &self,
+        purchase_order_id: i64,
+        operator_id: i64,
+        received_at: NaiveDateTime,
+    ) -> Result<(), CafeHubError> {
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .start_transaction(mysql::TxOpts::default())
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let status: Option<String> = tx
+            .exec_first(
+                "SELECT status FROM purchase_order WHERE id = :purchase_order_id FOR UPDATE",
+                params! { "purchase_order_id" => purchase_order_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Failed to query purchase order ID {}", purchase_order_id),
+                    e,
+                )
+            })?;
+        ensure_purchase_order_open(purchase_order_id, status)?;
+
+        let lines: Vec<(i32, i32, Decimal)> = tx
+            .exec(
+                "SELECT goods_id, quantity, unit_cost FROM purchase_order_item WHERE purchase_order_id = :purchase_order_id ORDER BY id ASC",
+                params! { "purchase_order_id" => purchase_order_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!(
+                        "Failed to query items of purchase order ID {}",
+                        purchase_order_id
+                    ),
+                    e,
+                )
+            })?;
+        for (goods_id, quantity, unit_cost) in lines {
+            apply_stock_change(
+                &mut tx,
+                goods_id,
+                quantity,
+                StockMovementReason::Restock,
+                Some(format!("purchase_order:{}", purchase_order_id)),
+                Some(operator_id),
+            )?;
+            apply_cost_change(
+                &mut tx,
+                goods_id,
+                unit_cost,
+                Some(format!("purchase_order:{}", purchase_order_id)),
+                Some(operator_id),
+            )?;
+        }
+
+        close_purchase_order(
+            &mut tx,
+            purchase_order_id,
+            PurchaseOrderStatus::Received,
+            operator_id,
+            received_at,
+        )?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))
+    }
+
+    fn cancel_purchase_order(
+        &self,
+        purchase_order_id: i64,
+        operator_id: i64,
+        cancelled_at: NaiveDateTime,
+    ) -> Result<(), CafeHubError> {
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .start_transaction(mysql::TxOpts::default())
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let status: Option<String> = tx
+            .exec_first(
+                "SELECT status FROM purchase_order WHERE id = :purchase_order_id FOR UPDATE",
+                params! { "purchase_order_id" => purchase_order_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Failed to query purchase order ID {}", purchase_order_id),
+                    e,
+                )
+            })?;
+        ensure_purchase_order_open(purchase_order_id, status)?;
+
+        close_purchase_order(
+            &mut tx,
+            purchase_order_id,
+            PurchaseOrderStatus::Cancelled,
+            operator_id,
+            cancelled_at,
+        )?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))
+    }
+}
+
+impl ConsumptionRepository for MySqlStorage {
+    fn monthly_consumption(
+        &self,
+        user_id: Option<i64>,
+    ) -> Result<Vec<MonthlyConsumptionSummary>, CafeHubError> {
+        let mut conn = self.conn()?;
+        let to_summary = |(month, total_amount)| MonthlyConsumptionSummary {
+            month,
+            total_amount,
+        };
+        match user_id {
+            Some(user_id) => conn.exec_map(
+                "
+                SELECT month, SUM(amount) as total_amount
+                FROM consumption
+                WHERE user_id = :user_id
+                GROUP BY month
+                ORDER BY month ASC",
+                params! { "user_id" => user_id },
+                to_summary,
+            ),
+            None => conn.query_map(
+                "SELECT month, SUM(amount) as total_amount FROM consumption GROUP BY month ORDER BY month ASC",
+                to_summary,
+            ),
+        }
+        .map_err(|e| CafeHubError::database("Database query failed for monthly consumption", e))
+    }
+
+    fn goods_consumption_share(
+        &self,
+        month: &str,
+    ) -> Result<Vec<GoodsConsumptionShare>, CafeHubError> {
+        let query = "
+            SELECT g.goods_name, SUM(c.amount) as consumed_amount
+            FROM consumption c
+            JOIN goods g ON c.goods_id = g.id
+            WHERE c.month = :current_month
+            GROUP BY g.goods_name
+            ORDER BY consumed_amount DESC";
+
+        self.conn()?
+            .exec_map(
+                query,
+                params! { "current_month" => month },
+                |(goods_name, amount)| GoodsConsumptionShare { goods_name, amount },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!(
+                        "Database query failed for goods consumption share (month: {})",
+                        month
+                    ),
+                    e,
+                )
+            })
+    }
+
+    fn margin_summary(
+        &self,
+        grouping: MarginGrouping,
+        month: Option<&str>,
+    ) -> Result<Vec<MarginSummary>, CafeHubError> {
+        let (label, group_by, order_by) = match grouping {
+            MarginGrouping::Goods => ("g.goods_name", "g.id, g.goods_name", "revenue DESC"),
+            MarginGrouping::Category => (
+                "COALESCE(c.display_name, 'Uncategorized')",
+                "label",
+                "revenue DESC",
+            ),
+            MarginGrouping::Month => ("DATE_FORMAT(o.order_time, '%Y-%m')", "label", "label ASC"),
+        };
+        let query = format!(
+            "
+            SELECT {} AS label,
+                CAST(SUM(oi.quantity - oi.refunded_quantity) AS SIGNED) AS quantity_sold,
+                SUM(oi.line_total - oi.refunded_amount) AS revenue,
+                COALESCE(SUM(oi.unit_cost * (oi.quantity - oi.refunded_quantity)), 0) AS cost,
+                CAST(SUM(CASE WHEN oi.unit_cost IS NULL THEN oi.quantity - oi.refunded_quantity ELSE 0 END) AS SIGNED) AS uncosted_quantity
+            FROM order_items oi
+            JOIN orders o ON oi.order_id = o.id
+            JOIN goods g ON oi.goods_id = g.id
+            LEFT JOIN category c ON g.category_id = c.id
+            WHERE :month IS NULL OR DATE_FORMAT(o.order_time, '%Y-%m') = :month
+            GROUP BY {}
+            ORDER BY {}",
+            label, group_by, order_by
+        );
+
+        self.conn()?
+            .exec_map(
+                query,
+                params! { "month" => month },
+                |(label, quantity_sold, revenue, cost, uncosted_quantity): (
+                    String,
+                    i64,
+                    Decimal,
+                    Decimal,
+                    i64,
+                )| MarginSummary {
+                    label,
+                    quantity_sold,
+                    revenue,
+                    cost,
+                    gross_margin: revenue - cost,
+                    uncosted_quantity,
+                },
+            )
+            .map_err(|e| CafeHubError::database("Database query failed for margin summary", e))
+    }
+}
+
+impl OrderTransaction for mysql::Transaction<'_> {
+    fn active_promotions(&mut self) -> Result<Vec<Promotion>, CafeHubError> {
+        load_promotions(self, "WHERE paused = 0")
+    }
+
+    fn lock_sale_goods(&mut self, goods_id: i32) -> Result<Option<SaleGoodsRow>, CafeHubError> {
+        self.exec_first(
+            "SELECT price, stock, goods_name, reorder_threshold, cost_price, available, category_id, points_price FROM goods WHERE id = :goods_id AND archived_at IS NULL FOR UPDATE",
+            params! { "goods_id" => goods_id },
+        )
+        .map_err(|e| CafeHubError::database(format!("Failed to query goods ID {}", goods_id), e))
+    }
+
+    fn lock_recipe(&mut self, goods_id: i32) -> Result<Vec<RecipeUsage>, CafeHubError> {
+        load_recipe_usage(self, goods_id)
+    }
+
+    fn modifier_groups(&mut self, goods_id: i32) -> Result<Vec<ModifierGroup>, CafeHubError> {
+        load_modifier_groups(self, goods_id)
+    }
+
+    fn lock_coupon(
+        &mut self,
+        code: &str,
+        user_id: i64,
+    ) -> Result<Option<(Coupon, i64)>, CafeHubError> {
+        let Some(mut coupon) = query_coupons(
+            self,
+            "WHERE c.code = :code FOR UPDATE",
+            params! { "code" => code },
+        )?
+        .pop() else {
+            return Ok(None);
+        };
+        // With the coupon locked, these counts cannot change until the order commits.
+        let (times_redeemed, customer_redemptions): (i64, i64) = self
+            .exec_first(
+                "SELECT COUNT(*), COUNT(CASE WHEN user_id = :user_id THEN 1 END) FROM coupon_redemption WHERE coupon_id = :coupon_id AND refunded_at IS NULL FOR UPDATE",
+                params! {
+                    "coupon_id" => coupon.id,
+                    "user_id" => user_id,
+                },
+            )
+            .map_err(|e| CafeHubError::database("Failed to count coupon redemptions", e))?
+            .unwrap_or((0, 0));
+        coupon.times_redeemed = times_redeemed;
+        Ok(Some((coupon, customer_redemptions)))
+    }
+
+    fn lock_balance(&mut self, user_id: i64) -> Result<Option<Decimal>, CafeHubError> {
+        self.exec_first(
+            "SELECT balance FROM account WHERE id = :user_id AND user_type = 1 FOR UPDATE",
+            params! { "user_id" => user_id },
+        )
+        .map_err(|e| CafeHubError::database("Failed to query user", e))
+    }
+
+    fn expire_points(&mut self, user_id: i64, now: NaiveDateTime) -> Result<i64, CafeHubError> {
+        expire_points(self, user_id, now)
+    }
+
+    fn use_ingredient(&mut self, ingredient_id: i64, quantity: i64) -> Result<(), CafeHubError> {
+        self.exec_drop(
+            "UPDATE ingredient SET stock = stock - :quantity WHERE id = :ingredient_id",
+            params! {
+                "quantity" => quantity,
+                "ingredient_id" => ingredient_id,
+            },
+        )
+        .map_err(|e| {
+            CafeHubError::database(
+                format!("Failed to update stock for ingredient ID {}", ingredient_id),
+                e,
+            )
+        })
+    }
+
+    fn insert_order(
+        &mut self,
+        user_id: i64,
+        order_time: NaiveDateTime,
+        total_amount: Decimal,
+        points_redeemed: i64,
+        points_earned: i64,
+    ) -> Result<i64, CafeHubError> {
+        self.exec_drop(
+            "INSERT INTO orders (user_id, order_time, total_amount, points_redeemed, points_earned) VALUES (:user_id, :order_time, :total_amount, :points_redeemed, :points_earned)",
+            params! {
+                "user_id" => user_id,
+                "order_time" => order_time,
+                "total_amount" => total_amount,
+                "points_redeemed" => points_redeemed,
+                "points_earned" => points_earned,
+            },
+        )
+        .map_err(|e| CafeHubError::database("Failed to create order record", e))?;
+        self.last_insert_id()
+            .map(|id| id as i64)
+            .ok_or_else(|| CafeHubError::internal("Failed to get ID of the new order record"))
+    }
+
+    fn insert_coupon_redemption(
+        &mut self,
+        coupon_id: i32,
+        order_id: i64,
+        user_id: i64,
+        discount: Decimal,
+        redeemed_at: NaiveDateTime,
+    ) -> Result<(), CafeHubError> {
+        self.exec_drop(
+            "INSERT INTO coupon_redemption (coupon_id, order_id, user_id, discount, redeemed_at) VALUES (:coupon_id, :order_id, :user_id, :discount, :redeemed_at)",
+            params! {
+                "coupon_id" => coupon_id,
+                "order_id" => order_id,
+                "user_id" => user_id,
+                "discount" => discount,
+                "redeemed_at" => redeemed_at,
+            },
+        )
+        .map_err(|e| CafeHubError::database("Failed to record coupon redemption", e))
+    }
+
+    fn insert_order_line(&mut self, order_id: i64, line: &OrderLine) -> Result<(), CafeHubError> {
+        self.exec_drop(
+            "INSERT INTO order_items (order_id, goods_id, quantity, unit_price, line_total, unit_cost, made_from_recipe, promotion_id, promotion_name, discount, coupon_discount, points_discount, points_redeemed) VALUES (:order_id, :goods_id, :quantity, :unit_price, :line_total, :unit_cost, :made_from_recipe, :promotion_id, :promotion_name, :discount, :coupon_discount, :points_discount, :points_redeemed)",
+            params! {
+                "order_id" => order_id,
+                "goods_id" => line.goods_id,
+                "quantity" => line.quantity,
+                "unit_price" => line.unit_price,
+                "line_total" => line.line_total,
+                "unit_cost" => line.unit_cost,
+                "made_from_recipe" => line.made_from_recipe,
+                "promotion_id" => line.promotion.as_ref().map(|(id, _)| *id),
+                "promotion_name" => line.promotion.as_ref().map(|(_, name)| name),
+                "discount" => line.discount,
+                "coupon_discount" => line.coupon_discount,
+                "points_discount" => line.points_discount,
+                "points_redeemed" => line.points_redeemed,
+            },
+        )
+        .map_err(|e| CafeHubError::database(format!("Failed to record order item for goods ID {}", line.goods_id), e))?;
+        let order_item_id = self
+            .last_insert_id()
+            .ok_or_else(|| CafeHubError::internal("Failed to get ID of the new order item"))?;
+
+        for modifier in &line.modifiers {
+            self.exec_drop(
+                "INSERT INTO order_item_modifier (order_item_id, option_id, group_name, option_name, price_delta) VALUES (:order_item_id, :option_id, :group_name, :option_name, :price_delta)",
+                params! {
+                    "order_item_id" => order_item_id,
+                    "option_id" => modifier.option_id,
+                    "group_name" => &modifier.group_name,
+                    "option_name" => &modifier.option_name,
+                    "price_delta" => modifier.price_delta,
+                },
+            )
+            .map_err(|e| CafeHubError::database(format!("Failed to record modifiers for goods ID {}", line.goods_id), e))?;
+        }
+        Ok(())
+    }
+
+    fn add_consumption(
+        &mut self,
+        user_id: i64,
+        month: &str,
+        goods_id: i32,
+        amount: Decimal,
+    ) -> Result<(), CafeHubError> {
+        self.exec_drop(
+            "INSERT INTO consumption (user_id, month, goods_id, amount) VALUES (:user_id, :month, :goods_id, :amount)
+             ON DUPLICATE KEY UPDATE amount = amount + VALUES(amount)",
+            params! {
+                "user_id" => user_id,
+                "month" => month,
+                "goods_id" => goods_id,
+                "amount" => amount,
+            },
+        )
+        .map_err(|e| CafeHubError::database(format!("Failed to record consumption for goods ID {}", goods_id), e))
+    }
+
+    fn change_balance(
+        &mut self,
+        user_id: i64,
+        amount: Decimal,
+        entry_type: LedgerEntryType,
+        reference: String,
+        operator_id: i64,
+    ) -> Result<(), CafeHubError> {
+        apply_balance_change(
+            self,
+            user_id,
+            amount,
+            entry_type,
+            Some(reference),
+            Some(operator_id),
+        )
+        .map(drop)
+    }
+
+    fn change_points(
+        &mut self,
+        user_id: i64,
+        points: i64,
+        entry_type: PointsEntryType,
+        reference: String,
+        expires_at: Option<NaiveDateTime>,
+        at: NaiveDateTime,
+    ) -> Result<(), CafeHubError> {
+        apply_points_change(
+            self,
+            user_id,
+            points,
+            entry_type,
+            Some(reference),
+            expires_at,
+            at,
+        )
+        .map(drop)
+    }
+
+    fn change_stock(
+        &mut self,
+        goods_id: i32,
+        delta: i32,
+        reason: StockMovementReason,
+        reference: String,
+        operator_id: i64,
+    ) -> Result<(), CafeHubError> {
+        apply_stock_change(
+            self,
+            goods_id,
+            delta,
+            reason,
+            Some(reference),
+            Some(operator_id),
+        )
+        .map(drop)
+    }
+
+    fn notify_administrator(
+        &mut self,
+        title: &str,
+        content: &str,
+        send_date: NaiveDate,
+    ) -> Result<(), CafeHubError> {
+        notify_administrator(self, title, content, send_date)
+    }
+
+    fn lock_order(
+        &mut self,
+        order_id: i64,
+    ) -> Result<Option<(i64, NaiveDateTime, i64)>, CafeHubError> {
+        self.exec_first(
+            "SELECT user_id, order_time, points_earned FROM orders WHERE id = :order_id FOR UPDATE",
+            params! { "order_id" => order_id },
+        )
+        .map_err(|e| CafeHubError::database(format!("Failed to query order ID {}", order_id), e))
+    }
+
+    fn refundable_items(&mut self, order_id: i64) -> Result<Vec<RefundItem>, CafeHubError> {
+        self.exec_map(
+            "SELECT id, quantity - refunded_quantity FROM order_items WHERE order_id = :order_id AND quantity > refunded_quantity",
+            params! { "order_id" => order_id },
+            |(order_item_id, quantity)| RefundItem {
+                order_item_id,
+                quantity,
+            },
+        )
+        .map_err(|e| CafeHubError::database(format!("Failed to query items of order ID {}", order_id), e))
+    }
+
+    fn lock_refund_line(
+        &mut self,
+        order_id: i64,
+        order_item_id: i64,
+    ) -> Result<Option<RefundLineRow>, CafeHubError> {
+        self.exec_first(
+            "SELECT goods_id, quantity, refunded_quantity, line_total, refunded_amount, made_from_recipe, points_redeemed, points_restored FROM order_items WHERE id = :order_item_id AND order_id = :order_id FOR UPDATE",
+            params! {
+                "order_item_id" => order_item_id,
+                "order_id" => order_id,
+            },
+        )
+        .map_err(|e| CafeHubError::database(format!("Failed to query order item ID {}", order_item_id), e))
+    }
+
+    fn add_line_refund(
+        &mut self,
+        order_item_id: i64,
+        quantity: i32,
+        amount: Decimal,
+        points: i64,
+    ) -> Result<(), CafeHubError> {
+        self.exec_drop(
+            "UPDATE order_items SET refunded_quantity = refunded_quantity + :quantity, refunded_amount = refunded_amount + :amount, points_restored = points_restored + :points WHERE id = :order_item_id",
+            params! {
+                "quantity" => quantity,
+                "amount" => amount,
+                "points" => points,
+                "order_item_id" => order_item_id,
+            },
+        )
+        .map_err(|e| CafeHubError::database(format!("Failed to update order item ID {}", order_item_id), e))
+    }
+
+    fn reduce_consumption(
+        &mut self,
+        user_id: i64,
+        month: &str,
+        goods_id: i32,
+        amount: Decimal,
+    ) -> Result<(), CafeHubError> {
+        self.exec_drop(
+            "UPDATE consumption SET amount = GREATEST(amount - :amount, 0) WHERE user_id = :user_id AND month = :month AND goods_id = :goods_id",
+            params! {
+                "amount" => amount,
+                "user_id" => user_id,
+                "month" => month,
+                "goods_id" => goods_id,
+            },
+        )
+        .map_err(|e| CafeHubError::database(format!("Failed to update consumption for goods ID {}", goods_id), e))
+    }
+
+    fn insert_refund(
+        &mut self,
+        order_id: i64,
+        operator_id: i64,
+        reason: &str,
+        refund_time: NaiveDateTime,
+        total_amount: Decimal,
+    ) -> Result<i64, CafeHubError> {
+        self.exec_drop(
+            "INSERT INTO refunds (order_id, operator_id, reason, refund_time, total_amount) VALUES (:order_id, :operator_id, :reason, :refund_time, :total_amount)",
+            params! {
+                "order_id" => order_id,
+                "operator_id" => operator_id,
+                "reason" => reason,
+                "refund_time" => refund_time,
+                "total_amount" => total_amount,
+            },
+        )
+        .map_err(|e| CafeHubError::database("Failed to record refund", e))?;
+        self.last_insert_id()
+            .map(|id| id as i64)
+            .ok_or_else(|| CafeHubError::internal("Failed to get ID of the new refund record"))
+    }
+
+    fn insert_refund_line(
+        &mut self,
+        refund_id: i64,
+        order_item_id: i64,
+        quantity: i32,
+        amount: Decimal,
+    ) -> Result<(), CafeHubError> {
+        self.exec_drop(
+            "INSERT INTO refund_items (refund_id, order_item_id, quantity, amount) VALUES (:refund_id, :order_item_id, :quantity, :amount)",
+            params! {
+                "refund_id" => refund_id,
+                "order_item_id" => order_item_id,
+                "quantity" => quantity,
+                "amount" => amount,
+            },
+        )
+        .map_err(|e| CafeHubError::database(format!("Failed to record refund item for order item ID {}", order_item_id), e))
+    }
+
+    fn release_coupon_redemption(
+        &mut self,
+        order_id: i64,
+        refunded_at: NaiveDateTime,
+    ) -> Result<(), CafeHubError> {
+        self.exec_drop(
+            "UPDATE coupon_redemption SET refunded_at = :refunded_at WHERE order_id = :order_id AND NOT EXISTS (SELECT 1 FROM order_items WHERE order_id = :order_id AND refunded_quantity < quantity)",
+            params! {
+                "refunded_at" => refunded_at,
+                "order_id" => order_id,
+            },
+        )
+        .map_err(|e| CafeHubError::database("Failed to update coupon redemption", e))
+    }
+}
+
+impl OrderRepository for MySqlStorage {
+    fn place_order(
+        &self,
+        user_id: i64,
+        items: &[PurchaseItem],
+        coupon_code: Option<&str>,
+        points_to_redeem: i64,
+        order_time: NaiveDateTime,
+    ) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .start_transaction(mysql::TxOpts::default())
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+        let order_id = checkout(
+            &mut tx,
+            user_id,
+            items,
+            coupon_code,
+            points_to_redeem,
+            order_time,
+        )?;
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(order_id)
+    }
+
+    fn list_orders(&self, user_id: i64) -> Result<Vec<OrderSummary>, CafeHubError> {
+        let query = "
+            SELECT o.id, o.order_time, o.total_amount, CAST(COALESCE(SUM(oi.quantity), 0) AS SIGNED) AS item_count
+            FROM orders o
+            LEFT JOIN order_items oi ON oi.order_id = o.id
+            WHERE o.user_id = :user_id
+            GROUP BY o.id, o.order_time, o.total_amount
+            ORDER BY o.order_time DESC, o.id DESC";
+
+        self.conn()?
+            .exec_map(
+                query,
+                params! { "user_id" => user_id },
+                |(id, order_time, total_amount, item_count)| OrderSummary {
+                    id,
+                    order_time,
+                    total_amount,
+                    item_count,
+                },
+            )
+            .map_err(|e| CafeHubError::database("Database query failed for order history", e))
+    }
+
+    fn find_order(&self, order_id: i64) -> Result<Option<OrderDetail>, CafeHubError> {
+        let mut conn = self.conn()?;
+
+        let order_info: Option<OrderHeaderRow> = conn
+            .exec_first(
+                "SELECT o.id, o.user_id, o.order_time, o.total_amount, c.code, r.discount, o.points_redeemed, o.points_earned
+                 FROM orders o
+                 LEFT JOIN coupon_redemption r ON r.order_id = o.id
+                 LEFT JOIN coupon c ON r.coupon_id = c.id
+                 WHERE o.id = :order_id",
+                params! { "order_id" => order_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Database query failed for order ID {}", order_id),
+                    e,
+                )
+            })?;
+
+        let (
+            id,
+            user_id,
+            order_time,
+            total_amount,
+            coupon_code,
+            coupon_discount,
+            points_redeemed,
+            points_earned,
+        ) = match order_info {
+            Some(info) => info,
+            None => return Ok(None),
+        };
+
+        let query = "
+            SELECT oi.id, oi.goods_id, g.goods_name, oi.quantity, oi.unit_price, oi.line_total, oi.refunded_quantity, oi.promotion_name, oi.discount, oi.points_discount
+            FROM order_items oi
+            JOIN goods g ON oi.goods_id = g.id
+            WHERE oi.order_id = :order_id
+            ORDER BY oi.id ASC";
+
+        let mut items: Vec<OrderItemInfo> = conn
+            .exec_map(
+                query,
+                params! { "order_id" => order_id },
+                |(
+                    id,
+                    goods_id,
+                    goods_name,
+                    quantity,
+                    unit_price,
+                    line_total,
+                    refunded_quantity,
+                    promotion_name,
+                    discount,
+                    points_discount,
+                )| {
+                    OrderItemInfo {
+                        id,
+                        goods_id,
+                        goods_name,
+                        quantity,
+                        unit_price,
+                        line_total,
+                        promotion_name,
+                        discount,
+                        points_discount,
+                        refunded_quantity,
+                        modifiers: Vec::new(),
+                    }
+                },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Database query failed for items of order ID {}", order_id),
+                    e,
+                )
+            })?;
+
+        let modifier_rows: Vec<(i64, Option<i64>, String, String, Decimal)> = conn
+            .exec(
+                "SELECT m.order_item_id, m.option_id, m.group_name, m.option_name, m.price_delta
+                 FROM order_item_modifier m
+                 JOIN order_items oi ON m.order_item_id = oi.id
+                 WHERE oi.order_id = :order_id
+                 ORDER BY m.id ASC",
+                params! { "order_id" => order_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!(
+                        "Database query failed for modifiers of order ID {}",
+                        order_id
+                    ),
+                    e,
+                )
+            })?;
+        for (order_item_id, option_id, group_name, option_name, price_delta) in modifier_rows {
+            if let Some(item) = items.iter_mut().find(|item| item.id == order_item_id) {
+                item.modifiers.push(OrderItemModifier {
+                    option_id,
+                    group_name,
+                    option_name,
+                    price_delta,
+                });
+            }
+        }
+
+        Ok(Some(OrderDetail {
+            id,
+            user_id,
+            order_time,
+            total_amount,
+            coupon_code,
+            coupon_discount: coupon_discount.unwrap_or(Decimal::ZERO),
+            points_redeemed,
+            points_earned,
+            items,
+        }))
+    }
+
+    fn refund_order(
+        &self,
+        refund: &RefundOrderData,
+        operator_id: i64,
+        refund_time: NaiveDateTime,
+    ) -> Result<Decimal, CafeHubError> {
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .start_transaction(mysql::TxOpts::default())
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+        let total_refund = issue_refund(&mut tx, refund, operator_id, refund_time)?;
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(total_refund)
+    }
+}
+
+impl LostItemRepository for MySqlStorage {
+    fn list_lost_items(&self) -> Result<Vec<LostItem>, CafeHubError> {
+        let query = "
+            SELECT
+                li.id,
+                li.item_name,
+                li.pick_place,
+                li.pick_user_id,
+                p_acc.username AS pick_user_name,
+                li.claim_user_id,
+                c_acc.username AS claim_user_name,
+                li.pick_time,
+                li.claim_time,
+                li.status
+            FROM
+                lost_items li
+            LEFT JOIN
+                account p_acc ON li.pick_user_id = p_acc.id
+            LEFT JOIN
+                account c_acc ON li.claim_user_id = c_acc.id
+            ORDER BY
+                li.pick_time DESC, li.id DESC;
+        ";
+
+        self.conn()?
+            .query_map(
+                query,
+                |(
+                    id,
+                    item_name,
+                    pick_place,
+                    pick_user_id,
+                    pick_user_name,
+                    claim_user_id,
+                    claim_user_name,
+                    pick_time,
+                    claim_time,
+                    status,
+                )| {
+                    LostItem {
+                        id,
+                        item_name,
+                        pick_place,
+                        pick_user_id,
+                        pick_user_name,
+                        claim_user_id,
+                        claim_user_name,
+                        pick_time,
+                        claim_time,
+                        status,
+                    }
+                },
+            )
+            .map_err(|e| CafeHubError::database("Database query failed for all lost items", e))
+    }
+
+    fn create_lost_item(
+        &self,
+        data: &ReportLostItemData,
+        pick_user_id: i64,
+        pick_time: NaiveDate,
+    ) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        conn.exec_drop(
+            "INSERT INTO lost_items (item_name, pick_place, pick_user_id, pick_time, status) VALUES (:item_name, :pick_place, :pick_user_id, :pick_time, :status)",
+            params! {
+                "item_name" => &data.item_name,
+                "pick_place" => &data.pick_place,
+                "pick_user_id" => pick_user_id,
+                "pick_time" => pick_time,
+                "status" => 0i8,
+            },
+        )
+        .map_err(|e| CafeHubError::database("Database error while reporting lost item", e))?;
+        Ok(conn.last_insert_id() as i64)
+    }
+
+    fn claim_lost_item(
+        &self,
+        item_id: i64,
+        claim_user_id: i64,
+        claim_time: NaiveDate,
+    ) -> Result<(), CafeHubError> {
+        let mut conn = self.conn()?;
+
+        let item_status: Option<i8> = conn
+            .exec_first(
+                "SELECT status FROM lost_items WHERE id = :item_id",
+                params! { "item_id" => item_id },
+            )
+            .map_err(|e| CafeHubError::database("Failed to query lost item status", e))?;
+
+        match item_status {
+            Some(0) => {
+                conn.exec_drop(
+                    "UPDATE lost_items SET status = 1, claim_user_id = :claim_user_id, claim_time = :claim_time WHERE id = :item_id AND status = 0",
+                    params! {
+                        "claim_user_id" => claim_user_id,
+                        "claim_time" => claim_time,
+                        "item_id" => item_id,
+                    },
+                )
+                .map_err(|e| CafeHubError::database("Database error while claiming item", e))?;
+
+                if conn.affected_rows() > 0 {
+                    Ok(())
+                } else {
+                    Err(CafeHubError::conflict(format!("Failed to update item ID {}. It might have been claimed or deleted concurrently.", item_id)))
+                }
+            }
+            Some(1) => Err(CafeHubError::AlreadyClaimed { item_id }),
+            None => Err(CafeHubError::NotFound {
+                entity: "Lost item",
+                id: item_id,
+            }),
+            Some(_) => Err(CafeHubError::internal(format!(
+                "Unknown status for item ID {}.",
+                item_id
+            ))),
+        }
+    }
+}
+
+impl MessageRepository for MySqlStorage {
+    fn create_message(&self, message: &NewMessage) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        conn.exec_drop(
+            "INSERT INTO message (sender_id, receiver_id, title, message_content, send_date, read_status) VALUES (:sender_id, :receiver_id, :title, :message_content, :send_date, :read_status)",
+            params! {
+                "sender_id" => message.sender_id,
+                "receiver_id" => message.receiver_id,
+                "title" => &message.title,
+                "message_content" => &message.message_content,
+                "send_date" => message.send_date,
+                "read_status" => 0i8,
+            },
+        )
+        .map_err(|e| CafeHubError::database("Database error while sending message", e))?;
+        Ok(conn.last_insert_id() as i64)
+    }
+
+    fn list_sent_messages(&self, user_id: i64) -> Result<Vec<MessageInfo>, CafeHubError> {
+        self.list_messages("sender_id", user_id)
+    }
+
+    fn list_received_messages(&self, user_id: i64) -> Result<Vec<MessageInfo>, CafeHubError> {
+        self.list_messages("receiver_id", user_id)
+    }
+
+    fn find_message_receipt(&self, message_id: i64) -> Result<Option<(i64, i8)>, CafeHubError> {
+        self.conn()?
+            .exec_first(
+                "SELECT receiver_id, read_status FROM message WHERE id = :message_id",
+                params! { "message_id" => message_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database("Database query failed to retrieve message details", e)
+            })
+    }
+
+    fn mark_message_read(&self, message_id: i64, receiver_id: i64) -> Result<bool, CafeHubError> {
+        let mut conn = self.conn()?;
+        conn.exec_drop(
+            "UPDATE message SET read_status = 1 WHERE id = :message_id AND receiver_id = :receiver_id",
+            params! {
+                "message_id" => message_id,
+                "receiver_id" => receiver_id,
+            },
+        )
+        .map_err(|e| CafeHubError::database("Database error while marking message as read", e))?;
+        Ok(conn.affected_rows() > 0)
+    }
+}
@@ -0,0 +1,3391 @@
+use super::{
+    checkout, ensure_purchase_order_open, goods_image_url, goods_sort_order, issue_refund,
+    like_pattern, modifier_groups_from_rows, weekday_mask, weekdays_from_mask, AccountRepository,
+    CategoryRepository, ConsumptionRepository, CouponRepository, CustomerChanges, GoodsChanges,
+    GoodsImage, GoodsRepository, ImageSize, IngredientRepository, LostItemRepository,
+    LoyaltyRepository, MarginGrouping, MessageRepository, ModifierRepository, NewAccount,
+    NewMessage, OrderLine, OrderRepository, OrderTransaction, PromotionRepository,
+    PurchasingRepository, RecipeUsage, RefundLineRow, SaleGoodsRow, WalletRepository,
+    GOODS_SEARCH_FILTER,
+};
+use crate::error::CafeHubError;
+use crate::migrations;
+use crate::models::*;
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
+use rusqlite::{
+    ffi, named_params, Connection, OptionalExtension, Params, Row, ToSql, TransactionBehavior,
+};
+use rust_decimal::Decimal;
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+const BUSY_TIMEOUT_SECS: u64 = 5;
+
+// SQLite has no fixed-point type: DECIMAL columns hold INTEGER or REAL values, so money
+// is read back through this wrapper and rounded to cents.
+struct Money(Decimal);
+
+impl FromSql for Money {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let mut amount = match value {
+            ValueRef::Integer(i) => Decimal::from(i),
+            ValueRef::Real(f) => {
+                Decimal::try_from(f).map_err(|e| FromSqlError::Other(Box::new(e)))?
+            }
+            ValueRef::Text(t) => std::str::from_utf8(t)
+                .map_err(|e| FromSqlError::Other(Box::new(e)))?
+                .parse::<Decimal>()
+                .map_err(|e| FromSqlError::Other(Box::new(e)))?,
+            _ => return Err(FromSqlError::InvalidType),
+        };
+        // NUMERIC affinity drops trailing zeros; restore the DECIMAL(10,2) scale.
+        amount.rescale(2);
+        Ok(Money(amount))
+    }
+}
+
+impl ToSql for Money {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.0.to_string()))
+    }
+}
+
+impl FromSql for DiscountType {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+fn money(row: &Row, idx: usize) -> rusqlite::Result<Decimal> {
+    row.get::<_, Money>(idx).map(|m| m.0)
+}
+
+fn optional_money(row: &Row, idx: usize) -> rusqlite::Result<Option<Decimal>> {
+    row.get::<_, Option<Money>>(idx).map(|m| m.map(|m| m.0))
+}
+
+fn is_unique_violation(e: &rusqlite::Error) -> bool {
+    e.sqlite_error()
+        .is_some_and(|err| err.extended_code == ffi::SQLITE_CONSTRAINT_UNIQUE)
+}
+
+fn query_list<T, P: Params>(
+    conn: &Connection,
+    sql: &str,
+    params: P,
+    f: impl FnMut(&Row) -> rusqlite::Result<T>,
+) -> rusqlite::Result<Vec<T>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map(params, f)?;
+    rows.collect()
+}
+
+// Order header with the code and discount of the coupon it redeemed, if any, and the
+// points redeemed and earned.
+type OrderHeaderRow = (
+    i64,
+    i64,
+    NaiveDateTime,
+    Decimal,
+    Option<String>,
+    Option<Decimal>,
+    i64,
+    i64,
+);
+
+// Every goods with its category, image and stock. Goods with a recipe report how many
+// can be made from the ingredients.
+const GOODS_SOURCE: &str = "
+    SELECT g.id, g.goods_name, g.category_id, c.display_name AS category,
+        c.sort_order AS category_order, g.description, g.price, g.available,
+        COALESCE((
+            SELECT MIN(i.stock / r.quantity)
+            FROM recipe_item r
+            JOIN ingredient i ON r.ingredient_id = i.id
+            WHERE r.goods_id = g.id
+        ), g.stock) AS stock,
+        g.reorder_threshold, g.cost_price, g.archived_at, gi.image_key, g.points_price
+    FROM goods g
+    LEFT JOIN category c ON g.category_id = c.id
+    LEFT JOIN goods_image gi ON gi.goods_id = g.id";
+
+fn account_from_row(row: &Row) -> rusqlite::Result<Account> {
+    Ok(Account {
+        id: row.get(0)?,
+        username: row.get(1)?,
+        phone: row.get(2)?,
+        gender: row.get(3)?,
+        join_time: row.get(4)?,
+        balance: optional_money(row, 5)?,
+        points: row.get(6)?,
+        user_type: row.get(7)?,
+    })
+}
+
+fn message_from_row(row: &Row) -> rusqlite::Result<MessageInfo> {
+    Ok(MessageInfo {
+        id: row.get(0)?,
+        sender_id: row.get(1)?,
+        receiver_id: row.get(2)?,
+        sender_username: row.get(3)?,
+        receiver_username: row.get(4)?,
+        title: row.get(5)?,
+        message_content: row.get(6)?,
+        send_date: row.get(7)?,
+        read_status: row.get(8)?,
+    })
+}
+
+// Applies a balance change and records it in `balance_ledger`. Must run inside the
+// caller's transaction so the ledger entry commits or rolls back with the balance.
+fn apply_balance_change(
+    tx: &Connection,
+    user_id: i64,
+    amount: Decimal,
+    entry_type: LedgerEntryType,
+    reference: Option<String>,
+    operator_id: Option<i64>,
+) -> Result<Decimal, CafeHubError> {
+    let current_balance: Option<Decimal> = tx
+        .query_row(
+            "SELECT balance FROM account WHERE id = :user_id",
+            named_params! { ":user_id": user_id },
+            |row| optional_money(row, 0),
+        )
+        .optional()
+        .map_err(|e| {
+            CafeHubError::database(format!("Failed to read balance for user ID {}", user_id), e)
+        })?
+        .ok_or(CafeHubError::NotFound {
+            entity: "User",
+            id: user_id,
+        })?;
+
+    // Computed here rather than in SQL so the stored value stays exact to the cent.
+    let balance_after = current_balance.unwrap_or(Decimal::ZERO) + amount;
+    tx.execute(
+        "UPDATE account SET balance = :balance WHERE id = :user_id",
+        named_params! {
+            ":balance": Money(balance_after),
+            ":user_id": user_id,
+        },
+    )
+    .map_err(|e| {
+        CafeHubError::database(
+            format!("Failed to update balance for user ID {}", user_id),
+            e,
+        )
+    })?;
+
+    tx.execute(
+        "INSERT INTO balance_ledger (user_id, entry_type, amount, balance_after, reference, operator_id, created_at) VALUES (:user_id, :entry_type, :amount, :balance_after, :reference, :operator_id, :created_at)",
+        named_params! {
+            ":user_id": user_id,
+            ":entry_type": entry_type.as_str(),
+            ":amount": Money(amount),
+            ":balance_after": Money(balance_after),
+            ":reference": reference,
+            ":operator_id": operator_id,
+            ":created_at": Local::now().naive_local(),
+        },
+    )
+    .map_err(|e| CafeHubError::database(format!("Failed to record ledger entry for user ID {}", user_id), e))?;
+
+    Ok(balance_after)
+}
+
+fn current_points(tx: &Connection, user_id: i64) -> Result<i64, CafeHubError> {
+    tx.query_row(
+        "SELECT points FROM account WHERE id = :user_id",
+        named_params! { ":user_id": user_id },
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| {
+        CafeHubError::database(format!("Failed to read points for user ID {}", user_id), e)
+    })?
+    .ok_or(CafeHubError::NotFound {
+        entity: "User",
+        id: user_id,
+    })
+}
+
+// Applies a points change and records it in `points_ledger`, inside the caller's
+// transaction. Earned points are kept as a batch expiring at `expires_at`; debits use
+// up the batches that expire first, so expiring points never takes from newer batches.
+// Returns the points left.
+fn apply_points_change(
+    tx: &Connection,
+    user_id: i64,
+    points: i64,
+    entry_type: PointsEntryType,
+    reference: Option<String>,
+    expires_at: Option<NaiveDateTime>,
+    at: NaiveDateTime,
+) -> Result<i64, CafeHubError> {
+    let points_after = current_points(tx, user_id)? + points;
+    tx.execute(
+        "UPDATE account SET points = :points WHERE id = :user_id",
+        named_params! {
+            ":points": points_after,
+            ":user_id": user_id,
+        },
+    )
+    .map_err(|e| {
+        CafeHubError::database(
+            format!("Failed to update points for user ID {}", user_id),
+            e,
+        )
+    })?;
+
+    let mut to_use = -points;
+    if to_use > 0 {
+        let batches: Vec<(i64, i64)> = query_list(
+            tx,
+            "SELECT id, remaining FROM points_ledger WHERE user_id = :user_id AND remaining > 0 ORDER BY expires_at ASC, id ASC",
+            named_params! { ":user_id": user_id },
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| CafeHubError::database(format!("Failed to read earned points of user ID {}", user_id), e))?;
+        for (batch_id, remaining) in batches {
+            if to_use == 0 {
+                break;
+            }
+            let used = remaining.min(to_use);
+            tx.execute(
+                "UPDATE points_ledger SET remaining = remaining - :used WHERE id = :batch_id",
+                named_params! {
+                    ":used": used,
+                    ":batch_id": batch_id,
+                },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Failed to use earned points of user ID {}", user_id),
+                    e,
+                )
+            })?;
+            to_use -= used;
+        }
+    }
+
+    tx.execute(
+        "INSERT INTO points_ledger (user_id, entry_type, points, points_after, remaining, expires_at, reference, created_at) VALUES (:user_id, :entry_type, :points, :points_after, :remaining, :expires_at, :reference, :created_at)",
+        named_params! {
+            ":user_id": user_id,
+            ":entry_type": entry_type.as_str(),
+            ":points": points,
+            ":points_after": points_after,
+            ":remaining": (points > 0).then_some(points),
+            ":expires_at": expires_at,
+            ":reference": reference,
+            ":created_at": at,
+        },
+    )
+    .map_err(|e| CafeHubError::database(format!("Failed to record points for user ID {}", user_id), e))?;
+
+    Ok(points_after)
+}
+
+// Takes the earned points that ran out by `now` off the customer's points. Returns the
+// points left.
+fn expire_points(tx: &Connection, user_id: i64, now: NaiveDateTime) -> Result<i64, CafeHubError> {
+    let expired: i64 = tx
+        .query_row(
+            "SELECT COALESCE(SUM(remaining), 0) FROM points_ledger WHERE user_id = :user_id AND remaining > 0 AND expires_at <= :now",
+            named_params! {
+                ":user_id": user_id,
+                ":now": now,
+            },
+            |row| row.get(0),
+        )
+        .map_err(|e| CafeHubError::database(format!("Failed to read expired points of user ID {}", user_id), e))?;
+    if expired == 0 {
+        return current_points(tx, user_id);
+    }
+    apply_points_change(
+        tx,
+        user_id,
+        -expired,
+        PointsEntryType::Expired,
+        None,
+        None,
+        now,
+    )
+}
+
+// Moves the stock of a goods item and records the movement. Returns the new stock.
+fn apply_stock_change(
+    tx: &Connection,
+    goods_id: i32,
+    delta: i32,
+    reason: StockMovementReason,
+    reference: Option<String>,
+    operator_id: Option<i64>,
+) -> Result<i32, CafeHubError> {
+    tx.execute(
+        "UPDATE goods SET stock = COALESCE(stock, 0) + :delta WHERE id = :goods_id",
+        named_params! {
+            ":delta": delta,
+            ":goods_id": goods_id,
+        },
+    )
+    .map_err(|e| {
+        CafeHubError::database(
+            format!("Failed to update stock for goods ID {}", goods_id),
+            e,
+        )
+    })?;
+
+    let stock_after: i32 = tx
+        .query_row(
+            "SELECT stock FROM goods WHERE id = :goods_id",
+            named_params! { ":goods_id": goods_id },
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| {
+            CafeHubError::database(format!("Failed to read stock for goods ID {}", goods_id), e)
+        })?
+        .ok_or(CafeHubError::NotFound {
+            entity: "Goods",
+            id: goods_id.into(),
+        })?;
+
+    tx.execute(
+        "INSERT INTO stock_movement (goods_id, reason, delta, stock_after, reference, operator_id, created_at) VALUES (:goods_id, :reason, :delta, :stock_after, :reference, :operator_id, :created_at)",
+        named_params! {
+            ":goods_id": goods_id,
+            ":reason": reason.as_str(),
+            ":delta": delta,
+            ":stock_after": stock_after,
+            ":reference": reference,
+            ":operator_id": operator_id,
+            ":created_at": Local::now().naive_local(),
+        },
+    )
+    .map_err(|e| CafeHubError::database(format!("Failed to record stock movement for goods ID {}", goods_id), e))?;
+
+    Ok(stock_after)
+}
+
+// Sets the cost price of a goods item and records it in the cost history,
+// unless the cost is unchanged.
+fn apply_cost_change(
+    tx: &Connection,
+    goods_id: i32,
+    cost_price: Decimal,
+    reference: Option<String>,
+    operator_id: Option<i64>,
+) -> Result<(), CafeHubError> {
+    let current_cost: Option<Option<Decimal>> = tx
+        .query_row(
+            "SELECT cost_price FROM goods WHERE id = :goods_id",
+            named_params! { ":goods_id": goods_id },
+            |row| optional_money(row, 0),
+        )
+        .optional()
+        .map_err(|e| {
+            CafeHubError::database(
+                format!("Failed to read cost price for goods ID {}", goods_id),
+                e,
+            )
+        })?;
+    match current_cost {
+        None => {
+            return Err(CafeHubError::NotFound {
+                entity: "Goods",
+                id: goods_id.into(),
+            })
+        }
+        Some(Some(current_cost)) if current_cost == cost_price => return Ok(()),
+        Some(_) => {}
+    }
+
+    tx.execute(
+        "UPDATE goods SET cost_price = :cost_price WHERE id = :goods_id",
+        named_params! {
+            ":cost_price": Money(cost_price),
+            ":goods_id": goods_id,
+        },
+    )
+    .map_err(|e| {
+        CafeHubError::database(
+            format!("Failed to update cost price for goods ID {}", goods_id),
+            e,
+        )
+    })?;
+
+    tx.execute(
+        "INSERT INTO goods_cost_history (goods_id, cost_price, reference, operator_id, changed_at) VALUES (:goods_id, :cost_price, :reference, :operator_id, :changed_at)",
+        named_params! {
+            ":goods_id": goods_id,
+            ":cost_price": Money(cost_price),
+            ":reference": reference,
+            ":operator_id": operator_id,
+            ":changed_at": Local::now().naive_local(),
+        },
+    )
+    .map_err(|e| CafeHubError::database(format!("Failed to record cost price for goods ID {}", goods_id), e))?;
+
+    Ok(())
+}
+
+fn load_modifier_groups(
+    conn: &Connection,
+    goods_id: i32,
+) -> Result<Vec<ModifierGroup>, CafeHubError> {
+    let rows = query_list(
+        conn,
+        "SELECT g.id, g.group_name, g.is_required, g.allow_multiple, o.id, o.option_name, o.price_delta
+         FROM modifier_group g
+         LEFT JOIN modifier_option o ON o.group_id = g.id
+         WHERE g.goods_id = :goods_id
+         ORDER BY g.id ASC, o.id ASC",
+        named_params! { ":goods_id": goods_id },
+        |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+                optional_money(row, 6)?,
+            ))
+        },
+    )
+    .map_err(|e| {
+        CafeHubError::database(
+            format!("Failed to query modifiers for goods ID {}", goods_id),
+            e,
+        )
+    })?;
+    Ok(modifier_groups_from_rows(goods_id, rows))
+}
+
+fn load_recipe_usage(conn: &Connection, goods_id: i32) -> Result<Vec<RecipeUsage>, CafeHubError> {
+    query_list(
+        conn,
+        "SELECT r.ingredient_id, i.ingredient_name, r.quantity, i.stock
+         FROM recipe_item r
+         JOIN ingredient i ON r.ingredient_id = i.id
+         WHERE r.goods_id = :goods_id",
+        named_params! { ":goods_id": goods_id },
+        |row| {
+            Ok(RecipeUsage {
+                ingredient_id: row.get(0)?,
+                ingredient_name: row.get(1)?,
+                quantity: row.get(2)?,
+                stock: row.get(3)?,
+            })
+        },
+    )
+    .map_err(|e| {
+        CafeHubError::database(
+            format!("Failed to query recipe for goods ID {}", goods_id),
+            e,
+        )
+    })
+}
+
+// Drops a message into the administrator's inbox, sent from their own account. Skipped
+// when there is no administrator to receive it.
+fn notify_administrator(
+    conn: &Connection,
+    title: &str,
+    message_content: &str,
+    send_date: NaiveDate,
+) -> Result<(), CafeHubError> {
+    let admin_id: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM account WHERE user_type = 0 ORDER BY id ASC LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| CafeHubError::database("Database error finding administrator", e))?;
+    let Some(admin_id) = admin_id else {
+        return Ok(());
+    };
+
+    conn.execute(
+        "INSERT INTO message (sender_id, receiver_id, title, message_content, send_date, read_status) VALUES (:admin_id, :admin_id, :title, :message_content, :send_date, 0)",
+        named_params! {
+            ":admin_id": admin_id,
+            ":title": title,
+            ":message_content": message_content,
+            ":send_date": send_date,
+        },
+    )
+    .map_err(|e| CafeHubError::database("Failed to notify administrator", e))?;
+    Ok(())
+}
+
+fn purchase_order_status(
+    conn: &Connection,
+    purchase_order_id: i64,
+) -> Result<Option<String>, CafeHubError> {
+    conn.query_row(
+        "SELECT status FROM purchase_order WHERE id = :purchase_order_id",
+        named_params! { ":purchase_order_id": purchase_order_id },
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| {
+        CafeHubError::database(
+            format!("Failed to query purchase order ID {}", purchase_order_id),
+            e,
+        )
+    })
+}
+
+fn close_purchase_order(
+    conn: &Connection,
+    purchase_order_id: i64,
+    status: PurchaseOrderStatus,
+    operator_id: i64,
+    closed_at: NaiveDateTime,
+) -> Result<(), CafeHubError> {
+    conn.execute(
+        "UPDATE purchase_order SET status = :status, closed_by = :closed_by, closed_at = :closed_at WHERE id = :purchase_order_id",
+        named_params! {
+            ":status": status.as_str(),
+            ":closed_by": operator_id,
+            ":closed_at": closed_at,
+            ":purchase_order_id": purchase_order_id,
+        },
+    )
+    .map_err(|e| {
+        CafeHubError::database(
+            format!("Failed to update purchase order ID {}", purchase_order_id),
+            e,
+        )
+    })?;
+    Ok(())
+}
+
+fn ensure_category_exists(conn: &Connection, category_id: i32) -> Result<(), CafeHubError> {
+    let category_exists: Option<i32> = conn
+        .query_row(
+            "SELECT id FROM category WHERE id = :category_id",
+            named_params! { ":category_id": category_id },
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| {
+            CafeHubError::database(format!("Failed to query category ID {}", category_id), e)
+        })?;
+    if category_exists.is_none() {
+        return Err(CafeHubError::NotFound {
+            entity: "Category",
+            id: category_id.into(),
+        });
+    }
+    Ok(())
+}
+
+fn ensure_goods_exists(conn: &Connection, goods_id: i32) -> Result<(), CafeHubError> {
+    let goods_exists: Option<i32> = conn
+        .query_row(
+            "SELECT id FROM goods WHERE id = :goods_id",
+            named_params! { ":goods_id": goods_id },
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| CafeHubError::database(format!("Failed to query goods ID {}", goods_id), e))?;
+    if goods_exists.is_none() {
+        return Err(CafeHubError::NotFound {
+            entity: "Goods",
+            id: goods_id.into(),
+        });
+    }
+    Ok(())
+}
+
+fn load_promotions(conn: &Connection, filter: &str) -> Result<Vec<Promotion>, CafeHubError> {
+    let query = format!(
+        "SELECT id, promotion_name, discount_type, discount_value, goods_id, category_id, weekdays, start_time, end_time, start_date, end_date, paused
+         FROM promotion {} ORDER BY id ASC",
+        filter
+    );
+    query_list(conn, &query, [], |row| {
+        Ok(Promotion {
+            id: row.get(0)?,
+            promotion_name: row.get(1)?,
+            discount_type: row.get(2)?,
+            discount_value: money(row, 3)?,
+            goods_id: row.get(4)?,
+            category_id: row.get(5)?,
+            weekdays: weekdays_from_mask(row.get(6)?),
+            start_time: row.get(7)?,
+            end_time: row.get(8)?,
+            start_date: row.get(9)?,
+            end_date: row.get(10)?,
+            paused: row.get(11)?,
+        })
+    })
+    .map_err(|e| CafeHubError::database("Database query failed for promotions", e))
+}
+
+fn query_coupons<P: Params>(
+    conn: &Connection,
+    filter: &str,
+    params: P,
+) -> Result<Vec<Coupon>, CafeHubError> {
+    let query = format!(
+        "SELECT c.id, c.code, c.discount_type, c.discount_value, c.min_spend, c.expires_at, c.max_redemptions, c.max_per_customer,
+                (SELECT COUNT(*) FROM coupon_redemption r WHERE r.coupon_id = c.id AND r.refunded_at IS NULL), c.disabled, c.created_at
+         FROM coupon c {}",
+        filter
+    );
+    query_list(conn, &query, params, |row| {
+        Ok(Coupon {
+            id: row.get(0)?,
+            code: row.get(1)?,
+            discount_type: row.get(2)?,
+            discount_value: money(row, 3)?,
+            min_spend: optional_money(row, 4)?,
+            expires_at: row.get(5)?,
+            max_redemptions: row.get(6)?,
+            max_per_customer: row.get(7)?,
+            times_redeemed: row.get(8)?,
+            disabled: row.get(9)?,
+            created_at: row.get(10)?,
+        })
+    })
+    .map_err(|e| CafeHubError::database("Database query failed for coupons", e))
+}
+
+// The promotion must target goods or a category that exists.
+fn ensure_promotion_target_exists(
+    conn: &Connection,
+    data: &PromotionData,
+) -> Result<(), CafeHubError> {
+    if let Some(goods_id) = data.goods_id {
+        ensure_goods_exists(conn, goods_id)?;
+    }
+    if let Some(category_id) = data.category_id {
+        ensure_category_exists(conn, category_id)?;
+    }
+    Ok(())
+}
+
+// Embedded backend for single-machine installs. A single connection is shared behind a
+// mutex, which also serializes transactions.
+pub struct SqliteStorage {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                format!(
+                    "Failed to create database directory {}: {}",
+                    parent.display(),
+                    e
+                )
+            })?;
+        }
+        let conn = Connection::open(path)
+            .map_err(|e| format!("Failed to open SQLite database {}: {}", path.display(), e))?;
+        println!("Using SQLite database {}.", path.display());
+        SqliteStorage::from_connection(conn)
+    }
+
+    // A private database that disappears when the storage is dropped, used by the tests.
+    pub fn open_in_memory() -> Result<Self, String> {
+        let conn = Connection::open_in_memory()
+            .map_err(|e| format!("Failed to open in-memory SQLite database: {}", e))?;
+        SqliteStorage::from_connection(conn)
+    }
+
+    fn from_connection(mut conn: Connection) -> Result<Self, String> {
+        conn.execute_batch("PRAGMA foreign_keys = ON;")
+            .map_err(|e| format!("Failed to enable foreign keys: {}", e))?;
+        conn.busy_timeout(Duration::from_secs(BUSY_TIMEOUT_SECS))
+            .map_err(|e| format!("Failed to set busy timeout: {}", e))?;
+
+        let applied = migrations::run_pending_sqlite(&mut conn)?;
+        println!(
+            "Database schema is at version {} ({} migration(s) applied).",
+            migrations::latest_version(),
+            applied
+        );
+        Ok(SqliteStorage {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn conn(&self) -> Result<MutexGuard<'_, Connection>, CafeHubError> {
+        self.conn
+            .lock()
+            .map_err(|_| CafeHubError::internal("SQLite connection is unavailable"))
+    }
+
+    // `filter` is appended to the query and can refer to any column of `GOODS_SOURCE`.
+    fn query_goods<P: Params>(&self, filter: &str, params: P) -> Result<Vec<Goods>, CafeHubError> {
+        let query = format!(
+            "
+            SELECT id, goods_name, category_id, category, description, price, available, stock,
+                reorder_threshold, cost_price, archived_at, image_key, points_price
+            FROM ({}) AS available
+            {}",
+            GOODS_SOURCE, filter
+        );
+
+        query_list(&*self.conn()?, &query, params, |row| {
+            let id = row.get(0)?;
+            let image_key: Option<String> = row.get(11)?;
+            Ok(Goods {
+                id,
+                goods_name: row.get(1)?,
+                category_id: row.get(2)?,
+                category: row.get(3)?,
+                description: row.get(4)?,
+                price: money(row, 5)?,
+                available: row.get(6)?,
+                stock: row.get(7)?,
+                reorder_threshold: row.get(8)?,
+                cost_price: optional_money(row, 9)?,
+                points_price: row.get(12)?,
+                archived_at: row.get(10)?,
+                image: image_key
+                    .as_deref()
+                    .map(|key| goods_image_url(id, key, ImageSize::Full)),
+                thumbnail: image_key
+                    .as_deref()
+                    .map(|key| goods_image_url(id, key, ImageSize::Thumbnail)),
+            })
+        })
+        .map_err(|e| CafeHubError::database("Database query failed for goods", e))
+    }
+
+    fn list_messages(&self, column: &str, user_id: i64) -> Result<Vec<MessageInfo>, CafeHubError> {
+        let query = format!(
+            "
+            SELECT
+                m.id, m.sender_id, m.receiver_id,
+                s_acc.username AS sender_username,
+                r_acc.username AS receiver_username,
+                m.title, m.message_content, m.send_date, m.read_status
+            FROM message m
+            JOIN account s_acc ON m.sender_id = s_acc.id
+            JOIN account r_acc ON m.receiver_id = r_acc.id
+            WHERE m.{} = :user_id",
+            column
+        );
+
+        query_list(
+            &*self.conn()?,
+            &query,
+            named_params! { ":user_id": user_id },
+            message_from_row,
+        )
+        .map_err(|e| CafeHubError::database("Database query failed for messages", e))
+    }
+}
+
+impl AccountRepository for SqliteStorage {
+    fn find_account_by_username(
+        &self,
+        username: &str,
+    ) -> Result<Option<(Account, String)>, CafeHubError> {
+        self.conn()?
+            .query_row(
+                "SELECT id, username, phone, gender, join_time, balance, points, user_type, password FROM account WHERE username = :username",
+                named_params! { ":username": username },
+                |row| Ok((account_from_row(row)?, row.get(8)?)),
+            )
+            .optional()
+            .map_err(|e| CafeHubError::database("Database query failed", e))
+    }
+
+    fn find_customer(&self, user_id: i64) -> Result<Option<Account>, CafeHubError> {
+        self.conn()?
+            .query_row(
+                "SELECT id, username, phone, gender, join_time, balance, points, user_type FROM account WHERE id = :user_id AND user_type = 1",
+                named_params! { ":user_id": user_id },
+                account_from_row,
+            )
+            .optional()
+            .map_err(|e| CafeHubError::database("Database query failed", e))
+    }
+
+    fn find_user_type(&self, user_id: i64) -> Result<Option<i8>, CafeHubError> {
+        self.conn()?
+            .query_row(
+                "SELECT user_type FROM account WHERE id = :user_id",
+                named_params! { ":user_id": user_id },
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| CafeHubError::database("Failed to query user", e))
+    }
+
+    fn find_first_staff_id(&self) -> Result<Option<i64>, CafeHubError> {
+        self.conn()?
+            .query_row(
+                "SELECT id FROM account WHERE user_type = 0 ORDER BY id ASC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| CafeHubError::database("Database error finding administrator", e))
+    }
+
+    fn customer_password_hash(&self, user_id: i64) -> Result<Option<String>, CafeHubError> {
+        self.conn()?
+            .query_row(
+                "SELECT password FROM account WHERE id = :user_id AND user_type = 1",
+                named_params! { ":user_id": user_id },
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| CafeHubError::database("Database query failed", e))
+    }
+
+    fn create_customer(&self, account: &NewAccount) -> Result<i64, CafeHubError> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO account (username, password, phone, gender, join_time, balance, user_type) VALUES (:username, :password, :phone, :gender, :join_time, :balance, :user_type)",
+            named_params! {
+                ":username": &account.username,
+                ":password": &account.password_hash,
+                ":phone": &account.phone,
+                ":gender": account.gender,
+                ":join_time": account.join_time,
+                ":balance": Money(Decimal::new(0, 2)),
+                ":user_type": 1i8,
+            },
+        )
+        .map_err(|e| {
+            if is_unique_violation(&e) {
+                CafeHubError::DuplicateUsername {
+                    username: account.username.clone(),
+                }
+            } else {
+                CafeHubError::database("Database error during registration", e)
+            }
+        })?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    fn create_first_staff(&self, account: &NewAccount) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let admin_count: i64 = tx
+            .query_row(
+                "SELECT COUNT(*) FROM account WHERE user_type = 0",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| CafeHubError::database("Failed to check for existing administrator", e))?;
+        if admin_count > 0 {
+            return Err(CafeHubError::conflict(
+                "Setup has already been completed: an administrator account exists.",
+            ));
+        }
+
+        tx.execute(
+            "INSERT INTO account (username, password, join_time, user_type) VALUES (:username, :password, :join_time, :user_type)",
+            named_params! {
+                ":username": &account.username,
+                ":password": &account.password_hash,
+                ":join_time": account.join_time,
+                ":user_type": 0i8,
+            },
+        )
+        .map_err(|e| CafeHubError::database("Failed to create administrator account", e))?;
+        let admin_id = tx.last_insert_rowid();
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(admin_id)
+    }
+
+    fn update_customer_password(
+        &self,
+        user_id: i64,
+        password_hash: &str,
+    ) -> Result<bool, CafeHubError> {
+        let updated = self
+            .conn()?
+            .execute(
+                "UPDATE account SET password = :new_password WHERE id = :user_id AND user_type = 1",
+                named_params! {
+                    ":new_password": password_hash,
+                    ":user_id": user_id,
+                },
+            )
+            .map_err(|e| CafeHubError::database("Database password update failed", e))?;
+        Ok(updated > 0)
+    }
+
+    fn update_customer_details(
+        &self,
+        user_id: i64,
+        changes: &CustomerChanges,
+    ) -> Result<bool, CafeHubError> {
+        if changes.username.is_none() && changes.phone.is_none() && changes.gender.is_none() {
+            return Ok(false);
+        }
+
+        let conn = self.conn()?;
+        if let Some(ref username) = changes.username {
+            let existing_user: Option<i64> = conn
+                .query_row(
+                    "SELECT id FROM account WHERE username = :username AND id != :user_id_to_exclude",
+                    named_params! { ":username": username, ":user_id_to_exclude": user_id },
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|e| CafeHubError::database("DB error checking username uniqueness", e))?;
+
+            if existing_user.is_some() {
+                return Err(CafeHubError::DuplicateUsername {
+                    username: username.clone(),
+                });
+            }
+        }
+
+        let updated = conn
+            .execute(
+                "UPDATE account SET
+                    username = COALESCE(:username, username),
+                    phone = CASE WHEN :set_phone THEN :phone ELSE phone END,
+                    gender = COALESCE(:gender, gender)
+                 WHERE id = :user_id AND user_type = 1",
+                named_params! {
+                    ":username": &changes.username,
+                    ":set_phone": changes.phone.is_some(),
+                    ":phone": changes.phone.clone().flatten(),
+                    ":gender": changes.gender,
+                    ":user_id": user_id,
+                },
+            )
+            .map_err(|e| {
+                if is_unique_violation(&e) {
+                    CafeHubError::conflict("Update failed: Username or Phone number might already be in use by another account.")
+                } else {
+                    CafeHubError::database("Database update failed", e)
+                }
+            })?;
+        Ok(updated > 0)
+    }
+
+    fn count_customers(&self, joined_since: Option<NaiveDate>) -> Result<i64, CafeHubError> {
+        let conn = self.conn()?;
+        match joined_since {
+            Some(start_date) => conn.query_row(
+                "SELECT COUNT(*) FROM account WHERE user_type = 1 AND join_time >= :start_date",
+                named_params! { ":start_date": start_date },
+                |row| row.get(0),
+            ),
+            None => conn.query_row(
+                "SELECT COUNT(*) FROM account WHERE user_type = 1",
+                [],
+                |row| row.get(0),
+            ),
+        }
+        .map_err(|e| CafeHubError::database("Database query failed", e))
+    }
+
+    fn count_staff(&self) -> Result<i64, CafeHubError> {
+        self.conn()?
+            .query_row(
+                "SELECT COUNT(*) FROM account WHERE user_type = 0",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| CafeHubError::database("Failed to count administrator accounts", e))
+    }
+
+    fn list_users(&self) -> Result<Vec<UserBasicInfo>, CafeHubError> {
+        query_list(
+            &*self.conn()?,
+            "SELECT id, username FROM account ORDER BY id ASC",
+            [],
+            |row| {
+                Ok(UserBasicInfo {
+                    id: row.get(0)?,
+                    username: row.get(1)?,
+                })
+            },
+        )
+        .map_err(|e| CafeHubError::database("Database query failed for fetching all users", e))
+    }
+}
+
+impl WalletRepository for SqliteStorage {
+    fn recharge_balance(
+        &self,
+        user_id: i64,
+        amount: Decimal,
+        operator_id: i64,
+    ) -> Result<Decimal, CafeHubError> {
+        let mut conn = self.conn()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let user_type: Option<i8> = tx
+            .query_row(
+                "SELECT user_type FROM account WHERE id = :user_id",
+                named_params! { ":user_id": user_id },
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| CafeHubError::database("Failed to query user", e))?;
+
+        match user_type {
+            Some(1) => {}
+            Some(_) => {
+                return Err(CafeHubError::validation(format!(
+                    "User with ID {} is not a customer.",
+                    user_id
+                )))
+            }
+            None => {
+                return Err(CafeHubError::NotFound {
+                    entity: "User",
+                    id: user_id,
+                })
+            }
+        }
+
+        let balance_after = apply_balance_change(
+            &tx,
+            user_id,
+            amount,
+            LedgerEntryType::Recharge,
+            None,
+            Some(operator_id),
+        )?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(balance_after)
+    }
+
+    fn adjust_balance(
+        &self,
+        user_id: i64,
+        amount: Decimal,
+        note: &str,
+        operator_id: i64,
+    ) -> Result<Decimal, CafeHubError> {
+        let mut conn = self.conn()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let user_info: Option<(Option<Decimal>, i8)> = tx
+            .query_row(
+                "SELECT balance, user_type FROM account WHERE id = :user_id",
+                named_params! { ":user_id": user_id },
+                |row| Ok((optional_money(row, 0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| CafeHubError::database("Failed to query user", e))?;
+
+        let current_balance = match user_info {
+            Some((balance, 1)) => balance.unwrap_or(Decimal::ZERO),
+            Some(_) => {
+                return Err(CafeHubError::validation(format!(
+                    "User with ID {} is not a customer.",
+                    user_id
+                )))
+            }
+            None => {
+                return Err(CafeHubError::NotFound {
+                    entity: "User",
+                    id: user_id,
+                })
+            }
+        };
+
+        if current_balance + amount < Decimal::ZERO {
+            return Err(CafeHubError::InsufficientBalance {
+                required: -amount,
+                available: current_balance,
+                shortfall: -(current_balance + amount),
+            });
+        }
+
+        let balance_after = apply_balance_change(
+            &tx,
+            user_id,
+            amount,
+            LedgerEntryType::Adjustment,
+            Some(note.to_string()),
+            Some(operator_id),
+        )?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(balance_after)
+    }
+
+    fn balance_statement(&self, user_id: i64) -> Result<Vec<BalanceLedgerEntry>, CafeHubError> {
+        let query = "
+            SELECT l.id, l.entry_type, l.amount, l.balance_after, l.reference, op.username, l.created_at
+            FROM balance_ledger l
+            LEFT JOIN account op ON l.operator_id = op.id
+            WHERE l.user_id = :user_id
+            ORDER BY l.created_at DESC, l.id DESC";
+
+        query_list(
+            &*self.conn()?,
+            query,
+            named_params! { ":user_id": user_id },
+            |row| {
+                Ok(BalanceLedgerEntry {
+                    id: row.get(0)?,
+                    entry_type: row.get(1)?,
+                    amount: money(row, 2)?,
+                    balance_after: money(row, 3)?,
+                    reference: row.get(4)?,
+                    operator_name: row.get(5)?,
+                    created_at: row.get(6)?,
+                })
+            },
+        )
+        .map_err(|e| CafeHubError::database("Database query failed for balance statement", e))
+    }
+
+    fn balance_mismatches(&self) -> Result<Vec<BalanceMismatch>, CafeHubError> {
+        // Sums of REAL values can pick up floating point noise, so compare at cent precision.
+        let query = "
+            SELECT a.id, a.username, COALESCE(a.balance, 0) AS current_balance, COALESCE(SUM(l.amount), 0) AS ledger_total
+            FROM account a
+            LEFT JOIN balance_ledger l ON l.user_id = a.id
+            WHERE a.user_type = 1
+            GROUP BY a.id, a.username, a.balance
+            HAVING ROUND(current_balance, 2) <> ROUND(ledger_total, 2)
+            ORDER BY a.id ASC";
+
+        query_list(&*self.conn()?, query, [], |row| {
+            Ok(BalanceMismatch {
+                user_id: row.get(0)?,
+                username: row.get(1)?,
+                balance: money(row, 2)?,
+                ledger_total: money(row, 3)?,
+            })
+        })
+        .map_err(|e| {
+            CafeHubError::database("Database query failed for balance consistency check", e)
+        })
+    }
+}
+
+impl LoyaltyRepository for SqliteStorage {
+    fn spendable_points(&self, user_id: i64, now: NaiveDateTime) -> Result<i64, CafeHubError> {
+        self.conn()?
+            .query_row(
+                "SELECT points - COALESCE((SELECT SUM(remaining) FROM points_ledger WHERE user_id = :user_id AND remaining > 0 AND expires_at <= :now), 0) FROM account WHERE id = :user_id",
+                named_params! {
+                    ":user_id": user_id,
+                    ":now": now,
+                },
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to read points for user ID {}", user_id), e)
+            })?
+            .ok_or(CafeHubError::NotFound {
+                entity: "User",
+                id: user_id,
+            })
+    }
+
+    fn points_history(&self, user_id: i64) -> Result<Vec<PointsLedgerEntry>, CafeHubError> {
+        let query = "
+            SELECT id, entry_type, points, points_after, remaining, expires_at, reference, created_at
+            FROM points_ledger
+            WHERE user_id = :user_id
+            ORDER BY created_at DESC, id DESC";
+
+        query_list(
+            &*self.conn()?,
+            query,
+            named_params! { ":user_id": user_id },
+            |row| {
+                Ok(PointsLedgerEntry {
+                    id: row.get(0)?,
+                    entry_type: row.get(1)?,
+                    points: row.get(2)?,
+                    points_after: row.get(3)?,
+                    remaining: row.get(4)?,
+                    expires_at: row.get(5)?,
+                    reference: row.get(6)?,
+                    created_at: row.get(7)?,
+                })
+            },
+        )
+        .map_err(|e| CafeHubError::database("Database query failed for points history", e))
+    }
+}
+
+impl GoodsRepository for SqliteStorage {
+    fn list_goods(&self) -> Result<Vec<Goods>, CafeHubError> {
+        self.query_goods("", [])
+    }
+
+    fn adjust_stock(
+        &self,
+        goods_id: i32,
+        delta: i32,
+        reason: StockMovementReason,
+        note: Option<&str>,
+        operator_id: i64,
+    ) -> Result<i32, CafeHubError> {
+        let mut conn = self.conn()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let current_stock: Option<Option<i32>> = tx
+            .query_row(
+                "SELECT stock FROM goods WHERE id = :goods_id",
+                named_params! { ":goods_id": goods_id },
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to query goods ID {}", goods_id), e)
+            })?;
+        let current_stock = match current_stock {
+            Some(stock) => stock.unwrap_or(0),
+            None => {
+                return Err(CafeHubError::NotFound {
+                    entity: "Goods",
+                    id: goods_id.into(),
+                })
+            }
+        };
+
+        if current_stock + delta < 0 {
+            return Err(CafeHubError::InsufficientStock {
+                goods_id,
+                requested: -delta,
+                available: current_stock,
+            });
+        }
+
+        let stock_after = apply_stock_change(
+            &tx,
+            goods_id,
+            delta,
+            reason,
+            note.map(str::to_string),
+            Some(operator_id),
+        )?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(stock_after)
+    }
+
+    fn stock_movements(&self, goods_id: i32) -> Result<Vec<StockMovement>, CafeHubError> {
+        let query = "
+            SELECT m.id, m.reason, m.delta, m.stock_after, m.reference, op.username, m.created_at
+            FROM stock_movement m
+            LEFT JOIN account op ON m.operator_id = op.id
+            WHERE m.goods_id = :goods_id
+            ORDER BY m.created_at DESC, m.id DESC";
+
+        query_list(
+            &*self.conn()?,
+            query,
+            named_params! { ":goods_id": goods_id },
+            |row| {
+                Ok(StockMovement {
+                    id: row.get(0)?,
+                    reason: row.get(1)?,
+                    delta: row.get(2)?,
+                    stock_after: row.get(3)?,
+                    reference: row.get(4)?,
+                    operator_name: row.get(5)?,
+                    created_at: row.get(6)?,
+                })
+            },
+        )
+        .map_err(|e| CafeHubError::database("Database query failed for stock movements", e))
+    }
+
+    fn cost_history(&self, goods_id: i32) -> Result<Vec<GoodsCostChange>, CafeHubError> {
+        let query = "
+            SELECT h.id, h.cost_price, h.reference, op.username, h.changed_at
+            FROM goods_cost_history h
+            LEFT JOIN account op ON h.operator_id = op.id
+            WHERE h.goods_id = :goods_id
+            ORDER BY h.changed_at DESC, h.id DESC";
+
+        query_list(
+            &*self.conn()?,
+            query,
+            named_params! { ":goods_id": goods_id },
+            |row| {
+                Ok(GoodsCostChange {
+                    id: row.get(0)?,
+                    cost_price: money(row, 1)?,
+                    reference: row.get(2)?,
+                    operator_name: row.get(3)?,
+                    changed_at: row.get(4)?,
+                })
+            },
+        )
+        .map_err(|e| CafeHubError::database("Database query failed for cost history", e))
+    }
+
+    fn set_goods_archived(
+        &self,
+        goods_id: i32,
+        archived_at: Option<NaiveDateTime>,
+    ) -> Result<bool, CafeHubError> {
+        let updated = self
+            .conn()?
+            .execute(
+                "UPDATE goods SET archived_at = CASE WHEN :archived_at IS NULL THEN NULL ELSE COALESCE(archived_at, :archived_at) END
+                 WHERE id = :goods_id",
+                named_params! {
+                    ":archived_at": archived_at,
+                    ":goods_id": goods_id,
+                },
+            )
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to archive goods ID {}", goods_id), e)
+            })?;
+        Ok(updated > 0)
+    }
+
+    fn delete_goods(&self, goods_id: i32) -> Result<bool, CafeHubError> {
+        let mut conn = self.conn()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let has_history: Option<bool> = tx
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM order_items WHERE goods_id = :goods_id)
+                    OR EXISTS (SELECT 1 FROM consumption WHERE goods_id = :goods_id)
+                    OR EXISTS (SELECT 1 FROM purchase_order_item WHERE goods_id = :goods_id)
+                 FROM goods WHERE id = :goods_id",
+                named_params! { ":goods_id": goods_id },
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Failed to check history of goods ID {}", goods_id),
+                    e,
+                )
+            })?;
+        match has_history {
+            None => return Ok(false),
+            Some(true) => {
+                return Err(CafeHubError::conflict(format!(
+                    "Goods ID {} has sales or purchase history and can only be archived.",
+                    goods_id
+                )))
+            }
+            Some(false) => {}
+        }
+
+        tx.execute(
+            "DELETE FROM goods WHERE id = :goods_id",
+            named_params! { ":goods_id": goods_id },
+        )
+        .map_err(|e| {
+            CafeHubError::database(format!("Failed to delete goods ID {}", goods_id), e)
+        })?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(true)
+    }
+
+    fn set_goods_image(&self, goods_id: i32, image: &GoodsImage) -> Result<bool, CafeHubError> {
+        let mut conn = self.conn()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let goods_exists: Option<i32> = tx
+            .query_row(
+                "SELECT id FROM goods WHERE id = :goods_id",
+                named_params! { ":goods_id": goods_id },
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to query goods ID {}", goods_id), e)
+            })?;
+        if goods_exists.is_none() {
+            return Ok(false);
+        }
+
+        tx.execute(
+            "INSERT INTO goods_image (goods_id, image_key, image, thumbnail) VALUES (:goods_id, :image_key, :image, :thumbnail)
+             ON CONFLICT (goods_id) DO UPDATE SET image_key = excluded.image_key, image = excluded.image, thumbnail = excluded.thumbnail",
+            named_params! {
+                ":goods_id": goods_id,
+                ":image_key": &image.image_key,
+                ":image": &image.image,
+                ":thumbnail": &image.thumbnail,
+            },
+        )
+        .map_err(|e| {
+            CafeHubError::database(format!("Failed to save image of goods ID {}", goods_id), e)
+        })?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(true)
+    }
+
+    fn remove_goods_image(&self, goods_id: i32) -> Result<bool, CafeHubError> {
+        let deleted = self
+            .conn()?
+            .execute(
+                "DELETE FROM goods_image WHERE goods_id = :goods_id",
+                named_params! { ":goods_id": goods_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Failed to remove image of goods ID {}", goods_id),
+                    e,
+                )
+            })?;
+        Ok(deleted > 0)
+    }
+
+    fn find_goods_image(
+        &self,
+        goods_id: i32,
+        size: ImageSize,
+    ) -> Result<Option<Vec<u8>>, CafeHubError> {
+        let column = match size {
+            ImageSize::Full => "image",
+            ImageSize::Thumbnail => "thumbnail",
+        };
+        self.conn()?
+            .query_row(
+                &format!(
+                    "SELECT {} FROM goods_image WHERE goods_id = :goods_id",
+                    column
+                ),
+                named_params! { ":goods_id": goods_id },
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to load image of goods ID {}", goods_id), e)
+            })
+    }
+
+    fn search_goods(
+        &self,
+        query: &GoodsQuery,
+        include_archived: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<(Vec<Goods>, i64), CafeHubError> {
+        let search = query.search.as_deref().map(like_pattern);
+        let params = named_params! {
+            ":include_archived": include_archived,
+            ":search": search,
+            ":category_id": query.category_id,
+            ":min_price": query.min_price.map(Money),
+            ":max_price": query.max_price.map(Money),
+            ":in_stock_only": query.in_stock_only,
+        };
+
+        let total: i64 = self
+            .conn()?
+            .query_row(
+                &format!(
+                    "SELECT COUNT(*) FROM ({}) AS available {}",
+                    GOODS_SOURCE, GOODS_SEARCH_FILTER
+                ),
+                params,
+                |row| row.get(0),
+            )
+            .map_err(|e| CafeHubError::database("Failed to count matching goods", e))?;
+        let goods = self.query_goods(
+            &format!(
+                "{} ORDER BY {} LIMIT {} OFFSET {}",
+                GOODS_SEARCH_FILTER,
+                goods_sort_order(query.sort),
+                limit,
+                offset
+            ),
+            params,
+        )?;
+        Ok((goods, total))
+    }
+
+    fn list_low_stock_goods(&self) -> Result<Vec<Goods>, CafeHubError> {
+        self.query_goods(
+            "WHERE archived_at IS NULL AND reorder_threshold IS NOT NULL AND stock <= reorder_threshold ORDER BY stock ASC, id ASC",
+            [],
+        )
+    }
+
+    fn create_goods(&self, data: &AddGoodsData, operator_id: i64) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        if let Some(category_id) = data.category_id {
+            ensure_category_exists(&tx, category_id)?;
+        }
+        tx.execute(
+            "INSERT INTO goods (goods_name, category_id, description, price, stock, reorder_threshold, points_price) VALUES (:goods_name, :category_id, :description, :price, 0, :reorder_threshold, :points_price)",
+            named_params! {
+                ":goods_name": &data.goods_name,
+                ":category_id": data.category_id,
+                ":description": &data.description,
+                ":price": Money(data.price),
+                ":reorder_threshold": data.reorder_threshold,
+                ":points_price": data.points_price,
+            },
+        )
+        .map_err(|e| {
+            if is_unique_violation(&e) {
+                CafeHubError::DuplicateGoodsName {
+                    goods_name: data.goods_name.clone(),
+                }
+            } else {
+                CafeHubError::database("Database error while adding goods", e)
+            }
+        })?;
+        let goods_id = tx.last_insert_rowid();
+
+        let initial_stock = data.stock.unwrap_or(0);
+        if initial_stock != 0 {
+            apply_stock_change(
+                &tx,
+                goods_id as i32,
+                initial_stock,
+                StockMovementReason::Restock,
+                Some("initial stock".to_string()),
+                Some(operator_id),
+            )?;
+        }
+        if let Some(cost_price) = data.cost_price {
+            apply_cost_change(&tx, goods_id as i32, cost_price, None, Some(operator_id))?;
+        }
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(goods_id)
+    }
+
+    fn update_goods(
+        &self,
+        goods_id: i32,
+        changes: &GoodsChanges,
+        operator_id: i64,
+    ) -> Result<bool, CafeHubError> {
+        if changes.is_empty() {
+            return Ok(false);
+        }
+
+        let mut conn = self.conn()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        if let Some(Some(category_id)) = changes.category_id {
+            ensure_category_exists(&tx, category_id)?;
+        }
+        let updated = tx
+            .execute(
+                "UPDATE goods SET
+                    goods_name = COALESCE(:goods_name, goods_name),
+                    category_id = CASE WHEN :set_category_id THEN :category_id ELSE category_id END,
+                    description = CASE WHEN :set_description THEN :description ELSE description END,
+                    price = COALESCE(:price, price),
+                    available = COALESCE(:available, available),
+                    reorder_threshold = COALESCE(:reorder_threshold, reorder_threshold),
+                    points_price = CASE WHEN :set_points_price THEN :points_price ELSE points_price END
+                 WHERE id = :goods_id",
+                named_params! {
+                    ":goods_name": &changes.goods_name,
+                    ":set_category_id": changes.category_id.is_some(),
+                    ":category_id": changes.category_id.flatten(),
+                    ":set_description": changes.description.is_some(),
+                    ":description": changes.description.clone().flatten(),
+                    ":price": changes.price.map(Money),
+                    ":available": changes.available,
+                    ":reorder_threshold": changes.reorder_threshold,
+                    ":set_points_price": changes.points_price.is_some(),
+                    ":points_price": changes.points_price.flatten(),
+                    ":goods_id": goods_id,
+                },
+            )
+            .map_err(|e| match changes.goods_name {
+                Some(ref goods_name) if is_unique_violation(&e) => {
+                    CafeHubError::DuplicateGoodsName {
+                        goods_name: goods_name.clone(),
+                    }
+                }
+                _ => CafeHubError::database("Database error while updating goods info", e),
+            })?;
+        if updated == 0 {
+            return Ok(false);
+        }
+        if let Some(cost_price) = changes.cost_price {
+            apply_cost_change(&tx, goods_id, cost_price, None, Some(operator_id))?;
+        }
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(true)
+    }
+}
+
+impl CategoryRepository for SqliteStorage {
+    fn list_categories(&self) -> Result<Vec<Category>, CafeHubError> {
+        query_list(
+            &*self.conn()?,
+            "SELECT id, category_name, display_name, sort_order FROM category ORDER BY sort_order ASC, id ASC",
+            [],
+            |row| {
+                Ok(Category {
+                    id: row.get(0)?,
+                    category_name: row.get(1)?,
+                    display_name: row.get(2)?,
+                    sort_order: row.get(3)?,
+                })
+            },
+        )
+        .map_err(|e| CafeHubError::database("Database query failed for categories", e))
+    }
+
+    fn create_category(&self, data: &AddCategoryData) -> Result<i64, CafeHubError> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO category (category_name, display_name, sort_order) VALUES (:category_name, COALESCE(:display_name, :category_name), COALESCE(:sort_order, 0))",
+            named_params! {
+                ":category_name": &data.category_name,
+                ":display_name": &data.display_name,
+                ":sort_order": data.sort_order,
+            },
+        )
+        .map_err(|e| {
+            if is_unique_violation(&e) {
+                CafeHubError::DuplicateCategoryName {
+                    category_name: data.category_name.clone(),
+                }
+            } else {
+                CafeHubError::database("Database error while adding category", e)
+            }
+        })?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    fn update_category(
+        &self,
+        category_id: i32,
+        data: &UpdateCategoryData,
+    ) -> Result<bool, CafeHubError> {
+        if data.category_name.is_none() && data.display_name.is_none() && data.sort_order.is_none()
+        {
+            return Ok(false);
+        }
+
+        let updated = self
+            .conn()?
+            .execute(
+                "UPDATE category SET
+                    category_name = COALESCE(:category_name, category_name),
+                    display_name = COALESCE(:display_name, display_name),
+                    sort_order = COALESCE(:sort_order, sort_order)
+                 WHERE id = :category_id",
+                named_params! {
+                    ":category_name": &data.category_name,
+                    ":display_name": &data.display_name,
+                    ":sort_order": data.sort_order,
+                    ":category_id": category_id,
+                },
+            )
+            .map_err(|e| match data.category_name {
+                Some(ref category_name) if is_unique_violation(&e) => {
+                    CafeHubError::DuplicateCategoryName {
+                        category_name: category_name.clone(),
+                    }
+                }
+                _ => CafeHubError::database("Database error while updating category", e),
+            })?;
+        Ok(updated > 0)
+    }
+
+    fn delete_category(&self, category_id: i32) -> Result<bool, CafeHubError> {
+        let mut conn = self.conn()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let goods_count: Option<i64> = tx
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM goods WHERE category_id = :category_id)
+                 FROM category WHERE id = :category_id",
+                named_params! { ":category_id": category_id },
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Failed to count goods in category ID {}", category_id),
+                    e,
+                )
+            })?;
+        match goods_count {
+            None => return Ok(false),
+            Some(0) => {}
+            Some(count) => {
+                return Err(CafeHubError::conflict(format!(
+                    "Category ID {} still has {} goods; move them to another category first.",
+                    category_id, count
+                )))
+            }
+        }
+
+        tx.execute(
+            "DELETE FROM category WHERE id = :category_id",
+            named_params! { ":category_id": category_id },
+        )
+        .map_err(|e| {
+            CafeHubError::database(format!("Failed to delete category ID {}", category_id), e)
+        })?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(true)
+    }
+}
+
+impl PromotionRepository for SqliteStorage {
+    fn list_promotions(&self) -> Result<Vec<Promotion>, CafeHubError> {
+        load_promotions(&*self.conn()?, "")
+    }
+
+    fn create_promotion(&self, data: &PromotionData) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        ensure_promotion_target_exists(&tx, data)?;
+        tx.execute(
+            "INSERT INTO promotion (promotion_name, discount_type, discount_value, goods_id, category_id, weekdays, start_time, end_time, start_date, end_date, paused)
+             VALUES (:promotion_name, :discount_type, :discount_value, :goods_id, :category_id, :weekdays, :start_time, :end_time, :start_date, :end_date, :paused)",
+            named_params! {
+                ":promotion_name": &data.promotion_name,
+                ":discount_type": data.discount_type.as_str(),
+                ":discount_value": Money(data.discount_value),
+                ":goods_id": data.goods_id,
+                ":category_id": data.category_id,
+                ":weekdays": weekday_mask(&data.weekdays),
+                ":start_time": data.start_time,
+                ":end_time": data.end_time,
+                ":start_date": data.start_date,
+                ":end_date": data.end_date,
+                ":paused": data.paused,
+            },
+        )
+        .map_err(|e| CafeHubError::database("Database error while adding promotion", e))?;
+        let promotion_id = tx.last_insert_rowid();
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(promotion_id)
+    }
+
+    fn update_promotion(
+        &self,
+        promotion_id: i32,
+        data: &PromotionData,
+    ) -> Result<bool, CafeHubError> {
+        let mut conn = self.conn()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        ensure_promotion_target_exists(&tx, data)?;
+        let updated = tx
+            .execute(
+                "UPDATE promotion SET
+                    promotion_name = :promotion_name,
+                    discount_type = :discount_type,
+                    discount_value = :discount_value,
+                    goods_id = :goods_id,
+                    category_id = :category_id,
+                    weekdays = :weekdays,
+                    start_time = :start_time,
+                    end_time = :end_time,
+                    start_date = :start_date,
+                    end_date = :end_date,
+                    paused = :paused
+                 WHERE id = :promotion_id",
+                named_params! {
+                    ":promotion_name": &data.promotion_name,
+                    ":discount_type": data.discount_type.as_str(),
+                    ":discount_value": Money(data.discount_value),
+                    ":goods_id": data.goods_id,
+                    ":category_id": data.category_id,
+                    ":weekdays": weekday_mask(&data.weekdays),
+                    ":start_time": data.start_time,
+                    ":end_time": data.end_time,
+                    ":start_date": data.start_date,
+                    ":end_date": data.end_date,
+                    ":paused": data.paused,
+                    ":promotion_id": promotion_id,
+                },
+            )
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to update promotion ID {}", promotion_id), e)
+            })?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(updated > 0)
+    }
+
+    fn delete_promotion(&self, promotion_id: i32) -> Result<bool, CafeHubError> {
+        let deleted = self
+            .conn()?
+            .execute(
+                "DELETE FROM promotion WHERE id = :promotion_id",
+                named_params! { ":promotion_id": promotion_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to delete promotion ID {}", promotion_id), e)
+            })?;
+        Ok(deleted > 0)
+    }
+}
+
+impl CouponRepository for SqliteStorage {
+    fn list_coupons(&self) -> Result<Vec<Coupon>, CafeHubError> {
+        query_coupons(&*self.conn()?, "ORDER BY c.id DESC", [])
+    }
+
+    fn create_coupon(
+        &self,
+        data: &AddCouponData,
+        created_at: NaiveDateTime,
+    ) -> Result<i64, CafeHubError> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO coupon (code, discount_type, discount_value, min_spend, expires_at, max_redemptions, max_per_customer, created_at)
+             VALUES (:code, :discount_type, :discount_value, :min_spend, :expires_at, :max_redemptions, :max_per_customer, :created_at)",
+            named_params! {
+                ":code": &data.code,
+                ":discount_type": data.discount_type.as_str(),
+                ":discount_value": Money(data.discount_value),
+                ":min_spend": data.min_spend.map(Money),
+                ":expires_at": data.expires_at,
+                ":max_redemptions": data.max_redemptions,
+                ":max_per_customer": data.max_per_customer,
+                ":created_at": created_at,
+            },
+        )
+        .map_err(|e| {
+            if is_unique_violation(&e) {
+                CafeHubError::DuplicateCouponCode {
+                    code: data.code.clone(),
+                }
+            } else {
+                CafeHubError::database("Database error while adding coupon", e)
+            }
+        })?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    fn disable_coupon(&self, coupon_id: i32) -> Result<bool, CafeHubError> {
+        let updated = self
+            .conn()?
+            .execute(
+                "UPDATE coupon SET disabled = 1 WHERE id = :coupon_id",
+                named_params! { ":coupon_id": coupon_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to disable coupon ID {}", coupon_id), e)
+            })?;
+        Ok(updated > 0)
+    }
+}
+
+impl ModifierRepository for SqliteStorage {
+    fn list_modifier_groups(&self, goods_id: i32) -> Result<Vec<ModifierGroup>, CafeHubError> {
+        load_modifier_groups(&*self.conn()?, goods_id)
+    }
+
+    fn create_modifier_group(&self, data: &AddModifierGroupData) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let goods_exists: Option<i32> = tx
+            .query_row(
+                "SELECT id FROM goods WHERE id = :goods_id",
+                named_params! { ":goods_id": data.goods_id },
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to query goods ID {}", data.goods_id), e)
+            })?;
+        if goods_exists.is_none() {
+            return Err(CafeHubError::NotFound {
+                entity: "Goods",
+                id: data.goods_id.into(),
+            });
+        }
+
+        tx.execute(
+            "INSERT INTO modifier_group (goods_id, group_name, is_required, allow_multiple) VALUES (:goods_id, :group_name, :is_required, :allow_multiple)",
+            named_params! {
+                ":goods_id": data.goods_id,
+                ":group_name": &data.group_name,
+                ":is_required": data.is_required,
+                ":allow_multiple": data.allow_multiple,
+            },
+        )
+        .map_err(|e| CafeHubError::database("Failed to create modifier group", e))?;
+        let group_id = tx.last_insert_rowid();
+
+        for option in &data.options {
+            tx.execute(
+                "INSERT INTO modifier_option (group_id, option_name, price_delta) VALUES (:group_id, :option_name, :price_delta)",
+                named_params! {
+                    ":group_id": group_id,
+                    ":option_name": &option.option_name,
+                    ":price_delta": Money(option.price_delta),
+                },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Failed to create modifier option {}", option.option_name),
+                    e,
+                )
+            })?;
+        }
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(group_id)
+    }
+
+    fn delete_modifier_group(&self, group_id: i64) -> Result<bool, CafeHubError> {
+        let deleted = self
+            .conn()?
+            .execute(
+                "DELETE FROM modifier_group WHERE id = :group_id",
+                named_params! { ":group_id": group_id },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Failed to delete modifier group ID {}", group_id),
+                    e,
+                )
+            })?;
+        Ok(deleted > 0)
+    }
+}
+
+impl IngredientRepository for SqliteStorage {
+    fn list_ingredients(&self) -> Result<Vec<Ingredient>, CafeHubError> {
+        query_list(
+            &*self.conn()?,
+            "SELECT id, ingredient_name, unit, stock FROM ingredient ORDER BY ingredient_name ASC",
+            [],
+            |row| {
+                Ok(Ingredient {
+                    id: row.get(0)?,
+                    ingredient_name: row.get(1)?,
+                    unit: row.get(2)?,
+                    stock: row.get(3)?,
+                })
+            },
+        )
+        .map_err(|e| CafeHubError::database("Database query failed for ingredients", e))
+    }
+
+    fn create_ingredient(&self, data: &AddIngredientData) -> Result<i64, CafeHubError> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO ingredient (ingredient_name, unit, stock) VALUES (:ingredient_name, :unit, :stock)",
+            named_params! {
+                ":ingredient_name": &data.ingredient_name,
+                ":unit": &data.unit,
+                ":stock": data.stock.unwrap_or(0),
+            },
+        )
+        .map_err(|e| {
+            if is_unique_violation(&e) {
+                CafeHubError::DuplicateIngredientName {
+                    ingredient_name: data.ingredient_name.clone(),
+                }
+            } else {
+                CafeHubError::database("Database error while adding ingredient", e)
+            }
+        })?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    fn update_ingredient(
+        &self,
+        ingredient_id: i64,
+        data: &UpdateIngredientData,
+    ) -> Result<bool, CafeHubError> {
+        if data.unit.is_none() && data.stock.is_none() {
+            return Ok(false);
+        }
+
+        let updated = self
+            .conn()?
+            .execute(
+                "UPDATE ingredient SET unit = COALESCE(:unit, unit), stock = COALESCE(:stock, stock) WHERE id = :ingredient_id",
+                named_params! {
+                    ":unit": &data.unit,
+                    ":stock": data.stock,
+                    ":ingredient_id": ingredient_id,
+                },
+            )
+            .map_err(|e| CafeHubError::database("Database error while updating ingredient", e))?;
+        Ok(updated > 0)
+    }
+
+    fn find_recipe(&self, goods_id: i32) -> Result<Vec<RecipeItem>, CafeHubError> {
+        query_list(
+            &*self.conn()?,
+            "SELECT r.ingredient_id, i.ingredient_name, i.unit, r.quantity
+             FROM recipe_item r
+             JOIN ingredient i ON r.ingredient_id = i.id
+             WHERE r.goods_id = :goods_id
+             ORDER BY i.ingredient_name ASC",
+            named_params! { ":goods_id": goods_id },
+            |row| {
+                Ok(RecipeItem {
+                    ingredient_id: row.get(0)?,
+                    ingredient_name: row.get(1)?,
+                    unit: row.get(2)?,
+                    quantity: row.get(3)?,
+                })
+            },
+        )
+        .map_err(|e| {
+            CafeHubError::database(
+                format!("Database query failed for recipe of goods ID {}", goods_id),
+                e,
+            )
+        })
+    }
+
+    fn set_recipe(&self, goods_id: i32, items: &[RecipeItemData]) -> Result<(), CafeHubError> {
+        let mut conn = self.conn()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let goods_exists: Option<i32> = tx
+            .query_row(
+                "SELECT id FROM goods WHERE id = :goods_id",
+                named_params! { ":goods_id": goods_id },
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| {
+                CafeHubError::database(format!("Failed to query goods ID {}", goods_id), e)
+            })?;
+        if goods_exists.is_none() {
+            return Err(CafeHubError::NotFound {
+                entity: "Goods",
+                id: goods_id.into(),
+            });
+        }
+
+        tx.execute(
+            "DELETE FROM recipe_item WHERE goods_id = :goods_id",
+            named_params! { ":goods_id": goods_id },
+        )
+        .map_err(|e| {
+            CafeHubError::database(
+                format!("Failed to clear recipe of goods ID {}", goods_id),
+                e,
+            )
+        })?;
+
+        for item in items {
+            let ingredient_exists: Option<i64> = tx
+                .query_row(
+                    "SELECT id FROM ingredient WHERE id = :ingredient_id",
+                    named_params! { ":ingredient_id": item.ingredient_id },
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|e| {
+                    CafeHubError::database(
+                        format!("Failed to query ingredient ID {}", item.ingredient_id),
+                        e,
+                    )
+                })?;
+            if ingredient_exists.is_none() {
+                return Err(CafeHubError::NotFound {
+                    entity: "Ingredient",
+                    id: item.ingredient_id,
+                });
+            }
+
+            tx.execute(
+                "INSERT INTO recipe_item (goods_id, ingredient_id, quantity) VALUES (:goods_id, :ingredient_id, :quantity)",
+                named_params! {
+                    ":goods_id": goods_id,
+                    ":ingredient_id": item.ingredient_id,
+                    ":quantity": item.quantity,
+                },
+            )
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Failed to add ingredient ID {} to recipe", item.ingredient_id),
+                    e,
+                )
+            })?;
+        }
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))
+    }
+}
+
+impl PurchasingRepository for SqliteStorage {
+    fn list_suppliers(&self) -> Result<Vec<Supplier>, CafeHubError> {
+        let conn = self.conn()?;
+        query_list(
+            &conn,
+            "SELECT id, supplier_name, contact FROM supplier ORDER BY supplier_name ASC",
+            [],
+            |row| {
+                Ok(Supplier {
+                    id: row.get(0)?,
+                    supplier_name: row.get(1)?,
+                    contact: row.get(2)?,
+                })
+            },
+        )
+        .map_err(|e| CafeHubError::database("Database query failed for suppliers", e))
+    }
+
+    fn create_supplier(&self, data: &AddSupplierData) -> Result<i64, CafeHubError> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO supplier (supplier_name, contact) VALUES (:supplier_name, :contact)",
+            named_params! {
+                ":supplier_name": &data.supplier_name,
+                ":contact": &data.contact,
+            },
+        )
+        .map_err(|e| {
+            if is_unique_violation(&e) {
+                CafeHubError::DuplicateSupplierName {
+                    supplier_name: data.supplier_name.clone(),
+                }
+            } else {
+                CafeHubError::database("Database error while adding supplier", e)
+            }
+        })?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    fn create_purchase_order(
+        &self,
+        data: &CreatePurchaseOrderData,
+        operator_id: i64,
+        created_at: NaiveDateTime,
+    ) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        let supplier_exists: Option<i64> = tx
+            .query_row(
+                "SELECT id FROM supplier WHERE id = :supplier_id",
+                named_params! { ":supplier_id": data.supplier_id },
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Failed to query supplier ID {}", data.supplier_id),
+                    e,
+                )
+            })?;
+        if supplier_exists.is_none() {
+            return Err(CafeHubError::NotFound {
+                entity: "Supplier",
+                id: data.supplier_id,
+            });
+        }
+
+        let mut total_cost = Decimal::ZERO;
+        for item in &data.items {
+            let goods_exists: Option<i32> = tx
+                .query_row(
+                    "SELECT id FROM goods WHERE id = :goods_id",
+                    named_params! { ":goods_id": item.goods_id },
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|e| {
+                    CafeHubError::database(format!("Failed to query goods ID {}", item.goods_id), e)
+                })?;
+            if goods_exists.is_none() {
+                return Err(CafeHubError::NotFound {
+                    entity: "Goods",
+                    id: item.goods_id.into(),
+                });
+            }
+            total_cost += item.unit_cost * Decimal::from(item.quantity);
+        }
+
+        tx.execute(
+            "INSERT INTO purchase_order (supplier_id, status, total_cost, created_by, created_at) VALUES (:supplier_id, :status, :total_cost, :created_by, :created_at)",
+            named_params! {
+                ":supplier_id": data.supplier_id,
+                ":status": PurchaseOrderStatus::Open.as_str(),
+                ":total_cost": Money(total_cost),
+                ":created_by": operator_id,
+                ":created_at": created_at,
+            },
+        )
+        .map_err(|e| CafeHubError::database("Failed to create purchase order", e))?;
+        let purchase_order_id = tx.last_insert_rowid();
+
+        for item in &data.items {
+            tx.execute(
+                "INSERT INTO purchase_order_item (purchase_order_id, goods_id, quantity, unit_cost) VALUES (:purchase_order_id, :goods_id, :quantity, :unit_cost)",
+                named_params! {
+                    ":purchase_order_id": purchase_order_id,
+                    ":goods_id": item.goods_id,
+                    ":quantity": item.quantity,
+                    ":unit_cost": Money(item.unit_cost),
+                },
+            )
+            .map_err(|e| CafeHubError::database(format!("Failed to add goods ID {} to purchase order", item.goods_id), e))?;
+        }
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(purchase_order_id)
+    }
+
+    fn list_purchase_orders(&self) -> Result<Vec<PurchaseOrderSummary>, CafeHubError> {
+        let query = "
+            SELECT po.id, po.supplier_id, s.supplier_name, po.status, po.total_cost, po.created_at, po.closed_at
+            FROM purchase_order po
+            JOIN supplier s ON po.supplier_id = s.id
+            ORDER BY po.created_at DESC, po.id DESC";
+
+        let conn = self.conn()?;
+        query_list(&conn, query, [], |row| {
+            Ok(PurchaseOrderSummary {
+                id: row.get(0)?,
+                supplier_id: row.get(1)?,
+                supplier_name: row.get(2)?,
+                status: row.get(3)?,
+                total_cost: money(row, 4)?,
+                created_at: row.get(5)?,
+                closed_at: row.get(6)?,
+            })
+        })
+        .map_err(|e| CafeHubError::database("Database query failed for purchase orders", e))
+    }
+
+    fn find_purchase_order(
+        &self,
+        purchase_order_id: i64,
+    ) -> Result<Option<PurchaseOrderDetail>, CafeHubError> {
+        let conn = self.conn()?;
+
+        let header_query = "
+            SELECT po.id, po.supplier_id, s.supplier_name, po.status, po.total_cost,
+                creator.username, po.created_at, closer.username, po.closed_at
+            FROM purchase_order po
+            JOIN supplier s ON po.supplier_id = s.id
+            JOIN account creator ON po.created_by = creator.id
+            LEFT JOIN account closer ON po.closed_by = closer.id
+            WHERE po.id = :purchase_order_id";
+        let detail = conn
+            .query_row(
+                header_query,
+                named_params! { ":purchase_order_id": purchase_order_id },
+                |row| {
+                    Ok(PurchaseOrderDetail {
+                        id: row.get(0)?,
+                        supplier_id: row.get(1)?,
+                        supplier_name: row.get(2)?,
+                        status: row.get(3)?,
+                        total_cost: money(row, 4)?,
+                        created_by: row.get(5)?,
+                        created_at: row.get(6)?,
+                        closed_by: row.get(7)?,
+                        closed_at: row.get(8)?,
+                        items: Vec::new(),
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!(
+                        "Database query failed for purchase order ID {}",
+                        purchase_order_id
+                    ),
+                    e,
+                )
+            })?;
+        let Some(mut detail) = detail else {
+            return Ok(None);
+        };
+
+        detail.items = query_list(
+            &conn,
+            "SELECT i.id, i.goods_id, g.goods_name, i.quantity, i.unit_cost
+             FROM purchase_order_item i
+             JOIN goods g ON i.goods_id = g.id
+             WHERE i.purchase_order_id = :purchase_order_id
+             ORDER BY i.id ASC",
+            named_params! { ":purchase_order_id": purchase_order_id },
+            |row| {
+                Ok(PurchaseOrderLine {
+                    id: row.get(0)?,
+                    goods_id: row.get(1)?,
+                    goods_name: row.get(2)?,
+                    quantity: row.get(3)?,
+                    unit_cost: money(row, 4)?,
+                })
+            },
+        )
+        .map_err(|e| {
+            CafeHubError::database(
+                format!(
+                    "Database query failed for items of purchase order ID {}",
+                    purchase_order_id
+                ),
+                e,
+            )
+        })?;
+
+        Ok(Some(detail))
+    }
+
+    fn receive_purchase_order(
+        &self,
+        purchase_order_id: i64,
+        operator_id: i64,
+        received_at: NaiveDateTime,
+    ) -> Result<(), CafeHubError> {
+        let mut conn = self.conn()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        ensure_purchase_order_open(
+            purchase_order_id,
+            purchase_order_status(&tx, purchase_order_id)?,
+        )?;
+
+        let lines: Vec<(i32, i32, Decimal)> = query_list(
+            &tx,
+            "SELECT goods_id, quantity, unit_cost FROM purchase_order_item WHERE purchase_order_id = :purchase_order_id ORDER BY id ASC",
+            named_params! { ":purchase_order_id": purchase_order_id },
+            |row| Ok((row.get(0)?, row.get(1)?, money(row, 2)?)),
+        )
+        .map_err(|e| {
+            CafeHubError::database(
+                format!(
+                    "Failed to query items of purchase order ID {}",
+                    purchase_order_id
+                ),
+                e,
+            )
+        })?;
+        for (goods_id, quantity, unit_cost) in lines {
+            apply_stock_change(
+                &tx,
+                goods_id,
+                quantity,
+                StockMovementReason::Restock,
+                Some(format!("purchase_order:{}", purchase_order_id)),
+                Some(operator_id),
+            )?;
+            apply_cost_change(
+                &tx,
+                goods_id,
+                unit_cost,
+                Some(format!("purchase_order:{}", purchase_order_id)),
+                Some(operator_id),
+            )?;
+        }
+
+        close_purchase_order(
+            &tx,
+            purchase_order_id,
+            PurchaseOrderStatus::Received,
+            operator_id,
+            received_at,
+        )?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))
+    }
+
+    fn cancel_purchase_order(
+        &self,
+        purchase_order_id: i64,
+        operator_id: i64,
+        cancelled_at: NaiveDateTime,
+    ) -> Result<(), CafeHubError> {
+        let mut conn = self.conn()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+
+        ensure_purchase_order_open(
+            purchase_order_id,
+            purchase_order_status(&tx, purchase_order_id)?,
+        )?;
+
+        close_purchase_order(
+            &tx,
+            purchase_order_id,
+            PurchaseOrderStatus::Cancelled,
+            operator_id,
+            cancelled_at,
+        )?;
+
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))
+    }
+}
+
+impl ConsumptionRepository for SqliteStorage {
+    fn monthly_consumption(
+        &self,
+        user_id: Option<i64>,
+    ) -> Result<Vec<MonthlyConsumptionSummary>, CafeHubError> {
+        let conn = self.conn()?;
+        let to_summary = |row: &Row| {
+            Ok(MonthlyConsumptionSummary {
+                month: row.get(0)?,
+                total_amount: money(row, 1)?,
+            })
+        };
+        match user_id {
+            Some(user_id) => query_list(
+                &conn,
+                "
+                SELECT month, SUM(amount) as total_amount
+                FROM consumption
+                WHERE user_id = :user_id
+                GROUP BY month
+                ORDER BY month ASC",
+                named_params! { ":user_id": user_id },
+                to_summary,
+            ),
+            None => query_list(
+                &conn,
+                "SELECT month, SUM(amount) as total_amount FROM consumption GROUP BY month ORDER BY month ASC",
+                [],
+                to_summary,
+            ),
+        }
+        .map_err(|e| CafeHubError::database("Database query failed for monthly consumption", e))
+    }
+
+    fn goods_consumption_share(
+        &self,
+        month: &str,
+    ) -> Result<Vec<GoodsConsumptionShare>, CafeHubError> {
+        let query = "
+            SELECT g.goods_name, SUM(c.amount) as consumed_amount
+            FROM consumption c
+            JOIN goods g ON c.goods_id = g.id
+            WHERE c.month = :current_month
+            GROUP BY g.goods_name
+            ORDER BY consumed_amount DESC";
+
+        query_list(
+            &*self.conn()?,
+            query,
+            named_params! { ":current_month": month },
+            |row| {
+                Ok(GoodsConsumptionShare {
+                    goods_name: row.get(0)?,
+                    amount: money(row, 1)?,
+                })
+            },
+        )
+        .map_err(|e| {
+            CafeHubError::database(
+                format!(
+                    "Database query failed for goods consumption share (month: {})",
+                    month
+                ),
+                e,
+            )
+        })
+    }
+
+    fn margin_summary(
+        &self,
+        grouping: MarginGrouping,
+        month: Option<&str>,
+    ) -> Result<Vec<MarginSummary>, CafeHubError> {
+        let (label, group_by, order_by) = match grouping {
+            MarginGrouping::Goods => ("g.goods_name", "g.id, g.goods_name", "revenue DESC"),
+            MarginGrouping::Category => (
+                "COALESCE(c.display_name, 'Uncategorized')",
+                "label",
+                "revenue DESC",
+            ),
+            MarginGrouping::Month => ("strftime('%Y-%m', o.order_time)", "label", "label ASC"),
+        };
+        let query = format!(
+            "
+            SELECT {} AS label,
+                SUM(oi.quantity - oi.refunded_quantity) AS quantity_sold,
+                SUM(oi.line_total - oi.refunded_amount) AS revenue,
+                COALESCE(SUM(oi.unit_cost * (oi.quantity - oi.refunded_quantity)), 0) AS cost,
+                SUM(CASE WHEN oi.unit_cost IS NULL THEN oi.quantity - oi.refunded_quantity ELSE 0 END) AS uncosted_quantity
+            FROM order_items oi
+            JOIN orders o ON oi.order_id = o.id
+            JOIN goods g ON oi.goods_id = g.id
+            LEFT JOIN category c ON g.category_id = c.id
+            WHERE :month IS NULL OR strftime('%Y-%m', o.order_time) = :month
+            GROUP BY {}
+            ORDER BY {}",
+            label, group_by, order_by
+        );
+
+        query_list(
+            &*self.conn()?,
+            &query,
+            named_params! { ":month": month },
+            |row| {
+                let revenue = money(row, 2)?;
+                let cost = money(row, 3)?;
+                Ok(MarginSummary {
+                    label: row.get(0)?,
+                    quantity_sold: row.get(1)?,
+                    revenue,
+                    cost,
+                    gross_margin: revenue - cost,
+                    uncosted_quantity: row.get(4)?,
+                })
+            },
+        )
+        .map_err(|e| CafeHubError::database("Database query failed for margin summary", e))
+    }
+}
+
+// The transaction is opened `IMMEDIATE`, which holds the database's write lock from the
+// start, so the reads below cannot go stale before it commits.
+impl OrderTransaction for rusqlite::Transaction<'_> {
+    fn active_promotions(&mut self) -> Result<Vec<Promotion>, CafeHubError> {
+        load_promotions(self, "WHERE paused = 0")
+    }
+
+    fn lock_sale_goods(&mut self, goods_id: i32) -> Result<Option<SaleGoodsRow>, CafeHubError> {
+        self.query_row(
+            "SELECT price, COALESCE(stock, 0), goods_name, reorder_threshold, cost_price, available, category_id, points_price FROM goods WHERE id = :goods_id AND archived_at IS NULL",
+            named_params! { ":goods_id": goods_id },
+            |row| {
+                Ok((
+                    money(row, 0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    optional_money(row, 4)?,
+                    row.get(5)?,
+                    row.get(6)?,
+                    row.get(7)?,
+                ))
+            },
+        )
+        .optional()
+        .map_err(|e| CafeHubError::database(format!("Failed to query goods ID {}", goods_id), e))
+    }
+
+    fn lock_recipe(&mut self, goods_id: i32) -> Result<Vec<RecipeUsage>, CafeHubError> {
+        load_recipe_usage(self, goods_id)
+    }
+
+    fn modifier_groups(&mut self, goods_id: i32) -> Result<Vec<ModifierGroup>, CafeHubError> {
+        load_modifier_groups(self, goods_id)
+    }
+
+    fn lock_coupon(
+        &mut self,
+        code: &str,
+        user_id: i64,
+    ) -> Result<Option<(Coupon, i64)>, CafeHubError> {
+        let Some(mut coupon) = query_coupons(
+            self,
+            "WHERE c.code = :code",
+            named_params! { ":code": code },
+        )?
+        .pop() else {
+            return Ok(None);
+        };
+        let (times_redeemed, customer_redemptions): (i64, i64) = self
+            .query_row(
+                "SELECT COUNT(*), COUNT(CASE WHEN user_id = :user_id THEN 1 END) FROM coupon_redemption WHERE coupon_id = :coupon_id AND refunded_at IS NULL",
+                named_params! {
+                    ":coupon_id": coupon.id,
+                    ":user_id": user_id,
+                },
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| CafeHubError::database("Failed to count coupon redemptions", e))?;
+        coupon.times_redeemed = times_redeemed;
+        Ok(Some((coupon, customer_redemptions)))
+    }
+
+    fn lock_balance(&mut self, user_id: i64) -> Result<Option<Decimal>, CafeHubError> {
+        let balance: Option<Option<Decimal>> = self
+            .query_row(
+                "SELECT balance FROM account WHERE id = :user_id AND user_type = 1",
+                named_params! { ":user_id": user_id },
+                |row| optional_money(row, 0),
+            )
+            .optional()
+            .map_err(|e| CafeHubError::database("Failed to query user", e))?;
+        Ok(balance.map(|balance| balance.unwrap_or(Decimal::ZERO)))
+    }
+
+    fn expire_points(&mut self, user_id: i64, now: NaiveDateTime) -> Result<i64, CafeHubError> {
+        expire_points(self, user_id, now)
+    }
+
+    fn use_ingredient(&mut self, ingredient_id: i64, quantity: i64) -> Result<(), CafeHubError> {
+        self.execute(
+            "UPDATE ingredient SET stock = stock - :quantity WHERE id = :ingredient_id",
+            named_params! {
+                ":quantity": quantity,
+                ":ingredient_id": ingredient_id,
+            },
+        )
+        .map(drop)
+        .map_err(|e| {
+            CafeHubError::database(
+                format!("Failed to update stock for ingredient ID {}", ingredient_id),
+                e,
+            )
+        })
+    }
+
+    fn insert_order(
+        &mut self,
+        user_id: i64,
+        order_time: NaiveDateTime,
+        total_amount: Decimal,
+        points_redeemed: i64,
+        points_earned: i64,
+    ) -> Result<i64, CafeHubError> {
+        self.execute(
+            "INSERT INTO orders (user_id, order_time, total_amount, points_redeemed, points_earned) VALUES (:user_id, :order_time, :total_amount, :points_redeemed, :points_earned)",
+            named_params! {
+                ":user_id": user_id,
+                ":order_time": order_time,
+                ":total_amount": Money(total_amount),
+                ":points_redeemed": points_redeemed,
+                ":points_earned": points_earned,
+            },
+        )
+        .map_err(|e| CafeHubError::database("Failed to create order record", e))?;
+        Ok(self.last_insert_rowid())
+    }
+
+    fn insert_coupon_redemption(
+        &mut self,
+        coupon_id: i32,
+        order_id: i64,
+        user_id: i64,
+        discount: Decimal,
+        redeemed_at: NaiveDateTime,
+    ) -> Result<(), CafeHubError> {
+        self.execute(
+            "INSERT INTO coupon_redemption (coupon_id, order_id, user_id, discount, redeemed_at) VALUES (:coupon_id, :order_id, :user_id, :discount, :redeemed_at)",
+            named_params! {
+                ":coupon_id": coupon_id,
+                ":order_id": order_id,
+                ":user_id": user_id,
+                ":discount": Money(discount),
+                ":redeemed_at": redeemed_at,
+            },
+        )
+        .map(drop)
+        .map_err(|e| CafeHubError::database("Failed to record coupon redemption", e))
+    }
+
+    fn insert_order_line(&mut self, order_id: i64, line: &OrderLine) -> Result<(), CafeHubError> {
+        self.execute(
+            "INSERT INTO order_items (order_id, goods_id, quantity, unit_price, line_total, unit_cost, made_from_recipe, promotion_id, promotion_name, discount, coupon_discount, points_discount, points_redeemed) VALUES (:order_id, :goods_id, :quantity, :unit_price, :line_total, :unit_cost, :made_from_recipe, :promotion_id, :promotion_name, :discount, :coupon_discount, :points_discount, :points_redeemed)",
+            named_params! {
+                ":order_id": order_id,
+                ":goods_id": line.goods_id,
+                ":quantity": line.quantity,
+                ":unit_price": Money(line.unit_price),
+                ":line_total": Money(line.line_total),
+                ":unit_cost": line.unit_cost.map(Money),
+                ":made_from_recipe": line.made_from_recipe,
+                ":promotion_id": line.promotion.as_ref().map(|(id, _)| id),
+                ":promotion_name": line.promotion.as_ref().map(|(_, name)| name),
+                ":discount": Money(line.discount),
+                ":coupon_discount": Money(line.coupon_discount),
+                ":points_discount": Money(line.points_discount),
+                ":points_redeemed": line.points_redeemed,
+            },
+        )
+        .map_err(|e| CafeHubError::database(format!("Failed to record order item for goods ID {}", line.goods_id), e))?;
+        let order_item_id = self.last_insert_rowid();
+
+        for modifier in &line.modifiers {
+            self.execute(
+                "INSERT INTO order_item_modifier (order_item_id, option_id, group_name, option_name, price_delta) VALUES (:order_item_id, :option_id, :group_name, :option_name, :price_delta)",
+                named_params! {
+                    ":order_item_id": order_item_id,
+                    ":option_id": modifier.option_id,
+                    ":group_name": &modifier.group_name,
+                    ":option_name": &modifier.option_name,
+                    ":price_delta": Money(modifier.price_delta),
+                },
+            )
+            .map_err(|e| CafeHubError::database(format!("Failed to record modifiers for goods ID {}", line.goods_id), e))?;
+        }
+        Ok(())
+    }
+
+    fn add_consumption(
+        &mut self,
+        user_id: i64,
+        month: &str,
+        goods_id: i32,
+        amount: Decimal,
+    ) -> Result<(), CafeHubError> {
+        self.execute(
+            "INSERT INTO consumption (user_id, month, goods_id, amount) VALUES (:user_id, :month, :goods_id, :amount)
+             ON CONFLICT (user_id, month, goods_id) DO UPDATE SET amount = ROUND(amount + excluded.amount, 2)",
+            named_params! {
+                ":user_id": user_id,
+                ":month": month,
+                ":goods_id": goods_id,
+                ":amount": Money(amount),
+            },
+        )
+        .map(drop)
+        .map_err(|e| CafeHubError::database(format!("Failed to record consumption for goods ID {}", goods_id), e))
+    }
+
+    fn change_balance(
+        &mut self,
+        user_id: i64,
+        amount: Decimal,
+        entry_type: LedgerEntryType,
+        reference: String,
+        operator_id: i64,
+    ) -> Result<(), CafeHubError> {
+        apply_balance_change(
+            self,
+            user_id,
+            amount,
+            entry_type,
+            Some(reference),
+            Some(operator_id),
+        )
+        .map(drop)
+    }
+
+    fn change_points(
+        &mut self,
+        user_id: i64,
+        points: i64,
+        entry_type: PointsEntryType,
+        reference: String,
+        expires_at: Option<NaiveDateTime>,
+        at: NaiveDateTime,
+    ) -> Result<(), CafeHubError> {
+        apply_points_change(
+            self,
+            user_id,
+            points,
+            entry_type,
+            Some(reference),
+            expires_at,
+            at,
+        )
+        .map(drop)
+    }
+
+    fn change_stock(
+        &mut self,
+        goods_id: i32,
+        delta: i32,
+        reason: StockMovementReason,
+        reference: String,
+        operator_id: i64,
+    ) -> Result<(), CafeHubError> {
+        apply_stock_change(
+            self,
+            goods_id,
+            delta,
+            reason,
+            Some(reference),
+            Some(operator_id),
+        )
+        .map(drop)
+    }
+
+    fn notify_administrator(
+        &mut self,
+        title: &str,
+        content: &str,
+        send_date: NaiveDate,
+    ) -> Result<(), CafeHubError> {
+        notify_administrator(self, title, content, send_date)
+    }
+
+    fn lock_order(
+        &mut self,
+        order_id: i64,
+    ) -> Result<Option<(i64, NaiveDateTime, i64)>, CafeHubError> {
+        self.query_row(
+            "SELECT user_id, order_time, points_earned FROM orders WHERE id = :order_id",
+            named_params! { ":order_id": order_id },
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| CafeHubError::database(format!("Failed to query order ID {}", order_id), e))
+    }
+
+    fn refundable_items(&mut self, order_id: i64) -> Result<Vec<RefundItem>, CafeHubError> {
+        query_list(
+            self,
+            "SELECT id, quantity - refunded_quantity FROM order_items WHERE order_id = :order_id AND quantity > refunded_quantity",
+            named_params! { ":order_id": order_id },
+            |row| {
+                Ok(RefundItem {
+                    order_item_id: row.get(0)?,
+                    quantity: row.get(1)?,
+                })
+            },
+        )
+        .map_err(|e| CafeHubError::database(format!("Failed to query items of order ID {}", order_id), e))
+    }
+
+    fn lock_refund_line(
+        &mut self,
+        order_id: i64,
+        order_item_id: i64,
+    ) -> Result<Option<RefundLineRow>, CafeHubError> {
+        self.query_row(
+            "SELECT goods_id, quantity, refunded_quantity, line_total, refunded_amount, made_from_recipe, points_redeemed, points_restored FROM order_items WHERE id = :order_item_id AND order_id = :order_id",
+            named_params! {
+                ":order_item_id": order_item_id,
+                ":order_id": order_id,
+            },
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    money(row, 3)?,
+                    money(row, 4)?,
+                    row.get(5)?,
+                    row.get(6)?,
+                    row.get(7)?,
+                ))
+            },
+        )
+        .optional()
+        .map_err(|e| CafeHubError::database(format!("Failed to query order item ID {}", order_item_id), e))
+    }
+
+    fn add_line_refund(
+        &mut self,
+        order_item_id: i64,
+        quantity: i32,
+        amount: Decimal,
+        points: i64,
+    ) -> Result<(), CafeHubError> {
+        self.execute(
+            "UPDATE order_items SET refunded_quantity = refunded_quantity + :quantity, refunded_amount = ROUND(refunded_amount + :amount, 2), points_restored = points_restored + :points WHERE id = :order_item_id",
+            named_params! {
+                ":quantity": quantity,
+                ":amount": Money(amount),
+                ":points": points,
+                ":order_item_id": order_item_id,
+            },
+        )
+        .map(drop)
+        .map_err(|e| CafeHubError::database(format!("Failed to update order item ID {}", order_item_id), e))
+    }
+
+    fn reduce_consumption(
+        &mut self,
+        user_id: i64,
+        month: &str,
+        goods_id: i32,
+        amount: Decimal,
+    ) -> Result<(), CafeHubError> {
+        self.execute(
+            "UPDATE consumption SET amount = MAX(ROUND(amount - :amount, 2), 0) WHERE user_id = :user_id AND month = :month AND goods_id = :goods_id",
+            named_params! {
+                ":amount": Money(amount),
+                ":user_id": user_id,
+                ":month": month,
+                ":goods_id": goods_id,
+            },
+        )
+        .map(drop)
+        .map_err(|e| CafeHubError::database(format!("Failed to update consumption for goods ID {}", goods_id), e))
+    }
+
+    fn insert_refund(
+        &mut self,
+        order_id: i64,
+        operator_id: i64,
+        reason: &str,
+        refund_time: NaiveDateTime,
+        total_amount: Decimal,
+    ) -> Result<i64, CafeHubError> {
+        self.execute(
+            "INSERT INTO refunds (order_id, operator_id, reason, refund_time, total_amount) VALUES (:order_id, :operator_id, :reason, :refund_time, :total_amount)",
+            named_params! {
+                ":order_id": order_id,
+                ":operator_id": operator_id,
+                ":reason": reason,
+                ":refund_time": refund_time,
+                ":total_amount": Money(total_amount),
+            },
+        )
+        .map_err(|e| CafeHubError::database("Failed to record refund", e))?;
+        Ok(self.last_insert_rowid())
+    }
+
+    fn insert_refund_line(
+        &mut self,
+        refund_id: i64,
+        order_item_id: i64,
+        quantity: i32,
+        amount: Decimal,
+    ) -> Result<(), CafeHubError> {
+        self.execute(
+            "INSERT INTO refund_items (refund_id, order_item_id, quantity, amount) VALUES (:refund_id, :order_item_id, :quantity, :amount)",
+            named_params! {
+                ":refund_id": refund_id,
+                ":order_item_id": order_item_id,
+                ":quantity": quantity,
+                ":amount": Money(amount),
+            },
+        )
+        .map(drop)
+        .map_err(|e| CafeHubError::database(format!("Failed to record refund item for order item ID {}", order_item_id), e))
+    }
+
+    fn release_coupon_redemption(
+        &mut self,
+        order_id: i64,
+        refunded_at: NaiveDateTime,
+    ) -> Result<(), CafeHubError> {
+        self.execute(
+            "UPDATE coupon_redemption SET refunded_at = :refunded_at WHERE order_id = :order_id AND NOT EXISTS (SELECT 1 FROM order_items WHERE order_id = :order_id AND refunded_quantity < quantity)",
+            named_params! {
+                ":refunded_at": refunded_at,
+                ":order_id": order_id,
+            },
+        )
+        .map(drop)
+        .map_err(|e| CafeHubError::database("Failed to update coupon redemption", e))
+    }
+}
+
+impl OrderRepository for SqliteStorage {
+    fn place_order(
+        &self,
+        user_id: i64,
+        items: &[PurchaseItem],
+        coupon_code: Option<&str>,
+        points_to_redeem: i64,
+        order_time: NaiveDateTime,
+    ) -> Result<i64, CafeHubError> {
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+        let order_id = checkout(
+            &mut tx,
+            user_id,
+            items,
+            coupon_code,
+            points_to_redeem,
+            order_time,
+        )?;
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(order_id)
+    }
+
+    fn list_orders(&self, user_id: i64) -> Result<Vec<OrderSummary>, CafeHubError> {
+        let query = "
+            SELECT o.id, o.order_time, o.total_amount, CAST(COALESCE(SUM(oi.quantity), 0) AS INTEGER) AS item_count
+            FROM orders o
+            LEFT JOIN order_items oi ON oi.order_id = o.id
+            WHERE o.user_id = :user_id
+            GROUP BY o.id, o.order_time, o.total_amount
+            ORDER BY o.order_time DESC, o.id DESC";
+
+        query_list(
+            &*self.conn()?,
+            query,
+            named_params! { ":user_id": user_id },
+            |row| {
+                Ok(OrderSummary {
+                    id: row.get(0)?,
+                    order_time: row.get(1)?,
+                    total_amount: money(row, 2)?,
+                    item_count: row.get(3)?,
+                })
+            },
+        )
+        .map_err(|e| CafeHubError::database("Database query failed for order history", e))
+    }
+
+    fn find_order(&self, order_id: i64) -> Result<Option<OrderDetail>, CafeHubError> {
+        let conn = self.conn()?;
+
+        let order_info: Option<OrderHeaderRow> = conn
+            .query_row(
+                "SELECT o.id, o.user_id, o.order_time, o.total_amount, c.code, r.discount, o.points_redeemed, o.points_earned
+                 FROM orders o
+                 LEFT JOIN coupon_redemption r ON r.order_id = o.id
+                 LEFT JOIN coupon c ON r.coupon_id = c.id
+                 WHERE o.id = :order_id",
+                named_params! { ":order_id": order_id },
+                |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        money(row, 3)?,
+                        row.get(4)?,
+                        optional_money(row, 5)?,
+                        row.get(6)?,
+                        row.get(7)?,
+                    ))
+                },
+            )
+            .optional()
+            .map_err(|e| {
+                CafeHubError::database(
+                    format!("Database query failed for order ID {}", order_id),
+                    e,
+                )
+            })?;
+
+        let (
+            id,
+            user_id,
+            order_time,
+            total_amount,
+            coupon_code,
+            coupon_discount,
+            points_redeemed,
+            points_earned,
+        ) = match order_info {
+            Some(info) => info,
+            None => return Ok(None),
+        };
+
+        let query = "
+            SELECT oi.id, oi.goods_id, g.goods_name, oi.quantity, oi.unit_price, oi.line_total, oi.refunded_quantity, oi.promotion_name, oi.discount, oi.points_discount
+            FROM order_items oi
+            JOIN goods g ON oi.goods_id = g.id
+            WHERE oi.order_id = :order_id
+            ORDER BY oi.id ASC";
+
+        let mut items = query_list(
+            &conn,
+            query,
+            named_params! { ":order_id": order_id },
+            |row| {
+                Ok(OrderItemInfo {
+                    id: row.get(0)?,
+                    goods_id: row.get(1)?,
+                    goods_name: row.get(2)?,
+                    quantity: row.get(3)?,
+                    unit_price: money(row, 4)?,
+                    line_total: money(row, 5)?,
+                    promotion_name: row.get(7)?,
+                    discount: money(row, 8)?,
+                    points_discount: money(row, 9)?,
+                    refunded_quantity: row.get(6)?,
+                    modifiers: Vec::new(),
+                })
+            },
+        )
+        .map_err(|e| {
+            CafeHubError::database(
+                format!("Database query failed for items of order ID {}", order_id),
+                e,
+            )
+        })?;
+
+        let modifier_rows = query_list(
+            &conn,
+            "SELECT m.order_item_id, m.option_id, m.group_name, m.option_name, m.price_delta
+             FROM order_item_modifier m
+             JOIN order_items oi ON m.order_item_id = oi.id
+             WHERE oi.order_id = :order_id
+             ORDER BY m.id ASC",
+            named_params! { ":order_id": order_id },
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    OrderItemModifier {
+                        option_id: row.get(1)?,
+                        group_name: row.get(2)?,
+                        option_name: row.get(3)?,
+                        price_delta: money(row, 4)?,
+                    },
+                ))
+            },
+        )
+        .map_err(|e| {
+            CafeHubError::database(
+                format!(
+                    "Database query failed for modifiers of order ID {}",
+                    order_id
+                ),
+                e,
+            )
+        })?;
+        for (order_item_id, modifier) in modifier_rows {
+            if let Some(item) = items.iter_mut().find(|item| item.id == order_item_id) {
+                item.modifiers.push(modifier);
+            }
+        }
+
+        Ok(Some(OrderDetail {
+            id,
+            user_id,
+            order_time,
+            total_amount,
+            coupon_code,
+            coupon_discount: coupon_discount.unwrap_or(Decimal::ZERO),
+            points_redeemed,
+            points_earned,
+            items,
+        }))
+    }
+
+    fn refund_order(
+        &self,
+        refund: &RefundOrderData,
+        operator_id: i64,
+        refund_time: NaiveDateTime,
+    ) -> Result<Decimal, CafeHubError> {
+        let mut conn = self.conn()?;
+        let mut tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| CafeHubError::database("Failed to start transaction", e))?;
+        let total_refund = issue_refund(&mut tx, refund, operator_id, refund_time)?;
+        tx.commit()
+            .map_err(|e| CafeHubError::database("Failed to commit transaction", e))?;
+        Ok(total_refund)
+    }
+}
+
+impl LostItemRepository for SqliteStorage {
+    fn list_lost_items(&self) -> Result<Vec<LostItem>, CafeHubError> {
+        let query = "
+            SELECT
+                li.id,
+                li.item_name,
+                li.pick_place,
+                li.pick_user_id,
+                p_acc.username AS pick_user_name,
+                li.claim_user_id,
+                c_acc.username AS claim_user_name,
+                li.pick_time,
+                li.claim_time,
+                li.status
+            FROM
+                lost_items li
+            LEFT JOIN
+                account p_acc ON li.pick_user_id = p_acc.id
+            LEFT JOIN
+                account c_acc ON li.claim_user_id = c_acc.id
+            ORDER BY
+                li.pick_time DESC, li.id DESC";
+
+        query_list(&*self.conn()?, query, [], |row| {
+            Ok(LostItem {
+                id: row.get(0)?,
+                item_name: row.get(1)?,
+                pick_place: row.get(2)?,
+                pick_user_id: row.get(3)?,
+                pick_user_name: row.get(4)?,
+                claim_user_id: row.get(5)?,
+                claim_user_name: row.get(6)?,
+                pick_time: row.get(7)?,
+                claim_time: row.get(8)?,
+                status: row.get(9)?,
+            })
+        })
+        .map_err(|e| CafeHubError::database("Database query failed for all lost items", e))
+    }
+
+    fn create_lost_item(
+        &self,
+        data: &ReportLostItemData,
+        pick_user_id: i64,
+        pick_time: NaiveDate,
+    ) -> Result<i64, CafeHubError> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO lost_items (item_name, pick_place, pick_user_id, pick_time, status) VALUES (:item_name, :pick_place, :pick_user_id, :pick_time, :status)",
+            named_params! {
+                ":item_name": &data.item_name,
+                ":pick_place": &data.pick_place,
+                ":pick_user_id": pick_user_id,
+                ":pick_time": pick_time,
+                ":status": 0i8,
+            },
+        )
+        .map_err(|e| CafeHubError::database("Database error while reporting lost item", e))?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    fn claim_lost_item(
+        &self,
+        item_id: i64,
+        claim_user_id: i64,
+        claim_time: NaiveDate,
+    ) -> Result<(), CafeHubError> {
+        let conn = self.conn()?;
+
+        let item_status: Option<i8> = conn
+            .query_row(
+                "SELECT status FROM lost_items WHERE id = :item_id",
+                named_params! { ":item_id": item_id },
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| CafeHubError::database("Failed to query lost item status", e))?;
+
+        match item_status {
+            Some(0) => {
+                let updated = conn
+                    .execute(
+                        "UPDATE lost_items SET status = 1, claim_user_id = :claim_user_id, claim_time = :claim_time WHERE id = :item_id AND status = 0",
+                        named_params! {
+                            ":claim_user_id": claim_user_id,
+                            ":claim_time": claim_time,
+                            ":item_id": item_id,
+                        },
+                    )
+                    .map_err(|e| CafeHubError::database("Database error while claiming item", e))?;
+
+                if updated > 0 {
+                    Ok(())
+                } else {
+                    Err(CafeHubError::conflict(format!("Failed to update item ID {}. It might have been claimed or deleted concurrently.", item_id)))
+                }
+            }
+            Some(1) => Err(CafeHubError::AlreadyClaimed { item_id }),
+            None => Err(CafeHubError::NotFound {
+                entity: "Lost item",
+                id: item_id,
+            }),
+            Some(_) => Err(CafeHubError::internal(format!(
+                "Unknown status for item ID {}.",
+                item_id
+            ))),
+        }
+    }
+}
+
+impl MessageRepository for SqliteStorage {
+    fn create_message(&self, message: &NewMessage) -> Result<i64, CafeHubError> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO message (sender_id, receiver_id, title, message_content, send_date, read_status) VALUES (:sender_id, :receiver_id, :title, :message_content, :send_date, :read_status)",
+            named_params! {
+                ":sender_id": message.sender_id,
+                ":receiver_id": message.receiver_id,
+                ":title": &message.title,
+                ":message_content": &message.message_content,
+                ":send_date": message.send_date,
+                ":read_status": 0i8,
+            },
+        )
+        .map_err(|e| CafeHubError::database("Database error while sending message", e))?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    fn list_sent_messages(&self, user_id: i64) -> Result<Vec<MessageInfo>, CafeHubError> {
+        self.list_messages("sender_id", user_id)
+    }
+
+    fn list_received_messages(&self, user_id: i64) -> Result<Vec<MessageInfo>, CafeHubError> {
+        self.list_messages("receiver_id", user_id)
+    }
+
+    fn find_message_receipt(&self, message_id: i64) -> Result<Option<(i64, i8)>, CafeHubError> {
+        self.conn()?
+            .query_row(
+                "SELECT receiver_id, read_status FROM message WHERE id = :message_id",
+                named_params! { ":message_id": message_id },
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| {
+                CafeHubError::database("Database query failed to retrieve message details", e)
+            })
+    }
+
+    fn mark_message_read(&self, message_id: i64, receiver_id: i64) -> Result<bool, CafeHubError> {
+        let updated = self
+            .conn()?
+            .execute(
+                "UPDATE message SET read_status = 1 WHERE id = :message_id AND receiver_id = :receiver_id",
+                named_params! {
+                    ":message_id": message_id,
+                    ":receiver_id": receiver_id,
+                },
+            )
+            .map_err(|e| CafeHubError::database("Database error while marking message as read", e))?;
+        Ok(updated > 0)
+    }
+}
@@ -0,0 +1,303 @@
+#[macro_use]
+mod common;
+
+use cafehub_lib::error::CafeHubError;
+use cafehub_lib::handlers;
+use cafehub_lib::models::*;
+use common::TestApp;
+
+fn registration(username: &str) -> RegistrationData {
+    RegistrationData {
+        username: username.to_string(),
+        password: "secret".to_string(),
+        phone: None,
+        gender: None,
+    }
+}
+
+#[test]
+fn register_rejects_empty_credentials() {
+    let app = TestApp::new();
+
+    assert_err!(
+        handlers::register_user(registration(""), app.storage()),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        handlers::register_user(
+            RegistrationData {
+                password: String::new(),
+                ..registration("alice")
+            },
+            app.storage()
+        ),
+        CafeHubError::Validation { .. }
+    );
+}
+
+#[test]
+fn register_rejects_invalid_phone_and_gender() {
+    let app = TestApp::new();
+
+    for phone in ["1234567890", "123456789012", "1234567890a"] {
+        assert_err!(
+            handlers::register_user(
+                RegistrationData {
+                    phone: Some(phone.to_string()),
+                    ..registration("alice")
+                },
+                app.storage()
+            ),
+            CafeHubError::InvalidPhone { .. }
+        );
+    }
+    assert_err!(
+        handlers::register_user(
+            RegistrationData {
+                gender: Some(2),
+                ..registration("alice")
+            },
+            app.storage()
+        ),
+        CafeHubError::InvalidGender { gender: 2 }
+    );
+
+    assert_eq!(app.storage().count_customers(None).unwrap(), 0);
+}
+
+#[test]
+fn register_rejects_duplicate_username() {
+    let app = TestApp::new();
+
+    handlers::register_user(
+        RegistrationData {
+            phone: Some("13800000000".to_string()),
+            gender: Some(1),
+            ..registration("alice")
+        },
+        app.storage(),
+    )
+    .unwrap();
+
+    assert_err!(
+        handlers::register_user(registration("alice"), app.storage()),
+        CafeHubError::DuplicateUsername { .. }
+    );
+    // Staff and customers share one namespace.
+    assert_err!(
+        handlers::register_user(registration(common::ADMIN_USERNAME), app.storage()),
+        CafeHubError::DuplicateUsername { .. }
+    );
+    assert_eq!(app.storage().count_customers(None).unwrap(), 1);
+}
+
+#[test]
+fn login_and_logout() {
+    let app = TestApp::new();
+    handlers::register_user(registration("alice"), app.storage()).unwrap();
+
+    assert_err!(
+        handlers::login(
+            "alice".to_string(),
+            "wrong".to_string(),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::InvalidCredentials
+    );
+    assert_err!(
+        handlers::login(
+            "nobody".to_string(),
+            "secret".to_string(),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::InvalidCredentials
+    );
+
+    let response = app.login("alice", "secret");
+    assert_eq!(response.account.username, "alice");
+    assert_eq!(response.account.user_type, 1);
+    assert_eq!(response.account.balance, Some(common::money("0.00")));
+
+    handlers::logout(response.token.clone(), &app.sessions).unwrap();
+    assert_err!(
+        handlers::logout(response.token.clone(), &app.sessions),
+        CafeHubError::NotLoggedIn
+    );
+    assert_err!(
+        handlers::get_user_details(response.token, app.storage(), &app.sessions),
+        CafeHubError::NotLoggedIn
+    );
+}
+
+#[test]
+fn commands_enforce_roles() {
+    let app = TestApp::new();
+    let (_, customer_token) = app.customer("alice");
+    let admin_token = app.admin_token();
+
+    assert_err!(
+        handlers::get_total_users(customer_token.clone(), app.storage(), &app.sessions),
+        CafeHubError::Forbidden { .. }
+    );
+    assert_err!(
+        handlers::get_all_users(customer_token, app.storage(), &app.sessions),
+        CafeHubError::Forbidden { .. }
+    );
+    assert_err!(
+        handlers::get_user_details(admin_token, app.storage(), &app.sessions),
+        CafeHubError::Forbidden { .. }
+    );
+}
+
+#[test]
+fn user_statistics() {
+    let app = TestApp::new();
+    app.customer("alice");
+    app.customer("bob");
+    let token = app.admin_token();
+
+    assert_eq!(
+        handlers::get_total_users(token.clone(), app.storage(), &app.sessions).unwrap(),
+        2
+    );
+    assert_eq!(
+        handlers::get_new_users_this_month(token.clone(), app.storage(), &app.sessions).unwrap(),
+        2
+    );
+
+    let users = handlers::get_all_users(token, app.storage(), &app.sessions).unwrap();
+    let mut names: Vec<_> = users.into_iter().map(|user| user.username).collect();
+    names.sort();
+    assert_eq!(names, ["admin", "alice", "bob"]);
+}
+
+#[test]
+fn update_user_details() {
+    let app = TestApp::new();
+    let (user_id, token) = app.customer("alice");
+    app.customer("bob");
+
+    let details =
+        |username: Option<&str>, phone: Option<&str>, gender: Option<i8>| UpdateUserData {
+            username: username.map(str::to_string),
+            phone: phone.map(str::to_string),
+            gender,
+        };
+
+    handlers::update_user_details(
+        token.clone(),
+        details(Some("alicia"), Some("13800000000"), Some(0)),
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    let account = handlers::get_user_details(token.clone(), app.storage(), &app.sessions).unwrap();
+    assert_eq!(account.id, user_id);
+    assert_eq!(account.username, "alicia");
+    assert_eq!(account.phone.as_deref(), Some("13800000000"));
+    assert_eq!(account.gender, Some(0));
+
+    // An empty phone number clears it.
+    handlers::update_user_details(
+        token.clone(),
+        details(None, Some(""), None),
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    let account = handlers::get_user_details(token.clone(), app.storage(), &app.sessions).unwrap();
+    assert_eq!(account.phone, None);
+    assert_eq!(account.username, "alicia");
+
+    assert_err!(
+        handlers::update_user_details(
+            token.clone(),
+            details(Some("bob"), None, None),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::DuplicateUsername { .. }
+    );
+    assert_err!(
+        handlers::update_user_details(
+            token.clone(),
+            details(Some(""), None, None),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        handlers::update_user_details(
+            token.clone(),
+            details(None, Some("123"), None),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::InvalidPhone { .. }
+    );
+    assert_err!(
+        handlers::update_user_details(
+            token,
+            details(None, None, Some(5)),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::InvalidGender { gender: 5 }
+    );
+}
+
+#[test]
+fn update_user_password() {
+    let app = TestApp::new();
+    let (_, token) = app.customer("alice");
+
+    assert_err!(
+        handlers::update_user_password(
+            token.clone(),
+            UpdatePasswordData {
+                current_password: "wrong".to_string(),
+                new_password: "new-password".to_string(),
+            },
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::IncorrectPassword
+    );
+    assert_err!(
+        handlers::update_user_password(
+            token.clone(),
+            UpdatePasswordData {
+                current_password: "alice-password".to_string(),
+                new_password: String::new(),
+            },
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Validation { .. }
+    );
+
+    handlers::update_user_password(
+        token,
+        UpdatePasswordData {
+            current_password: "alice-password".to_string(),
+            new_password: "new-password".to_string(),
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+
+    assert_err!(
+        handlers::login(
+            "alice".to_string(),
+            "alice-password".to_string(),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::InvalidCredentials
+    );
+    app.login("alice", "new-password");
+}
@@ -0,0 +1,164 @@
+#[macro_use]
+mod common;
+
+use cafehub_lib::error::CafeHubError;
+use cafehub_lib::handlers;
+use cafehub_lib::models::*;
+use chrono::NaiveDate;
+use common::{item, money, TestApp};
+
+fn goods_names(app: &TestApp, token: &str) -> Vec<String> {
+    handlers::get_all_goods(token.to_string(), app.storage(), &app.sessions)
+        .unwrap()
+        .into_iter()
+        .map(|goods| goods.goods_name)
+        .collect()
+}
+
+#[test]
+fn archived_goods_are_hidden_and_off_sale() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 4);
+    app.add_goods("Cookie", "1.20", 10);
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "50.00");
+    app.buy(&token, vec![item(latte, 1)]).unwrap();
+    handlers::update_goods_info(
+        app.admin_token(),
+        latte,
+        UpdateGoodsData {
+            reorder_threshold: Some(5),
+            ..Default::default()
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+
+    assert_err!(
+        handlers::archive_goods(token.clone(), latte, app.storage(), &app.sessions),
+        CafeHubError::Forbidden { .. }
+    );
+    assert_err!(
+        handlers::archive_goods(app.admin_token(), latte + 100, app.storage(), &app.sessions),
+        CafeHubError::NotFound { .. }
+    );
+    handlers::archive_goods(app.admin_token(), latte, app.storage(), &app.sessions).unwrap();
+
+    assert_eq!(goods_names(&app, &token), ["Cookie"]);
+    assert_eq!(goods_names(&app, &app.admin_token()), ["Latte", "Cookie"]);
+    assert!(app.find_goods("Latte").archived_at.is_some());
+    assert_err!(
+        app.buy(&token, vec![item(latte, 1)]),
+        CafeHubError::NotFound { .. }
+    );
+    assert!(
+        handlers::get_low_stock_goods(app.admin_token(), app.storage(), &app.sessions)
+            .unwrap()
+            .is_empty()
+    );
+
+    // Past sales still show up in the reports.
+    let margins =
+        handlers::get_goods_margins(app.admin_token(), None, app.storage(), &app.sessions).unwrap();
+    assert_eq!(margins[0].label, "Latte");
+    assert_eq!(margins[0].revenue, money("3.50"));
+
+    handlers::restore_goods(app.admin_token(), latte, app.storage(), &app.sessions).unwrap();
+    assert_eq!(goods_names(&app, &token), ["Latte", "Cookie"]);
+    assert!(app.find_goods("Latte").archived_at.is_none());
+    app.buy(&token, vec![item(latte, 1)]).unwrap();
+}
+
+#[test]
+fn only_goods_without_history_can_be_deleted() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 10);
+    let muffin = app.add_goods("Muffin", "2.00", 10);
+    let cookie = app.add_goods("Cookie", "1.20", 0);
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "50.00");
+    app.buy(&token, vec![item(latte, 1)]).unwrap();
+
+    handlers::add_supplier(
+        app.admin_token(),
+        AddSupplierData {
+            supplier_name: "Bakery".to_string(),
+            contact: None,
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    let supplier_id =
+        handlers::get_suppliers(app.admin_token(), app.storage(), &app.sessions).unwrap()[0].id;
+    handlers::create_purchase_order(
+        app.admin_token(),
+        CreatePurchaseOrderData {
+            supplier_id,
+            items: vec![PurchaseOrderItemData {
+                goods_id: cookie,
+                quantity: 12,
+                unit_cost: money("0.40"),
+            }],
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+
+    for goods_id in [latte, cookie] {
+        assert_err!(
+            handlers::delete_goods(app.admin_token(), goods_id, app.storage(), &app.sessions),
+            CafeHubError::Conflict { .. }
+        );
+    }
+    assert_err!(
+        handlers::delete_goods(token, muffin, app.storage(), &app.sessions),
+        CafeHubError::Forbidden { .. }
+    );
+
+    // Stock movements and cost history belong to the goods and go with them.
+    handlers::update_goods_info(
+        app.admin_token(),
+        muffin,
+        UpdateGoodsData {
+            cost_price: Some(money("0.80")),
+            ..Default::default()
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    handlers::delete_goods(app.admin_token(), muffin, app.storage(), &app.sessions).unwrap();
+    assert_eq!(goods_names(&app, &app.admin_token()), ["Latte", "Cookie"]);
+    assert!(app.storage().stock_movements(muffin).unwrap().is_empty());
+    assert_err!(
+        handlers::delete_goods(app.admin_token(), muffin, app.storage(), &app.sessions),
+        CafeHubError::NotFound { .. }
+    );
+}
+
+#[test]
+fn archiving_again_keeps_the_original_time() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 4);
+    let first_archived = NaiveDate::from_ymd_opt(2024, 1, 15)
+        .unwrap()
+        .and_hms_opt(9, 30, 0)
+        .unwrap();
+    app.storage()
+        .set_goods_archived(latte, Some(first_archived))
+        .unwrap();
+
+    handlers::archive_goods(app.admin_token(), latte, app.storage(), &app.sessions).unwrap();
+    assert_eq!(app.find_goods("Latte").archived_at, Some(first_archived));
+
+    // Archiving after a restore records a new time.
+    handlers::restore_goods(app.admin_token(), latte, app.storage(), &app.sessions).unwrap();
+    handlers::archive_goods(app.admin_token(), latte, app.storage(), &app.sessions).unwrap();
+    assert!(app
+        .find_goods("Latte")
+        .archived_at
+        .is_some_and(|archived_at| archived_at > first_archived));
+}
@@ -0,0 +1,167 @@
+#[macro_use]
+mod common;
+
+use cafehub_lib::error::CafeHubError;
+use cafehub_lib::handlers;
+use cafehub_lib::models::*;
+use common::{money, TestApp};
+
+#[test]
+fn recharge_balance() {
+    let app = TestApp::new();
+    let (user_id, customer_token) = app.customer("alice");
+    let token = app.admin_token();
+
+    let recharge = |user_id: i64, amount: &str| RechargeBalanceData {
+        user_id,
+        amount: money(amount),
+    };
+
+    assert_err!(
+        handlers::recharge_balance(
+            customer_token,
+            recharge(user_id, "10.00"),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Forbidden { .. }
+    );
+    assert_err!(
+        handlers::recharge_balance(
+            token.clone(),
+            recharge(user_id, "-1.00"),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        handlers::recharge_balance(
+            token.clone(),
+            recharge(user_id + 100, "10.00"),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::NotFound { .. }
+    );
+
+    handlers::recharge_balance(
+        token.clone(),
+        recharge(user_id, "10.00"),
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    handlers::recharge_balance(
+        token,
+        recharge(user_id, "2.50"),
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    assert_eq!(app.balance(user_id), money("12.50"));
+}
+
+#[test]
+fn adjust_balance() {
+    let app = TestApp::new();
+    let (user_id, _) = app.customer("alice");
+    app.recharge(user_id, "10.00");
+    let token = app.admin_token();
+
+    let adjustment = |amount: &str, note: &str| AdjustBalanceData {
+        user_id,
+        amount: money(amount),
+        note: note.to_string(),
+    };
+
+    assert_err!(
+        handlers::adjust_balance(
+            token.clone(),
+            adjustment("0", "Nothing"),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        handlers::adjust_balance(
+            token.clone(),
+            adjustment("-1.00", " "),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        handlers::adjust_balance(
+            token.clone(),
+            adjustment("-10.01", "Correction"),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::InsufficientBalance { .. }
+    );
+    assert_eq!(app.balance(user_id), money("10.00"));
+
+    handlers::adjust_balance(
+        token,
+        adjustment("-2.25", "Correction"),
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    assert_eq!(app.balance(user_id), money("7.75"));
+}
+
+#[test]
+fn balance_statement_matches_balance() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 10);
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "10.00");
+    let order_id = handlers::purchase_goods(
+        token.clone(),
+        PurchaseGoodsData {
+            items: vec![PurchaseItem {
+                goods_id: latte,
+                quantity: 2,
+                modifier_ids: Vec::new(),
+                redeem_with_points: false,
+            }],
+            coupon_code: None,
+            points_to_redeem: None,
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    let admin_token = app.admin_token();
+    handlers::refund_order(
+        admin_token.clone(),
+        RefundOrderData {
+            order_id,
+            reason: "Wrong order".to_string(),
+            items: Vec::new(),
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+
+    let statement = handlers::get_balance_statement(token, app.storage(), &app.sessions).unwrap();
+    let entries: Vec<_> = statement
+        .iter()
+        .map(|entry| (entry.amount, entry.balance_after))
+        .collect();
+    assert_eq!(entries.len(), 3);
+    assert!(entries.contains(&(money("10.00"), money("10.00"))));
+    assert!(entries.contains(&(money("-7.00"), money("3.00"))));
+    assert!(entries.contains(&(money("7.00"), money("10.00"))));
+
+    assert!(
+        handlers::check_balance_consistency(admin_token, app.storage(), &app.sessions)
+            .unwrap()
+            .is_empty()
+    );
+}
@@ -0,0 +1,247 @@
+#[macro_use]
+mod common;
+
+use cafehub_lib::error::CafeHubError;
+use cafehub_lib::handlers;
+use cafehub_lib::models::*;
+use common::TestApp;
+
+fn add_category(
+    app: &TestApp,
+    category_name: &str,
+    display_name: Option<&str>,
+    sort_order: Option<i32>,
+) -> Result<String, CafeHubError> {
+    handlers::add_category(
+        app.admin_token(),
+        AddCategoryData {
+            category_name: category_name.to_string(),
+            display_name: display_name.map(str::to_string),
+            sort_order,
+        },
+        app.storage(),
+        &app.sessions,
+    )
+}
+
+fn set_category(app: &TestApp, goods_id: i32, category_id: i32) -> Result<String, CafeHubError> {
+    handlers::update_goods_info(
+        app.admin_token(),
+        goods_id,
+        UpdateGoodsData {
+            category_id: Some(category_id),
+            ..Default::default()
+        },
+        app.storage(),
+        &app.sessions,
+    )
+}
+
+fn category_names(app: &TestApp) -> Vec<String> {
+    handlers::get_categories(app.admin_token(), app.storage(), &app.sessions)
+        .unwrap()
+        .into_iter()
+        .map(|category| category.display_name)
+        .collect()
+}
+
+#[test]
+fn categories_are_validated_and_ordered() {
+    let app = TestApp::new();
+    let (_, customer_token) = app.customer("alice");
+
+    add_category(&app, "bakery", Some("Fresh from the oven"), Some(2)).unwrap();
+    add_category(&app, " coffee ", None, Some(1)).unwrap();
+    add_category(&app, "tea", None, None).unwrap();
+    assert_eq!(
+        category_names(&app),
+        ["tea", "coffee", "Fresh from the oven"]
+    );
+
+    assert_err!(
+        add_category(&app, "coffee", Some("Coffee"), None),
+        CafeHubError::DuplicateCategoryName { .. }
+    );
+    assert_err!(
+        add_category(&app, "  ", None, None),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        add_category(&app, "juice", Some(""), None),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        handlers::add_category(
+            customer_token.clone(),
+            AddCategoryData {
+                category_name: "juice".to_string(),
+                display_name: None,
+                sort_order: None,
+            },
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Forbidden { .. }
+    );
+
+    let categories =
+        handlers::get_categories(customer_token, app.storage(), &app.sessions).unwrap();
+    let tea = categories[0].id;
+    let coffee = categories[1].id;
+    handlers::update_category(
+        app.admin_token(),
+        tea,
+        UpdateCategoryData {
+            display_name: Some("Tea".to_string()),
+            sort_order: Some(3),
+            ..Default::default()
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    assert_eq!(
+        category_names(&app),
+        ["coffee", "Fresh from the oven", "Tea"]
+    );
+    assert_err!(
+        handlers::update_category(
+            app.admin_token(),
+            tea,
+            UpdateCategoryData {
+                category_name: Some("coffee".to_string()),
+                ..Default::default()
+            },
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::DuplicateCategoryName { .. }
+    );
+    assert_err!(
+        handlers::update_category(
+            app.admin_token(),
+            coffee + 100,
+            UpdateCategoryData {
+                sort_order: Some(0),
+                ..Default::default()
+            },
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::NotFound { .. }
+    );
+}
+
+#[test]
+fn only_empty_categories_can_be_deleted() {
+    let app = TestApp::new();
+    let coffee = app.add_category("coffee");
+    let tea = app.add_category("tea");
+    let latte = app.add_goods("Latte", "3.50", 10);
+
+    assert_err!(
+        set_category(&app, latte, tea + 100),
+        CafeHubError::NotFound { .. }
+    );
+    assert_err!(
+        handlers::update_goods_info(
+            app.admin_token(),
+            latte,
+            UpdateGoodsData {
+                category_id: Some(coffee),
+                clear_category: true,
+                ..Default::default()
+            },
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Validation { .. }
+    );
+    set_category(&app, latte, coffee).unwrap();
+
+    assert_err!(
+        handlers::delete_category(app.admin_token(), coffee, app.storage(), &app.sessions),
+        CafeHubError::Conflict { .. }
+    );
+    handlers::delete_category(app.admin_token(), tea, app.storage(), &app.sessions).unwrap();
+    assert_err!(
+        handlers::delete_category(app.admin_token(), tea, app.storage(), &app.sessions),
+        CafeHubError::NotFound { .. }
+    );
+
+    set_category(&app, latte, coffee).unwrap();
+    handlers::update_goods_info(
+        app.admin_token(),
+        latte,
+        UpdateGoodsData {
+            clear_category: true,
+            ..Default::default()
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    handlers::delete_category(app.admin_token(), coffee, app.storage(), &app.sessions).unwrap();
+    assert!(category_names(&app).is_empty());
+}
+
+#[test]
+fn goods_are_grouped_by_category_in_menu_order() {
+    let app = TestApp::new();
+    let bakery = app.add_category("bakery");
+    let coffee = app.add_category("coffee");
+    app.add_category("tea");
+    handlers::update_category(
+        app.admin_token(),
+        coffee,
+        UpdateCategoryData {
+            sort_order: Some(-1),
+            ..Default::default()
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+
+    let croissant = app.add_goods("Croissant", "2.50", 10);
+    let latte = app.add_goods("Latte", "3.50", 10);
+    let mocha = app.add_goods("Mocha", "3.80", 10);
+    app.add_goods("Gift card", "20.00", 10);
+    set_category(&app, croissant, bakery).unwrap();
+    set_category(&app, latte, coffee).unwrap();
+    set_category(&app, mocha, coffee).unwrap();
+    handlers::archive_goods(app.admin_token(), mocha, app.storage(), &app.sessions).unwrap();
+
+    let (_, token) = app.customer("alice");
+    let menu: Vec<(Option<String>, Vec<String>)> =
+        handlers::get_goods_by_category(token, app.storage(), &app.sessions)
+            .unwrap()
+            .into_iter()
+            .map(|section| {
+                (
+                    section.category.map(|category| category.category_name),
+                    section
+                        .goods
+                        .into_iter()
+                        .map(|goods| goods.goods_name)
+                        .collect(),
+                )
+            })
+            .collect();
+    assert_eq!(
+        menu,
+        [
+            (Some("coffee".to_string()), vec!["Latte".to_string()]),
+            (Some("bakery".to_string()), vec!["Croissant".to_string()]),
+            (None, vec!["Gift card".to_string()]),
+        ]
+    );
+
+    let staff_menu =
+        handlers::get_goods_by_category(app.admin_token(), app.storage(), &app.sessions).unwrap();
+    assert_eq!(staff_menu[0].goods.len(), 2);
+    assert_eq!(
+        app.find_goods("Croissant").category.as_deref(),
+        Some("bakery")
+    );
+}
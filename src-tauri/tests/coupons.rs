@@ -0,0 +1,293 @@
+#[macro_use]
+mod common;
+
+use cafehub_lib::error::{CafeHubError, CouponRejection};
+use cafehub_lib::handlers;
+use cafehub_lib::models::*;
+use chrono::{Duration, Local};
+use common::{item, money, TestApp};
+
+fn coupon(code: &str, discount_type: DiscountType, discount_value: &str) -> AddCouponData {
+    AddCouponData {
+        code: code.to_string(),
+        discount_type,
+        discount_value: money(discount_value),
+        min_spend: None,
+        expires_at: None,
+        max_redemptions: None,
+        max_per_customer: None,
+    }
+}
+
+fn add_coupon(app: &TestApp, data: AddCouponData) -> Result<String, CafeHubError> {
+    handlers::add_coupon(app.admin_token(), data, app.storage(), &app.sessions)
+}
+
+fn with_coupon(items: &[(i32, i32)], coupon_code: &str) -> PurchaseGoodsData {
+    PurchaseGoodsData {
+        items: items
+            .iter()
+            .map(|&(goods_id, quantity)| item(goods_id, quantity))
+            .collect(),
+        coupon_code: Some(coupon_code.to_string()),
+        points_to_redeem: None,
+    }
+}
+
+#[test]
+fn coupon_discount_is_spread_over_the_order() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "4.00", 20);
+    let cookie = app.add_goods("Cookie", "2.00", 20);
+    let mut customers = Vec::new();
+    for username in ["alice", "bob", "carol"] {
+        let (user_id, token) = app.customer(username);
+        app.recharge(user_id, "50.00");
+        customers.push((user_id, token));
+    }
+    let [(alice, alice_token), (bob, bob_token), (_, carol_token)] = &customers[..] else {
+        unreachable!()
+    };
+    add_coupon(
+        &app,
+        AddCouponData {
+            min_spend: Some(money("5.00")),
+            max_redemptions: Some(2),
+            max_per_customer: Some(1),
+            ..coupon(" save10 ", DiscountType::Percent, "10")
+        },
+    )
+    .unwrap();
+
+    let order_id = app
+        .purchase(
+            alice_token,
+            with_coupon(&[(latte, 2), (cookie, 1)], "Save10"),
+        )
+        .unwrap();
+    let detail = app.order(order_id);
+    assert_eq!(detail.coupon_code.as_deref(), Some("SAVE10"));
+    assert_eq!(detail.coupon_discount, money("1.00"));
+    assert_eq!(detail.total_amount, money("9.00"));
+    assert_eq!(detail.items[0].line_total, money("7.20"));
+    assert_eq!(detail.items[1].line_total, money("1.80"));
+    assert_eq!(app.balance(*alice), money("41.00"));
+
+    assert_err!(
+        app.purchase(alice_token, with_coupon(&[(latte, 2)], "SAVE10")),
+        CafeHubError::CouponRejected {
+            reason: CouponRejection::CustomerLimitReached,
+            ..
+        }
+    );
+    // A rejected coupon leaves the order unplaced.
+    assert_err!(
+        app.purchase(bob_token, with_coupon(&[(cookie, 2)], "SAVE10")),
+        CafeHubError::CouponRejected {
+            reason: CouponRejection::BelowMinimumSpend,
+            ..
+        }
+    );
+    assert_eq!(app.balance(*bob), money("50.00"));
+    assert_eq!(app.find_goods("Cookie").stock, Some(19));
+    assert_err!(
+        app.purchase(bob_token, with_coupon(&[(cookie, 2)], "NOPE")),
+        CafeHubError::CouponRejected {
+            reason: CouponRejection::Unknown,
+            ..
+        }
+    );
+
+    app.purchase(bob_token, with_coupon(&[(latte, 2)], "SAVE10"))
+        .unwrap();
+    assert_err!(
+        app.purchase(carol_token, with_coupon(&[(latte, 2)], "SAVE10")),
+        CafeHubError::CouponRejected {
+            reason: CouponRejection::UsedUp,
+            ..
+        }
+    );
+    let coupons = handlers::get_coupons(app.admin_token(), app.storage(), &app.sessions).unwrap();
+    assert_eq!(coupons[0].times_redeemed, 2);
+
+    // Refunds give back what was paid for the line after the coupon.
+    handlers::refund_order(
+        app.admin_token(),
+        RefundOrderData {
+            order_id,
+            reason: "Burnt".to_string(),
+            items: vec![RefundItem {
+                order_item_id: detail.items[1].id,
+                quantity: 1,
+            }],
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    assert_eq!(app.balance(*alice), money("42.80"));
+}
+
+#[test]
+fn fully_refunded_orders_give_their_coupon_use_back() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "4.00", 20);
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "50.00");
+    add_coupon(
+        &app,
+        AddCouponData {
+            max_redemptions: Some(1),
+            ..coupon("ONCE", DiscountType::Fixed, "1.00")
+        },
+    )
+    .unwrap();
+    let refund = |order_id, items| {
+        handlers::refund_order(
+            app.admin_token(),
+            RefundOrderData {
+                order_id,
+                reason: "Wrong order".to_string(),
+                items,
+            },
+            app.storage(),
+            &app.sessions,
+        )
+        .unwrap();
+    };
+    let times_redeemed = || {
+        handlers::get_coupons(app.admin_token(), app.storage(), &app.sessions).unwrap()[0]
+            .times_redeemed
+    };
+
+    // A partly refunded order still used the coupon.
+    let order_id = app
+        .purchase(&token, with_coupon(&[(latte, 2)], "ONCE"))
+        .unwrap();
+    let line_id = app.order(order_id).items[0].id;
+    refund(
+        order_id,
+        vec![RefundItem {
+            order_item_id: line_id,
+            quantity: 1,
+        }],
+    );
+    assert_eq!(times_redeemed(), 1);
+    assert_err!(
+        app.purchase(&token, with_coupon(&[(latte, 2)], "ONCE")),
+        CafeHubError::CouponRejected {
+            reason: CouponRejection::UsedUp,
+            ..
+        }
+    );
+
+    // Refunding the rest frees it again, and the order keeps showing the coupon.
+    refund(order_id, Vec::new());
+    assert_eq!(times_redeemed(), 0);
+    assert_eq!(app.order(order_id).coupon_code.as_deref(), Some("ONCE"));
+    app.purchase(&token, with_coupon(&[(latte, 2)], "ONCE"))
+        .unwrap();
+    assert_eq!(times_redeemed(), 1);
+}
+
+#[test]
+fn coupons_are_validated_and_can_be_disabled() {
+    let app = TestApp::new();
+    let cookie = app.add_goods("Cookie", "1.50", 20);
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "50.00");
+
+    for invalid in [
+        coupon("no spaces", DiscountType::Fixed, "1.00"),
+        coupon("", DiscountType::Fixed, "1.00"),
+        coupon("ZERO", DiscountType::Fixed, "0"),
+        coupon("TOOMUCH", DiscountType::Percent, "120"),
+        AddCouponData {
+            max_per_customer: Some(0),
+            ..coupon("LIMIT", DiscountType::Fixed, "1.00")
+        },
+        AddCouponData {
+            min_spend: Some(money("-1.00")),
+            ..coupon("SPEND", DiscountType::Fixed, "1.00")
+        },
+        AddCouponData {
+            expires_at: Some(Local::now().naive_local() - Duration::hours(1)),
+            ..coupon("STALE", DiscountType::Fixed, "1.00")
+        },
+    ] {
+        assert_err!(add_coupon(&app, invalid), CafeHubError::Validation { .. });
+    }
+    assert_err!(
+        handlers::add_coupon(
+            token.clone(),
+            coupon("MINE", DiscountType::Fixed, "1.00"),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Forbidden { .. }
+    );
+
+    let tomorrow = Local::now().naive_local() + Duration::days(1);
+    add_coupon(
+        &app,
+        AddCouponData {
+            expires_at: Some(tomorrow),
+            ..coupon("FIVE-OFF", DiscountType::Fixed, "5.00")
+        },
+    )
+    .unwrap();
+    assert_err!(
+        add_coupon(&app, coupon("five-off", DiscountType::Fixed, "2.00")),
+        CafeHubError::DuplicateCouponCode { .. }
+    );
+
+    // The discount never takes an order below zero.
+    let order_id = app
+        .purchase(&token, with_coupon(&[(cookie, 2)], "five-off"))
+        .unwrap();
+    assert_eq!(app.order(order_id).total_amount, money("0.00"));
+    assert_eq!(app.balance(user_id), money("50.00"));
+
+    let items = [PurchaseItem {
+        goods_id: cookie,
+        quantity: 1,
+        modifier_ids: Vec::new(),
+        redeem_with_points: false,
+    }];
+    assert_err!(
+        app.storage().place_order(
+            user_id,
+            &items,
+            Some("FIVE-OFF"),
+            0,
+            tomorrow + Duration::minutes(1)
+        ),
+        CafeHubError::CouponRejected {
+            reason: CouponRejection::Expired,
+            ..
+        }
+    );
+
+    let coupon_id =
+        handlers::get_coupons(app.admin_token(), app.storage(), &app.sessions).unwrap()[0].id;
+    handlers::disable_coupon(app.admin_token(), coupon_id, app.storage(), &app.sessions).unwrap();
+    assert_err!(
+        app.purchase(&token, with_coupon(&[(cookie, 2)], "FIVE-OFF")),
+        CafeHubError::CouponRejected {
+            reason: CouponRejection::Disabled,
+            ..
+        }
+    );
+    assert!(
+        handlers::get_coupons(app.admin_token(), app.storage(), &app.sessions).unwrap()[0].disabled
+    );
+    assert_err!(
+        handlers::disable_coupon(
+            app.admin_token(),
+            coupon_id + 100,
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::NotFound { .. }
+    );
+}
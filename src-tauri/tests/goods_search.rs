@@ -0,0 +1,186 @@
+#[macro_use]
+mod common;
+
+use cafehub_lib::error::CafeHubError;
+use cafehub_lib::handlers;
+use cafehub_lib::models::*;
+use common::{money, TestApp};
+
+fn update(app: &TestApp, goods_id: i32, data: UpdateGoodsData) {
+    handlers::update_goods_info(
+        app.admin_token(),
+        goods_id,
+        data,
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+}
+
+fn search(app: &TestApp, token: &str, query: GoodsQuery) -> Result<GoodsPage, CafeHubError> {
+    handlers::search_goods(token.to_string(), query, app.storage(), &app.sessions)
+}
+
+fn names(page: &GoodsPage) -> Vec<&str> {
+    page.items
+        .iter()
+        .map(|goods| goods.goods_name.as_str())
+        .collect()
+}
+
+#[test]
+fn goods_can_be_searched_filtered_and_sorted() {
+    let app = TestApp::new();
+    let coffee = app.add_category("coffee");
+    let latte = app.add_goods("Latte", "3.50", 10);
+    let mocha = app.add_goods("Iced Mocha", "4.20", 0);
+    let cookie = app.add_goods("Cookie", "1.20", 30);
+    app.add_goods("100% Juice", "2.80", 5);
+    for goods_id in [latte, mocha] {
+        update(
+            &app,
+            goods_id,
+            UpdateGoodsData {
+                category_id: Some(coffee),
+                ..Default::default()
+            },
+        );
+    }
+    update(
+        &app,
+        cookie,
+        UpdateGoodsData {
+            available: Some(false),
+            ..Default::default()
+        },
+    );
+    let token = app.admin_token();
+
+    let all = search(&app, &token, GoodsQuery::default()).unwrap();
+    assert_eq!(all.total, 4);
+    assert_eq!(names(&all), ["100% Juice", "Cookie", "Iced Mocha", "Latte"]);
+
+    // Matches part of the name, whatever the case, and `%` is not a wildcard.
+    let query = |search: &str| GoodsQuery {
+        search: Some(search.to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        names(&search(&app, &token, query(" moch ")).unwrap()),
+        ["Iced Mocha"]
+    );
+    assert_eq!(
+        names(&search(&app, &token, query("0%")).unwrap()),
+        ["100% Juice"]
+    );
+    assert!(search(&app, &token, query("_")).unwrap().items.is_empty());
+
+    let filtered = search(
+        &app,
+        &token,
+        GoodsQuery {
+            category_id: Some(coffee),
+            in_stock_only: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(names(&filtered), ["Latte"]);
+    assert_eq!(filtered.items[0].category.as_deref(), Some("coffee"));
+
+    let by_price = search(
+        &app,
+        &token,
+        GoodsQuery {
+            min_price: Some(money("2.80")),
+            max_price: Some(money("4.00")),
+            sort: GoodsSort::PriceDesc,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(names(&by_price), ["Latte", "100% Juice"]);
+
+    let by_category = search(
+        &app,
+        &token,
+        GoodsQuery {
+            sort: GoodsSort::Category,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        names(&by_category),
+        ["Iced Mocha", "Latte", "100% Juice", "Cookie"]
+    );
+
+    let newest = search(
+        &app,
+        &token,
+        GoodsQuery {
+            sort: GoodsSort::Newest,
+            page: Some(2),
+            limit: Some(3),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(newest.total, 4);
+    assert_eq!((newest.page, newest.limit), (2, 3));
+    assert_eq!(names(&newest), ["Latte"]);
+}
+
+#[test]
+fn customers_never_see_archived_goods() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 10);
+    app.add_goods("Cookie", "1.20", 30);
+    update(
+        &app,
+        latte,
+        UpdateGoodsData {
+            cost_price: Some(money("1.00")),
+            ..Default::default()
+        },
+    );
+    handlers::archive_goods(app.admin_token(), latte, app.storage(), &app.sessions).unwrap();
+    let (_, token) = app.customer("alice");
+    let with_archived = || GoodsQuery {
+        include_archived: true,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        names(&search(&app, &app.admin_token(), GoodsQuery::default()).unwrap()),
+        ["Cookie"]
+    );
+    let staff_page = search(&app, &app.admin_token(), with_archived()).unwrap();
+    assert_eq!(names(&staff_page), ["Cookie", "Latte"]);
+    assert_eq!(staff_page.items[1].cost_price, Some(money("1.00")));
+    let customer_page = search(&app, &token, with_archived()).unwrap();
+    assert_eq!(customer_page.total, 1);
+    assert_eq!(names(&customer_page), ["Cookie"]);
+
+    for query in [
+        GoodsQuery {
+            page: Some(0),
+            ..Default::default()
+        },
+        GoodsQuery {
+            limit: Some(101),
+            ..Default::default()
+        },
+        GoodsQuery {
+            min_price: Some(money("-1.00")),
+            ..Default::default()
+        },
+        GoodsQuery {
+            min_price: Some(money("3.00")),
+            max_price: Some(money("2.00")),
+            ..Default::default()
+        },
+    ] {
+        assert_err!(search(&app, &token, query), CafeHubError::Validation { .. });
+    }
+}
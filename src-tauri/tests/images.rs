@@ -0,0 +1,145 @@
+#[macro_use]
+mod common;
+
+use cafehub_lib::error::CafeHubError;
+use cafehub_lib::handlers;
+use cafehub_lib::image_protocol;
+use cafehub_lib::storage::ImageSize;
+use common::TestApp;
+use image::{ImageFormat, Rgba, RgbaImage};
+use std::io::Cursor;
+
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let picture = RgbaImage::from_pixel(width, height, Rgba([120, 80, 40, 255]));
+    let mut bytes = Vec::new();
+    picture
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .unwrap();
+    bytes
+}
+
+fn upload(app: &TestApp, goods_id: i32, image: Vec<u8>) -> Result<String, CafeHubError> {
+    handlers::upload_goods_image(
+        app.admin_token(),
+        goods_id,
+        image,
+        app.storage(),
+        &app.sessions,
+    )
+}
+
+fn stored_size(app: &TestApp, goods_id: i32, size: ImageSize) -> (u32, u32) {
+    let bytes = app
+        .storage()
+        .find_goods_image(goods_id, size)
+        .unwrap()
+        .expect("image not found");
+    let picture = image::load_from_memory_with_format(&bytes, ImageFormat::Jpeg).unwrap();
+    (picture.width(), picture.height())
+}
+
+#[test]
+fn uploads_are_resized_and_referenced_from_goods() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 10);
+    let cookie = app.add_goods("Cookie", "1.20", 10);
+    let (_, customer_token) = app.customer("alice");
+
+    upload(&app, latte, png(2000, 1000)).unwrap();
+    assert_eq!(stored_size(&app, latte, ImageSize::Full), (1024, 512));
+    assert_eq!(stored_size(&app, latte, ImageSize::Thumbnail), (256, 128));
+
+    // Small pictures are not scaled up.
+    upload(&app, cookie, png(100, 200)).unwrap();
+    assert_eq!(stored_size(&app, cookie, ImageSize::Full), (100, 200));
+    assert_eq!(stored_size(&app, cookie, ImageSize::Thumbnail), (100, 200));
+
+    let goods = handlers::get_all_goods(customer_token.clone(), app.storage(), &app.sessions)
+        .unwrap()
+        .into_iter()
+        .find(|goods| goods.id == latte)
+        .unwrap();
+    let image = goods.image.unwrap();
+    let thumbnail = goods.thumbnail.unwrap();
+    assert!(image.starts_with(&format!(
+        "{}{}/image.jpg?v=",
+        image_protocol::BASE_URL,
+        latte
+    )));
+    assert!(thumbnail.starts_with(&format!(
+        "{}{}/thumbnail.jpg?v=",
+        image_protocol::BASE_URL,
+        latte
+    )));
+
+    // A new upload gets a new URL.
+    upload(&app, latte, png(300, 300)).unwrap();
+    let replaced = app.find_goods("Latte");
+    assert_ne!(replaced.image.as_deref(), Some(image.as_str()));
+    assert_eq!(stored_size(&app, latte, ImageSize::Full), (300, 300));
+
+    assert_err!(
+        handlers::upload_goods_image(
+            customer_token,
+            latte,
+            png(10, 10),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Forbidden { .. }
+    );
+    assert_err!(
+        upload(&app, latte, b"not an image".to_vec()),
+        CafeHubError::Validation { .. }
+    );
+    // Tiny on disk, but too wide to decode.
+    assert_err!(
+        upload(&app, latte, png(9000, 1)),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        upload(&app, latte + 100, png(10, 10)),
+        CafeHubError::NotFound { .. }
+    );
+}
+
+#[test]
+fn images_are_served_through_the_protocol() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 10);
+    upload(&app, latte, png(400, 400)).unwrap();
+
+    let response =
+        image_protocol::respond(Some(app.storage()), &format!("/{}/thumbnail.jpg", latte));
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "image/jpeg");
+    let picture = image::load_from_memory(response.body()).unwrap();
+    assert_eq!((picture.width(), picture.height()), (256, 256));
+
+    for path in [
+        format!("/{}/original.png", latte),
+        format!("/{}/image.jpg/extra", latte),
+        "/latte/image.jpg".to_string(),
+        format!("/{}/image.jpg", latte + 100),
+    ] {
+        assert_eq!(
+            image_protocol::respond(Some(app.storage()), &path).status(),
+            404
+        );
+    }
+    assert_eq!(
+        image_protocol::respond(None, &format!("/{}/image.jpg", latte)).status(),
+        503
+    );
+
+    handlers::remove_goods_image(app.admin_token(), latte, app.storage(), &app.sessions).unwrap();
+    assert_eq!(app.find_goods("Latte").image, None);
+    assert_eq!(
+        image_protocol::respond(Some(app.storage()), &format!("/{}/image.jpg", latte)).status(),
+        404
+    );
+    assert_err!(
+        handlers::remove_goods_image(app.admin_token(), latte, app.storage(), &app.sessions),
+        CafeHubError::NotFound { .. }
+    );
+}
@@ -0,0 +1,279 @@
+#[macro_use]
+mod common;
+
+use cafehub_lib::error::CafeHubError;
+use cafehub_lib::handlers;
+use cafehub_lib::models::*;
+use common::{item, money, TestApp};
+
+fn add_ingredient(app: &TestApp, ingredient_name: &str, unit: &str, stock: i32) -> i64 {
+    handlers::add_ingredient(
+        app.admin_token(),
+        AddIngredientData {
+            ingredient_name: ingredient_name.to_string(),
+            unit: unit.to_string(),
+            stock: Some(stock),
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    app.storage()
+        .list_ingredients()
+        .unwrap()
+        .into_iter()
+        .find(|ingredient| ingredient.ingredient_name == ingredient_name)
+        .expect("ingredient not found")
+        .id
+}
+
+fn set_recipe(app: &TestApp, goods_id: i32, items: &[(i64, i32)]) -> Result<String, CafeHubError> {
+    handlers::set_goods_recipe(
+        app.admin_token(),
+        goods_id,
+        SetRecipeData {
+            items: items
+                .iter()
+                .map(|&(ingredient_id, quantity)| RecipeItemData {
+                    ingredient_id,
+                    quantity,
+                })
+                .collect(),
+        },
+        app.storage(),
+        &app.sessions,
+    )
+}
+
+fn ingredient_stock(app: &TestApp, ingredient_id: i64) -> i32 {
+    app.storage()
+        .list_ingredients()
+        .unwrap()
+        .into_iter()
+        .find(|ingredient| ingredient.id == ingredient_id)
+        .expect("ingredient not found")
+        .stock
+}
+
+#[test]
+fn ingredients_are_managed_by_staff() {
+    let app = TestApp::new();
+    let (_, customer_token) = app.customer("alice");
+    let beans = add_ingredient(&app, "Coffee beans", "g", 1000);
+
+    assert_err!(
+        handlers::add_ingredient(
+            app.admin_token(),
+            AddIngredientData {
+                ingredient_name: "Coffee beans".to_string(),
+                unit: "g".to_string(),
+                stock: None,
+            },
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::DuplicateIngredientName { .. }
+    );
+    assert_err!(
+        handlers::add_ingredient(
+            app.admin_token(),
+            AddIngredientData {
+                ingredient_name: "Milk".to_string(),
+                unit: "ml".to_string(),
+                stock: Some(-1),
+            },
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Validation { .. }
+    );
+    // Names and units are trimmed and must fit their columns.
+    let long_name = "M".repeat(21);
+    for (ingredient_name, unit) in [
+        ("  ", "ml"),
+        (long_name.as_str(), "ml"),
+        ("Milk", "millilitres"),
+    ] {
+        assert_err!(
+            handlers::add_ingredient(
+                app.admin_token(),
+                AddIngredientData {
+                    ingredient_name: ingredient_name.to_string(),
+                    unit: unit.to_string(),
+                    stock: None,
+                },
+                app.storage(),
+                &app.sessions
+            ),
+            CafeHubError::Validation { .. }
+        );
+    }
+    assert_err!(
+        handlers::add_ingredient(
+            app.admin_token(),
+            AddIngredientData {
+                ingredient_name: " Coffee beans ".to_string(),
+                unit: "g".to_string(),
+                stock: None,
+            },
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::DuplicateIngredientName { .. }
+    );
+    assert_err!(
+        handlers::get_all_ingredients(customer_token, app.storage(), &app.sessions),
+        CafeHubError::Forbidden { .. }
+    );
+
+    handlers::update_ingredient(
+        app.admin_token(),
+        beans,
+        UpdateIngredientData {
+            unit: None,
+            stock: Some(750),
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    let ingredients =
+        handlers::get_all_ingredients(app.admin_token(), app.storage(), &app.sessions).unwrap();
+    assert_eq!(ingredients.len(), 1);
+    assert_eq!(ingredients[0].unit, "g");
+    assert_eq!(ingredients[0].stock, 750);
+
+    assert_err!(
+        handlers::update_ingredient(
+            app.admin_token(),
+            beans + 100,
+            UpdateIngredientData {
+                unit: Some("kg".to_string()),
+                stock: None,
+            },
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::NotFound { .. }
+    );
+}
+
+#[test]
+fn recipes_are_validated() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 0);
+    let beans = add_ingredient(&app, "Coffee beans", "g", 1000);
+
+    assert_err!(
+        set_recipe(&app, latte, &[(beans, 0)]),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        set_recipe(&app, latte, &[(beans, 18), (beans, 4)]),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        set_recipe(&app, latte + 100, &[(beans, 18)]),
+        CafeHubError::NotFound { .. }
+    );
+    assert_err!(
+        set_recipe(&app, latte, &[(beans, 18), (beans + 100, 1)]),
+        CafeHubError::NotFound { .. }
+    );
+    assert!(app.storage().find_recipe(latte).unwrap().is_empty());
+
+    set_recipe(&app, latte, &[(beans, 18)]).unwrap();
+    let recipe =
+        handlers::get_goods_recipe(app.admin_token(), latte, app.storage(), &app.sessions).unwrap();
+    assert_eq!(recipe.len(), 1);
+    assert_eq!(recipe[0].ingredient_name, "Coffee beans");
+    assert_eq!(recipe[0].quantity, 18);
+
+    set_recipe(&app, latte, &[]).unwrap();
+    assert!(app.storage().find_recipe(latte).unwrap().is_empty());
+}
+
+#[test]
+fn sales_deplete_shared_ingredients() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 0);
+    let espresso = app.add_goods("Espresso", "2.50", 0);
+    let beans = add_ingredient(&app, "Coffee beans", "g", 100);
+    let milk = add_ingredient(&app, "Milk", "ml", 1000);
+    set_recipe(&app, latte, &[(beans, 18), (milk, 200)]).unwrap();
+    set_recipe(&app, espresso, &[(beans, 18)]).unwrap();
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "50.00");
+
+    // Beans limit both drinks; milk also limits lattes.
+    assert_eq!(app.find_goods("Latte").stock, Some(5));
+    assert_eq!(app.find_goods("Espresso").stock, Some(5));
+
+    app.buy(&token, vec![item(latte, 2), item(espresso, 1)])
+        .unwrap();
+
+    assert_eq!(ingredient_stock(&app, beans), 46);
+    assert_eq!(ingredient_stock(&app, milk), 600);
+    assert_eq!(app.find_goods("Latte").stock, Some(2));
+    assert_eq!(app.find_goods("Espresso").stock, Some(2));
+    assert_eq!(app.balance(user_id), money("40.50"));
+}
+
+#[test]
+fn sale_is_rejected_when_an_ingredient_runs_out() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 0);
+    let espresso = app.add_goods("Espresso", "2.50", 0);
+    let beans = add_ingredient(&app, "Coffee beans", "g", 40);
+    set_recipe(&app, latte, &[(beans, 18)]).unwrap();
+    set_recipe(&app, espresso, &[(beans, 18)]).unwrap();
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "50.00");
+
+    // Each line fits on its own, but together they need 54g.
+    let result = app.buy(&token, vec![item(latte, 2), item(espresso, 1)]);
+    match result {
+        Err(CafeHubError::InsufficientIngredient {
+            ingredient_name,
+            required,
+            available,
+            ..
+        }) => {
+            assert_eq!(ingredient_name, "Coffee beans");
+            assert_eq!(required, 54);
+            assert_eq!(available, 40);
+        }
+        _ => panic!("expected InsufficientIngredient"),
+    }
+
+    assert_eq!(ingredient_stock(&app, beans), 40);
+    assert_eq!(app.balance(user_id), money("50.00"));
+}
+
+#[test]
+fn refunds_do_not_restore_ingredients() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 0);
+    let beans = add_ingredient(&app, "Coffee beans", "g", 100);
+    set_recipe(&app, latte, &[(beans, 18)]).unwrap();
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "20.00");
+
+    let order_id = app.buy(&token, vec![item(latte, 2)]).unwrap();
+    handlers::refund_order(
+        app.admin_token(),
+        RefundOrderData {
+            order_id,
+            reason: "Spilled".to_string(),
+            items: Vec::new(),
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+
+    // The drinks were already made, so the beans stay used.
+    assert_eq!(app.balance(user_id), money("20.00"));
+    assert_eq!(ingredient_stock(&app, beans), 64);
+    assert_eq!(app.find_goods("Latte").stock, Some(3));
+}
@@ -0,0 +1,111 @@
+#[macro_use]
+mod common;
+
+use cafehub_lib::error::CafeHubError;
+use cafehub_lib::handlers;
+use cafehub_lib::models::*;
+use common::TestApp;
+
+fn report(item_name: &str) -> ReportLostItemData {
+    ReportLostItemData {
+        item_name: item_name.to_string(),
+        pick_place: Some("Table 4".to_string()),
+    }
+}
+
+#[test]
+fn report_and_list_lost_items() {
+    let app = TestApp::new();
+    let (user_id, token) = app.customer("alice");
+
+    assert_err!(
+        handlers::report_lost_item(token.clone(), report(""), app.storage(), &app.sessions),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        handlers::report_lost_item(
+            app.admin_token(),
+            report("Umbrella"),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Forbidden { .. }
+    );
+
+    handlers::report_lost_item(
+        token.clone(),
+        report("Umbrella"),
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+
+    let items = handlers::get_all_lost_items(token, app.storage(), &app.sessions).unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].item_name, "Umbrella");
+    assert_eq!(items[0].pick_place.as_deref(), Some("Table 4"));
+    assert_eq!(items[0].pick_user_id, Some(user_id));
+    assert_eq!(items[0].pick_user_name.as_deref(), Some("alice"));
+    assert_eq!(items[0].status, 0);
+}
+
+#[test]
+fn lost_item_can_only_be_claimed_once() {
+    let app = TestApp::new();
+    let (_, finder_token) = app.customer("alice");
+    let (owner_id, owner_token) = app.customer("bob");
+    let (_, other_token) = app.customer("carol");
+    handlers::report_lost_item(
+        finder_token.clone(),
+        report("Umbrella"),
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    let item_id =
+        handlers::get_all_lost_items(finder_token, app.storage(), &app.sessions).unwrap()[0].id;
+
+    handlers::claim_lost_item(
+        owner_token.clone(),
+        ClaimLostItemData { item_id },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+
+    assert_err!(
+        handlers::claim_lost_item(
+            other_token,
+            ClaimLostItemData { item_id },
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::AlreadyClaimed { .. }
+    );
+    assert_err!(
+        handlers::claim_lost_item(
+            owner_token.clone(),
+            ClaimLostItemData { item_id },
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::AlreadyClaimed { .. }
+    );
+    assert_err!(
+        handlers::claim_lost_item(
+            owner_token.clone(),
+            ClaimLostItemData {
+                item_id: item_id + 100
+            },
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::NotFound { .. }
+    );
+
+    let items = handlers::get_all_lost_items(owner_token, app.storage(), &app.sessions).unwrap();
+    assert_eq!(items[0].status, 1);
+    assert_eq!(items[0].claim_user_id, Some(owner_id));
+    assert_eq!(items[0].claim_user_name.as_deref(), Some("bob"));
+    assert!(items[0].claim_time.is_some());
+}
@@ -0,0 +1,101 @@
+#[macro_use]
+mod common;
+
+use cafehub_lib::error::CafeHubError;
+use cafehub_lib::handlers;
+use cafehub_lib::models::*;
+use common::{item, TestApp};
+
+fn set_threshold(
+    app: &TestApp,
+    goods_id: i32,
+    reorder_threshold: i32,
+) -> Result<String, CafeHubError> {
+    handlers::update_goods_info(
+        app.admin_token(),
+        goods_id,
+        UpdateGoodsData {
+            reorder_threshold: Some(reorder_threshold),
+            ..Default::default()
+        },
+        app.storage(),
+        &app.sessions,
+    )
+}
+
+fn admin_inbox(app: &TestApp) -> Vec<MessageInfo> {
+    handlers::get_recived_messages(app.admin_token(), app.storage(), &app.sessions).unwrap()
+}
+
+#[test]
+fn goods_at_or_below_threshold_are_listed() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 10);
+    let cookie = app.add_goods("Cookie", "1.20", 3);
+    app.add_goods("Muffin", "2.00", 2);
+    let (_, customer_token) = app.customer("alice");
+
+    assert_err!(
+        set_threshold(&app, latte, -1),
+        CafeHubError::Validation { .. }
+    );
+    set_threshold(&app, latte, 5).unwrap();
+    set_threshold(&app, cookie, 3).unwrap();
+
+    // The muffin has no threshold, so it is never reported however low it gets.
+    let low =
+        handlers::get_low_stock_goods(app.admin_token(), app.storage(), &app.sessions).unwrap();
+    assert_eq!(low.len(), 1);
+    assert_eq!(low[0].id, cookie);
+    assert_eq!(low[0].reorder_threshold, Some(3));
+    assert_eq!(app.find_goods("Muffin").reorder_threshold, None);
+
+    assert_err!(
+        handlers::get_low_stock_goods(customer_token, app.storage(), &app.sessions),
+        CafeHubError::Forbidden { .. }
+    );
+}
+
+#[test]
+fn purchase_crossing_threshold_alerts_admin_once() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 10);
+    let muffin = app.add_goods("Muffin", "2.00", 10);
+    set_threshold(&app, latte, 5).unwrap();
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "100.00");
+
+    // Still above the threshold, and the muffin has none.
+    app.buy(&token, vec![item(latte, 4)]).unwrap();
+    app.buy(&token, vec![item(muffin, 9)]).unwrap();
+    assert!(admin_inbox(&app).is_empty());
+
+    app.buy(&token, vec![item(latte, 2)]).unwrap();
+    let inbox = admin_inbox(&app);
+    assert_eq!(inbox.len(), 1);
+    assert_eq!(inbox[0].title.as_deref(), Some("Low stock alert"));
+    assert!(inbox[0].message_content.contains("Latte"));
+    assert!(inbox[0].message_content.contains("down to 4"));
+    assert_eq!(inbox[0].read_status, 0);
+
+    // Already low, so further sales do not repeat the alert.
+    app.buy(&token, vec![item(latte, 1)]).unwrap();
+    assert_eq!(admin_inbox(&app).len(), 1);
+
+    // Restocking re-arms it.
+    handlers::adjust_goods_stock(
+        app.admin_token(),
+        latte,
+        AdjustStockData {
+            delta: 5,
+            reason: StockMovementReason::Restock,
+            note: None,
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    app.buy(&token, vec![item(latte, 3)]).unwrap();
+    assert_eq!(admin_inbox(&app).len(), 2);
+    assert_eq!(app.find_goods("Latte").reorder_threshold, Some(5));
+}
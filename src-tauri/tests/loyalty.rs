@@ -0,0 +1,278 @@
+#[macro_use]
+mod common;
+
+use cafehub_lib::error::CafeHubError;
+use cafehub_lib::handlers;
+use cafehub_lib::models::*;
+use cafehub_lib::storage::POINTS_VALID_DAYS;
+use chrono::{Duration, Local};
+use common::{item, money, TestApp};
+
+fn free_item(goods_id: i32, quantity: i32) -> PurchaseItem {
+    PurchaseItem {
+        redeem_with_points: true,
+        ..item(goods_id, quantity)
+    }
+}
+
+fn with_points(items: Vec<PurchaseItem>, points_to_redeem: i64) -> PurchaseGoodsData {
+    PurchaseGoodsData {
+        items,
+        coupon_code: None,
+        points_to_redeem: Some(points_to_redeem),
+    }
+}
+
+fn set_points_price(
+    app: &TestApp,
+    goods_id: i32,
+    points_price: i32,
+) -> Result<String, CafeHubError> {
+    handlers::update_goods_info(
+        app.admin_token(),
+        goods_id,
+        UpdateGoodsData {
+            points_price: Some(points_price),
+            ..Default::default()
+        },
+        app.storage(),
+        &app.sessions,
+    )
+}
+
+fn points_history(app: &TestApp, token: &str) -> PointsHistory {
+    handlers::get_points_history(token.to_string(), app.storage(), &app.sessions).unwrap()
+}
+
+#[test]
+fn points_are_earned_redeemed_and_expire() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "4.00", 50);
+    let cookie = app.add_goods("Cookie", "1.50", 50);
+    set_points_price(&app, cookie, 30).unwrap();
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "200.00");
+    let now = Local::now().naive_local();
+
+    // The points from over a year ago run out before the second order is placed.
+    app.order_at(user_id, &[item(latte, 5)], now - Duration::days(400));
+    app.order_at(user_id, &[item(latte, 20)], now - Duration::days(10));
+    let history = points_history(&app, &token);
+    assert_eq!(history.points, 80);
+    let entries: Vec<(&str, i64)> = history
+        .entries
+        .iter()
+        .map(|entry| (entry.entry_type.as_str(), entry.points))
+        .collect();
+    assert_eq!(entries, [("earned", 80), ("expired", -20), ("earned", 20)]);
+    assert_eq!(history.entries[0].remaining, Some(80));
+    assert_eq!(history.entries[2].remaining, Some(0));
+
+    // Two free cookies for 60 points, and 20 points off the latte.
+    let order_id = app
+        .purchase(
+            &token,
+            with_points(vec![free_item(cookie, 2), item(latte, 1)], 20),
+        )
+        .unwrap();
+    let order = app.order(order_id);
+    assert_eq!(order.points_redeemed, 80);
+    assert_eq!(order.points_earned, 3);
+    assert_eq!(order.total_amount, money("3.80"));
+    assert_eq!(order.items[0].line_total, money("0.00"));
+    assert_eq!(order.items[0].points_discount, money("3.00"));
+    assert_eq!(order.items[1].line_total, money("3.80"));
+    assert_eq!(order.items[1].points_discount, money("0.20"));
+    assert_eq!(app.balance(user_id), money("96.20"));
+    let account = handlers::get_user_details(token.clone(), app.storage(), &app.sessions).unwrap();
+    assert_eq!(account.points, 3);
+
+    assert_err!(
+        app.buy(&token, vec![free_item(cookie, 1)]),
+        CafeHubError::InsufficientPoints {
+            required: 30,
+            available: 3,
+        }
+    );
+    assert_err!(
+        app.buy(&token, vec![free_item(latte, 1)]),
+        CafeHubError::Validation { .. }
+    );
+    // Worth more than the order costs.
+    assert_err!(
+        app.purchase(&token, with_points(vec![item(cookie, 1)], 500)),
+        CafeHubError::Validation { .. }
+    );
+    assert_eq!(app.find_goods("Cookie").stock, Some(48));
+
+    // Refunding the latte takes back the points earned on it and gives back the 20
+    // points taken off it, as a new batch.
+    let refund = |order_item_id, quantity| {
+        handlers::refund_order(
+            app.admin_token(),
+            RefundOrderData {
+                order_id,
+                reason: "Cold".to_string(),
+                items: vec![RefundItem {
+                    order_item_id,
+                    quantity,
+                }],
+            },
+            app.storage(),
+            &app.sessions,
+        )
+        .unwrap();
+    };
+    refund(order.items[1].id, 1);
+    assert_eq!(app.balance(user_id), money("100.00"));
+    let history = points_history(&app, &token);
+    assert_eq!(history.points, 20);
+    assert_eq!(history.entries[1].entry_type, "reversed");
+    assert_eq!(history.entries[1].points, -3);
+    assert_eq!(history.entries[0].entry_type, "restored");
+    assert_eq!(history.entries[0].points, 20);
+    assert_eq!(history.entries[0].remaining, Some(20));
+    assert!(history.entries[0].expires_at.unwrap() > now + Duration::days(364));
+
+    // A free cookie refunds no money but gives back the points it was bought with.
+    refund(order.items[0].id, 1);
+    assert_eq!(app.balance(user_id), money("100.00"));
+    let history = points_history(&app, &token);
+    assert_eq!(history.points, 50);
+    assert_eq!(history.entries[0].entry_type, "restored");
+    assert_eq!(history.entries[0].points, 30);
+    assert_eq!(app.find_goods("Cookie").stock, Some(49));
+}
+
+#[test]
+fn reading_points_leaves_expired_batches_to_the_next_order() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "4.00", 50);
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "100.00");
+    let now = Local::now().naive_local();
+    app.order_at(user_id, &[item(latte, 5)], now - Duration::days(400));
+
+    // The lapsed batch is not counted, but looking at the points writes nothing.
+    for _ in 0..2 {
+        let history = points_history(&app, &token);
+        assert_eq!(history.points, 0);
+        assert_eq!(history.entries.len(), 1);
+        assert_eq!(history.entries[0].remaining, Some(20));
+        let account =
+            handlers::get_user_details(token.clone(), app.storage(), &app.sessions).unwrap();
+        assert_eq!(account.points, 0);
+    }
+
+    app.buy(&token, vec![item(latte, 1)]).unwrap();
+    let history = points_history(&app, &token);
+    assert_eq!(history.points, 4);
+    let entries: Vec<(&str, i64)> = history
+        .entries
+        .iter()
+        .map(|entry| (entry.entry_type.as_str(), entry.points))
+        .collect();
+    assert_eq!(entries, [("earned", 4), ("expired", -20), ("earned", 20)]);
+}
+
+#[test]
+fn restored_points_are_valid_for_a_full_period_from_the_refund() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "4.00", 50);
+    let cookie = app.add_goods("Cookie", "1.50", 50);
+    set_points_price(&app, cookie, 30).unwrap();
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "100.00");
+    let today = Local::now().date_naive().and_hms_opt(12, 0, 0).unwrap();
+
+    // The 40 points run out 65 days from today; the cookie spends 30 of them.
+    app.order_at(user_id, &[item(latte, 10)], today - Duration::days(300));
+    let order_id = app.order_at(
+        user_id,
+        &[free_item(cookie, 1)],
+        today - Duration::days(200),
+    );
+    let order = app.order(order_id);
+
+    let refund_time = today - Duration::days(100);
+    app.storage()
+        .refund_order(
+            &RefundOrderData {
+                order_id,
+                reason: "Stale".to_string(),
+                items: vec![RefundItem {
+                    order_item_id: order.items[0].id,
+                    quantity: 1,
+                }],
+            },
+            app.admin_id,
+            refund_time,
+        )
+        .unwrap();
+
+    // The 30 points come back as a new batch, not with the spent batch's expiry.
+    let history = points_history(&app, &token);
+    assert_eq!(history.points, 40);
+    assert_eq!(history.entries[0].entry_type, "restored");
+    assert_eq!(history.entries[0].points, 30);
+    assert_eq!(history.entries[0].remaining, Some(30));
+    assert_eq!(
+        history.entries[0].expires_at,
+        Some(refund_time + Duration::days(POINTS_VALID_DAYS))
+    );
+
+    // Once the earned batch lapses, only the restored points are left.
+    assert_eq!(
+        app.storage()
+            .spendable_points(user_id, today + Duration::days(100))
+            .unwrap(),
+        30
+    );
+}
+
+#[test]
+fn points_prices_and_redemptions_are_validated() {
+    let app = TestApp::new();
+    let cookie = app.add_goods("Cookie", "1.50", 50);
+    let (_, token) = app.customer("alice");
+
+    assert_err!(
+        handlers::add_goods(
+            app.admin_token(),
+            AddGoodsData {
+                goods_name: "Muffin".to_string(),
+                category_id: None,
+                description: None,
+                price: money("2.00"),
+                stock: None,
+                reorder_threshold: None,
+                cost_price: None,
+                points_price: Some(0),
+            },
+            app.storage(),
+            &app.sessions,
+        ),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        set_points_price(&app, cookie, -1),
+        CafeHubError::Validation { .. }
+    );
+    set_points_price(&app, cookie, 150).unwrap();
+    assert_eq!(app.find_goods("Cookie").points_price, Some(150));
+    // A points price of 0 takes the goods off the rewards.
+    set_points_price(&app, cookie, 0).unwrap();
+    assert_eq!(app.find_goods("Cookie").points_price, None);
+
+    assert_err!(
+        app.purchase(&token, with_points(vec![item(cookie, 1)], -5)),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        handlers::get_points_history(app.admin_token(), app.storage(), &app.sessions),
+        CafeHubError::Forbidden { .. }
+    );
+    let history = points_history(&app, &token);
+    assert_eq!(history.points, 0);
+    assert!(history.entries.is_empty());
+}
@@ -0,0 +1,193 @@
+#[macro_use]
+mod common;
+
+use cafehub_lib::error::CafeHubError;
+use cafehub_lib::handlers;
+use cafehub_lib::models::*;
+use chrono::Local;
+use common::{item, money, TestApp};
+
+fn set_cost(app: &TestApp, goods_id: i32, cost_price: &str) -> Result<String, CafeHubError> {
+    handlers::update_goods_info(
+        app.admin_token(),
+        goods_id,
+        UpdateGoodsData {
+            cost_price: Some(money(cost_price)),
+            ..Default::default()
+        },
+        app.storage(),
+        &app.sessions,
+    )
+}
+
+#[test]
+fn cost_price_changes_are_recorded() {
+    let app = TestApp::new();
+    let cookie = app.add_goods("Cookie", "1.20", 0);
+    let (_, customer_token) = app.customer("alice");
+
+    assert_err!(
+        set_cost(&app, cookie, "-0.10"),
+        CafeHubError::Validation { .. }
+    );
+    set_cost(&app, cookie, "0.40").unwrap();
+    // An unchanged cost is not recorded again.
+    set_cost(&app, cookie, "0.40").unwrap();
+
+    let supplier_id = {
+        handlers::add_supplier(
+            app.admin_token(),
+            AddSupplierData {
+                supplier_name: "Bakery".to_string(),
+                contact: None,
+            },
+            app.storage(),
+            &app.sessions,
+        )
+        .unwrap();
+        handlers::get_suppliers(app.admin_token(), app.storage(), &app.sessions).unwrap()[0].id
+    };
+    let purchase_order_id = handlers::create_purchase_order(
+        app.admin_token(),
+        CreatePurchaseOrderData {
+            supplier_id,
+            items: vec![PurchaseOrderItemData {
+                goods_id: cookie,
+                quantity: 24,
+                unit_cost: money("0.45"),
+            }],
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    handlers::receive_purchase_order(
+        app.admin_token(),
+        purchase_order_id,
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+
+    let history =
+        handlers::get_goods_cost_history(app.admin_token(), cookie, app.storage(), &app.sessions)
+            .unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].cost_price, money("0.45"));
+    assert_eq!(
+        history[0].reference,
+        Some(format!("purchase_order:{}", purchase_order_id))
+    );
+    assert_eq!(history[1].cost_price, money("0.40"));
+    assert_eq!(history[1].reference, None);
+    assert_eq!(history[1].operator_name.as_deref(), Some("admin"));
+    assert_eq!(app.find_goods("Cookie").cost_price, Some(money("0.45")));
+
+    let seen_by_customer =
+        handlers::get_all_goods(customer_token.clone(), app.storage(), &app.sessions).unwrap();
+    assert_eq!(seen_by_customer[0].cost_price, None);
+    assert_err!(
+        handlers::get_goods_cost_history(customer_token, cookie, app.storage(), &app.sessions),
+        CafeHubError::Forbidden { .. }
+    );
+}
+
+#[test]
+fn margins_use_the_cost_at_the_time_of_sale() {
+    let app = TestApp::new();
+    let coffee = app.add_category("coffee");
+    handlers::add_goods(
+        app.admin_token(),
+        AddGoodsData {
+            goods_name: "Latte".to_string(),
+            category_id: Some(coffee),
+            description: None,
+            price: money("3.50"),
+            stock: Some(20),
+            reorder_threshold: None,
+            cost_price: None,
+            points_price: None,
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    let latte = app.find_goods("Latte").id;
+    let cookie = app.add_goods("Cookie", "1.20", 20);
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "100.00");
+
+    // Sold before the latte had a cost price.
+    app.buy(&token, vec![item(latte, 2)]).unwrap();
+    set_cost(&app, latte, "1.00").unwrap();
+    let order_id = app.buy(&token, vec![item(latte, 3)]).unwrap();
+    set_cost(&app, latte, "2.00").unwrap();
+    app.buy(&token, vec![item(latte, 1)]).unwrap();
+    set_cost(&app, cookie, "0.40").unwrap();
+    app.buy(&token, vec![item(cookie, 5)]).unwrap();
+
+    let order = app.order(order_id);
+    handlers::refund_order(
+        app.admin_token(),
+        RefundOrderData {
+            order_id,
+            reason: "Spilled".to_string(),
+            items: vec![RefundItem {
+                order_item_id: order.items[0].id,
+                quantity: 1,
+            }],
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+
+    let by_goods =
+        handlers::get_goods_margins(app.admin_token(), None, app.storage(), &app.sessions).unwrap();
+    assert_eq!(by_goods.len(), 2);
+    assert_eq!(by_goods[0].label, "Latte");
+    assert_eq!(by_goods[0].quantity_sold, 5);
+    assert_eq!(by_goods[0].revenue, money("17.50"));
+    assert_eq!(by_goods[0].cost, money("4.00"));
+    assert_eq!(by_goods[0].gross_margin, money("13.50"));
+    assert_eq!(by_goods[0].uncosted_quantity, 2);
+    assert_eq!(by_goods[1].label, "Cookie");
+    assert_eq!(by_goods[1].gross_margin, money("4.00"));
+
+    let by_category =
+        handlers::get_category_margins(app.admin_token(), None, app.storage(), &app.sessions)
+            .unwrap();
+    let categories: Vec<&str> = by_category.iter().map(|m| m.label.as_str()).collect();
+    assert_eq!(categories, ["coffee", "Uncategorized"]);
+
+    let current_month = Local::now().format("%Y-%m").to_string();
+    let by_month =
+        handlers::get_monthly_margins(app.admin_token(), app.storage(), &app.sessions).unwrap();
+    assert_eq!(by_month.len(), 1);
+    assert_eq!(by_month[0].label, current_month);
+    assert_eq!(by_month[0].quantity_sold, 10);
+    assert_eq!(by_month[0].revenue, money("23.50"));
+    assert_eq!(by_month[0].gross_margin, money("17.50"));
+
+    assert!(handlers::get_goods_margins(
+        app.admin_token(),
+        Some("2000-01".to_string()),
+        app.storage(),
+        &app.sessions
+    )
+    .unwrap()
+    .is_empty());
+    assert_err!(
+        handlers::get_goods_margins(
+            app.admin_token(),
+            Some("2024-13".to_string()),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        handlers::get_monthly_margins(token, app.storage(), &app.sessions),
+        CafeHubError::Forbidden { .. }
+    );
+}
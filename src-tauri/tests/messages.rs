@@ -0,0 +1,160 @@
+#[macro_use]
+mod common;
+
+use cafehub_lib::error::CafeHubError;
+use cafehub_lib::handlers;
+use cafehub_lib::models::*;
+use common::TestApp;
+
+fn to_admin(content: &str) -> CusSendMessageData {
+    CusSendMessageData {
+        title: Some("Hello".to_string()),
+        message_content: content.to_string(),
+    }
+}
+
+#[test]
+fn customer_messages_go_to_the_administrator() {
+    let app = TestApp::new();
+    let (user_id, token) = app.customer("alice");
+
+    assert_err!(
+        handlers::customer_send_message(token.clone(), to_admin(""), app.storage(), &app.sessions),
+        CafeHubError::Validation { .. }
+    );
+    handlers::customer_send_message(
+        token.clone(),
+        to_admin("The wifi is down"),
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+
+    let sent = handlers::get_sent_messages(token, app.storage(), &app.sessions).unwrap();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].sender_id, user_id);
+    assert_eq!(sent[0].receiver_id, app.admin_id);
+    assert_eq!(sent[0].receiver_username, common::ADMIN_USERNAME);
+
+    let received =
+        handlers::get_recived_messages(app.admin_token(), app.storage(), &app.sessions).unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].sender_username, "alice");
+    assert_eq!(received[0].message_content, "The wifi is down");
+    assert_eq!(received[0].read_status, 0);
+}
+
+#[test]
+fn admin_messages_validate_receiver() {
+    let app = TestApp::new();
+    let (user_id, customer_token) = app.customer("alice");
+    let token = app.admin_token();
+
+    let message = |receiver_id: i64| SendMessageData {
+        receiver_id,
+        title: None,
+        message_content: "Your order is ready".to_string(),
+    };
+
+    assert_err!(
+        handlers::admin_send_message(
+            customer_token.clone(),
+            message(user_id),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Forbidden { .. }
+    );
+    assert_err!(
+        handlers::admin_send_message(
+            token.clone(),
+            message(app.admin_id),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        handlers::admin_send_message(
+            token.clone(),
+            message(user_id + 100),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::NotFound {
+            entity: "Receiver",
+            ..
+        }
+    );
+
+    handlers::admin_send_message(token, message(user_id), app.storage(), &app.sessions).unwrap();
+    let received =
+        handlers::get_recived_messages(customer_token, app.storage(), &app.sessions).unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].sender_id, app.admin_id);
+    assert_eq!(received[0].title, None);
+}
+
+#[test]
+fn only_the_receiver_can_mark_a_message_as_read() {
+    let app = TestApp::new();
+    let (_, alice_token) = app.customer("alice");
+    let (_, bob_token) = app.customer("bob");
+    handlers::customer_send_message(
+        alice_token.clone(),
+        to_admin("Lost my scarf"),
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    let admin_token = app.admin_token();
+    let message_id =
+        handlers::get_recived_messages(admin_token.clone(), app.storage(), &app.sessions).unwrap()
+            [0]
+        .id;
+
+    // Neither the sender nor an unrelated customer may mark it.
+    for token in [alice_token, bob_token] {
+        assert_err!(
+            handlers::mark_message_as_read(
+                MarkReadData { message_id },
+                token,
+                app.storage(),
+                &app.sessions
+            ),
+            CafeHubError::NotMessageReceiver { .. }
+        );
+    }
+    assert_err!(
+        handlers::mark_message_as_read(
+            MarkReadData {
+                message_id: message_id + 100
+            },
+            admin_token.clone(),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::NotFound { .. }
+    );
+
+    let first = handlers::mark_message_as_read(
+        MarkReadData { message_id },
+        admin_token.clone(),
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    assert!(first.contains("marked as read"));
+    let second = handlers::mark_message_as_read(
+        MarkReadData { message_id },
+        admin_token.clone(),
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    assert!(second.contains("already read"));
+
+    let received =
+        handlers::get_recived_messages(admin_token, app.storage(), &app.sessions).unwrap();
+    assert_eq!(received[0].read_status, 1);
+}
@@ -0,0 +1,363 @@
+#[macro_use]
+mod common;
+
+use cafehub_lib::error::CafeHubError;
+use cafehub_lib::handlers;
+use cafehub_lib::models::*;
+use common::{item, money, TestApp};
+
+// A latte with a required size, an optional milk choice and any number of add-ons.
+struct Latte {
+    goods_id: i32,
+    small: i64,
+    large: i64,
+    oat_milk: i64,
+    soy_milk: i64,
+    vanilla: i64,
+    extra_shot: i64,
+}
+
+fn add_group(
+    app: &TestApp,
+    goods_id: i32,
+    group_name: &str,
+    is_required: bool,
+    allow_multiple: bool,
+    options: &[(&str, &str)],
+) -> Result<String, CafeHubError> {
+    handlers::add_modifier_group(
+        app.admin_token(),
+        AddModifierGroupData {
+            goods_id,
+            group_name: group_name.to_string(),
+            is_required,
+            allow_multiple,
+            options: options
+                .iter()
+                .map(|&(option_name, price_delta)| AddModifierOptionData {
+                    option_name: option_name.to_string(),
+                    price_delta: money(price_delta),
+                })
+                .collect(),
+        },
+        app.storage(),
+        &app.sessions,
+    )
+}
+
+fn option_id(groups: &[ModifierGroup], option_name: &str) -> i64 {
+    groups
+        .iter()
+        .flat_map(|group| &group.options)
+        .find(|option| option.option_name == option_name)
+        .expect("option not found")
+        .id
+}
+
+fn setup_latte(app: &TestApp) -> Latte {
+    let goods_id = app.add_goods("Latte", "3.00", 10);
+    add_group(
+        app,
+        goods_id,
+        "Size",
+        true,
+        false,
+        &[("Small", "-0.50"), ("Large", "0.80")],
+    )
+    .unwrap();
+    add_group(
+        app,
+        goods_id,
+        "Milk",
+        false,
+        false,
+        &[("Oat", "0.40"), ("Soy", "0.30")],
+    )
+    .unwrap();
+    add_group(
+        app,
+        goods_id,
+        "Add-ons",
+        false,
+        true,
+        &[("Vanilla", "0.50"), ("Extra shot", "0.70")],
+    )
+    .unwrap();
+
+    let groups = app.storage().list_modifier_groups(goods_id).unwrap();
+    Latte {
+        goods_id,
+        small: option_id(&groups, "Small"),
+        large: option_id(&groups, "Large"),
+        oat_milk: option_id(&groups, "Oat"),
+        soy_milk: option_id(&groups, "Soy"),
+        vanilla: option_id(&groups, "Vanilla"),
+        extra_shot: option_id(&groups, "Extra shot"),
+    }
+}
+
+fn with_modifiers(goods_id: i32, quantity: i32, modifier_ids: &[i64]) -> PurchaseItem {
+    PurchaseItem {
+        modifier_ids: modifier_ids.to_vec(),
+        ..item(goods_id, quantity)
+    }
+}
+
+#[test]
+fn modifier_groups_are_listed_with_their_options() {
+    let app = TestApp::new();
+    let latte = setup_latte(&app);
+    let (_, token) = app.customer("alice");
+
+    let groups =
+        handlers::get_goods_modifiers(token, latte.goods_id, app.storage(), &app.sessions).unwrap();
+    assert_eq!(groups.len(), 3);
+    assert_eq!(groups[0].group_name, "Size");
+    assert!(groups[0].is_required);
+    assert!(!groups[0].allow_multiple);
+    assert_eq!(groups[0].options.len(), 2);
+    assert_eq!(groups[0].options[0].option_name, "Small");
+    assert_eq!(groups[0].options[0].price_delta, money("-0.50"));
+    assert!(groups[2].allow_multiple);
+}
+
+#[test]
+fn adding_modifier_groups_is_validated() {
+    let app = TestApp::new();
+    let goods_id = app.add_goods("Latte", "3.00", 10);
+    let (_, customer_token) = app.customer("alice");
+
+    assert_err!(
+        add_group(&app, goods_id, "", true, false, &[("Small", "0")]),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        add_group(&app, goods_id, "Size", true, false, &[]),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        add_group(
+            &app,
+            goods_id,
+            "Size",
+            true,
+            false,
+            &[("Small", "0"), ("Small", "1.00")]
+        ),
+        CafeHubError::Validation { .. }
+    );
+    // Names are trimmed before they are compared and must fit their column.
+    assert_err!(
+        add_group(
+            &app,
+            goods_id,
+            "Size",
+            true,
+            false,
+            &[("Small", "0"), (" Small ", "1.00")]
+        ),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        add_group(
+            &app,
+            goods_id,
+            &"S".repeat(21),
+            true,
+            false,
+            &[("Small", "0")]
+        ),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        add_group(
+            &app,
+            goods_id,
+            "Size",
+            true,
+            false,
+            &[(&"S".repeat(21), "0")]
+        ),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        add_group(&app, goods_id + 100, "Size", true, false, &[("Small", "0")]),
+        CafeHubError::NotFound { .. }
+    );
+    assert_err!(
+        handlers::add_modifier_group(
+            customer_token,
+            AddModifierGroupData {
+                goods_id,
+                group_name: "Size".to_string(),
+                is_required: true,
+                allow_multiple: false,
+                options: Vec::new(),
+            },
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Forbidden { .. }
+    );
+    assert!(app
+        .storage()
+        .list_modifier_groups(goods_id)
+        .unwrap()
+        .is_empty());
+
+    add_group(&app, goods_id, " Size ", true, false, &[("  Small", "0")]).unwrap();
+    let groups = app.storage().list_modifier_groups(goods_id).unwrap();
+    assert_eq!(groups[0].group_name, "Size");
+    assert_eq!(groups[0].options[0].option_name, "Small");
+}
+
+#[test]
+fn purchase_prices_and_records_modifiers() {
+    let app = TestApp::new();
+    let latte = setup_latte(&app);
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "20.00");
+
+    // 3.00 + 0.80 (large) + 0.40 (oat) + 0.50 (vanilla) + 0.70 (extra shot)
+    let order_id = app
+        .buy(
+            &token,
+            vec![with_modifiers(
+                latte.goods_id,
+                2,
+                &[latte.large, latte.oat_milk, latte.vanilla, latte.extra_shot],
+            )],
+        )
+        .unwrap();
+
+    assert_eq!(app.balance(user_id), money("9.20"));
+    let order = handlers::get_order_details(token, order_id, app.storage(), &app.sessions).unwrap();
+    assert_eq!(order.total_amount, money("10.80"));
+    let item = &order.items[0];
+    assert_eq!(item.unit_price, money("5.40"));
+    assert_eq!(item.line_total, money("10.80"));
+    let recorded: Vec<_> = item
+        .modifiers
+        .iter()
+        .map(|modifier| {
+            (
+                modifier.group_name.as_str(),
+                modifier.option_name.as_str(),
+                modifier.price_delta,
+            )
+        })
+        .collect();
+    assert_eq!(
+        recorded,
+        [
+            ("Size", "Large", money("0.80")),
+            ("Milk", "Oat", money("0.40")),
+            ("Add-ons", "Vanilla", money("0.50")),
+            ("Add-ons", "Extra shot", money("0.70")),
+        ]
+    );
+}
+
+#[test]
+fn purchase_rejects_invalid_modifier_choices() {
+    let app = TestApp::new();
+    let latte = setup_latte(&app);
+    let tea = app.add_goods("Tea", "2.00", 10);
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "20.00");
+
+    let attempts: [(i32, Vec<i64>); 5] = [
+        // The size is required.
+        (latte.goods_id, vec![latte.oat_milk]),
+        // Only one size and one milk.
+        (latte.goods_id, vec![latte.small, latte.large]),
+        (
+            latte.goods_id,
+            vec![latte.small, latte.oat_milk, latte.soy_milk],
+        ),
+        // Options must belong to the goods and may not repeat.
+        (tea, vec![latte.vanilla]),
+        (
+            latte.goods_id,
+            vec![latte.small, latte.vanilla, latte.vanilla],
+        ),
+    ];
+    for (goods_id, modifier_ids) in attempts {
+        assert_err!(
+            app.buy(&token, vec![with_modifiers(goods_id, 1, &modifier_ids)]),
+            CafeHubError::Validation { .. }
+        );
+    }
+
+    assert_eq!(app.balance(user_id), money("20.00"));
+    assert_eq!(app.find_goods("Latte").stock, Some(10));
+}
+
+#[test]
+fn stock_covers_all_lines_of_the_same_goods() {
+    let app = TestApp::new();
+    let latte = setup_latte(&app);
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "100.00");
+
+    // Each line fits the stock of 10 on its own, but not together.
+    let items = vec![
+        with_modifiers(latte.goods_id, 6, &[latte.small]),
+        with_modifiers(latte.goods_id, 6, &[latte.large]),
+    ];
+    assert_err!(
+        app.buy(&token, items),
+        CafeHubError::InsufficientStock {
+            requested: 12,
+            available: 10,
+            ..
+        }
+    );
+    assert_eq!(app.find_goods("Latte").stock, Some(10));
+}
+
+#[test]
+fn deleted_modifiers_stay_on_past_orders() {
+    let app = TestApp::new();
+    let latte = setup_latte(&app);
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "20.00");
+    let order_id = app
+        .buy(
+            &token,
+            vec![with_modifiers(
+                latte.goods_id,
+                1,
+                &[latte.small, latte.soy_milk],
+            )],
+        )
+        .unwrap();
+
+    let groups = app.storage().list_modifier_groups(latte.goods_id).unwrap();
+    let admin_token = app.admin_token();
+    handlers::delete_modifier_group(
+        admin_token.clone(),
+        groups[1].id,
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    assert_err!(
+        handlers::delete_modifier_group(admin_token, groups[1].id, app.storage(), &app.sessions),
+        CafeHubError::NotFound { .. }
+    );
+    assert_eq!(
+        app.storage()
+            .list_modifier_groups(latte.goods_id)
+            .unwrap()
+            .len(),
+        2
+    );
+
+    let order = handlers::get_order_details(token, order_id, app.storage(), &app.sessions).unwrap();
+    let milk = &order.items[0].modifiers[1];
+    assert_eq!(milk.option_id, None);
+    assert_eq!(milk.option_name, "Soy");
+    assert_eq!(milk.price_delta, money("0.30"));
+    assert_eq!(order.items[0].unit_price, money("2.80"));
+}
@@ -0,0 +1,500 @@
+#[macro_use]
+mod common;
+
+use cafehub_lib::error::CafeHubError;
+use cafehub_lib::handlers;
+use cafehub_lib::models::*;
+use common::{money, TestApp};
+
+fn purchase(items: &[(i32, i32)]) -> PurchaseGoodsData {
+    PurchaseGoodsData {
+        items: items
+            .iter()
+            .map(|&(goods_id, quantity)| PurchaseItem {
+                goods_id,
+                quantity,
+                modifier_ids: Vec::new(),
+                redeem_with_points: false,
+            })
+            .collect(),
+        coupon_code: None,
+        points_to_redeem: None,
+    }
+}
+
+#[test]
+fn add_and_update_goods() {
+    let app = TestApp::new();
+    let token = app.admin_token();
+    let coffee = app.add_category("coffee");
+
+    let goods = |goods_name: &str, price: &str| AddGoodsData {
+        goods_name: goods_name.to_string(),
+        category_id: Some(coffee),
+        description: None,
+        price: money(price),
+        stock: Some(10),
+        reorder_threshold: None,
+        cost_price: None,
+        points_price: None,
+    };
+    assert_err!(
+        handlers::add_goods(
+            token.clone(),
+            goods("", "3.00"),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        handlers::add_goods(
+            token.clone(),
+            goods("Latte", "0"),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Validation { .. }
+    );
+    handlers::add_goods(
+        token.clone(),
+        goods("Latte", "3.50"),
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+
+    let (_, customer_token) = app.customer("alice");
+    let all_goods = handlers::get_all_goods(customer_token, app.storage(), &app.sessions).unwrap();
+    assert_eq!(all_goods.len(), 1);
+    let latte = &all_goods[0];
+    assert_eq!(latte.goods_name, "Latte");
+    assert_eq!(latte.category_id, Some(coffee));
+    assert_eq!(latte.category.as_deref(), Some("coffee"));
+    assert_eq!(latte.price, money("3.50"));
+    assert_eq!(latte.stock, Some(10));
+
+    handlers::update_goods_info(
+        token.clone(),
+        latte.id,
+        UpdateGoodsData {
+            price: Some(money("3.80")),
+            ..Default::default()
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    let latte = app.find_goods("Latte");
+    assert_eq!(latte.price, money("3.80"));
+    assert_eq!(latte.stock, Some(10));
+
+    assert_err!(
+        handlers::update_goods_info(
+            token.clone(),
+            latte.id,
+            UpdateGoodsData {
+                price: Some(money("-1.00")),
+                ..Default::default()
+            },
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        handlers::update_goods_info(
+            token,
+            latte.id + 100,
+            UpdateGoodsData {
+                price: Some(money("1.00")),
+                ..Default::default()
+            },
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::NotFound { .. }
+    );
+}
+
+#[test]
+fn goods_details_can_be_edited() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 10);
+    app.add_goods("Mocha", "3.80", 10);
+    let coffee = app.add_category("coffee");
+    let update = |data: UpdateGoodsData| {
+        handlers::update_goods_info(app.admin_token(), latte, data, app.storage(), &app.sessions)
+    };
+
+    update(UpdateGoodsData {
+        goods_name: Some(" Caffe Latte ".to_string()),
+        category_id: Some(coffee),
+        description: Some("Espresso with steamed milk".to_string()),
+        ..Default::default()
+    })
+    .unwrap();
+    let edited = app.find_goods("Caffe Latte");
+    assert_eq!(edited.id, latte);
+    assert_eq!(edited.category_id, Some(coffee));
+    assert_eq!(
+        edited.description.as_deref(),
+        Some("Espresso with steamed milk")
+    );
+
+    update(UpdateGoodsData {
+        clear_category: true,
+        ..Default::default()
+    })
+    .unwrap();
+    let edited = app.find_goods("Caffe Latte");
+    assert_eq!(edited.category_id, None);
+    assert!(edited.description.is_some());
+
+    assert_err!(
+        update(UpdateGoodsData {
+            goods_name: Some("Mocha".to_string()),
+            ..Default::default()
+        }),
+        CafeHubError::DuplicateGoodsName { .. }
+    );
+    for goods_name in ["  ", "A name that is far too long"] {
+        assert_err!(
+            update(UpdateGoodsData {
+                goods_name: Some(goods_name.to_string()),
+                ..Default::default()
+            }),
+            CafeHubError::Validation { .. }
+        );
+    }
+    assert_err!(
+        handlers::add_goods(
+            app.admin_token(),
+            AddGoodsData {
+                goods_name: "Mocha".to_string(),
+                category_id: None,
+                description: None,
+                price: money("4.00"),
+                stock: None,
+                reorder_threshold: None,
+                cost_price: None,
+                points_price: None,
+            },
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::DuplicateGoodsName { .. }
+    );
+    assert_eq!(app.find_goods("Caffe Latte").price, money("3.50"));
+}
+
+#[test]
+fn unavailable_goods_cannot_be_bought() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 10);
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "20.00");
+    let set_available = |available: bool| {
+        handlers::update_goods_info(
+            app.admin_token(),
+            latte,
+            UpdateGoodsData {
+                available: Some(available),
+                ..Default::default()
+            },
+            app.storage(),
+            &app.sessions,
+        )
+        .unwrap();
+    };
+
+    set_available(false);
+    let seen_by_customer =
+        handlers::get_all_goods(token.clone(), app.storage(), &app.sessions).unwrap();
+    assert!(!seen_by_customer[0].available);
+    assert_eq!(seen_by_customer[0].stock, Some(10));
+    assert_err!(
+        handlers::purchase_goods(
+            token.clone(),
+            purchase(&[(latte, 1)]),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::GoodsUnavailable { .. }
+    );
+    assert_eq!(app.balance(user_id), money("20.00"));
+
+    set_available(true);
+    handlers::purchase_goods(token, purchase(&[(latte, 1)]), app.storage(), &app.sessions).unwrap();
+    assert_eq!(app.find_goods("Latte").stock, Some(9));
+}
+
+#[test]
+fn purchase_debits_stock_and_balance() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 10);
+    let cookie = app.add_goods("Cookie", "1.20", 5);
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "20.00");
+
+    let order_id = handlers::purchase_goods(
+        token.clone(),
+        purchase(&[(latte, 2), (cookie, 3)]),
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+
+    assert_eq!(app.balance(user_id), money("9.40"));
+    assert_eq!(app.find_goods("Latte").stock, Some(8));
+    assert_eq!(app.find_goods("Cookie").stock, Some(2));
+
+    let history = handlers::get_order_history(token.clone(), app.storage(), &app.sessions).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].id, order_id);
+    assert_eq!(history[0].total_amount, money("10.60"));
+    assert_eq!(history[0].item_count, 5);
+
+    let order = handlers::get_order_details(token, order_id, app.storage(), &app.sessions).unwrap();
+    assert_eq!(order.user_id, user_id);
+    assert_eq!(order.items.len(), 2);
+    assert_eq!(order.items[0].unit_price, money("3.50"));
+    assert_eq!(order.items[0].line_total, money("7.00"));
+
+    // Staff can see any order, other customers cannot.
+    handlers::get_order_details(app.admin_token(), order_id, app.storage(), &app.sessions).unwrap();
+    let (_, other_token) = app.customer("bob");
+    assert_err!(
+        handlers::get_order_details(other_token, order_id, app.storage(), &app.sessions),
+        CafeHubError::NotFound { .. }
+    );
+}
+
+#[test]
+fn purchase_with_insufficient_stock_changes_nothing() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 10);
+    let cookie = app.add_goods("Cookie", "1.20", 2);
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "50.00");
+
+    // The first line is fine; the second must roll the whole order back.
+    assert_err!(
+        handlers::purchase_goods(
+            token.clone(),
+            purchase(&[(latte, 2), (cookie, 3)]),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::InsufficientStock {
+            requested: 3,
+            available: 2,
+            ..
+        }
+    );
+
+    assert_eq!(app.balance(user_id), money("50.00"));
+    assert_eq!(app.find_goods("Latte").stock, Some(10));
+    assert_eq!(app.find_goods("Cookie").stock, Some(2));
+    assert!(
+        handlers::get_order_history(token, app.storage(), &app.sessions)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn purchase_with_insufficient_balance_changes_nothing() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 10);
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "5.00");
+
+    assert_err!(
+        handlers::purchase_goods(
+            token.clone(),
+            purchase(&[(latte, 2)]),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::InsufficientBalance { .. }
+    );
+
+    assert_eq!(app.balance(user_id), money("5.00"));
+    assert_eq!(app.find_goods("Latte").stock, Some(10));
+    assert!(
+        handlers::get_order_history(token, app.storage(), &app.sessions)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn purchase_rejects_invalid_items() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 10);
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "10.00");
+
+    assert_err!(
+        handlers::purchase_goods(token.clone(), purchase(&[]), app.storage(), &app.sessions),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        handlers::purchase_goods(
+            token.clone(),
+            purchase(&[(latte, 0)]),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        handlers::purchase_goods(
+            token,
+            purchase(&[(latte + 100, 1)]),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::NotFound { .. }
+    );
+    assert_eq!(app.balance(user_id), money("10.00"));
+}
+
+#[test]
+fn refunds_restore_stock_and_balance() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 10);
+    let cookie = app.add_goods("Cookie", "1.20", 5);
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "20.00");
+    let order_id = handlers::purchase_goods(
+        token.clone(),
+        purchase(&[(latte, 2), (cookie, 3)]),
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    let order =
+        handlers::get_order_details(token.clone(), order_id, app.storage(), &app.sessions).unwrap();
+    let admin_token = app.admin_token();
+
+    let refund = |items: Vec<RefundItem>| RefundOrderData {
+        order_id,
+        reason: "Spilled".to_string(),
+        items,
+    };
+
+    assert_err!(
+        handlers::refund_order(token, refund(Vec::new()), app.storage(), &app.sessions),
+        CafeHubError::Forbidden { .. }
+    );
+    assert_err!(
+        handlers::refund_order(
+            admin_token.clone(),
+            RefundOrderData {
+                reason: " ".to_string(),
+                ..refund(Vec::new())
+            },
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Validation { .. }
+    );
+
+    // Refund one cookie, then everything that is left.
+    handlers::refund_order(
+        admin_token.clone(),
+        refund(vec![RefundItem {
+            order_item_id: order.items[1].id,
+            quantity: 1,
+        }]),
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    assert_eq!(app.balance(user_id), money("10.60"));
+    assert_eq!(app.find_goods("Cookie").stock, Some(3));
+
+    assert_err!(
+        handlers::refund_order(
+            admin_token.clone(),
+            refund(vec![RefundItem {
+                order_item_id: order.items[1].id,
+                quantity: 3,
+            }]),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Conflict { .. }
+    );
+
+    handlers::refund_order(
+        admin_token.clone(),
+        refund(Vec::new()),
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    assert_eq!(app.balance(user_id), money("20.00"));
+    assert_eq!(app.find_goods("Latte").stock, Some(10));
+    assert_eq!(app.find_goods("Cookie").stock, Some(5));
+
+    assert_err!(
+        handlers::refund_order(
+            admin_token,
+            refund(Vec::new()),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Conflict { .. }
+    );
+}
+
+#[test]
+fn consumption_reports() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "3.50", 10);
+    let cookie = app.add_goods("Cookie", "1.20", 5);
+    let (alice_id, alice_token) = app.customer("alice");
+    let (bob_id, bob_token) = app.customer("bob");
+    app.recharge(alice_id, "20.00");
+    app.recharge(bob_id, "20.00");
+
+    handlers::purchase_goods(
+        alice_token.clone(),
+        purchase(&[(latte, 2)]),
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    handlers::purchase_goods(
+        bob_token,
+        purchase(&[(latte, 1), (cookie, 1)]),
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+
+    let mine =
+        handlers::get_user_monthly_consumption(alice_token, app.storage(), &app.sessions).unwrap();
+    assert_eq!(mine.len(), 1);
+    assert_eq!(mine[0].total_amount, money("7.00"));
+
+    let token = app.admin_token();
+    let everyone =
+        handlers::get_monthly_consumption_summary(token.clone(), app.storage(), &app.sessions)
+            .unwrap();
+    assert_eq!(everyone.len(), 1);
+    assert_eq!(everyone[0].total_amount, money("11.70"));
+
+    let mut share =
+        handlers::get_goods_consumption_share_current_month(token, app.storage(), &app.sessions)
+            .unwrap();
+    share.sort_by(|a, b| a.goods_name.cmp(&b.goods_name));
+    assert_eq!(share.len(), 2);
+    assert_eq!(share[0].goods_name, "Cookie");
+    assert_eq!(share[0].amount, money("1.20"));
+    assert_eq!(share[1].goods_name, "Latte");
+    assert_eq!(share[1].amount, money("10.50"));
+}
@@ -0,0 +1,332 @@
+#[macro_use]
+mod common;
+
+use cafehub_lib::error::CafeHubError;
+use cafehub_lib::handlers;
+use cafehub_lib::models::*;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use common::{item, money, TestApp};
+
+fn at(date: &str, time: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").unwrap()
+}
+
+fn time(time: &str) -> Option<NaiveTime> {
+    Some(NaiveTime::parse_from_str(time, "%H:%M").unwrap())
+}
+
+fn date(date: &str) -> Option<NaiveDate> {
+    Some(NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap())
+}
+
+fn promotion(name: &str, discount_type: DiscountType, discount_value: &str) -> PromotionData {
+    PromotionData {
+        promotion_name: name.to_string(),
+        discount_type,
+        discount_value: money(discount_value),
+        goods_id: None,
+        category_id: None,
+        weekdays: Vec::new(),
+        start_time: None,
+        end_time: None,
+        start_date: None,
+        end_date: None,
+        paused: false,
+    }
+}
+
+fn add_promotion(app: &TestApp, data: PromotionData) -> Result<String, CafeHubError> {
+    handlers::add_promotion(app.admin_token(), data, app.storage(), &app.sessions)
+}
+
+fn promotion_id(app: &TestApp, name: &str) -> i32 {
+    handlers::get_promotions(app.admin_token(), app.storage(), &app.sessions)
+        .unwrap()
+        .into_iter()
+        .find(|promotion| promotion.promotion_name == name)
+        .expect("promotion not found")
+        .id
+}
+
+#[test]
+fn best_running_promotion_is_applied_and_recorded() {
+    let app = TestApp::new();
+    let coffee = app.add_category("coffee");
+    let latte = app.add_goods("Latte", "4.00", 50);
+    handlers::update_goods_info(
+        app.admin_token(),
+        latte,
+        UpdateGoodsData {
+            category_id: Some(coffee),
+            ..Default::default()
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    let cookie = app.add_goods("Cookie", "2.00", 50);
+    app.add_goods("Muffin", "2.50", 50);
+    let (user_id, _) = app.customer("alice");
+    app.recharge(user_id, "100.00");
+
+    add_promotion(
+        &app,
+        PromotionData {
+            category_id: Some(coffee),
+            weekdays: vec![1, 2, 3, 4, 5],
+            start_time: time("15:00"),
+            end_time: time("17:00"),
+            ..promotion("Happy hour", DiscountType::Percent, "25")
+        },
+    )
+    .unwrap();
+    add_promotion(
+        &app,
+        PromotionData {
+            goods_id: Some(latte),
+            ..promotion("Latte lovers", DiscountType::Percent, "10")
+        },
+    )
+    .unwrap();
+    add_promotion(
+        &app,
+        PromotionData {
+            goods_id: Some(cookie),
+            start_date: date("2026-03-01"),
+            end_date: date("2026-03-31"),
+            ..promotion("March cookies", DiscountType::Fixed, "0.50")
+        },
+    )
+    .unwrap();
+    add_promotion(
+        &app,
+        PromotionData {
+            goods_id: Some(cookie),
+            paused: true,
+            ..promotion("Free cookies", DiscountType::Fixed, "5.00")
+        },
+    )
+    .unwrap();
+
+    // Wednesday afternoon: happy hour beats the latte's own promotion.
+    let first_order = app.order(app.order_at(
+        user_id,
+        &[item(latte, 2), item(cookie, 3)],
+        at("2026-03-04", "16:00"),
+    ));
+    assert_eq!(
+        first_order.items[0].promotion_name.as_deref(),
+        Some("Happy hour")
+    );
+    assert_eq!(first_order.items[0].unit_price, money("4.00"));
+    assert_eq!(first_order.items[0].discount, money("2.00"));
+    assert_eq!(first_order.items[0].line_total, money("6.00"));
+    assert_eq!(
+        first_order.items[1].promotion_name.as_deref(),
+        Some("March cookies")
+    );
+    assert_eq!(first_order.items[1].line_total, money("4.50"));
+    assert_eq!(first_order.total_amount, money("10.50"));
+    assert_eq!(app.balance(user_id), money("89.50"));
+
+    // The window ends at 17:00 and happy hour does not run at weekends.
+    for time in [at("2026-03-04", "17:00"), at("2026-03-07", "16:00")] {
+        let order = app.order(app.order_at(user_id, &[item(latte, 1)], time));
+        assert_eq!(
+            order.items[0].promotion_name.as_deref(),
+            Some("Latte lovers")
+        );
+        assert_eq!(order.items[0].line_total, money("3.60"));
+    }
+
+    let order = app.order(app.order_at(user_id, &[item(cookie, 1)], at("2026-04-01", "16:00")));
+    assert_eq!(order.items[0].promotion_name, None);
+    assert_eq!(order.items[0].discount, money("0.00"));
+    assert_eq!(order.total_amount, money("2.00"));
+
+    // Past sales keep the name of a deleted promotion.
+    let happy_hour = promotion_id(&app, "Happy hour");
+    handlers::delete_promotion(app.admin_token(), happy_hour, app.storage(), &app.sessions)
+        .unwrap();
+    let first_order = app.order(first_order.id);
+    assert_eq!(
+        first_order.items[0].promotion_name.as_deref(),
+        Some("Happy hour")
+    );
+    let order = app.order(app.order_at(user_id, &[item(latte, 1)], at("2026-03-04", "16:00")));
+    assert_eq!(
+        order.items[0].promotion_name.as_deref(),
+        Some("Latte lovers")
+    );
+}
+
+#[test]
+fn order_discounts_stay_within_each_line_after_promotions() {
+    let app = TestApp::new();
+    let latte = app.add_goods("Latte", "4.00", 50);
+    let cookie = app.add_goods("Cookie", "1.00", 50);
+    let muffin = app.add_goods("Muffin", "1.00", 50);
+    let (user_id, _) = app.customer("alice");
+    app.recharge(user_id, "100.00");
+    add_promotion(
+        &app,
+        PromotionData {
+            goods_id: Some(latte),
+            ..promotion("Free latte", DiscountType::Fixed, "4.00")
+        },
+    )
+    .unwrap();
+    handlers::add_coupon(
+        app.admin_token(),
+        AddCouponData {
+            code: "NICKEL".to_string(),
+            discount_type: DiscountType::Fixed,
+            discount_value: money("0.05"),
+            min_spend: None,
+            expires_at: None,
+            max_redemptions: None,
+            max_per_customer: None,
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+
+    // The free latte takes none of the coupon, and the cent that does not split evenly
+    // goes to one line only.
+    let items = [item(latte, 1), item(cookie, 1), item(muffin, 1)];
+    let order_id = app
+        .storage()
+        .place_order(
+            user_id,
+            &items,
+            Some("NICKEL"),
+            0,
+            at("2026-03-04", "16:00"),
+        )
+        .unwrap();
+    let order = app.order(order_id);
+    let line_totals: Vec<_> = order.items.iter().map(|line| line.line_total).collect();
+    assert_eq!(line_totals, [money("0.00"), money("0.97"), money("0.98")]);
+    assert_eq!(order.coupon_discount, money("0.05"));
+    assert_eq!(order.total_amount, money("1.95"));
+}
+
+#[test]
+fn promotions_are_validated_and_managed_by_staff() {
+    let app = TestApp::new();
+    let cookie = app.add_goods("Cookie", "2.00", 50);
+    let coffee = app.add_category("coffee");
+    let (_, customer_token) = app.customer("alice");
+    let on_cookie = |discount_type, discount_value| PromotionData {
+        goods_id: Some(cookie),
+        ..promotion("Cookie deal", discount_type, discount_value)
+    };
+
+    for invalid in [
+        promotion("Everything", DiscountType::Percent, "10"),
+        PromotionData {
+            category_id: Some(coffee),
+            ..on_cookie(DiscountType::Percent, "10")
+        },
+        on_cookie(DiscountType::Fixed, "0"),
+        on_cookie(DiscountType::Percent, "150"),
+        PromotionData {
+            weekdays: vec![0],
+            ..on_cookie(DiscountType::Percent, "10")
+        },
+        PromotionData {
+            start_time: time("15:00"),
+            ..on_cookie(DiscountType::Percent, "10")
+        },
+        PromotionData {
+            start_time: time("17:00"),
+            end_time: time("15:00"),
+            ..on_cookie(DiscountType::Percent, "10")
+        },
+        PromotionData {
+            start_date: date("2026-03-31"),
+            end_date: date("2026-03-01"),
+            ..on_cookie(DiscountType::Percent, "10")
+        },
+        PromotionData {
+            promotion_name: " ".to_string(),
+            ..on_cookie(DiscountType::Percent, "10")
+        },
+    ] {
+        assert_err!(
+            add_promotion(&app, invalid),
+            CafeHubError::Validation { .. }
+        );
+    }
+    assert_err!(
+        add_promotion(
+            &app,
+            PromotionData {
+                goods_id: Some(cookie + 100),
+                ..promotion("Ghost", DiscountType::Fixed, "1.00")
+            }
+        ),
+        CafeHubError::NotFound { .. }
+    );
+    assert_err!(
+        handlers::add_promotion(
+            customer_token.clone(),
+            on_cookie(DiscountType::Percent, "10"),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::Forbidden { .. }
+    );
+
+    add_promotion(&app, on_cookie(DiscountType::Percent, "10")).unwrap();
+    let id = promotion_id(&app, "Cookie deal");
+    let listed =
+        &handlers::get_promotions(app.admin_token(), app.storage(), &app.sessions).unwrap()[0];
+    assert_eq!(listed.discount_type, DiscountType::Percent);
+    assert_eq!(listed.weekdays, [1, 2, 3, 4, 5, 6, 7]);
+
+    // Updating replaces every setting.
+    handlers::update_promotion(
+        app.admin_token(),
+        id,
+        PromotionData {
+            category_id: Some(coffee),
+            weekdays: vec![6, 7],
+            paused: true,
+            ..promotion("Weekend coffee", DiscountType::Fixed, "0.75")
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    let promotions =
+        handlers::get_promotions(app.admin_token(), app.storage(), &app.sessions).unwrap();
+    assert_eq!(promotions.len(), 1);
+    assert_eq!(promotions[0].promotion_name, "Weekend coffee");
+    assert_eq!(promotions[0].goods_id, None);
+    assert_eq!(promotions[0].category_id, Some(coffee));
+    assert_eq!(promotions[0].weekdays, [6, 7]);
+    assert_eq!(promotions[0].discount_value, money("0.75"));
+    assert!(promotions[0].paused);
+
+    assert_err!(
+        handlers::update_promotion(
+            app.admin_token(),
+            id + 100,
+            on_cookie(DiscountType::Percent, "10"),
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::NotFound { .. }
+    );
+    assert_err!(
+        handlers::get_promotions(customer_token, app.storage(), &app.sessions),
+        CafeHubError::Forbidden { .. }
+    );
+    handlers::delete_promotion(app.admin_token(), id, app.storage(), &app.sessions).unwrap();
+    assert_err!(
+        handlers::delete_promotion(app.admin_token(), id, app.storage(), &app.sessions),
+        CafeHubError::NotFound { .. }
+    );
+}
@@ -0,0 +1,232 @@
+#[macro_use]
+mod common;
+
+use cafehub_lib::error::CafeHubError;
+use cafehub_lib::handlers;
+use cafehub_lib::models::*;
+use common::{money, TestApp};
+
+fn add_supplier(app: &TestApp, supplier_name: &str) -> i64 {
+    handlers::add_supplier(
+        app.admin_token(),
+        AddSupplierData {
+            supplier_name: supplier_name.to_string(),
+            contact: Some("orders@example.com".to_string()),
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    handlers::get_suppliers(app.admin_token(), app.storage(), &app.sessions)
+        .unwrap()
+        .into_iter()
+        .find(|supplier| supplier.supplier_name == supplier_name)
+        .expect("supplier not found")
+        .id
+}
+
+fn create_order(
+    app: &TestApp,
+    supplier_id: i64,
+    items: &[(i32, i32, &str)],
+) -> Result<i64, CafeHubError> {
+    handlers::create_purchase_order(
+        app.admin_token(),
+        CreatePurchaseOrderData {
+            supplier_id,
+            items: items
+                .iter()
+                .map(|&(goods_id, quantity, unit_cost)| PurchaseOrderItemData {
+                    goods_id,
+                    quantity,
+                    unit_cost: money(unit_cost),
+                })
+                .collect(),
+        },
+        app.storage(),
+        &app.sessions,
+    )
+}
+
+#[test]
+fn suppliers_are_validated() {
+    let app = TestApp::new();
+    let (_, customer_token) = app.customer("alice");
+    add_supplier(&app, "Bean Co");
+
+    // Names and contacts are trimmed and must fit their columns.
+    let long_name = "B".repeat(51);
+    let long_contact = "c".repeat(101);
+    for (supplier_name, contact) in [
+        (" Bean Co ", None),
+        ("  ", None),
+        (long_name.as_str(), None),
+        ("Milk Co", Some(long_contact.as_str())),
+    ] {
+        let result = handlers::add_supplier(
+            app.admin_token(),
+            AddSupplierData {
+                supplier_name: supplier_name.to_string(),
+                contact: contact.map(str::to_string),
+            },
+            app.storage(),
+            &app.sessions,
+        );
+        if supplier_name == " Bean Co " {
+            assert_err!(result, CafeHubError::DuplicateSupplierName { .. });
+        } else {
+            assert_err!(result, CafeHubError::Validation { .. });
+        }
+    }
+    handlers::add_supplier(
+        app.admin_token(),
+        AddSupplierData {
+            supplier_name: " Milk Co ".to_string(),
+            contact: Some("  ".to_string()),
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    let suppliers =
+        handlers::get_suppliers(app.admin_token(), app.storage(), &app.sessions).unwrap();
+    let milk_co = suppliers
+        .iter()
+        .find(|supplier| supplier.supplier_name == "Milk Co")
+        .expect("supplier not found");
+    assert_eq!(milk_co.contact, None);
+
+    assert_err!(
+        handlers::get_suppliers(customer_token, app.storage(), &app.sessions),
+        CafeHubError::Forbidden { .. }
+    );
+}
+
+#[test]
+fn purchase_orders_are_validated() {
+    let app = TestApp::new();
+    let cookie = app.add_goods("Cookie", "1.20", 0);
+    let supplier = add_supplier(&app, "Bakery");
+
+    assert_err!(
+        create_order(&app, supplier, &[]),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        create_order(&app, supplier, &[(cookie, 0, "0.40")]),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        create_order(&app, supplier, &[(cookie, 10, "-0.40")]),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        create_order(&app, supplier, &[(cookie, 10, "0.40"), (cookie, 5, "0.40")]),
+        CafeHubError::Validation { .. }
+    );
+    assert_err!(
+        create_order(&app, supplier + 100, &[(cookie, 10, "0.40")]),
+        CafeHubError::NotFound { .. }
+    );
+    assert_err!(
+        create_order(&app, supplier, &[(cookie + 100, 10, "0.40")]),
+        CafeHubError::NotFound { .. }
+    );
+    assert!(
+        handlers::get_purchase_orders(app.admin_token(), app.storage(), &app.sessions)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn receiving_restocks_goods_once() {
+    let app = TestApp::new();
+    let cookie = app.add_goods("Cookie", "1.20", 2);
+    let muffin = app.add_goods("Muffin", "2.00", 0);
+    let supplier = add_supplier(&app, "Bakery");
+
+    let order_id = create_order(
+        &app,
+        supplier,
+        &[(cookie, 24, "0.40"), (muffin, 12, "0.75")],
+    )
+    .unwrap();
+    let orders =
+        handlers::get_purchase_orders(app.admin_token(), app.storage(), &app.sessions).unwrap();
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].supplier_name, "Bakery");
+    assert_eq!(orders[0].status, "open");
+    assert_eq!(orders[0].total_cost, money("18.60"));
+    assert_eq!(app.find_goods("Cookie").stock, Some(2));
+
+    handlers::receive_purchase_order(app.admin_token(), order_id, app.storage(), &app.sessions)
+        .unwrap();
+    assert_eq!(app.find_goods("Cookie").stock, Some(26));
+    assert_eq!(app.find_goods("Muffin").stock, Some(12));
+
+    let movements =
+        handlers::get_stock_movements(app.admin_token(), muffin, app.storage(), &app.sessions)
+            .unwrap();
+    assert_eq!(movements[0].reason, "restock");
+    assert_eq!(movements[0].delta, 12);
+    assert_eq!(
+        movements[0].reference,
+        Some(format!("purchase_order:{}", order_id))
+    );
+
+    let detail = handlers::get_purchase_order_details(
+        app.admin_token(),
+        order_id,
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    assert_eq!(detail.status, "received");
+    assert_eq!(detail.created_by, "admin");
+    assert_eq!(detail.closed_by.as_deref(), Some("admin"));
+    assert!(detail.closed_at.is_some());
+    assert_eq!(detail.items.len(), 2);
+    assert_eq!(detail.items[1].goods_name, "Muffin");
+    assert_eq!(detail.items[1].unit_cost, money("0.75"));
+
+    assert_err!(
+        handlers::receive_purchase_order(app.admin_token(), order_id, app.storage(), &app.sessions),
+        CafeHubError::Conflict { .. }
+    );
+    assert_err!(
+        handlers::cancel_purchase_order(app.admin_token(), order_id, app.storage(), &app.sessions),
+        CafeHubError::Conflict { .. }
+    );
+    assert_eq!(app.find_goods("Cookie").stock, Some(26));
+}
+
+#[test]
+fn cancelled_orders_cannot_be_received() {
+    let app = TestApp::new();
+    let cookie = app.add_goods("Cookie", "1.20", 0);
+    let supplier = add_supplier(&app, "Bakery");
+    let (_, customer_token) = app.customer("alice");
+    let order_id = create_order(&app, supplier, &[(cookie, 24, "0.40")]).unwrap();
+
+    assert_err!(
+        handlers::receive_purchase_order(customer_token, order_id, app.storage(), &app.sessions),
+        CafeHubError::Forbidden { .. }
+    );
+    handlers::cancel_purchase_order(app.admin_token(), order_id, app.storage(), &app.sessions)
+        .unwrap();
+    assert_err!(
+        handlers::receive_purchase_order(app.admin_token(), order_id, app.storage(), &app.sessions),
+        CafeHubError::Conflict { .. }
+    );
+    assert_err!(
+        handlers::receive_purchase_order(
+            app.admin_token(),
+            order_id + 100,
+            app.storage(),
+            &app.sessions
+        ),
+        CafeHubError::NotFound { .. }
+    );
+    assert_eq!(app.find_goods("Cookie").stock, Some(0));
+}
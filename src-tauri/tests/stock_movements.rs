@@ -0,0 +1,197 @@
+#[macro_use]
+mod common;
+
+use cafehub_lib::error::CafeHubError;
+use cafehub_lib::handlers;
+use cafehub_lib::models::*;
+use common::TestApp;
+
+fn adjust(
+    app: &TestApp,
+    goods_id: i32,
+    delta: i32,
+    reason: StockMovementReason,
+    note: Option<&str>,
+) -> Result<String, CafeHubError> {
+    handlers::adjust_goods_stock(
+        app.admin_token(),
+        goods_id,
+        AdjustStockData {
+            delta,
+            reason,
+            note: note.map(str::to_string),
+        },
+        app.storage(),
+        &app.sessions,
+    )
+}
+
+fn movements(app: &TestApp, goods_id: i32) -> Vec<(String, i32, i32, Option<String>)> {
+    handlers::get_stock_movements(app.admin_token(), goods_id, app.storage(), &app.sessions)
+        .unwrap()
+        .into_iter()
+        .rev()
+        .map(|movement| {
+            (
+                movement.reason,
+                movement.delta,
+                movement.stock_after,
+                movement.reference,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn manual_adjustments_are_validated_and_recorded() {
+    let app = TestApp::new();
+    let cookie = app.add_goods("Cookie", "1.20", 5);
+    let (_, customer_token) = app.customer("alice");
+
+    adjust(&app, cookie, 10, StockMovementReason::Restock, None).unwrap();
+    adjust(
+        &app,
+        cookie,
+        -3,
+        StockMovementReason::Waste,
+        Some(" Dropped tray "),
+    )
+    .unwrap();
+    adjust(
+        &app,
+        cookie,
+        -1,
+        StockMovementReason::Correction,
+        Some("Recount"),
+    )
+    .unwrap();
+    assert_eq!(app.find_goods("Cookie").stock, Some(11));
+
+    let history = movements(&app, cookie);
+    assert_eq!(
+        history,
+        [
+            (
+                "restock".to_string(),
+                5,
+                5,
+                Some("initial stock".to_string())
+            ),
+            ("restock".to_string(), 10, 15, None),
+            (
+                "waste".to_string(),
+                -3,
+                12,
+                Some("Dropped tray".to_string())
+            ),
+            (
+                "correction".to_string(),
+                -1,
+                11,
+                Some("Recount".to_string())
+            ),
+        ]
+    );
+    let latest =
+        &handlers::get_stock_movements(app.admin_token(), cookie, app.storage(), &app.sessions)
+            .unwrap()[0];
+    assert_eq!(latest.operator_name.as_deref(), Some("admin"));
+
+    for (delta, reason) in [
+        (0, StockMovementReason::Correction),
+        (-1, StockMovementReason::Restock),
+        (1, StockMovementReason::Waste),
+        (-1, StockMovementReason::Sale),
+        (1, StockMovementReason::Refund),
+    ] {
+        assert_err!(
+            adjust(&app, cookie, delta, reason, None),
+            CafeHubError::Validation { .. }
+        );
+    }
+    assert_err!(
+        adjust(&app, cookie, -12, StockMovementReason::Waste, None),
+        CafeHubError::InsufficientStock { .. }
+    );
+    assert_err!(
+        adjust(&app, cookie + 100, 1, StockMovementReason::Restock, None),
+        CafeHubError::NotFound { .. }
+    );
+    assert_err!(
+        handlers::get_stock_movements(customer_token, cookie, app.storage(), &app.sessions),
+        CafeHubError::Forbidden { .. }
+    );
+    assert_eq!(movements(&app, cookie).len(), 4);
+    assert_eq!(app.find_goods("Cookie").stock, Some(11));
+}
+
+#[test]
+fn sales_and_refunds_are_recorded() {
+    let app = TestApp::new();
+    let cookie = app.add_goods("Cookie", "1.20", 5);
+    let (user_id, token) = app.customer("alice");
+    app.recharge(user_id, "20.00");
+
+    let order_id = handlers::purchase_goods(
+        token,
+        PurchaseGoodsData {
+            items: vec![PurchaseItem {
+                goods_id: cookie,
+                quantity: 3,
+                modifier_ids: Vec::new(),
+                redeem_with_points: false,
+            }],
+            coupon_code: None,
+            points_to_redeem: None,
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+    let order =
+        handlers::get_order_details(app.admin_token(), order_id, app.storage(), &app.sessions)
+            .unwrap();
+    handlers::refund_order(
+        app.admin_token(),
+        RefundOrderData {
+            order_id,
+            reason: "Stale".to_string(),
+            items: vec![RefundItem {
+                order_item_id: order.items[0].id,
+                quantity: 2,
+            }],
+        },
+        app.storage(),
+        &app.sessions,
+    )
+    .unwrap();
+
+    let history = movements(&app, cookie);
+    assert_eq!(history.len(), 3);
+    assert_eq!(
+        history[1],
+        (
+            "sale".to_string(),
+            -3,
+            2,
+            Some(format!("order:{}", order_id))
+        )
+    );
+    let (reason, delta, stock_after, reference) = &history[2];
+    assert_eq!((reason.as_str(), *delta, *stock_after), ("refund", 2, 4));
+    assert!(reference.as_deref().unwrap().starts_with("refund:"));
+    assert_eq!(app.find_goods("Cookie").stock, Some(4));
+}
+
+#[test]
+fn goods_updates_cannot_set_stock() {
+    let error = serde_json::from_str::<UpdateGoodsData>(r#"{"price": "2.50", "stock": 99}"#)
+        .err()
+        .unwrap();
+    assert!(
+        error.to_string().contains("unknown field `stock`"),
+        "{}",
+        error
+    );
+    assert!(serde_json::from_str::<UpdateGoodsData>(r#"{"price": "2.50"}"#).is_ok());
+}
@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import { getToken } from "./session";
 
 export interface LostItem {
     id: number;
@@ -13,10 +14,10 @@ export interface LostItem {
     status: 0 | 1; // 0: Unclaimed, 1: Claimed
 }
 
+// The finder is the logged-in user, taken from the session by the backend
 interface ReportLostItemPayload {
     item_name: string;
     pick_place?: string;
-    pick_user_id?: number;
 }
 
 /**
@@ -25,7 +26,7 @@ interface ReportLostItemPayload {
  */
 export const getAllLostItems = async (): Promise<LostItem[]> => {
     try {
-        const items = await invoke<LostItem[]>('get_all_lost_items');
+        const items = await invoke<LostItem[]>('get_all_lost_items', { token: getToken() });
         return items;
     } catch (error) {
         // Consider re-throwing the error or returning a more specific error object
@@ -34,9 +35,9 @@ export const getAllLostItems = async (): Promise<LostItem[]> => {
     }
 };
 
-export const claimLostItem = async (itemId: number, claim_user_id: number): Promise<number> => {
+export const claimLostItem = async (itemId: number): Promise<number> => {
     try {
-        await invoke('claim_lost_item', { data: { item_id: itemId, claim_user_id: claim_user_id } });
+        await invoke('claim_lost_item', { token: getToken(), data: { item_id: itemId } });
         return 0;
     } catch (err) {
         return 1;
@@ -45,7 +46,7 @@ export const claimLostItem = async (itemId: number, claim_user_id: number): Prom
 
 export const reportLostItem = async (reportData: ReportLostItemPayload): Promise<void> => {
     try {
-        await invoke('report_lost_item', { data: reportData });
+        await invoke('report_lost_item', { token: getToken(), data: reportData });
     } catch (error) {
         throw error; // Re-throw to allow caller to handle
     }
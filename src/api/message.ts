@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import { getToken } from "./session";
 
 export interface Message {
   id: number;
@@ -16,23 +17,29 @@ export interface MarkReadPayload {
   message_id: number;
 }
 
-// Add these new interfaces and functions:
+// The sender is always the logged-in user, taken from the session by the backend.
 export interface SendMessagePayload {
-  sender_id: number;
   receiver_id: number;
   title: string;
   message_content: string;
 }
 
 export interface CustomerSendMessagePayload {
-  sender_id: number;
   title?: string; // Matching backend's Option<String>
   message_content: string;
 }
 
-export const fetchReceivedMessages = async (userId: number): Promise<Message[]> => {
+// Backend errors arrive as { code, message, details } objects
+const errorMessage = (error: unknown): string | null => {
+  if (typeof error === 'object' && error !== null && 'message' in error) {
+    return String((error as { message: unknown }).message);
+  }
+  return null;
+};
+
+export const fetchReceivedMessages = async (): Promise<Message[]> => {
   try {
-    const messages = await invoke<Message[]>("get_recived_messages", { userId });
+    const messages = await invoke<Message[]>("get_recived_messages", { token: getToken() });
     return messages;
   } catch (error) {
     console.error("Failed to fetch received messages:", error);
@@ -40,9 +47,9 @@ export const fetchReceivedMessages = async (userId: number): Promise<Message[]>
   }
 };
 
-export const fetchSentMessages = async (userId: number): Promise<Message[]> => {
+export const fetchSentMessages = async (): Promise<Message[]> => {
   try {
-    const messages = await invoke<Message[]>("get_sent_messages", { userId });
+    const messages = await invoke<Message[]>("get_sent_messages", { token: getToken() });
     return messages;
   } catch (error) {
     console.error("Failed to fetch sent messages:", error);
@@ -51,31 +58,33 @@ export const fetchSentMessages = async (userId: number): Promise<Message[]> => {
 };
 
 /**
- * Marks a message as read by the current user.
+ * Marks a message as read by the logged-in user.
  * @param messageId The ID of the message to mark as read.
- * @param currentUserId The ID of the user performing the action (should be the receiver).
  * @returns A promise that resolves to a number:
  *          0: Successfully marked as read (or was already read by this user).
  *          1: Current user is not the receiver of the message.
  *          Rejects with an error message for other failures (e.g., message not found, DB error).
  */
-export const markMessageAsReadApi = async (messageId: number, currentUserId: number): Promise<number> => {
+export const markMessageAsReadApi = async (messageId: number): Promise<number> => {
   try {
-    // The Rust command `mark_message_as_read` expects `data` (MarkReadData) and `current_user_id`.
+    // The Rust command `mark_message_as_read` expects `data` (MarkReadData) and the session `token`.
     const payload: MarkReadPayload = { message_id: messageId };
-    const result = await invoke<number>("mark_message_as_read", {
+    await invoke<string>("mark_message_as_read", {
       data: payload,
-      currentUserId: currentUserId,
+      token: getToken(),
     });
-    return result;
+    return 0;
   } catch (error) {
     console.error(`Failed to mark message ${messageId} as read:`, error);
+    if ((error as { code?: string })?.code === 'NOT_MESSAGE_RECEIVER') {
+      return 1;
+    }
     if (typeof error === 'string') {
       throw new Error(error);
     } else if (error instanceof Error) {
       throw error;
     } else {
-      throw new Error("An unknown error occurred while marking the message as read.");
+      throw new Error(errorMessage(error) ?? "An unknown error occurred while marking the message as read.");
     }
   }
 };
@@ -90,30 +99,15 @@ export const sendMessageApi = async (payload: SendMessagePayload): Promise<numbe
       // Frontend validation, though backend also validates
       throw new Error("消息标题不能为空");
     }
-    if (payload.sender_id === payload.receiver_id) {
-      throw new Error("发送者和接收者不能是同一用户");
-    }
-    // The backend command is "send_message" and expects the payload wrapped in a "data" field.
-    const result = await invoke<number>("admin_send_message", { data: payload });
-
-    // Backend returns:
-    // Ok(0) -> Message sent successfully
-    // Ok(1) -> Sender not found
-    // Ok(2) -> Receiver not found
-    // Err(string) -> Other errors (which invoke will throw as an error)
-
-    if (result === 0) {
-      return 0; // Success
-    } else if (result === 1) {
-      throw new Error("发送方用户不存在。");
-    } else if (result === 2) {
-      throw new Error("接收方用户不存在。");
-    } else {
-      // This case should ideally not be reached if backend strictly returns 0, 1, or 2 for Ok variants.
-      throw new Error(`发送消息时发生未知服务端状态码: ${result}`);
-    }
+    // The backend command expects the session token and the payload wrapped in a "data" field.
+    // It rejects with NOT_FOUND when the receiver does not exist.
+    await invoke<string>("admin_send_message", { token: getToken(), data: payload });
+    return 0; // Success
   } catch (error) {
     console.error("Failed to send message:", error);
+    if ((error as { code?: string })?.code === 'NOT_FOUND') {
+      throw new Error("接收方用户不存在。");
+    }
     if (typeof error === 'string') {
       // This can be an error message from invoke if the command itself fails (e.g., command not found)
       // or a string error from the Rust Err(String)
@@ -121,7 +115,7 @@ export const sendMessageApi = async (payload: SendMessagePayload): Promise<numbe
     } else if (error instanceof Error) {
       throw error; // Rethrow errors from validation or specific error cases above
     } else {
-      throw new Error("发送消息时发生未知网络或系统错误。");
+      throw new Error(errorMessage(error) ?? "发送消息时发生未知网络或系统错误。");
     }
   }
 };
@@ -132,33 +126,20 @@ export const customerSendMessageApi = async (payload: CustomerSendMessagePayload
       throw new Error("消息内容不能为空");
     }
     // Title can be optional, backend handles Option<String>
-    // Backend's customer_send_message determines admin receiver and validates sender.
-
-    const result = await invoke<number>("customer_send_message", { data: payload });
-
-    // Backend returns:
-    // Ok(0) -> Message sent successfully
-    // Ok(1) -> Sender (customer) not found
-    // Ok(2) -> Administrator (receiver) not found
-    // Err(string) -> Other errors
-
-    if (result === 0) {
-      return 0; // Success
-    } else if (result === 1) {
-      throw new Error("发送方用户不存在或非客户账户。");
-    } else if (result === 2) {
-      throw new Error("管理员账户未找到，无法发送消息。");
-    } else {
-      throw new Error(`发送消息时发生未知服务端状态码: ${result}`);
-    }
+    // Backend's customer_send_message determines admin receiver; the sender comes from the session.
+    await invoke<string>("customer_send_message", { token: getToken(), data: payload });
+    return 0; // Success
   } catch (error) {
     console.error("Failed to send customer message:", error);
+    if ((error as { code?: string })?.code === 'NO_ADMINISTRATOR') {
+      throw new Error("管理员账户未找到，无法发送消息。");
+    }
     if (typeof error === 'string') {
       throw new Error(error);
     } else if (error instanceof Error) {
       throw error;
     } else {
-      throw new Error("发送客户消息时发生未知网络或系统错误。");
+      throw new Error(errorMessage(error) ?? "发送客户消息时发生未知网络或系统错误。");
     }
   }
 };
\ No newline at end of file
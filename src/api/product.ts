@@ -1,24 +1,51 @@
 import { invoke } from "@tauri-apps/api/core";
+import { getToken } from "./session";
 
 // Defines the structure of a product object used in the frontend
 export interface Product {
   id: number;
   goods_name: string;
-  goods_type: string;
+  category_id: number | null; // null while the goods are uncategorized
+  category: string | null;    // Display name of the category
   price: number;
   stock: number;
 }
 
+// Matches the `Category` struct in Rust
+export interface Category {
+  id: number;
+  category_name: string;
+  display_name: string;
+  sort_order: number; // Lower comes first
+}
+
+// Matches the `CategoryGoods` struct in Rust; `category` is null for the uncategorized section
+export interface CategoryGoods {
+  category: Category | null;
+  goods: Product[];
+}
+
 // Defines the data structure for updating product information via the API.
-// Matches the `UpdateGoodsData` struct in the Rust backend.
+// Matches the `UpdateGoodsData` struct in the Rust backend, which rejects unknown fields.
+// Stock is not part of it: use `adjustGoodsStock` so every change is recorded with a reason.
 export interface UpdateProductData {
-  stock?: number; // Optional: new stock quantity
   price?: number; // Optional: new price
 }
 
+// Matches the `StockMovementReason` values staff may use for a manual adjustment.
+// Sales and refunds are recorded by the orders that cause them.
+export type StockAdjustmentReason = 'restock' | 'waste' | 'correction';
+
+// Matches the `AdjustStockData` struct in Rust
+export interface AdjustStockData {
+  delta: number; // Negative to take stock out
+  reason: StockAdjustmentReason;
+  note?: string;
+}
+
 export interface AddProductData {
   goods_name: string;
-  goods_type?: string; // Optional, as in Rust
+  category_id?: number; // Optional, as in Rust
   price: number;
   stock?: number;      // Optional, as in Rust
 }
@@ -29,9 +56,8 @@ export interface PurchaseItemData {
   quantity: number; // Changed from i32 to number for TypeScript
 }
 
-// Matches the `PurchaseGoodsData` struct in Rust
+// Matches the `PurchaseGoodsData` struct in Rust; the buyer is the logged-in user
 export interface PurchaseGoodsPayload {
-  user_id: number; // Changed from i64 to number for TypeScript
   items: PurchaseItemData[];
 }
 
@@ -41,7 +67,7 @@ export interface PurchaseGoodsPayload {
  */
 export const getProducts = async (): Promise<Product[]> => {
   try {
-    const products = await invoke<Product[]>("get_all_goods");
+    const products = await invoke<Product[]>("get_all_goods", { token: getToken() });
     return products;
   } catch (error) {
     console.error("Failed to fetch products:", error);
@@ -52,9 +78,36 @@ export const getProducts = async (): Promise<Product[]> => {
 };
 
 /**
- * Updates the information (stock and/or price) of a specific product on the backend.
+ * Fetches the goods grouped by category, in category order.
+ * Empty categories are left out and uncategorized goods come last.
+ * @returns A promise that resolves to an array of category sections.
+ */
+export const getGoodsByCategory = async (): Promise<CategoryGoods[]> => {
+  try {
+    return await invoke<CategoryGoods[]>("get_goods_by_category", { token: getToken() });
+  } catch (error) {
+    console.error("Failed to fetch goods by category:", error);
+    return [];
+  }
+};
+
+/**
+ * Fetches all categories, in display order.
+ * @returns A promise that resolves to an array of Category objects.
+ */
+export const getCategories = async (): Promise<Category[]> => {
+  try {
+    return await invoke<Category[]>("get_categories", { token: getToken() });
+  } catch (error) {
+    console.error("Failed to fetch categories:", error);
+    return [];
+  }
+};
+
+/**
+ * Updates the information (e.g. price) of a specific product on the backend.
  * @param goodsId The ID of the product to update.
- * @param data An object containing the product fields to update (e.g., stock, price).
+ * @param data An object containing the product fields to update.
  * @returns A promise that resolves with a success message string from the backend
  *          or rejects with an error if the update fails.
  */
@@ -64,8 +117,9 @@ export const AdminUpdateProduct = async (
 ): Promise<string> => {
   try {
     // Construct the payload to match the Rust function's arguments.
-    // The Rust command `update_goods_info` expects `goods_id` and `data`.
+    // The Rust command `update_goods_info` expects the session `token`, `goods_id` and `data`.
     const payload = {
+      token: getToken(),
       goodsId: goodsId,
       data: data, // `data` here directly matches the `UpdateGoodsData` Rust struct
     };
@@ -91,6 +145,33 @@ export const AdminUpdateProduct = async (
   }
 };
 
+/**
+ * Adds stock to or takes stock out of a product, recording the reason for the change.
+ * @param goodsId The ID of the product to adjust.
+ * @param data The change in stock, its reason and an optional note.
+ * @returns A promise that resolves with a success message string from the backend
+ *          or rejects with an error if the adjustment fails.
+ */
+export const adjustGoodsStock = async (
+  goodsId: number,
+  data: AdjustStockData
+): Promise<string> => {
+  try {
+    // The Rust command `adjust_goods_stock` expects the session `token`, `goods_id` and `data`.
+    const result = await invoke<string>("adjust_goods_stock", { token: getToken(), goodsId, data });
+    return result;
+  } catch (error) {
+    console.error(`Failed to adjust stock of product ${goodsId}:`, error);
+    if (typeof error === 'string') {
+      throw new Error(error);
+    } else if (error instanceof Error) {
+      throw error;
+    } else {
+      throw new Error((error as { message?: string })?.message ?? "An unknown error occurred while adjusting the stock.");
+    }
+  }
+};
+
 /**
  * Adds a new product to the backend.
  * @param data An object containing the new product's details.
@@ -99,8 +180,8 @@ export const AdminUpdateProduct = async (
  */
 export const addProduct = async (data: AddProductData): Promise<string> => {
   try {
-    // The Rust command `add_goods` expects `data` as its argument.
-    const result = await invoke<string>("add_goods", { data });
+    // The Rust command `add_goods` expects the session `token` and `data` as its arguments.
+    const result = await invoke<string>("add_goods", { token: getToken(), data });
     return result; // Return the success message from the backend
   } catch (error) {
     console.error(`Failed to add product:`, error);
@@ -116,8 +197,8 @@ export const addProduct = async (data: AddProductData): Promise<string> => {
 };
 
 /**
- * Processes the purchase of goods for a user.
- * @param payload An object containing the user_id and a list of items to purchase.
+ * Processes the purchase of goods for the logged-in user.
+ * @param payload An object containing the list of items to purchase.
  * @returns A promise that resolves to a number indicating the purchase status:
  *          0: Purchase successful
  *          1: Insufficient stock
@@ -126,12 +207,18 @@ export const addProduct = async (data: AddProductData): Promise<string> => {
  */
 export const purchaseGoods = async (payload: PurchaseGoodsPayload): Promise<number> => {
   try {
-    // The Rust command `purchase_goods` expects `data` as its argument,
-    // which corresponds to the `PurchaseGoodsData` struct.
-    const result = await invoke<number>("purchase_goods", { data: payload });
-    return result;
+    // The Rust command `purchase_goods` expects the session `token` and `data`,
+    // which corresponds to the `PurchaseGoodsData` struct. It resolves to the new order ID.
+    await invoke<number>("purchase_goods", { token: getToken(), data: payload });
+    return 0;
   } catch (error) {
-    console.error(`Failed to purchase goods for user ${payload.user_id}:`, error);
+    console.error("Failed to purchase goods:", error);
+    const code = (error as { code?: string })?.code;
+    if (code === 'INSUFFICIENT_STOCK') {
+      return 1;
+    } else if (code === 'INSUFFICIENT_BALANCE') {
+      return 2;
+    }
     if (typeof error === 'string') {
       throw new Error(error);
     } else if (error instanceof Error) {
@@ -0,0 +1,37 @@
+import type { Account } from "./user";
+
+// The backend keeps the session; the webview only holds the token it handed out at login
+// and sends it with every command. The acting user is always derived from that token.
+const TOKEN_KEY = 'sessionToken';
+const ACCOUNT_KEY = 'loginAccount';
+const AUTH_KEY = 'isAuthenticated';
+
+export const saveSession = (token: string, account: Account) => {
+  localStorage.setItem(TOKEN_KEY, token);
+  localStorage.setItem(ACCOUNT_KEY, JSON.stringify(account));
+  localStorage.setItem(AUTH_KEY, 'true');
+};
+
+export const clearSession = () => {
+  localStorage.removeItem(TOKEN_KEY);
+  localStorage.removeItem(ACCOUNT_KEY);
+  localStorage.removeItem(AUTH_KEY);
+};
+
+// An empty token makes the backend answer NOT_LOGGED_IN, which callers already handle as an error.
+export const getToken = (): string => localStorage.getItem(TOKEN_KEY) ?? '';
+
+// The account returned at login, for display and role checks only.
+export const getLoginAccount = (): Account | null => {
+  const stored = localStorage.getItem(ACCOUNT_KEY);
+  if (!stored) {
+    return null;
+  }
+  try {
+    return JSON.parse(stored) as Account;
+  } catch (e) {
+    console.error("Failed to parse account data from localStorage", e);
+    clearSession();
+    return null;
+  }
+};
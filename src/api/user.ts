@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import { clearSession, getToken, saveSession } from "./session";
 
 export interface Account {
   id: number;
@@ -7,28 +8,54 @@ export interface Account {
   gender?: number | null;
   join_time?: string | null;
   balance?: number | null;
+  points: number;
   user_type: number;
 }
 
+export interface LoginResponse {
+  token: string;
+  account: Account;
+}
+
 export const login = async (uname: string, pwd: string): Promise<Account | null> => {
   try {
-    // 后端的 "login" tauri 命令应该直接返回 Account 结构体或者在错误时抛出异常
-    const account = await invoke<Account>("login", {
+    // 后端的 "login" tauri 命令返回会话 token 和 Account，或者在错误时抛出异常
+    const { token, account } = await invoke<LoginResponse>("login", {
       username: uname,
       password: pwd,
     });
-    // 如果 invoke 成功，它会返回 Account 对象
+    // 之后的每个命令都要带上这个 token，后端据此确定当前用户
+    saveSession(token, account);
     return account;
   } catch (error) {
     // 如果 invoke 失败 (例如后端返回 Err 或 tauri 通信错误)，会进入 catch 块
     console.error("Login API call failed:", error);
+    clearSession();
     return null; // 返回 null 表示登录失败
   }
 };
 
+export const logout = async () => {
+  try {
+    await invoke("logout", { token: getToken() });
+  } catch (error) {
+    // 会话可能已经过期，本地状态照样清除
+    console.error("Logout API call failed:", error);
+  } finally {
+    clearSession();
+  }
+};
+
+// 后端的错误码对应的提示
+const registerErrors: Record<string, string> = {
+  DUPLICATE_USERNAME: '用户名重复',
+  INVALID_PHONE: '手机号不是11位',
+  INVALID_GENDER: '性别设置错误',
+};
+
 export const register = async (uname: string, pwd: string, phe: string, gen: number) => {
   try {
-    let res = await invoke("register_user", {
+    await invoke("register_user", {
       data: {
         username: uname,
         password: pwd,
@@ -36,21 +63,14 @@ export const register = async (uname: string, pwd: string, phe: string, gen: num
         gender: gen, // Or pass actual gender if available (0 for Male, 1 for Female)
       },
     });
-    let account: Account | null = null;
-    if (res == 1) {
-      //注册成功
-      account = await login(uname, pwd);
-      return account;
-    } else if (res == 2) {
-      throw new Error('用户名重复');
-    } else if (res == 3) {
-      throw new Error('手机号不是11位');
-    } else if (res == 4) {
-      throw new Error('性别设置错误');
-    }
-    return account;
+    //注册成功
+    return await login(uname, pwd);
   } catch (error) {
     console.error("register failed:", error);
+    const code = (error as { code?: string })?.code;
+    if (code && registerErrors[code]) {
+      throw new Error(registerErrors[code]);
+    }
     throw error;
   }
 };
@@ -1,15 +1,15 @@
 import { Button, Form, Input, InputNumber, message, Modal, Select } from 'antd'
 import React, { useState } from 'react'
-import { AddProductData } from '@/api/product'; // Import AddProductData
+import { AddProductData, Category } from '@/api/product'; // Import AddProductData
 
 // 定义组件的 props 接口
 interface AddGoodsBtnProps {
   // initProducts is no longer needed as ID is generated by backend
-  initCategories: string[];
+  categories: Category[]; // From get_categories, in display order
   onProductAdded: () => void; // Callback to refresh product list in parent
 }
 
-const AddGoodsBtn: React.FC<AddGoodsBtnProps> = ({ initCategories, onProductAdded }) => {
+const AddGoodsBtn: React.FC<AddGoodsBtnProps> = ({ categories, onProductAdded }) => {
   const [isAddModalVisible, setIsAddModalVisible] = useState(false);
   const [addForm] = Form.useForm();
   const [loading, setLoading] = useState(false);
@@ -27,7 +27,7 @@ const AddGoodsBtn: React.FC<AddGoodsBtnProps> = ({ initCategories, onProductAdde
       const newProductData: AddProductData = {
         goods_name: values.goods_name,
         price: values.price,
-        goods_type: values.goods_type || undefined, // Send undefined if not selected, backend handles Option<String>
+        category_id: values.category_id ?? undefined, // Send undefined if not selected, backend handles Option<i32>
         stock: values.stock !== undefined && values.stock !== null ? values.stock : undefined, // Send undefined if empty, backend handles Option<i32>
       };
 
@@ -68,7 +68,7 @@ const AddGoodsBtn: React.FC<AddGoodsBtnProps> = ({ initCategories, onProductAdde
         confirmLoading={loading}
         destroyOnClose
       >
-        <Form form={addForm} layout="vertical" name="addProductForm" initialValues={{ price: null, stock: null, goods_type: undefined }}>
+        <Form form={addForm} layout="vertical" name="addProductForm" initialValues={{ price: null, stock: null, category_id: undefined }}>
           <Form.Item
             name="goods_name"
             label="商品名称"
@@ -107,14 +107,14 @@ const AddGoodsBtn: React.FC<AddGoodsBtnProps> = ({ initCategories, onProductAdde
             <InputNumber style={{ width: '100%' }} min={0} step={1} precision={0} placeholder="可选，默认为0" />
           </Form.Item>
           <Form.Item
-            name="goods_type" // Changed from 'category' to 'goods_type'
+            name="category_id"
             label="类别"
-          // goods_type is optional in backend
+          // category_id is optional in backend
           >
-            <Select placeholder="选择一个类别 (可选)">
-              {initCategories.map(cat => (
-                <Select.Option key={cat} value={cat}>
-                  {cat}
+            <Select placeholder="选择一个类别 (可选)" allowClear>
+              {categories.map(cat => (
+                <Select.Option key={cat.id} value={cat.id}>
+                  {cat.display_name}
                 </Select.Option>
               ))}
             </Select>
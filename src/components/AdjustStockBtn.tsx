@@ -0,0 +1,128 @@
+import { adjustGoodsStock, StockAdjustmentReason } from '@/api/product';
+import { Button, Form, Input, InputNumber, message, Modal, Select } from 'antd'
+import React, { useState } from 'react'
+
+// 定义商品接口
+interface Product {
+  id: number;
+  goods_name: string;
+  stock: number;
+}
+
+// 定义组件的 props 接口
+interface AdjustStockBtnProps {
+  record: Product; // 传入的商品记录
+  onStockAdjusted: () => void; // 库存调整成功后的回调函数
+}
+
+// 手动调整库存可用的原因，销售和退款由订单自动记录
+const reasonOptions: { value: StockAdjustmentReason; label: string }[] = [
+  { value: 'restock', label: '进货（增加库存）' },
+  { value: 'waste', label: '损耗（减少库存）' },
+  { value: 'correction', label: '盘点更正' },
+];
+
+const AdjustStockBtn: React.FC<AdjustStockBtnProps> = ({ record, onStockAdjusted }) => {
+  const [isModalVisible, setIsModalVisible] = useState(false);
+  const [submitting, setSubmitting] = useState(false);
+
+  const [adjustForm] = Form.useForm();
+
+  const handleCancel = () => {
+    setIsModalVisible(false);
+    adjustForm.resetFields();
+  };
+
+  const handleOk = async () => {
+    let values: { reason: StockAdjustmentReason; delta: number; note?: string };
+    try {
+      values = await adjustForm.validateFields();
+    } catch (errorInfo) {
+      // This catch block handles form validation errors
+      console.log('表单验证失败:', errorInfo);
+      return;
+    }
+
+    try {
+      setSubmitting(true);
+      await adjustGoodsStock(record.id, {
+        delta: values.delta,
+        reason: values.reason,
+        ...(values.note?.trim() && { note: values.note.trim() }),
+      });
+      message.success(`商品 "${record.goods_name}" 库存已调整`);
+      setIsModalVisible(false);
+      adjustForm.resetFields();
+      onStockAdjusted();
+    } catch (error: any) {
+      console.error("调整库存失败:", error);
+      message.error(error?.message || '调整库存失败，请稍后再试。');
+    } finally {
+      setSubmitting(false);
+    }
+  };
+
+  return (
+    <>
+      <Button type="link" onClick={() => setIsModalVisible(true)}>
+        调整库存
+      </Button>
+      <Modal
+        title={`调整库存（当前库存 ${record.stock}）`}
+        open={isModalVisible}
+        onOk={handleOk}
+        onCancel={handleCancel}
+        okText="确认调整"
+        cancelText="取消"
+        confirmLoading={submitting}
+        destroyOnClose // Destroys modal children when closed, useful for resetting form state
+      >
+        <Form form={adjustForm} layout="vertical" name="adjustStockForm">
+          <Form.Item
+            name="reason"
+            label="调整原因"
+            rules={[{ required: true, message: '请选择调整原因!' }]}
+          >
+            <Select options={reasonOptions} placeholder="请选择调整原因" />
+          </Form.Item>
+          <Form.Item
+            name="delta"
+            label="变动数量（负数表示减少）"
+            dependencies={['reason']}
+            rules={[
+              { required: true, message: '请输入变动数量!' },
+              ({ getFieldValue }) => ({
+                validator(_, value) {
+                  if (value === undefined || value === null) {
+                    return Promise.resolve();
+                  }
+                  if (value === 0) {
+                    return Promise.reject(new Error('变动数量不能为0!'));
+                  }
+                  const reason = getFieldValue('reason');
+                  if (reason === 'restock' && value < 0) {
+                    return Promise.reject(new Error('进货必须增加库存!'));
+                  }
+                  if (reason === 'waste' && value > 0) {
+                    return Promise.reject(new Error('损耗必须减少库存!'));
+                  }
+                  if (record.stock + value < 0) {
+                    return Promise.reject(new Error('调整后库存不能为负数!'));
+                  }
+                  return Promise.resolve();
+                },
+              }),
+            ]}
+          >
+            <InputNumber style={{ width: '100%' }} step={1} precision={0} />
+          </Form.Item>
+          <Form.Item name="note" label="备注">
+            <Input.TextArea rows={2} maxLength={100} placeholder="可选，例如供应商或盘点批次" />
+          </Form.Item>
+        </Form>
+      </Modal>
+    </>
+  )
+}
+
+export default AdjustStockBtn
@@ -22,7 +22,6 @@ interface AdminReciveBoxProps {
   setIsModalVisible: React.Dispatch<React.SetStateAction<boolean>>;
   setIsReplyModalVisible: React.Dispatch<React.SetStateAction<boolean>>;
   setReplyingToMessage: React.Dispatch<React.SetStateAction<Message | null>>;
-  adminId: number;
   loading: boolean;
 }
 
@@ -34,7 +33,6 @@ const AdminReciveBox: React.FC<AdminReciveBoxProps> = (
     setIsModalVisible,
     setIsReplyModalVisible,
     setReplyingToMessage,
-    adminId,
     loading,
   }
 ) => {
@@ -46,7 +44,7 @@ const AdminReciveBox: React.FC<AdminReciveBoxProps> = (
     // Only attempt to mark as read if it's currently unread
     if (messageToView.read_status === 0) {
       try {
-        const status = await markMessageAsReadApi(messageToView.id, adminId);
+        const status = await markMessageAsReadApi(messageToView.id);
         if (status === 0) {
           // Successfully marked as read (or was already read)
           setMessages(prevMessages =>
@@ -56,9 +54,9 @@ const AdminReciveBox: React.FC<AdminReciveBoxProps> = (
           );
           // AntMessage.success('消息已标记为已读'); // Optional: can be too noisy
         } else if (status === 1) {
-          // This case should ideally not happen if adminId is correctly the receiver.
+          // This case should ideally not happen for a message in the admin's own inbox.
           // Logging it for debugging.
-          console.warn(`Attempted to mark message ${messageToView.id} as read, but admin ${adminId} is not the receiver.`);
+          console.warn(`Attempted to mark message ${messageToView.id} as read, but the current admin is not the receiver.`);
           message.error('无法将此消息标记为已读：权限不足。');
         } else {
           // Handle other unexpected status codes if any are defined in the future
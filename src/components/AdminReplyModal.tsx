@@ -2,12 +2,12 @@ import React, { useState, useEffect } from 'react';
 import { Modal, Form, Input, Button, message as AntMessage } from 'antd';
 import { sendMessageApi, fetchSentMessages } from '@/api/message';
 import type { SendMessagePayload, Message } from '@/api/message';
+import { getToken } from '@/api/session';
 
 const { TextArea } = Input;
 
 interface AdminReplyModalProps {
   visible: boolean;
-  adminId: number; // ID of the admin sending the message
   recipientId: number; // ID of the user receiving the message
   recipientName: string; // Username of the recipient
   originalMessageTitle: string; // Title of the message being replied to
@@ -17,7 +17,6 @@ interface AdminReplyModalProps {
 
 const AdminReplyModal: React.FC<AdminReplyModalProps> = ({
   visible,
-  adminId,
   recipientId,
   recipientName,
   originalMessageTitle,
@@ -37,8 +36,8 @@ const AdminReplyModal: React.FC<AdminReplyModalProps> = ({
   }, [visible, originalMessageTitle, form]);
 
   const handleSendReply = async (values: { title: string; message_content: string }) => {
-    if (adminId === -1) {
-      AntMessage.error('管理员ID无效，无法发送消息。请确保管理员已登录。');
+    if (!getToken()) { // The sender is the session's admin
+      AntMessage.error('无法发送消息。请确保管理员已登录。');
       return;
     }
     if (!values.message_content.trim()) {
@@ -52,7 +51,6 @@ const AdminReplyModal: React.FC<AdminReplyModalProps> = ({
 
     setLoading(true);
     const payload: SendMessagePayload = {
-      sender_id: adminId,
       receiver_id: recipientId,
       title: values.title,
       message_content: values.message_content,
@@ -63,10 +61,8 @@ const AdminReplyModal: React.FC<AdminReplyModalProps> = ({
       AntMessage.success('回复发送成功！');
 
       // Refresh the sent messages list in AdminMessagePage
-      if (adminId !== -1) {
-        const updatedSentMessages = await fetchSentMessages(adminId);
-        setAdminSentMessages(updatedSentMessages);
-      }
+      const updatedSentMessages = await fetchSentMessages();
+      setAdminSentMessages(updatedSentMessages);
       handleCloseReplyModal();
       form.resetFields();
     } catch (error: any) {
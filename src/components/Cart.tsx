@@ -8,7 +8,8 @@ interface Product {
   goods_name: string;
   stock: number;
   price: number;
-  goods_type: string;
+  category_id: number | null;
+  category: string | null;
 }
 
 interface CartItem extends Product {
@@ -22,7 +23,7 @@ interface CartProps {
   setIsCartDrawerVisible: (visible: boolean) => void;
   setProducts: React.Dispatch<React.SetStateAction<Product[]>>;
   isCartDrawerVisible: boolean;
-  userId: number | null; // Add userId prop
+  isLoggedIn: boolean; // Checkout needs a session
   onPurchaseSuccess: () => void; // Callback to refresh products on parent
 }
 
@@ -32,7 +33,7 @@ const Cart: React.FC<CartProps> = ({
   products,
   setIsCartDrawerVisible,
   isCartDrawerVisible,
-  userId, // Destructure userId
+  isLoggedIn,
   onPurchaseSuccess, // Destructure callback
 }) => {
   const [checkoutLoading, setCheckoutLoading] = useState(false);
@@ -100,7 +101,7 @@ const Cart: React.FC<CartProps> = ({
   };
 
   const handleCheckout = async () => {
-    if (!userId) {
+    if (!isLoggedIn) {
       message.error('用户未登录，无法结算！');
       // Optionally, redirect to login or show login modal
       return;
@@ -118,7 +119,6 @@ const Cart: React.FC<CartProps> = ({
     }));
 
     const payload: PurchaseGoodsPayload = {
-      user_id: userId,
       items: itemsToPurchase,
     };
 
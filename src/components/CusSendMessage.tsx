@@ -1,6 +1,7 @@
 import { Modal, Input, Button, Typography, Form, message as AntMessage } from 'antd'; // Renamed message to AntMessage
 import React, { useState } from 'react';
 import { customerSendMessageApi, fetchSentMessages, CustomerSendMessagePayload, Message } from '@/api/message'; // Import necessary items
+import { getToken } from '@/api/session';
 
 const { Title } = Typography;
 const { TextArea } = Input;
@@ -9,14 +10,12 @@ interface CusSendMessageProps {
   isNewMessageModalVisible: boolean;
   setIsNewMessageModalVisible: (visible: boolean) => void;
   setSentMessages: React.Dispatch<React.SetStateAction<Message[]>>;
-  currentUserId: number | null; // ID of the logged-in customer, can be null if not logged in
 }
 
 const CusSendMessage: React.FC<CusSendMessageProps> = ({
   isNewMessageModalVisible,
   setIsNewMessageModalVisible,
   setSentMessages,
-  currentUserId,
 }) => {
   const [form] = Form.useForm();
   const [loading, setLoading] = useState(false);
@@ -28,13 +27,12 @@ const CusSendMessage: React.FC<CusSendMessageProps> = ({
   };
 
   const handleSendMessage = async (values: { title: string; message_content: string }) => {
-    if (currentUserId === null || currentUserId === -1) { // Check for null or invalid ID
-      AntMessage.error('用户未登录或ID无效，无法发送消息。');
+    if (!getToken()) { // The sender is the session's user
+      AntMessage.error('用户未登录，无法发送消息。');
       return;
     }
     setLoading(true);
     const payload: CustomerSendMessagePayload = {
-      sender_id: currentUserId,
       title: values.title || undefined, // Send undefined if title is empty, matching Option<String>
       message_content: values.message_content,
     };
@@ -44,7 +42,7 @@ const CusSendMessage: React.FC<CusSendMessageProps> = ({
       AntMessage.success('消息发送成功!');
 
       // Refresh sent messages list
-      const updatedSentMessages = await fetchSentMessages(currentUserId);
+      const updatedSentMessages = await fetchSentMessages();
       setSentMessages(updatedSentMessages);
 
       handleNewMessageModalClose();
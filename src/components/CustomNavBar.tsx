@@ -3,6 +3,7 @@ import { TransactionOutlined, InboxOutlined, RestOutlined, MailOutlined, LogoutO
 import type { MenuProps } from 'antd';
 import { Menu } from 'antd';
 import { Link } from 'react-router-dom';
+import { logout } from '@/api/user';
 
 type MenuItem = Required<MenuProps>['items'][number];
 
@@ -42,8 +43,7 @@ const items: MenuItem[] = [
     ),
     icon: <LogoutOutlined />,
     onClick: () => {
-      localStorage.removeItem('isAuthenticated'); // 清除登录状态
-      localStorage.removeItem('loginAccount'); // 清除登录状态
+      logout(); // 注销后端会话并清除登录状态
     }
   }
 ];
@@ -2,11 +2,11 @@
 import React from 'react';
 import { Modal, Form, Input, message } from 'antd';
 import { invoke } from '@tauri-apps/api/core';
+import { getToken } from '@/api/session';
 
 interface CustomerChangePasswordModalProps {
   visible: boolean;
   onCancel: () => void;
-  userId: number | null;
 }
 
 // 这个接口对应表单的字段
@@ -16,12 +16,12 @@ interface ChangePasswordFormValues {
   confirm_new_password: string;
 }
 
-const CustomerChangePasswordModal: React.FC<CustomerChangePasswordModalProps> = ({ visible, onCancel, userId }) => {
+const CustomerChangePasswordModal: React.FC<CustomerChangePasswordModalProps> = ({ visible, onCancel }) => {
   const [form] = Form.useForm();
   const [loading, setLoading] = React.useState(false);
 
   const handleSubmit = async (values: ChangePasswordFormValues) => {
-    if (!userId) {
+    if (!getToken()) {
       message.error('无法获取当前用户信息！');
       return;
     }
@@ -34,9 +34,9 @@ const CustomerChangePasswordModal: React.FC<CustomerChangePasswordModalProps> =
         new_password: values.new_password,         // 确保这里是 snake_case
       };
 
-      // 构建传递给 invoke 的 payload，其顶层键名 'data' 和 'userId' 对应 Rust 函数的参数名
+      // 构建传递给 invoke 的 payload，其顶层键名 'token' 和 'data' 对应 Rust 函数的参数名
       const payloadForInvoke = {
-        userId: userId,                     // 对应 Rust 函数的第一个参数 userId
+        token: getToken(),                  // 对应 Rust 函数的第一个参数 token，后端据此确定当前用户
         data: updatePasswordPayloadForRust  // 对应 Rust 函数的第二个参数 data
       };
 
@@ -3,6 +3,7 @@ import React, { useEffect } from 'react';
 import { Modal, Form, Input, Select, message } from 'antd';
 import { invoke } from '@tauri-apps/api/core';
 import type { Account } from '@/api/user';
+import { getToken } from '@/api/session';
 
 const { Option } = Select;
 
@@ -95,7 +96,7 @@ const CustomerEditProfileModal: React.FC<CustomerEditProfileModalProps> = ({
     setLoading(true);
     try {
       await invoke<string>('update_user_details', {
-        userId: currentUser.id,
+        token: getToken(), // 后端根据会话确定要修改的用户
         data: payload, // data 现在可能包含 username, phone, gender
       });
       message.success('用户信息更新成功！');
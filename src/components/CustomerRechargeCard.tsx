@@ -3,6 +3,7 @@ import React, { useState } from 'react';
 import { Card, InputNumber, Button, Form, message, Typography } from 'antd';
 import { PropertySafetyOutlined, DollarCircleOutlined } from '@ant-design/icons';
 import { invoke } from '@tauri-apps/api/core';
+import { getToken } from '@/api/session';
 
 // 如果 Account 类型也包含 balance，可以复用，否则定义一个简单类型
 // interface AccountForBalance {
@@ -10,7 +11,7 @@ import { invoke } from '@tauri-apps/api/core';
 // }
 
 interface CustomerRechargeCardProps {
-  userId: number | null;
+  userId: number | null; // 被充值的账户；操作人由会话 token 决定
   currentBalance: number | string | null | undefined; // 明确它可能从父组件传来的是字符串
   onRechargeSuccess: (estimatedNewBalance: number) => void;
 }
@@ -37,8 +38,9 @@ const CustomerRechargeCard: React.FC<CustomerRechargeCardProps> = ({ userId, cur
         amount: values.amount, // 字段名 amount 与 Rust 结构体匹配
       };
 
-      // 2. 构建传递给 invoke 的 payload，其顶层键名 'data' 对应 Rust 函数的参数名
+      // 2. 构建传递给 invoke 的 payload，其顶层键名 'token' 和 'data' 对应 Rust 函数的参数名
       const payloadForInvoke = {
+        token: getToken(),
         data: rechargeDataForRust
       };
 
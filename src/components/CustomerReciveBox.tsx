@@ -10,7 +10,6 @@ interface CustomerReciveBoxProps {
   setSelectedReceivedMessage: React.Dispatch<React.SetStateAction<Message | null>>;
   setIsViewReceivedModalVisible: React.Dispatch<React.SetStateAction<boolean>>;
   loading: boolean; // Add loading prop
-  currentUserId: number; // Add currentUserId to call markMessageAsReadApi
 }
 
 const CustomerReciveBox: React.FC<CustomerReciveBoxProps> = ({
@@ -19,16 +18,15 @@ const CustomerReciveBox: React.FC<CustomerReciveBoxProps> = ({
   setSelectedReceivedMessage,
   setIsViewReceivedModalVisible,
   loading, // Destructure loading prop
-  currentUserId, // Destructure currentUserId
 }) => {
 
   const handleViewReceivedMessage = async (messageToView: Message) => {
     setSelectedReceivedMessage(messageToView);
     setIsViewReceivedModalVisible(true);
 
-    if (messageToView.read_status === 0 && currentUserId !== -1) {
+    if (messageToView.read_status === 0) {
       try {
-        const status = await markMessageAsReadApi(messageToView.id, currentUserId);
+        const status = await markMessageAsReadApi(messageToView.id);
         if (status === 0) {
           // Successfully marked as read on backend, now update local state
           setReceivedMessages(prevMessages =>
@@ -38,8 +36,8 @@ const CustomerReciveBox: React.FC<CustomerReciveBoxProps> = ({
           );
         } else if (status === 1) {
           // This case means the current user is not the receiver, which shouldn't happen
-          // if the message is in their received box and currentUserId is correct.
-          console.warn(`Attempted to mark message ${messageToView.id} as read, but user ${currentUserId} is not the receiver.`);
+          // if the message is in their received box.
+          console.warn(`Attempted to mark message ${messageToView.id} as read, but the current user is not the receiver.`);
           AntMessage.error('无法将此消息标记为已读：权限不足。');
         } else {
           AntMessage.error(`标记已读时发生未知状态: ${status}`);
@@ -7,19 +7,20 @@ interface Product {
   goods_name: string;
   price: number;
   stock: number;
-  goods_type: string;
+  category_id: number | null;
+  category: string | null;
 }
 
 // 定义组件的 props 接口
 interface EditGoodsBtnProps {
   record: Product; // 传入的商品记录
   onEditProduct: (updatedProduct: Product) => void; // 编辑商品的回调函数
-  // categories: string[]; // Removed as it's not used in this version for price/stock edit
+  // categories: string[]; // Removed as it's not used in this version for price edit
 }
 
 const EditGoodsBtn: React.FC<EditGoodsBtnProps> = ({ record, onEditProduct }) => {
   const [isEditModalVisible, setIsEditModalVisible] = useState(false);
-  // Store the product being edited to ensure we have its original data (like id, goods_type)
+  // Store the product being edited to ensure we have its original data (like id, category)
   const [editingProduct, setEditingProduct] = useState<Product | null>(null);
 
   const [editForm] = Form.useForm();
@@ -30,8 +31,7 @@ const EditGoodsBtn: React.FC<EditGoodsBtnProps> = ({ record, onEditProduct }) =>
     editForm.setFieldsValue({
       goods_name: productToEdit.goods_name,
       price: productToEdit.price,
-      stock: productToEdit.stock,
-      // goods_type is not editable in this form, so no need to set it explicitly for an editable field
+      // category is not editable in this form, so no need to set it explicitly for an editable field
     });
     setIsEditModalVisible(true);
   };
@@ -47,15 +47,14 @@ const EditGoodsBtn: React.FC<EditGoodsBtnProps> = ({ record, onEditProduct }) =>
 
     try {
       // Validate form fields
-      const values = await editForm.validateFields(); // values will contain { price: number, stock: number }
+      const values = await editForm.validateFields(); // values will contain { price: number }
 
       // Construct the updated product object
       // It's important to include all fields of the Product interface
       // as onEditProduct expects a full Product object.
       const updatedProduct: Product = {
-        ...editingProduct, // Spread original product data (id, goods_name, goods_type)
+        ...editingProduct, // Spread original product data (id, goods_name, stock, category)
         price: values.price,   // Override with new price from form
-        stock: values.stock,   // Override with new stock from form
       };
 
       onEditProduct(updatedProduct); // Call the callback with the fully updated product object
@@ -78,7 +77,6 @@ const EditGoodsBtn: React.FC<EditGoodsBtnProps> = ({ record, onEditProduct }) =>
       editForm.setFieldsValue({
         goods_name: record.goods_name,
         price: record.price,
-        stock: record.stock,
       });
     }
   }, [record, editForm, editingProduct]);
@@ -103,7 +101,6 @@ const EditGoodsBtn: React.FC<EditGoodsBtnProps> = ({ record, onEditProduct }) =>
           <Form form={editForm} layout="vertical" name="editProductForm" initialValues={{
             goods_name: editingProduct.goods_name,
             price: editingProduct.price,
-            stock: editingProduct.stock,
           }}>
             <Form.Item name="goods_name" label="商品名称">
               <Input disabled />
@@ -123,22 +120,8 @@ const EditGoodsBtn: React.FC<EditGoodsBtnProps> = ({ record, onEditProduct }) =>
             >
               <InputNumber style={{ width: '100%' }} min={0.01} step={0.01} precision={2} addonAfter="元 (￥)" />
             </Form.Item>
-            <Form.Item
-              name="stock"
-              label="库存"
-              rules={[
-                { required: true, message: '请输入商品库存!' },
-                {
-                  type: 'number',
-                  min: 0,
-                  message: '库存不能为负数!',
-                  transform: value => Number(value) // Ensure value is treated as number
-                },
-              ]}
-            >
-              <InputNumber style={{ width: '100%' }} min={0} step={1} precision={0} />
-            </Form.Item>
-            {/* goods_type is not editable here, it will be preserved from editingProduct */}
+            {/* Stock is changed with the 调整库存 button, which records a reason */}
+            {/* category is not editable here, it will be preserved from editingProduct */}
           </Form>
         )}
       </Modal>
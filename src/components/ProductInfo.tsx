@@ -7,9 +7,10 @@ interface ProductInfoProps {
     goods_name: string;
     stock: number;
     price: number;
-    goods_type: string;
+    category_id: number | null;
+    category: string | null;
   };
-  addToCart: (product: { id: number; goods_name: string; stock: number; price: number; goods_type: string }) => void;
+  addToCart: (product: { id: number; goods_name: string; stock: number; price: number; category_id: number | null; category: string | null }) => void;
 }
 
 const ProductInfo: React.FC<ProductInfoProps> = ({ p, addToCart }) => {
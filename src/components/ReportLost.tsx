@@ -8,25 +8,22 @@ interface ReportFormValues {
   pick_place?: string;
 }
 
-// Interface for the data payload sent to the backend
+// Interface for the data payload sent to the backend; the finder comes from the session
 interface ReportLostItemPayload {
   item_name: string;
   pick_place?: string;
-  pick_user_id?: number;
 }
 
 interface ReportLostProps {
   isModalOpen: boolean;
   setIsModalOpen: React.Dispatch<React.SetStateAction<boolean>>;
   onReportSuccess: () => void;
-  currentUserId?: number;
 }
 
 const ReportLost: React.FC<ReportLostProps> = ({
   isModalOpen,
   setIsModalOpen,
   onReportSuccess,
-  currentUserId,
 }) => {
   const [form] = Form.useForm<ReportFormValues>();
   const [isSubmitting, setIsSubmitting] = useState(false);
@@ -39,7 +36,6 @@ const ReportLost: React.FC<ReportLostProps> = ({
       const reportData: ReportLostItemPayload = {
         item_name: values.item_name,
         pick_place: values.pick_place,
-        pick_user_id: currentUserId,
         // pick_time is handled by the backend
       };
 
@@ -5,7 +5,7 @@ import AdminReplyModal from '@/components/AdminReplyModal';
 import AdminReciveBox from '@/components/AdminReciveBox';
 import AdminSentBox from '@/components/AdminSentBox';
 import { fetchReceivedMessages, fetchSentMessages } from '@/api/message';
-import type { Account } from '@/api/user'; // Import Account type
+import { getToken } from '@/api/session';
 
 const { Title } = Typography;
 
@@ -22,7 +22,6 @@ interface Message {
 }
 
 const AdminMessagePage = () => {
-  const [adminId, setAdminId] = useState<number>(-1);
   const [messages, setMessages] = useState<Message[]>([]);
   const [selectedMessage, setSelectedMessage] = useState<Message | null>(null);
   const [isModalVisible, setIsModalVisible] = useState(false);
@@ -34,22 +33,19 @@ const AdminMessagePage = () => {
   const [loading, setLoading] = useState<boolean>(false);
 
   useEffect(() => {
-    const storedAccountString = localStorage.getItem('loginAccount');
-    if (storedAccountString) {
-      const storedAccount: Account = JSON.parse(storedAccountString);
-      setAdminId(storedAccount.id);
-    } else {
+    if (!getToken()) {
       message.error('请重新登录');
+      return;
     }
 
     const loadMessages = async () => {
       setLoading(true);
       try {
         if (currentView === 'inbox') {
-          const received = await fetchReceivedMessages(adminId);
+          const received = await fetchReceivedMessages();
           setMessages(received);
         } else if (currentView === 'sent') {
-          const sent = await fetchSentMessages(adminId);
+          const sent = await fetchSentMessages();
           setAdminSentMessages(sent);
         }
       } catch (error) {
@@ -60,7 +56,7 @@ const AdminMessagePage = () => {
     };
 
     loadMessages();
-  }, [currentView, adminId]); // Reload messages when currentView changes
+  }, [currentView]); // Reload messages when currentView changes
 
   const handleCloseReplyModal = () => {
     setIsReplyModalVisible(false);
@@ -91,7 +87,6 @@ const AdminMessagePage = () => {
           setIsModalVisible={setIsModalVisible}
           setIsReplyModalVisible={setIsReplyModalVisible}
           setReplyingToMessage={setReplyingToMessage}
-          adminId={adminId}
           loading={loading}
         />
       )}
@@ -117,7 +112,6 @@ const AdminMessagePage = () => {
       {replyingToMessage && (
         <AdminReplyModal
           visible={isReplyModalVisible}
-          adminId={adminId}
           recipientId={replyingToMessage.sender_id}
           recipientName={replyingToMessage.sender_username}
           originalMessageTitle={replyingToMessage.title}
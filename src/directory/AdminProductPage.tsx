@@ -1,5 +1,6 @@
-import { AdminUpdateProduct, getProducts } from '@/api/product';
+import { AdminUpdateProduct, Category, getCategories, getProducts } from '@/api/product';
 import AddGoodsBtn from '@/components/AddGoodsBtn';
+import AdjustStockBtn from '@/components/AdjustStockBtn';
 import EditGoodsBtn from '@/components/EditGoodsBtn';
 import { Table, Space, TableProps, message } from 'antd';
 import { SortOrder } from 'antd/es/table/interface';
@@ -11,23 +12,21 @@ interface Product {
   goods_name: string;
   price: number;
   stock: number;
-  goods_type: string;
+  category_id: number | null;
+  category: string | null;
 }
 
-// 模拟商品类别
-const initialCategories = ['咖啡类', '非咖啡饮品', '烘焙食品', '轻食简餐', '咖啡豆与周边'];
-
 const AdminProductPage = () => {
   const [products, setProducts] = useState<Product[]>([]); // 商品列表
-  const [categories] = useState<string[]>(initialCategories);
+  const [categories, setCategories] = useState<Category[]>([]); // 商品类别，来自后端
   const [loading, setLoading] = useState<boolean>(true); // 添加加载状态
 
   const handleEditProduct = async (updatedProduct: Product) => {
     // 从 updatedProduct 中提取需要发送给后端的数据
-    const { id, stock, price } = updatedProduct;
+    // 库存不在这里修改，而是通过“调整库存”记录原因
+    const { id, price } = updatedProduct;
     const updateData = {
       // 只发送后端 UpdateGoodsData 结构中定义的字段
-      ...(stock !== undefined && { stock }), // 确保 stock 存在才添加
       ...(price !== undefined && { price }), // 确保 price 存在才添加
     };
 
@@ -54,8 +53,6 @@ const AdminProductPage = () => {
     try {
       const fetchedProducts = await getProducts(); // 调用API获取商品
       // 注意：确保 getProducts 返回的数据结构与 Product 接口匹配
-      // 例如，如果后端返回 goods_name, goods_type，需要在这里或 getProducts 函数中进行映射
-      // setProducts(fetchedProducts.map(p => ({ id: p.id, goods_name: p.goods_name, price: p.price, stock: p.stock, goods_type: p.goods_type })));
       setProducts(fetchedProducts);
     } catch (error) {
       console.error("获取商品失败:", error);
@@ -69,6 +66,7 @@ const AdminProductPage = () => {
   // 使用useEffect在组件挂载时获取商品数据
   useEffect(() => {
     fetchProducts();
+    getCategories().then(setCategories);
   }, []); // 空依赖数组意味着这个effect只会在组件挂载时运行一次
 
   const columns: TableProps<Product>['columns'] = [
@@ -94,15 +92,11 @@ const AdminProductPage = () => {
     },
     {
       title: '类别',
-      dataIndex: 'goods_type',
-      key: 'goods_type',
-      filters: categories.map(goods_type => ({ text: goods_type, value: goods_type })),
-      onFilter: (value: React.Key | boolean, record: Product) => {
-        if (typeof value === 'string') {
-          return record.goods_type.includes(value);
-        }
-        return false;
-      },
+      dataIndex: 'category',
+      key: 'category',
+      render: (category: string | null) => category ?? '未分类',
+      filters: categories.map(category => ({ text: category.display_name, value: category.id })),
+      onFilter: (value: React.Key | boolean, record: Product) => record.category_id === value,
       filterMultiple: true,
     },
     {
@@ -114,6 +108,10 @@ const AdminProductPage = () => {
             record={record}
             onEditProduct={handleEditProduct}
           />
+          <AdjustStockBtn
+            record={record}
+            onStockAdjusted={fetchProducts}
+          />
         </Space>
       ),
     },
@@ -135,7 +133,7 @@ const AdminProductPage = () => {
   return (
     <div style={{ padding: '20px' }}>
       <AddGoodsBtn
-        initCategories={initialCategories}
+        categories={categories}
         onProductAdded={fetchProducts}
       />
 
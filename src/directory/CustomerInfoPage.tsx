@@ -9,6 +9,7 @@ import CustomerEditProfileModal from '@/components/CustomerEditProfileModal'; //
 import CustomerChangePasswordModal from '@/components/CustomerChangePasswordModal'; // 引入新组件
 import type { Account, MonthlyConsumptionSummary } from '@/api/user';
 import { getUserDetails, getUserMonthlyConsumption } from '@/api/info'; // <-- 引入新的 API 函数
+import { getToken } from '@/api/session';
 
 const { Title } = Typography;
 
@@ -27,20 +28,13 @@ const CustomerInfoPage: React.FC = () => {
     }
     setError(null);
     try {
-      const storedAccountString = localStorage.getItem('loginAccount');
-      if (!storedAccountString) {
+      if (!getToken()) {
         throw new Error("用户登录信息未找到，请重新登录。");
       }
-      const loggedInAccount: Account = JSON.parse(storedAccountString);
-      const userId = loggedInAccount.id;
-
-      if (!userId) {
-        throw new Error("无法获取用户ID。");
-      }
 
       const [detailsData, consumptionData] = await Promise.all([
-        getUserDetails(userId), // <-- 使用新的 API 函数
-        getUserMonthlyConsumption(userId) // <-- 使用新的 API 函数
+        getUserDetails(), // 后端根据会话 token 返回当前用户的数据
+        getUserMonthlyConsumption()
       ]);
 
       setAccount(detailsData);
@@ -136,7 +130,6 @@ const CustomerInfoPage: React.FC = () => {
       <CustomerChangePasswordModal
         visible={isChangePasswordModalVisible}
         onCancel={() => setIsChangePasswordModalVisible(false)}
-        userId={account.id}
       />
     </div>
   );
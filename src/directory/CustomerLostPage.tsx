@@ -3,34 +3,19 @@ import { Table, Button, Tag, Space, message } from 'antd';
 import type { TableProps } from 'antd';
 import ReportLost from '@/components/ReportLost';
 import { getAllLostItems, LostItem as ApiLostItem, claimLostItem } from '../api/lost';
-import type { Account } from '../api/user'; // Added import for Account type
-
-// Helper function to get user ID - updated based on CustomerInfoPage.tsx
-const getLoggedInUser = (): number | null => {
-  const accountDataString = localStorage.getItem('loginAccount'); // Changed key to 'loginAccount'
-  if (accountDataString) {
-    try {
-      const account: Account = JSON.parse(accountDataString);
-      return account && typeof account.id === 'number' ? account.id : null;
-    } catch (e) {
-      console.error("Failed to parse account data from localStorage", e);
-      return null;
-    }
-  }
-  return null;
-};
+import { getToken } from '../api/session';
 
 const CustomerLostPage = () => {
   const [lostItems, setLostItems] = useState<ApiLostItem[]>([]);
   const [loading, setLoading] = useState<boolean>(true);
   const [error, setError] = useState<string | null>(null);
   const [isModalOpen, setIsModalOpen] = useState(false);
-  const [currentUserId, setCurrentUserId] = useState<number | null>(null);
+  const [isLoggedIn, setIsLoggedIn] = useState<boolean>(false);
 
   useEffect(() => {
-    const userId = getLoggedInUser();
-    setCurrentUserId(userId);
-    if (userId === null) {
+    const hasSession = getToken() !== '';
+    setIsLoggedIn(hasSession);
+    if (!hasSession) {
       message.warning('请先登录以执行操作。'); // Changed from message.warn to message.warning
     }
   }, []);
@@ -55,7 +40,7 @@ const CustomerLostPage = () => {
   }, []);
 
   const handleReportLostItem = () => {
-    if (currentUserId === null) {
+    if (!isLoggedIn) {
       message.error('请先登录才能报告失物。');
       return;
     }
@@ -63,11 +48,11 @@ const CustomerLostPage = () => {
   };
 
   const handleClaimItem = async (itemId: number) => {
-    if (currentUserId === null) {
+    if (!isLoggedIn) {
       message.error('请先登录才能认领物品。');
       return;
     }
-    const res = await claimLostItem(itemId, currentUserId);
+    const res = await claimLostItem(itemId);
     if (res == 0) {
       message.success('认领成功！');
       fetchLostItems();
@@ -119,7 +104,7 @@ const CustomerLostPage = () => {
             <Button
               type="primary"
               onClick={() => handleClaimItem(record.id)}
-              disabled={currentUserId === null}
+              disabled={!isLoggedIn}
             >
               认领
             </Button>
@@ -144,7 +129,7 @@ const CustomerLostPage = () => {
         <Button
           type="primary"
           onClick={handleReportLostItem}
-          disabled={currentUserId === null}
+          disabled={!isLoggedIn}
         >
           报告失物
         </Button>
@@ -157,12 +142,11 @@ const CustomerLostPage = () => {
         pagination={{ pageSize: 10 }}
       />
 
-      {currentUserId !== null && (
+      {isLoggedIn && (
         <ReportLost
           isModalOpen={isModalOpen}
           setIsModalOpen={setIsModalOpen}
           onReportSuccess={fetchLostItems}
-          currentUserId={currentUserId}
         />
       )}
     </div>
@@ -7,6 +7,7 @@ import CustomerReciveBox from '@/components/CustomerReciveBox';
 import CustomerSentBox from '@/components/CustomerSentBox';
 import { fetchReceivedMessages, fetchSentMessages, Message } from '@/api/message'; // Import Message type from API
 import type { Account } from '@/api/user'; // Import Account type
+import { getLoginAccount } from '@/api/session';
 
 const { Title } = Typography;
 const { TabPane } = Tabs;
@@ -28,20 +29,14 @@ const CustomerMessagePage = () => {
   const [loading, setLoading] = useState<boolean>(false); // Added loading state
 
   useEffect(() => {
-    const storedAccountString = localStorage.getItem('loginAccount');
-    if (storedAccountString) {
-      try {
-        const storedAccount: Account = JSON.parse(storedAccountString);
-        if (storedAccount.user_type !== 1) { // Ensure it's a customer (user_type 1)
-          AntMessage.error('您不是客户账户，无法访问此页面。');
-          // Potentially redirect or disable functionality
-          return;
-        }
-        setCurrentUser(storedAccount);
-      } catch (e) {
-        AntMessage.error('登录信息解析失败，请重新登录。');
-        localStorage.removeItem('loginAccount');
+    const storedAccount = getLoginAccount();
+    if (storedAccount) {
+      if (storedAccount.user_type !== 1) { // Ensure it's a customer (user_type 1)
+        AntMessage.error('您不是客户账户，无法访问此页面。');
+        // Potentially redirect or disable functionality
+        return;
       }
+      setCurrentUser(storedAccount);
     } else {
       AntMessage.error('请先登录以访问消息中心。');
       // Potentially redirect to login
@@ -49,13 +44,13 @@ const CustomerMessagePage = () => {
   }, []);
 
   useEffect(() => {
-    if (currentUser && currentUser.id !== -1) { // Ensure currentUser and its id are valid
+    if (currentUser) { // Messages are loaded for the session's user
       const loadMessages = async () => {
         setLoading(true);
         try {
           const [sent, received] = await Promise.all([
-            fetchSentMessages(currentUser.id),
-            fetchReceivedMessages(currentUser.id),
+            fetchSentMessages(),
+            fetchReceivedMessages(),
           ]);
           setSentMessages(sent);
           setReceivedMessages(received);
@@ -114,7 +109,6 @@ const CustomerMessagePage = () => {
                 setReceivedMessages={setReceivedMessages}
                 setSelectedReceivedMessage={setSelectedReceivedMessage}
                 setIsViewReceivedModalVisible={setIsViewReceivedModalVisible}
-                currentUserId={currentUser?.id || -1} // Pass current user ID
                 loading={loading}
               />
             </div>
@@ -166,7 +160,6 @@ const CustomerMessagePage = () => {
           isNewMessageModalVisible={isNewMessageModalVisible}
           setIsNewMessageModalVisible={setIsNewMessageModalVisible}
           setSentMessages={setSentMessages}
-          currentUserId={currentUser.id} // Pass the current user's ID
         />
       )}
     </div>
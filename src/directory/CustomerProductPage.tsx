@@ -3,8 +3,8 @@ import { Col, Row, FloatButton, Typography, Divider, message, Spin } from 'antd'
 import { ShoppingCartOutlined } from '@ant-design/icons';
 import Cart from '../components/Cart';
 import ProductInfo from '@/components/ProductInfo';
-import { getProducts, Product as ApiProduct } from '@/api/product';
-import type { Account } from '@/api/user'; // Import Account type
+import { CategoryGoods, getGoodsByCategory, Product as ApiProduct } from '@/api/product';
+import { getToken } from '@/api/session';
 
 interface Product extends ApiProduct { }
 
@@ -13,34 +13,19 @@ interface CartItem extends Product {
 }
 
 const CustomerProductPage = () => {
+  const [sections, setSections] = useState<CategoryGoods[]>([]);
   const [products, setProducts] = useState<Product[]>([]);
   const [loading, setLoading] = useState<boolean>(true);
   const [cart, setCart] = useState<CartItem[]>([]);
   const [isCartDrawerVisible, setIsCartDrawerVisible] = useState(false);
-  const [userId, setUserId] = useState<number | null>(null);
+  const [isLoggedIn, setIsLoggedIn] = useState<boolean>(false);
 
-  // Fetch user ID from localStorage
+  // Purchases are made for whoever holds the session token
   useEffect(() => {
-    const storedAccountString = localStorage.getItem('loginAccount');
-    if (storedAccountString) {
-      try {
-        const storedAccount: Account = JSON.parse(storedAccountString);
-        if (storedAccount && typeof storedAccount.id === 'number') {
-          setUserId(storedAccount.id);
-        } else {
-          console.error("User ID not found or invalid in stored account data.");
-          message.error("无法获取用户信息，请重新登录。");
-          // Optionally, redirect to login page
-          // navigate('/login');
-        }
-      } catch (error) {
-        console.error("Failed to parse stored account data:", error);
-        message.error("用户信息解析失败，请重新登录。");
-        localStorage.removeItem('loginAccount'); // Clear corrupted data
-        // Optionally, redirect to login page
-      }
+    if (getToken()) {
+      setIsLoggedIn(true);
     } else {
-      console.warn("No user account found in localStorage. Please login.");
+      console.warn("No session found in localStorage. Please login.");
       message.error("请先登录再进行操作。");
       // Optionally, redirect to login page
       // navigate('/login'); // You would need to import useNavigate from 'react-router-dom'
@@ -51,8 +36,10 @@ const CustomerProductPage = () => {
   const fetchProducts = async () => {
     setLoading(true);
     try {
-      const fetchedProducts = await getProducts();
-      setProducts(fetchedProducts);
+      // The backend groups the goods by category, in category order
+      const fetchedSections = await getGoodsByCategory();
+      setSections(fetchedSections);
+      setProducts(fetchedSections.flatMap(section => section.goods));
     } catch (error) {
       console.error("加载商品失败:", error);
       message.error('商品加载失败，请稍后重试。');
@@ -92,7 +79,6 @@ const CustomerProductPage = () => {
     message.success(`${product.goods_name} 已添加到购物车`);
   };
 
-  const uniqueCategories = Array.from(new Set(products.map(p => p.goods_type))).sort();
   const totalCartItems = cart.reduce((total, item) => total + item.quantity, 0);
 
   if (loading && products.length === 0) { // Show loading only if products are not yet loaded
@@ -106,15 +92,14 @@ const CustomerProductPage = () => {
   return (
     <div style={{ padding: '20px' }}>
       <Typography.Title level={2} style={{ textAlign: 'center', marginBottom: '30px' }}>欢迎选购</Typography.Title>
-      {uniqueCategories.length === 0 && !loading && (
+      {sections.length === 0 && !loading && (
         <Typography.Text style={{ textAlign: 'center', display: 'block' }}>暂无商品</Typography.Text>
       )}
-      {uniqueCategories.map(goods_type => (
-        <div key={goods_type} style={{ marginBottom: '30px' }}>
-          <Divider orientation="left"><Typography.Title level={3}>{goods_type}</Typography.Title></Divider>
+      {sections.map(section => (
+        <div key={section.category?.id ?? 'uncategorized'} style={{ marginBottom: '30px' }}>
+          <Divider orientation="left"><Typography.Title level={3}>{section.category?.display_name ?? '其他'}</Typography.Title></Divider>
           <Row gutter={[16, 16]}>
-            {products
-              .filter(p => p.goods_type === goods_type)
+            {section.goods
               .map(p => (
                 <Col key={p.id} xs={24} sm={12} md={8} lg={6}>
                   <ProductInfo p={p} addToCart={addToCart} />
@@ -139,7 +124,7 @@ const CustomerProductPage = () => {
         setProducts={setProducts} // Still needed for optimistic updates in cart quantity adjustment
         isCartDrawerVisible={isCartDrawerVisible}
         setIsCartDrawerVisible={setIsCartDrawerVisible}
-        userId={userId} // Pass the user ID
+        isLoggedIn={isLoggedIn}
         onPurchaseSuccess={fetchProducts} // Pass fetchProducts as callback
       />
     </div>
@@ -5,6 +5,7 @@ import { EyeInvisibleOutlined, EyeTwoTone, InfoCircleOutlined, KeyOutlined, User
 import bg from "@/assets/login.png"; // ****** 1. 确保导入背景图片 ******
 import { useState } from "react";
 import { login } from "@/api/user";
+import { clearSession } from "@/api/session";
 import type { Account } from "@/api/user";
 
 const LoginPage = () => {
@@ -23,8 +24,6 @@ const LoginPage = () => {
           content: '登录成功',
           duration: 2,
         });
-        localStorage.setItem('loginAccount', JSON.stringify(account));
-        localStorage.setItem('isAuthenticated', 'true');
         if (account.user_type === 0) {
           navigate('/admin');
         } else if (account.user_type === 1) {
@@ -44,8 +43,6 @@ const LoginPage = () => {
           content: '账号或密码错误，或登录服务暂时不可用',
           duration: 2,
         });
-        localStorage.removeItem('loginAccount');
-        localStorage.removeItem('isAuthenticated');
       }
     } catch (error) {
       console.error('登录流程中发生意外错误:', error);
@@ -54,8 +51,7 @@ const LoginPage = () => {
         content: '登录失败，请稍后再试',
         duration: 2,
       });
-      localStorage.removeItem('loginAccount');
-      localStorage.removeItem('isAuthenticated');
+      clearSession();
     }
   };
 
@@ -37,8 +37,6 @@ const RegisterPage = () => {
         content: '注册成功',
         duration: 2,
       });
-      localStorage.setItem('loginAccount', JSON.stringify(account));
-      localStorage.setItem('isAuthenticated', 'true');
       if (account != null && account.user_type === 1) {
         navigate('/customer');
       }